@@ -0,0 +1,166 @@
+//! Parsing of the CELL blocks by chunks, which are independent once the header is known: in parallel with the
+//! `rayon` feature, or one after the other to report the progress.
+
+use super::*;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Location of a top-level `(CELL` in the source.
+struct CellStart {
+    offset: usize,
+    span: SDFSpan,
+}
+
+/// Find the top-level `(CELL` blocks and the `)` closing the DELAYFILE, skipping
+/// strings, comments and escaped characters. None if the file isn't closed.
+fn split_cells(s: &str) -> Option<(Vec<CellStart>, usize)> {
+    let b = s.as_bytes();
+    let mut starts = Vec::new();
+    let mut depth = 0usize;
+    let mut line = 1;
+    let mut line_start = 0;
+    let mut i = 0;
+
+    // moves past the byte at `i`, counting the lines
+    macro_rules! advance {
+        () => {{
+            if b[i] == b'\n' {
+                line += 1;
+                line_start = i + 1;
+            }
+            i += 1;
+        }};
+    }
+
+    while i < b.len() {
+        match b[i] {
+            b'"' => {
+                i += 1;
+                while i < b.len() && b[i] != b'"' {
+                    if b[i] == b'\\' {
+                        i += 1;
+                    }
+                    if i < b.len() {
+                        advance!();
+                    }
+                }
+            }
+            b'\\' => i += 1,
+            b'/' if b.get(i + 1) == Some(&b'/') => {
+                while i < b.len() && b[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            b'/' if b.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i < b.len() && !b[i..].starts_with(b"*/") {
+                    advance!();
+                }
+                i += 1;
+            }
+            b'(' => {
+                depth += 1;
+                let rest = &b[i + 1..];
+                let is_cell = rest.starts_with(b"CELL")
+                    && !rest
+                        .get(4)
+                        .is_some_and(|&c| c.is_ascii_alphanumeric() || c == b'_');
+                if depth == 2 && is_cell {
+                    starts.push(CellStart {
+                        offset: i,
+                        span: SDFSpan {
+                            line,
+                            column: (i - line_start + 1) as u32,
+                        },
+                    });
+                }
+            }
+            b')' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    return Some((starts, i));
+                }
+            }
+            _ => {}
+        }
+        if i < b.len() {
+            advance!();
+        }
+    }
+    None
+}
+
+/// Parse the CELL blocks by chunks, on `threads` threads of the rayon pool (one without the `rayon` feature).
+/// `progress` is called with the bytes parsed so far and the size of the source after each chunk.
+pub(crate) fn parse_sdf_chunks(
+    s: &str,
+    corner: Option<SDFCorner>,
+    threads: usize,
+    progress: Option<&(dyn Fn(usize, usize) + Sync)>,
+) -> Result<SDF, String> {
+    let report = |parsed: usize| {
+        if let Some(progress) = progress {
+            progress(parsed, s.len());
+        }
+    };
+    let parse_whole = || {
+        let sdf = sdfpest::parse_sdf(s, corner);
+        report(s.len());
+        sdf
+    };
+
+    let Some((starts, end)) = split_cells(s) else {
+        return parse_whole();
+    };
+    if starts.len() < 2 {
+        return parse_whole();
+    }
+
+    // the header alone is a SDF without cells
+    let header = sdfpest::parse_sdf(&format!("{})", &s[..starts[0].offset]), corner)?.header;
+
+    // a few chunks per thread, of about the same size. Enough of them to update a progress bar.
+    let n_chunks = match progress {
+        Some(_) => (threads * 4).max(100),
+        None => threads * 4,
+    };
+    let chunk_size = s.len() / n_chunks + 1;
+    let mut chunks: Vec<(&CellStart, usize)> = Vec::new();
+    for (i, start) in starts.iter().enumerate() {
+        let chunk_end = starts.get(i + 1).map_or(end, |next| next.offset);
+        match chunks.last_mut() {
+            Some((first, last_end)) if *last_end - first.offset < chunk_size => *last_end = chunk_end,
+            _ => chunks.push((start, chunk_end)),
+        }
+    }
+
+    let parsed = AtomicUsize::new(starts[0].offset);
+    let parse_chunk = |(start, end): &(&CellStart, usize)| {
+        let cells = sdfpest::parse_cells(&s[start.offset..*end], header.hier_divider, start.span, corner);
+        let len = end - start.offset;
+        report(parsed.fetch_add(len, Ordering::Relaxed) + len);
+        cells
+    };
+
+    #[cfg(feature = "rayon")]
+    let cells: Result<Vec<Vec<SDFCell>>, String> = match threads {
+        1 => chunks.iter().map(parse_chunk).collect(),
+        _ => chunks.par_iter().map(parse_chunk).collect(),
+    };
+    #[cfg(not(feature = "rayon"))]
+    let cells: Result<Vec<Vec<SDFCell>>, String> = chunks.iter().map(parse_chunk).collect();
+
+    match cells {
+        Ok(cells) => {
+            report(s.len());
+            Ok(SDF {
+                header,
+                cells: cells.into_iter().flatten().collect(),
+            })
+        }
+        // parse it again for an error message with the right line number
+        Err(_) => parse_whole(),
+    }
+}
@@ -0,0 +1,25 @@
+//! Transparent decompression of the sources, SDF files from real flows are routinely gzipped.
+
+use std::io::{self, BufRead, BufReader, Read};
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Read a source to the end, decompressing it first when it starts with the
+/// magic bytes of gzip or zstd. Other sources are read as plain text.
+pub fn read_to_string_decompressed(reader: impl Read) -> io::Result<String> {
+    let mut reader = BufReader::new(reader);
+    let magic = reader.fill_buf()?;
+
+    let mut content = String::new();
+    if magic.starts_with(GZIP_MAGIC) {
+        flate2::read::MultiGzDecoder::new(reader).read_to_string(&mut content)?;
+    } else if magic.starts_with(ZSTD_MAGIC) {
+        ruzstd::decoding::StreamingDecoder::new(reader)
+            .map_err(io::Error::other)?
+            .read_to_string(&mut content)?;
+    } else {
+        reader.read_to_string(&mut content)?;
+    }
+    Ok(content)
+}
@@ -0,0 +1,510 @@
+//! Standard delay format (SDF) parser for EDA applications.
+//!
+//! ## How to use
+//! See [`SDF::parse_str`], or [`SDF::parse_reader`] for gzip or zstd
+//! compressed sources.
+//!
+//! A number of features, including the SCOND and CCOND of the
+//! SETUPHOLD and RECREM timing checks and some TIMINGENV
+//! constructs, are unsupported at this moment.
+
+use compact_str::CompactString;
+
+/// The main entry of SDF.
+#[derive(Debug)]
+pub struct SDF {
+    pub header: SDFHeader,
+    pub cells: Vec<SDFCell>,
+}
+
+/// The header information of SDF.
+#[derive(Debug)]
+pub struct SDFHeader {
+    pub sdf_version: CompactString,
+    pub design_name: Option<CompactString>,
+    pub date: Option<CompactString>,
+    pub vendor: Option<CompactString>,
+    pub program: Option<CompactString>,
+    pub program_version: Option<CompactString>,
+    pub hier_divider: char,
+    pub voltage: Option<SDFValue>,
+    pub process: Option<CompactString>,
+    pub temperature: Option<SDFValue>,
+    pub timescale: f32,
+}
+
+mod path;
+pub use path::{SDFBus, SDFPath};
+
+mod decompress;
+pub use decompress::read_to_string_decompressed;
+
+/// One port in SDF
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SDFPort {
+    pub port_name: CompactString,
+    pub bus: SDFBus,
+}
+
+/// One value specification in SDF with at most 3 corners.
+#[derive(Debug)]
+pub enum SDFValue {
+    None,
+    Single(f32),
+    Multi(Option<f32>, Option<f32>, Option<f32>),
+}
+
+/// One of the values of a `(min:typ:max)` triple.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SDFCorner {
+    Min,
+    Typ,
+    Max,
+}
+
+impl SDFValue {
+    /// The value of the corner as a [`SDFValue::Single`], falling back to the other
+    /// corners (typ first) when it is missing, as in `(1.0::1.2)`.
+    pub fn select(&self, corner: SDFCorner) -> SDFValue {
+        let SDFValue::Multi(min, typ, max) = *self else {
+            return match *self {
+                SDFValue::Single(v) => SDFValue::Single(v),
+                _ => SDFValue::None,
+            };
+        };
+        let selected = match corner {
+            SDFCorner::Min => min,
+            SDFCorner::Typ => typ,
+            SDFCorner::Max => max,
+        };
+        match selected.or(typ).or(min).or(max) {
+            Some(v) => SDFValue::Single(v),
+            None => SDFValue::None,
+        }
+    }
+}
+
+/// Location in the SDF source, 1-based, the column counted in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SDFSpan {
+    pub line: u32,
+    pub column: u32,
+}
+
+impl std::fmt::Display for SDFSpan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+/// One SDF cell containing delay and constraint definitions.
+#[derive(Debug)]
+pub struct SDFCell {
+    pub celltype: CompactString,
+    pub instance: Option<SDFPath>,
+    /// location of the `(CELL`, None if the cell wasn't parsed from a source
+    pub span: Option<SDFSpan>,
+    /// delays of the ABSOLUTE sections
+    pub delays: Vec<SDFDelay>,
+    /// location of each of the `delays`, or empty
+    pub delay_spans: Vec<SDFSpan>,
+    /// delays of the INCREMENT sections, to add to the ABSOLUTE ones
+    pub increments: Vec<SDFDelay>,
+    /// location of each of the `increments`, or empty
+    pub increment_spans: Vec<SDFSpan>,
+    pub timing_checks: Vec<SDFTimingCheck>,
+    /// constraints and environment of the TIMINGENV sections
+    pub timing_env: Vec<SDFTimingEnv>,
+}
+
+impl SDFCell {
+    /// Keep only the value of the corner in each of the triples of the cell.
+    pub fn select_corner(&mut self, corner: SDFCorner) {
+        let select = |values: &mut Vec<SDFValue>| {
+            for value in values {
+                *value = value.select(corner);
+            }
+        };
+        for delay in self.delays.iter_mut().chain(&mut self.increments) {
+            match delay {
+                SDFDelay::Interconnect(SDFDelayInterconnect { delay, .. })
+                | SDFDelay::Port(SDFDelayPort { delay, .. })
+                | SDFDelay::NetDelay(SDFDelayNet { delay, .. })
+                | SDFDelay::Device { delay, .. } => select(delay),
+                SDFDelay::IOPath(_, iopath) => {
+                    select(&mut iopath.delay);
+                    if let Some(retain) = &mut iopath.retain {
+                        select(retain);
+                    }
+                }
+            }
+        }
+        for check in &mut self.timing_checks {
+            select(&mut check.values);
+        }
+        for env in &mut self.timing_env {
+            select(&mut env.values);
+        }
+    }
+}
+
+/// SDF interconnect delay.
+#[derive(Debug)]
+pub struct SDFDelayInterconnect {
+    pub a: SDFPath,
+    pub b: SDFPath,
+    pub delay: Vec<SDFValue>,
+}
+
+/// SDF port delay, the delay of all the interconnects
+/// ending at an input port, e.g. `(PORT u1/A (0.1) (0.2))`.
+#[derive(Debug)]
+pub struct SDFDelayPort {
+    pub port: SDFPath,
+    pub delay: Vec<SDFValue>,
+}
+
+/// SDF net delay, the delay from the driver of a net to all
+/// its loads, e.g. `(NETDELAY u1/X (0.1) (0.2))`.
+/// The net is named by its driving port or by the net name.
+#[derive(Debug)]
+pub struct SDFDelayNet {
+    pub net: SDFPath,
+    pub delay: Vec<SDFValue>,
+}
+
+/// SDF IO path delay.
+#[derive(Debug)]
+pub struct SDFDelayIOPath {
+    pub a: SDFPortSpec,
+    pub b: SDFPort,
+    /// The retain value of SDF IO path delay.
+    /// See SDF docs or synopsys VCS docs for information.
+    pub retain: Option<Vec<SDFValue>>,
+    pub delay: Vec<SDFValue>,
+}
+
+/// One SDF delay definition.
+#[derive(Debug)]
+pub enum SDFDelay {
+    Interconnect(SDFDelayInterconnect),
+    Port(SDFDelayPort),
+    NetDelay(SDFDelayNet),
+    /// `(DEVICE Y? ...)`, the delay of all the paths through the cell
+    /// to the output port, or to all its outputs without a port.
+    Device {
+        port: Option<SDFPort>,
+        delay: Vec<SDFValue>,
+    },
+    IOPath(SDFIOPathCond, SDFDelayIOPath),
+}
+
+/// IO path delay condition.
+#[derive(Debug)]
+pub enum SDFIOPathCond {
+    None,
+    /// `(COND "name"? expr (IOPATH ...))`
+    Cond(Option<CompactString>, SDFCondExpr),
+    CondElse,
+}
+
+/// A conditional port expression, e.g. `A && !B || C == 1'b0`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SDFCondExpr {
+    Const(bool),
+    Port(SDFPort),
+    Not(Box<SDFCondExpr>),
+    Binary(SDFCondOp, Box<SDFCondExpr>, Box<SDFCondExpr>),
+}
+
+/// Binary operators of conditional port expressions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SDFCondOp {
+    /// `||`
+    LogicalOr,
+    /// `&&`
+    LogicalAnd,
+    /// `|`
+    BitOr,
+    /// `^`
+    Xor,
+    /// `^~` or `~^`
+    Xnor,
+    /// `&`
+    BitAnd,
+    /// `==` or `===`
+    Eq,
+    /// `!=` or `!==`
+    Ne,
+}
+
+impl SDFCondExpr {
+    /// Evaluate the expression given the logic value of each port.
+    pub fn eval(&self, port_value: &impl Fn(&SDFPort) -> bool) -> bool {
+        use SDFCondOp::*;
+        match self {
+            SDFCondExpr::Const(v) => *v,
+            SDFCondExpr::Port(p) => port_value(p),
+            SDFCondExpr::Not(e) => !e.eval(port_value),
+            SDFCondExpr::Binary(op, a, b) => {
+                let (a, b) = (a.eval(port_value), b.eval(port_value));
+                match op {
+                    LogicalOr | BitOr => a || b,
+                    LogicalAnd | BitAnd => a && b,
+                    Xor | Ne => a ^ b,
+                    Xnor | Eq => a == b,
+                }
+            }
+        }
+    }
+
+    /// All the ports the expression depends on, in order of appearance.
+    pub fn ports(&self) -> Vec<&SDFPort> {
+        fn visit<'a>(e: &'a SDFCondExpr, ports: &mut Vec<&'a SDFPort>) {
+            match e {
+                SDFCondExpr::Const(_) => {}
+                SDFCondExpr::Port(p) => ports.push(p),
+                SDFCondExpr::Not(e) => visit(e, ports),
+                SDFCondExpr::Binary(_, a, b) => {
+                    visit(a, ports);
+                    visit(b, ports);
+                }
+            }
+        }
+        let mut ports = Vec::new();
+        visit(self, &mut ports);
+        ports
+    }
+}
+
+impl std::fmt::Display for SDFCondExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use SDFCondOp::*;
+        match self {
+            SDFCondExpr::Const(v) => write!(f, "1'b{}", *v as u8),
+            SDFCondExpr::Port(p) => {
+                write!(f, "{}", p.port_name)?;
+                match p.bus {
+                    SDFBus::None => Ok(()),
+                    SDFBus::SingleBit(i) => write!(f, "[{}]", i),
+                    SDFBus::BitRange(l, r) => write!(f, "[{}:{}]", l, r),
+                }
+            }
+            SDFCondExpr::Not(e) => write!(f, "!{}", e),
+            SDFCondExpr::Binary(op, a, b) => {
+                let op = match op {
+                    LogicalOr => "||",
+                    LogicalAnd => "&&",
+                    BitOr => "|",
+                    Xor => "^",
+                    Xnor => "^~",
+                    BitAnd => "&",
+                    Eq => "==",
+                    Ne => "!=",
+                };
+                write!(f, "({} {} {})", a, op, b)
+            }
+        }
+    }
+}
+
+/// The kinds of timing checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SDFTimingCheckKind {
+    Setup,
+    Hold,
+    /// The setup value then the hold value
+    Setuphold,
+    Recovery,
+    Removal,
+    Width,
+    /// The recovery value then the removal value
+    Recrem,
+    Skew,
+    Period,
+    Nochange,
+}
+
+/// A port of a timing check, with its optional condition
+/// e.g. `(COND ENABLE (posedge D))`.
+#[derive(Debug)]
+pub struct SDFTimingCheckPort {
+    pub cond: Option<SDFCondExpr>,
+    pub spec: SDFPortSpec,
+}
+
+/// One SDF timing check, e.g. `(SETUP (posedge D) (posedge CLK) (0.1))`.
+/// For setup/hold-like checks, the first port is the data and
+/// the second one the reference (clock).
+#[derive(Debug)]
+pub struct SDFTimingCheck {
+    pub kind: SDFTimingCheckKind,
+    pub ports: Vec<SDFTimingCheckPort>,
+    pub values: Vec<SDFValue>,
+}
+
+/// The kinds of TIMINGENV entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SDFTimingEnvKind {
+    PathConstraint,
+    Arrival,
+    Departure,
+    Slack,
+}
+
+/// A port of a TIMINGENV entry, e.g. `(posedge clk)` or `top/u1/A`.
+#[derive(Debug)]
+pub struct SDFTimingEnvPort {
+    pub edge_type: SDFPortEdge,
+    pub path: SDFPath,
+}
+
+/// One SDF TIMINGENV entry, e.g. `(ARRIVAL (posedge clk) in1 (1) (1) (2) (2))`.
+/// ARRIVAL and DEPARTURE have an optional reference edge before the port, and the
+/// rise min/max then fall min/max times. PATHCONSTRAINT has the ports of the path
+/// followed by the rise and fall max delays, SLACK a port with four slacks.
+#[derive(Debug)]
+pub struct SDFTimingEnv {
+    pub kind: SDFTimingEnvKind,
+    /// `(NAME "...")` of a PATHCONSTRAINT
+    pub name: Option<CompactString>,
+    pub ports: Vec<SDFTimingEnvPort>,
+    pub values: Vec<SDFValue>,
+    /// clock period of a SLACK
+    pub period: Option<u32>,
+}
+
+/// A port with edge specification
+#[derive(Debug)]
+pub struct SDFPortSpec {
+    pub edge_type: SDFPortEdge,
+    pub port: SDFPort,
+}
+
+/// The types of specified edges.
+#[derive(Debug)]
+pub enum SDFPortEdge {
+    None,
+    Posedge,
+    Negedge,
+    T01,
+    T10,
+    T0Z,
+    TZ1,
+    T1Z,
+    TZ0,
+}
+
+mod sdfpest;
+
+mod chunks;
+
+/// Options of [`SDF::parse_str_with`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SDFParseOptions {
+    /// keep only this corner of the triples, which are read as [`SDFValue::Single`]
+    pub corner: Option<SDFCorner>,
+    /// parse the CELL blocks in parallel, only with the `rayon` feature
+    pub parallel: bool,
+}
+
+impl SDF {
+    /// Parse a SDF source string to the SDF object, or an error message with line number.
+    /// The cells and delays keep their location in the source, see [`SDFSpan`].
+    /// This is the main entry.
+    #[inline]
+    pub fn parse_str(s: &str) -> Result<SDF, String> {
+        Self::parse_str_with(s, &SDFParseOptions::default())
+    }
+
+    /// Same as [`SDF::parse_str`] with the given options.
+    pub fn parse_str_with(s: &str, options: &SDFParseOptions) -> Result<SDF, String> {
+        #[cfg(feature = "rayon")]
+        if options.parallel {
+            return chunks::parse_sdf_chunks(s, options.corner, rayon::current_num_threads(), None);
+        }
+        sdfpest::parse_sdf(s, options.corner)
+    }
+
+    /// Same as [`SDF::parse_str_with`], calling `progress` with the bytes parsed so far and the size of `s` as the
+    /// CELL blocks are parsed. The CELL blocks are then always parsed by chunks (see [`SDF::parse_str_parallel`]).
+    pub fn parse_str_with_progress(
+        s: &str,
+        options: &SDFParseOptions,
+        progress: impl Fn(usize, usize) + Sync,
+    ) -> Result<SDF, String> {
+        #[cfg(feature = "rayon")]
+        let threads = match options.parallel {
+            true => rayon::current_num_threads(),
+            false => 1,
+        };
+        #[cfg(not(feature = "rayon"))]
+        let threads = 1;
+        chunks::parse_sdf_chunks(s, options.corner, threads, Some(&progress))
+    }
+
+    /// Same as [`SDF::parse_str`], the CELL blocks being parsed in parallel.
+    /// Large files are split on the top-level `(CELL`, each part being parsed on
+    /// the rayon thread pool.
+    #[cfg(feature = "rayon")]
+    pub fn parse_str_parallel(s: &str) -> Result<SDF, String> {
+        Self::parse_str_with(
+            s,
+            &SDFParseOptions {
+                parallel: true,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Combine the SDFs of the blocks of a design, each given with the instance path of its block
+    /// (e.g. `u_core/u_alu`, empty for the top level) which prefixes the instances of its cells.
+    /// The SDFs must have the same timescale, the header is the one of the first SDF. Their dividers may differ:
+    /// the paths are already split, each prefix is split at the divider of its own SDF.
+    pub fn merge<P: AsRef<str>>(sdfs: impl IntoIterator<Item = (P, SDF)>) -> Result<SDF, String> {
+        let mut merged: Option<SDF> = None;
+        for (prefix, sdf) in sdfs {
+            let header = &merged.as_ref().unwrap_or(&sdf).header;
+            if (sdf.header.timescale - header.timescale).abs() > header.timescale.abs() * 1e-6 {
+                return Err(format!(
+                    "cannot merge {}: timescale {} instead of {}",
+                    prefix.as_ref(),
+                    sdf.header.timescale,
+                    header.timescale
+                ));
+            }
+
+            let divider = sdf.header.hier_divider;
+            let prefix: Vec<CompactString> = (prefix.as_ref().split(divider))
+                .filter(|part| !part.is_empty())
+                .map(CompactString::from)
+                .collect();
+            let merged = merged.get_or_insert_with(|| SDF {
+                header: sdf.header,
+                cells: Vec::new(),
+            });
+            merged.cells.extend(sdf.cells.into_iter().map(|mut cell| {
+                cell.instance = match cell.instance {
+                    Some(mut instance) => {
+                        instance.path.splice(0..0, prefix.iter().cloned());
+                        Some(instance)
+                    }
+                    None if prefix.is_empty() => None,
+                    None => Some(SDFPath {
+                        path: prefix.clone(),
+                        bus: SDFBus::None,
+                    }),
+                };
+                cell
+            }));
+        }
+        merged.ok_or_else(|| "no SDF to merge".to_string())
+    }
+
+    /// Read and parse a SDF source, which can be gzip or zstd compressed
+    /// (detected from its first bytes).
+    pub fn parse_reader(reader: impl std::io::Read) -> Result<SDF, String> {
+        let s = read_to_string_decompressed(reader).map_err(|e| e.to_string())?;
+        Self::parse_str(&s)
+    }
+}
@@ -0,0 +1,536 @@
+//! Pest token to data structure.
+
+use super::*;
+use parsing_utils::PairsHelper;
+use pest::Parser;
+use pest_derive::Parser;
+use std::str::FromStr;
+
+#[derive(Parser)]
+#[grammar = "sdf.pest"]
+struct SDFParser;
+
+type Pair<'i> = pest::iterators::Pair<'i, Rule>;
+
+/// Line and column of increasing offsets in the source, without rescanning it
+/// from the start like `pest::Position::line_col`.
+struct LineCounter<'i> {
+    input: &'i str,
+    /// offset of the input at the given location, where the counting starts
+    origin: (usize, SDFSpan),
+    offset: usize,
+    line: u32,
+    line_start: usize,
+}
+
+impl<'i> LineCounter<'i> {
+    fn new(input: &'i str) -> Self {
+        Self::starting_at(input, 0, SDFSpan { line: 1, column: 1 })
+    }
+
+    fn starting_at(input: &'i str, offset: usize, span: SDFSpan) -> Self {
+        Self {
+            input,
+            origin: (offset, span),
+            offset,
+            line: span.line,
+            line_start: offset,
+        }
+    }
+
+    fn span(&mut self, offset: usize) -> SDFSpan {
+        if offset < self.offset {
+            *self = Self::starting_at(self.input, self.origin.0, self.origin.1);
+        }
+        for (i, &b) in self.input.as_bytes()[self.offset..offset].iter().enumerate() {
+            if b == b'\n' {
+                self.line += 1;
+                self.line_start = self.offset + i + 1;
+            }
+        }
+        self.offset = offset;
+        let mut column = (offset - self.line_start + 1) as u32;
+        if self.line == self.origin.1.line {
+            column += self.origin.1.column - 1;
+        }
+        SDFSpan {
+            line: self.line,
+            column,
+        }
+    }
+}
+
+#[inline]
+fn unescape(s: &str) -> CompactString {
+    if s.chars().all(|c| c != '\\') {
+        return s.into();
+    }
+    let mut cs = CompactString::with_capacity(s.len());
+    let mut s = s.chars();
+    while let Some(c) = s.next() {
+        if c == '\\' {
+            cs.push(s.next().unwrap());
+        } else {
+            cs.push(c);
+        }
+    }
+    cs
+}
+
+#[inline]
+fn parse_str(p: Pair) -> CompactString {
+    assert_eq!(p.as_rule(), Rule::str);
+    let substr = p.as_str();
+    let substr = &substr[1..substr.len() - 1];
+    unescape(substr)
+}
+
+#[inline]
+fn parse_ident(p: Pair) -> CompactString {
+    assert_eq!(p.as_rule(), Rule::ident);
+    let substr = p.as_str();
+    unescape(substr)
+}
+
+#[inline]
+fn parse_int(p: Pair) -> isize {
+    assert_eq!(p.as_rule(), Rule::int);
+    isize::from_str(p.as_str()).unwrap()
+}
+
+#[inline]
+fn parse_real(p: Pair) -> f32 {
+    assert_eq!(p.as_rule(), Rule::real);
+    f32::from_str(p.as_str()).unwrap()
+}
+
+#[inline]
+fn parse_rvalue(p: Pair) -> SDFValue {
+    assert_eq!(p.as_rule(), Rule::rvalue);
+    let p = unwrap_one(p);
+    match p.as_rule() {
+        Rule::real_optional => match p.into_inner().next() {
+            Some(p) => SDFValue::Single(parse_real(p)),
+            None => SDFValue::None,
+        },
+        Rule::rvalue_multi => {
+            let mut p = PairsHelper(p.into_inner());
+            SDFValue::Multi(
+                p.next().into_inner().next().map(parse_real),
+                p.next().into_inner().next().map(parse_real),
+                p.next().into_inner().next().map(parse_real),
+            )
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[inline]
+fn parse_rvalue_list(p: Pair) -> Vec<SDFValue> {
+    p.into_inner().map(parse_rvalue).collect()
+}
+
+#[inline]
+fn parse_char(p: Pair) -> char {
+    assert!(p.as_rule() == Rule::hchar);
+    let s = p.as_str();
+    assert_eq!(s.len(), 1);
+    s.chars().next().unwrap()
+}
+
+#[inline]
+fn unwrap_one(p: Pair) -> Pair {
+    let mut p = PairsHelper(p.into_inner());
+    p.next()
+}
+
+#[inline]
+fn parse_bus(p: Pair) -> SDFBus {
+    assert_eq!(p.as_rule(), Rule::bus);
+    let mut p = PairsHelper(p.into_inner());
+    let l = parse_int(p.next());
+    match p.next_rule_opt(Rule::int) {
+        Some(p) => SDFBus::BitRange(l, parse_int(p)),
+        None => SDFBus::SingleBit(l),
+    }
+}
+
+#[inline]
+fn parse_path(p: Pair) -> SDFPath {
+    assert_eq!(p.as_rule(), Rule::path);
+    let mut p = PairsHelper(p.into_inner());
+    SDFPath {
+        path: p.iter_while(Rule::ident).map(parse_ident).collect(),
+        bus: p.next_rule_opt(Rule::bus).map(parse_bus).unwrap_or(SDFBus::None),
+    }
+}
+
+#[inline]
+fn parse_port(p: Pair) -> SDFPort {
+    assert_eq!(p.as_rule(), Rule::port);
+    let mut p = PairsHelper(p.into_inner());
+    SDFPort {
+        port_name: parse_ident(p.next()),
+        bus: p.next_rule_opt(Rule::bus).map(parse_bus).unwrap_or(SDFBus::None),
+    }
+}
+
+#[inline]
+fn parse_port_edge_type(p: Option<Pair>) -> SDFPortEdge {
+    use SDFPortEdge::*;
+    p.map(|p| match p.as_str() {
+        "posedge" => Posedge,
+        "negedge" => Negedge,
+        "01" => T01,
+        "10" => T10,
+        "0z" => T0Z,
+        "z1" => TZ1,
+        "1z" => T1Z,
+        "z0" => TZ0,
+        _ => unreachable!(),
+    })
+    .unwrap_or(SDFPortEdge::None)
+}
+
+#[inline]
+fn parse_port_spec(p: Pair) -> SDFPortSpec {
+    assert_eq!(p.as_rule(), Rule::port_spec);
+    let mut p = PairsHelper(p.into_inner());
+    SDFPortSpec {
+        edge_type: parse_port_edge_type(p.next_rule_opt(Rule::port_edge_type)),
+        port: parse_port(p.next()),
+    }
+}
+
+#[inline]
+fn parse_header(p: Pair) -> SDFHeader {
+    assert_eq!(p.as_rule(), Rule::header);
+    let mut p = PairsHelper(p.into_inner());
+    macro_rules! parse_fields {
+        ($($($field:ident)|+ => $parse:expr),+) => {
+            $($(let $field = p.next_rule_opt(Rule::$field)
+              .map(|p| $parse(unwrap_one(p)));)+)+
+        }
+    }
+    parse_fields! {
+        sdf_version | design_name | date |
+        vendor | program | program_version
+            => parse_str,
+        hier_divider => parse_char,
+        voltage => parse_rvalue,
+        process => parse_str,
+        temperature => parse_rvalue
+    }
+    let timescale = p
+        .next_rule_opt(Rule::timescale)
+        .map(|p| {
+            let mut p = PairsHelper(p.into_inner());
+            parse_real(p.next())
+                * match p.next().as_str() {
+                    "us" => 1e-6,
+                    "ns" => 1e-9,
+                    "ps" => 1e-12,
+                    _ => unreachable!(),
+                }
+        })
+        .unwrap_or(1e-9); // default 1ns
+    SDFHeader {
+        sdf_version: sdf_version.unwrap(),
+        design_name,
+        date,
+        vendor,
+        program,
+        program_version,
+        hier_divider: hier_divider.unwrap(),
+        voltage,
+        process,
+        temperature,
+        timescale,
+    }
+}
+
+fn parse_delay_interconnect(p: Pair) -> SDFDelayInterconnect {
+    assert_eq!(p.as_rule(), Rule::delay_interconnect);
+    let mut p = PairsHelper(p.into_inner());
+    SDFDelayInterconnect {
+        a: parse_path(p.next()),
+        b: parse_path(p.next()),
+        delay: parse_rvalue_list(p.next()),
+    }
+}
+
+fn parse_delay_port(p: Pair) -> SDFDelayPort {
+    assert_eq!(p.as_rule(), Rule::delay_port);
+    let mut p = PairsHelper(p.into_inner());
+    SDFDelayPort {
+        port: parse_path(p.next()),
+        delay: parse_rvalue_list(p.next()),
+    }
+}
+
+fn parse_delay_netdelay(p: Pair) -> SDFDelayNet {
+    assert_eq!(p.as_rule(), Rule::delay_netdelay);
+    let mut p = PairsHelper(p.into_inner());
+    SDFDelayNet {
+        net: parse_path(p.next()),
+        delay: parse_rvalue_list(p.next()),
+    }
+}
+
+fn parse_delay_device(p: Pair) -> SDFDelay {
+    assert_eq!(p.as_rule(), Rule::delay_device);
+    let mut p = PairsHelper(p.into_inner());
+    SDFDelay::Device {
+        port: p.next_rule_opt(Rule::port).map(parse_port),
+        delay: parse_rvalue_list(p.next()),
+    }
+}
+
+fn parse_delay_iopath(p: Pair) -> SDFDelayIOPath {
+    assert_eq!(p.as_rule(), Rule::delay_iopath);
+    let mut p = PairsHelper(p.into_inner());
+    SDFDelayIOPath {
+        a: parse_port_spec(p.next()),
+        b: parse_port(p.next()),
+        retain: p
+            .next_rule_opt(Rule::delay_iopath_retain)
+            .map(|p| parse_rvalue_list(unwrap_one(p))),
+        delay: parse_rvalue_list(p.next()),
+    }
+}
+
+fn parse_cond_expr(p: Pair) -> SDFCondExpr {
+    use SDFCondOp::*;
+    match p.as_rule() {
+        Rule::cond_expr => parse_cond_expr(unwrap_one(p)),
+        Rule::cond_lor | Rule::cond_land | Rule::cond_bor | Rule::cond_bxor | Rule::cond_band | Rule::cond_eq => {
+            let fixed_op = match p.as_rule() {
+                Rule::cond_lor => Some(LogicalOr),
+                Rule::cond_land => Some(LogicalAnd),
+                Rule::cond_bor => Some(BitOr),
+                Rule::cond_band => Some(BitAnd),
+                _ => None,
+            };
+            let mut p = p.into_inner();
+            let mut expr = parse_cond_expr(p.next().unwrap());
+            while let Some(next) = p.next() {
+                let (op, rhs) = match fixed_op {
+                    Some(op) => (op, next),
+                    None => (
+                        match next.as_str() {
+                            "^" => Xor,
+                            "^~" | "~^" => Xnor,
+                            "==" | "===" => Eq,
+                            "!=" | "!==" => Ne,
+                            _ => unreachable!(),
+                        },
+                        p.next().unwrap(),
+                    ),
+                };
+                expr = SDFCondExpr::Binary(op, Box::new(expr), Box::new(parse_cond_expr(rhs)));
+            }
+            expr
+        }
+        Rule::cond_unary => {
+            let mut p = p.into_inner();
+            let first = p.next().unwrap();
+            match first.as_rule() {
+                Rule::cond_not => SDFCondExpr::Not(Box::new(parse_cond_expr(p.next().unwrap()))),
+                _ => parse_cond_expr(first),
+            }
+        }
+        Rule::cond_primary => parse_cond_expr(unwrap_one(p)),
+        Rule::cond_const => SDFCondExpr::Const(p.as_str().ends_with('1')),
+        Rule::port => SDFCondExpr::Port(parse_port(p)),
+        _ => unreachable!(),
+    }
+}
+
+#[inline]
+fn parse_delay(p: Pair) -> SDFDelay {
+    let p = unwrap_one(p);
+    match p.as_rule() {
+        Rule::delay_interconnect => SDFDelay::Interconnect(parse_delay_interconnect(p)),
+        Rule::delay_port => SDFDelay::Port(parse_delay_port(p)),
+        Rule::delay_netdelay => SDFDelay::NetDelay(parse_delay_netdelay(p)),
+        Rule::delay_device => parse_delay_device(p),
+        Rule::delay_iopath => SDFDelay::IOPath(SDFIOPathCond::None, parse_delay_iopath(p)),
+        Rule::delay_cond_iopath => {
+            let mut p = PairsHelper(p.into_inner());
+            let name = p.next_rule_opt(Rule::str).map(parse_str);
+            SDFDelay::IOPath(
+                SDFIOPathCond::Cond(name, parse_cond_expr(p.next())),
+                parse_delay_iopath(p.next()),
+            )
+        }
+        Rule::delay_condelse_iopath => SDFDelay::IOPath(SDFIOPathCond::CondElse, parse_delay_iopath(unwrap_one(p))),
+        _ => unreachable!(),
+    }
+}
+
+fn parse_timing_check(p: Pair) -> SDFTimingCheck {
+    assert_eq!(p.as_rule(), Rule::timingcheck_def);
+    let mut p = p.into_inner();
+    use SDFTimingCheckKind::*;
+    let kind = match p.next().unwrap().as_str() {
+        "SETUP" => Setup,
+        "HOLD" => Hold,
+        "SETUPHOLD" => Setuphold,
+        "RECOVERY" => Recovery,
+        "REMOVAL" => Removal,
+        "WIDTH" => Width,
+        "RECREM" => Recrem,
+        "SKEW" => Skew,
+        "PERIOD" => Period,
+        "NOCHANGE" => Nochange,
+        _ => unreachable!(),
+    };
+    let mut ports = Vec::new();
+    let mut values = Vec::new();
+    for p in p {
+        match p.as_rule() {
+            Rule::port_tchk => {
+                let mut p = PairsHelper(p.into_inner());
+                ports.push(SDFTimingCheckPort {
+                    cond: p.next_rule_opt(Rule::cond_expr).map(parse_cond_expr),
+                    spec: parse_port_spec(p.next()),
+                });
+            }
+            Rule::tchk_value => values.push(parse_rvalue(unwrap_one(p))),
+            _ => unreachable!(),
+        }
+    }
+    SDFTimingCheck { kind, ports, values }
+}
+
+fn parse_timing_env(p: Pair) -> SDFTimingEnv {
+    assert_eq!(p.as_rule(), Rule::timingenv_def);
+    let mut p = p.into_inner();
+    use SDFTimingEnvKind::*;
+    let kind = match p.next().unwrap().as_str() {
+        "PATHCONSTRAINT" => PathConstraint,
+        "ARRIVAL" => Arrival,
+        "DEPARTURE" => Departure,
+        "SLACK" => Slack,
+        _ => unreachable!(),
+    };
+    let mut name = None;
+    let mut ports = Vec::new();
+    let mut values = Vec::new();
+    let mut period = None;
+    for p in p {
+        match p.as_rule() {
+            Rule::te_name => name = Some(parse_str(unwrap_one(p))),
+            Rule::te_port => {
+                let mut p = PairsHelper(p.into_inner());
+                ports.push(SDFTimingEnvPort {
+                    edge_type: parse_port_edge_type(p.next_rule_opt(Rule::port_edge_type)),
+                    path: parse_path(p.next()),
+                });
+            }
+            Rule::tchk_value => values.push(parse_rvalue(unwrap_one(p))),
+            Rule::int => period = Some(p.as_str().parse().unwrap()),
+            _ => unreachable!(),
+        }
+    }
+    SDFTimingEnv {
+        kind,
+        name,
+        ports,
+        values,
+        period,
+    }
+}
+
+/// Parse a cell, keeping only the `corner` of its triples if given
+fn parse_cell(p: Pair, lines: &mut LineCounter, corner: Option<SDFCorner>) -> SDFCell {
+    let span = Some(lines.span(p.as_span().start()));
+    let mut p = PairsHelper(p.into_inner());
+    let celltype = parse_str(p.next());
+    let instance = p.next_rule_opt(Rule::path).map(parse_path);
+    let mut delays = Vec::new();
+    let mut delay_spans = Vec::new();
+    let mut increments = Vec::new();
+    let mut increment_spans = Vec::new();
+    let mut timing_checks = Vec::new();
+    let mut timing_env = Vec::new();
+    for timing_spec in p.iter_while(Rule::timing_spec).map(unwrap_one) {
+        match timing_spec.as_rule() {
+            Rule::delay => {
+                for section in timing_spec.into_inner() {
+                    let (target, spans) = match section.as_rule() {
+                        Rule::delay_absolute => (&mut delays, &mut delay_spans),
+                        Rule::delay_increment => (&mut increments, &mut increment_spans),
+                        _ => unreachable!(),
+                    };
+                    for delay in section.into_inner() {
+                        spans.push(lines.span(delay.as_span().start()));
+                        target.push(parse_delay(delay));
+                    }
+                }
+            }
+            Rule::timingcheck => {
+                timing_checks.extend(timing_spec.into_inner().map(parse_timing_check));
+            }
+            Rule::timingenv => {
+                timing_env.extend(timing_spec.into_inner().map(parse_timing_env));
+            }
+            _ => unreachable!(),
+        }
+    }
+    let mut cell = SDFCell {
+        celltype,
+        instance,
+        span,
+        delays,
+        delay_spans,
+        increments,
+        increment_spans,
+        timing_checks,
+        timing_env,
+    };
+    if let Some(corner) = corner {
+        cell.select_corner(corner);
+    }
+    cell
+}
+
+/// Parse the `(CELL` blocks of `cells`, a part of a SDF file starting at `span`
+/// whose header gave the `divider`.
+pub(crate) fn parse_cells(
+    cells: &str,
+    divider: char,
+    span: SDFSpan,
+    corner: Option<SDFCorner>,
+) -> Result<Vec<SDFCell>, String> {
+    let prefix = format!("(DIVIDER {})", divider);
+    let start = prefix.len();
+    let s = prefix + cells;
+    let p = match SDFParser::parse(Rule::cells_chunk, &s) {
+        Ok(mut r) => r.next().unwrap(),
+        Err(e) => return Err(format!("{}", e)),
+    };
+    let mut p = PairsHelper(p.into_inner());
+    p.next_rule(Rule::hier_divider);
+    let mut lines = LineCounter::starting_at(&s, start, span);
+    Ok(p.iter_while(Rule::cell).map(|cell| parse_cell(cell, &mut lines, corner)).collect())
+}
+
+pub(crate) fn parse_sdf(s: &str, corner: Option<SDFCorner>) -> Result<SDF, String> {
+    let p = match SDFParser::parse(Rule::main, s) {
+        Ok(mut r) => r.next().unwrap(),
+        Err(e) => return Err(format!("{}", e)),
+    };
+    let mut p = PairsHelper(p.into_inner());
+    let mut header = parse_header(p.next());
+    if let Some(corner) = corner {
+        for value in [&mut header.voltage, &mut header.temperature].into_iter().flatten() {
+            *value = value.select(corner);
+        }
+    }
+    let mut lines = LineCounter::new(s);
+    Ok(SDF {
+        header,
+        cells: p.iter_while(Rule::cell).map(|cell| parse_cell(cell, &mut lines, corner)).collect(),
+    })
+}
@@ -0,0 +1,320 @@
+use sdfparse::*;
+
+const SDF_SPM: &str = include_str!("spm_simplify.sdf");
+
+#[test]
+fn test_spm_simplify() {
+    let sdf = match SDF::parse_str(SDF_SPM) {
+        Ok(sdf) => sdf,
+        Err(e) => panic!("Parsing error: {e}"),
+    };
+    assert_eq!(format!("{:?}", sdf.header), "SDFHeader { sdf_version: \"3.0\", design_name: Some(\"spm\"), date: Some(\"Wed Oct 13 19:52:19 2021\"), vendor: Some(\"Parallax\"), program: Some(\"STA\"), program_version: Some(\"2.3.0\"), hier_divider: '/', voltage: Some(Multi(Some(1.95), None, Some(1.95))), process: Some(\"1.000::1.000\"), temperature: Some(Multi(Some(-40.0), None, Some(-40.0))), timescale: 1e-9 }");
+
+    assert_eq!(sdf.cells.len(), 4);
+    assert_eq!(sdf.cells[0].celltype, "spm");
+    assert!(sdf.cells[0].instance.is_none());
+    assert_eq!(sdf.cells[0].delays.len(), 4);
+    assert_eq!(format!("{:?}", sdf.cells[0].delays[3]), "Interconnect(SDFDelayInterconnect { a: SDFPath { path: [\"input1\", \"X\"], bus: None }, b: SDFPath { path: [\"_182_\", \"A\"], bus: SingleBit(1) }, delay: [Multi(Some(0.00019543248), None, Some(0.00019546332)), Multi(Some(0.00018196118), None, Some(0.00018203554))] })");
+
+    let SDFDelay::IOPath(SDFIOPathCond::Cond(None, cond), iopath) = &sdf.cells[3].delays[2] else {
+        panic!("Expected a conditional IOPATH, got {:?}", sdf.cells[3].delays[2]);
+    };
+    assert_eq!(cond.to_string(), "((((!SD & !SLP) & BIST) & !CEBM) & WEBM)");
+    assert_eq!(format!("{:?}", iopath), "SDFDelayIOPath { a: SDFPortSpec { edge_type: Posedge, port: SDFPort { port_name: \"CLK\", bus: None } }, b: SDFPort { port_name: \"Q\", bus: SingleBit(9) }, retain: Some([Multi(Some(0.789), None, Some(0.789)), Multi(Some(0.789), None, Some(0.789))]), delay: [Multi(Some(0.984), None, Some(0.984)), Multi(Some(0.984), None, Some(0.984))] }");
+
+    let SDFDelay::IOPath(SDFIOPathCond::Cond(None, cond), _) = &sdf.cells[3].delays[3] else {
+        panic!("Expected a conditional IOPATH, got {:?}", sdf.cells[3].delays[3]);
+    };
+    assert_eq!(cond.to_string(), "((CP == 1'b1) && (D == 1'b1))");
+}
+
+#[test]
+fn test_cond_expressions() {
+    let sdf = r#"(DELAYFILE
+ (SDFVERSION "3.0")
+ (DIVIDER /)
+ (CELL
+  (CELLTYPE "mux")
+  (INSTANCE m)
+  (DELAY
+   (ABSOLUTE
+    (COND "sel" (A && !B || C ^ D) != 1'b0 (IOPATH S Y (0.1) (0.2)))
+    (COND ~(A | B) & C[2] ^~ 0 (IOPATH A Y (0.1) (0.2)))
+    (CONDELSE (IOPATH B Y (0.3) (0.4)))
+   )
+  )
+ )
+)"#;
+    let sdf = match SDF::parse_str(sdf) {
+        Ok(sdf) => sdf,
+        Err(e) => panic!("Parsing error: {e}"),
+    };
+    let conds: Vec<_> = sdf.cells[0]
+        .delays
+        .iter()
+        .map(|d| match d {
+            SDFDelay::IOPath(SDFIOPathCond::Cond(name, cond), _) => format!("{:?} {}", name, cond),
+            SDFDelay::IOPath(SDFIOPathCond::CondElse, _) => "else".to_string(),
+            d => panic!("Unexpected delay {:?}", d),
+        })
+        .collect();
+    assert_eq!(
+        conds,
+        [
+            "Some(\"sel\") (((A && !B) || (C ^ D)) != 1'b0)",
+            "None ((!(A | B) & C[2]) ^~ 1'b0)",
+            "else"
+        ]
+    );
+
+    let SDFDelay::IOPath(SDFIOPathCond::Cond(_, cond), _) = &sdf.cells[0].delays[0] else {
+        unreachable!()
+    };
+    assert!(cond.eval(&|p| p.port_name == "C"));
+    assert!(!cond.eval(&|p| p.port_name == "C" || p.port_name == "D"));
+}
+
+#[test]
+fn test_timing_checks() {
+    let sdf = r#"(DELAYFILE
+ (SDFVERSION "3.0")
+ (DIVIDER /)
+ (CELL
+  (CELLTYPE "sky130_fd_sc_hd__dfrtp_1")
+  (INSTANCE _1_)
+  (DELAY
+   (ABSOLUTE
+    (IOPATH (posedge CLK) Q (0.3:0.3:0.3) (0.3:0.3:0.3))
+   )
+  )
+  (TIMINGCHECK
+    (REMOVAL (posedge RESET_B) (posedge CLK) (0.353:0.353:0.353))
+    (HOLD (negedge D) (posedge CLK) (-0.045:-0.046:-0.046))
+    (SETUP (COND SCE (posedge D)) (posedge CLK) (0.066))
+    (WIDTH (negedge CLK) (0.2))
+    (SETUPHOLD (posedge D) (posedge CLK) (0.066) (-0.040))
+    (RECREM (posedge RESET_B) (posedge CLK) (-0.201) (0.353))
+    (NOCHANGE (negedge D) (posedge CLK) (0.1) (0.1))
+  )
+ )
+)"#;
+    let sdf = match SDF::parse_str(sdf) {
+        Ok(sdf) => sdf,
+        Err(e) => panic!("Parsing error: {e}"),
+    };
+    let checks = &sdf.cells[0].timing_checks;
+    assert_eq!(sdf.cells[0].delays.len(), 1);
+    assert_eq!(
+        checks.iter().map(|c| (c.kind, c.ports.len(), c.values.len())).collect::<Vec<_>>(),
+        [
+            (SDFTimingCheckKind::Removal, 2, 1),
+            (SDFTimingCheckKind::Hold, 2, 1),
+            (SDFTimingCheckKind::Setup, 2, 1),
+            (SDFTimingCheckKind::Width, 1, 1),
+            (SDFTimingCheckKind::Setuphold, 2, 2),
+            (SDFTimingCheckKind::Recrem, 2, 2),
+            (SDFTimingCheckKind::Nochange, 2, 2)
+        ]
+    );
+    assert_eq!(format!("{:?}", checks[1]), "SDFTimingCheck { kind: Hold, ports: [SDFTimingCheckPort { cond: None, spec: SDFPortSpec { edge_type: Negedge, port: SDFPort { port_name: \"D\", bus: None } } }, SDFTimingCheckPort { cond: None, spec: SDFPortSpec { edge_type: Posedge, port: SDFPort { port_name: \"CLK\", bus: None } } }], values: [Multi(Some(-0.045), Some(-0.046), Some(-0.046))] }");
+    assert_eq!(checks[2].ports[0].cond.as_ref().map(|c| c.to_string()).as_deref(), Some("SCE"));
+    assert_eq!(format!("{:?}", checks[4].values), "[Single(0.066), Single(-0.04)]");
+}
+
+#[test]
+fn test_parse_reader_gzip() {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(SDF_SPM.as_bytes()).unwrap();
+    let gzipped = encoder.finish().unwrap();
+
+    let sdf = SDF::parse_reader(&gzipped[..]).unwrap();
+    assert_eq!(sdf.cells.len(), 4);
+
+    let sdf = SDF::parse_reader(SDF_SPM.as_bytes()).unwrap();
+    assert_eq!(sdf.cells.len(), 4);
+}
+
+#[test]
+fn test_port_netdelay() {
+    let sdf = r#"(DELAYFILE
+ (SDFVERSION "3.0")
+ (DIVIDER /)
+ (CELL
+  (CELLTYPE "top")
+  (INSTANCE)
+  (DELAY
+   (ABSOLUTE
+    (PORT u1/A (0.1) (0.2))
+    (NETDELAY net1 (0.05:0.06:0.07))
+   )
+  )
+ )
+)"#;
+    let sdf = match SDF::parse_str(sdf) {
+        Ok(sdf) => sdf,
+        Err(e) => panic!("Parsing error: {e}"),
+    };
+    let delays = &sdf.cells[0].delays;
+    assert_eq!(format!("{:?}", delays[0]), "Port(SDFDelayPort { port: SDFPath { path: [\"u1\", \"A\"], bus: None }, delay: [Single(0.1), Single(0.2)] })");
+    assert_eq!(format!("{:?}", delays[1]), "NetDelay(SDFDelayNet { net: SDFPath { path: [\"net1\"], bus: None }, delay: [Multi(Some(0.05), Some(0.06), Some(0.07))] })");
+}
+
+#[test]
+fn test_device() {
+    let sdf = r#"(DELAYFILE
+ (SDFVERSION "3.0")
+ (DIVIDER /)
+ (CELL
+  (CELLTYPE "sky130_fd_sc_hd__inv_1")
+  (INSTANCE u1)
+  (DELAY
+   (ABSOLUTE
+    (DEVICE Y (0.1) (0.2))
+    (DEVICE (0.3) (0.4))
+   )
+  )
+ )
+)"#;
+    let sdf = match SDF::parse_str(sdf) {
+        Ok(sdf) => sdf,
+        Err(e) => panic!("Parsing error: {e}"),
+    };
+    let delays = &sdf.cells[0].delays;
+    assert!(matches!(&delays[0], SDFDelay::Device { port: Some(port), delay } if port.port_name == "Y" && delay.len() == 2));
+    assert!(matches!(&delays[1], SDFDelay::Device { port: None, delay } if delay.len() == 2));
+}
+
+#[test]
+fn test_increment() {
+    let sdf = r#"(DELAYFILE
+ (SDFVERSION "3.0")
+ (DIVIDER /)
+ (CELL
+  (CELLTYPE "sky130_fd_sc_hd__inv_1")
+  (INSTANCE u1)
+  (DELAY
+   (ABSOLUTE
+    (IOPATH A Y (1.0) (2.0))
+   )
+   (INCREMENT
+    (IOPATH A Y (0.1) (0.2))
+   )
+  )
+ )
+)"#;
+    let sdf = match SDF::parse_str(sdf) {
+        Ok(sdf) => sdf,
+        Err(e) => panic!("Parsing error: {e}"),
+    };
+    assert_eq!(sdf.cells[0].delays.len(), 1);
+    assert_eq!(sdf.cells[0].increments.len(), 1);
+    let SDFDelay::IOPath(_, iopath) = &sdf.cells[0].increments[0] else {
+        panic!("Expected an IOPATH, got {:?}", sdf.cells[0].increments[0]);
+    };
+    assert_eq!(format!("{:?}", iopath.delay), "[Single(0.1), Single(0.2)]");
+    assert_eq!(sdf.cells[0].span, Some(SDFSpan { line: 4, column: 2 }));
+    assert_eq!(sdf.cells[0].increment_spans, [SDFSpan { line: 12, column: 5 }]);
+}
+
+#[test]
+fn test_timing_env() {
+    let sdf = r#"(DELAYFILE
+ (SDFVERSION "3.0")
+ (DIVIDER /)
+ (CELL
+  (CELLTYPE "top")
+  (INSTANCE)
+  (TIMINGENV
+   (PATHCONSTRAINT (NAME "p1") u1/A u2/Y (2.0) (2.5))
+   (ARRIVAL (posedge clk) in1 (0.1) (0.2) (0.3) (0.4))
+   (DEPARTURE out1 (1.0) (1.0) (1.5) (1.5))
+   (SLACK u3/D (0.5) (0.6) (0.7) (0.8) 10)
+  )
+ )
+)"#;
+    let sdf = match SDF::parse_str(sdf) {
+        Ok(sdf) => sdf,
+        Err(e) => panic!("Parsing error: {e}"),
+    };
+    let env = &sdf.cells[0].timing_env;
+    assert_eq!(env.len(), 4);
+    assert_eq!(env[0].kind, SDFTimingEnvKind::PathConstraint);
+    assert_eq!(env[0].name.as_deref(), Some("p1"));
+    assert_eq!(env[0].ports.len(), 2);
+    assert_eq!(env[0].values.len(), 2);
+    assert_eq!(env[1].kind, SDFTimingEnvKind::Arrival);
+    assert!(matches!(env[1].ports[0].edge_type, SDFPortEdge::Posedge));
+    assert_eq!(format!("{:?}", env[1].ports[1].path), "SDFPath { path: [\"in1\"], bus: None }");
+    assert_eq!(env[2].values.len(), 4);
+    assert_eq!(env[3].kind, SDFTimingEnvKind::Slack);
+    assert_eq!(env[3].period, Some(10));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_parse_str_parallel() {
+    let sequential = SDF::parse_str(SDF_SPM).unwrap();
+    let parallel = SDF::parse_str_parallel(SDF_SPM).unwrap();
+    assert_eq!(format!("{:?}", sequential), format!("{:?}", parallel));
+}
+
+#[test]
+fn test_parse_str_with_progress() {
+    let sequential = SDF::parse_str(SDF_SPM).unwrap();
+    let reports = std::sync::Mutex::new(Vec::new());
+    let sdf = SDF::parse_str_with_progress(SDF_SPM, &SDFParseOptions::default(), |parsed, total| {
+        reports.lock().unwrap().push((parsed, total))
+    })
+    .unwrap();
+    assert_eq!(format!("{:?}", sequential), format!("{:?}", sdf));
+
+    let reports = reports.into_inner().unwrap();
+    assert!(reports.len() > 2);
+    assert!(reports.windows(2).all(|w| w[0].0 <= w[1].0));
+    assert_eq!(reports.last(), Some(&(SDF_SPM.len(), SDF_SPM.len())));
+}
+
+#[test]
+fn test_parse_corner() {
+    let options = SDFParseOptions {
+        corner: Some(SDFCorner::Max),
+        ..Default::default()
+    };
+    let sdf = SDF::parse_str_with(SDF_SPM, &options).unwrap();
+    let SDFDelay::Interconnect(inter) = &sdf.cells[0].delays[3] else {
+        panic!("Expected an INTERCONNECT, got {:?}", sdf.cells[0].delays[3]);
+    };
+    assert_eq!(format!("{:?}", inter.delay), "[Single(0.00019546332), Single(0.00018203554)]");
+}
+
+#[test]
+fn test_merge() {
+    let block = r#"(DELAYFILE
+ (SDFVERSION "3.0")
+ (DIVIDER /)
+ (TIMESCALE 1ns)
+ (CELL
+  (CELLTYPE "block")
+  (INSTANCE)
+  (DELAY (ABSOLUTE (INTERCONNECT u1/Y u2/A (0.1))))
+ )
+ (CELL
+  (CELLTYPE "sky130_fd_sc_hd__inv_1")
+  (INSTANCE u1)
+  (DELAY (ABSOLUTE (IOPATH A Y (0.2) (0.3))))
+ )
+)"#;
+    let parse = |s: &str| SDF::parse_str(s).unwrap();
+    let sdf = SDF::merge([("", parse(block)), ("u_core/u_alu", parse(block))]).unwrap();
+    let instances: Vec<String> = sdf
+        .cells
+        .iter()
+        .map(|cell| cell.instance.as_ref().map_or(String::new(), |path| path.path.join("/")))
+        .collect();
+    assert_eq!(instances, ["", "u1", "u_core/u_alu", "u_core/u_alu/u1"]);
+
+    let other = parse(&block.replace("(TIMESCALE 1ns)", "(TIMESCALE 1ps)"));
+    assert!(SDF::merge([("", parse(block)), ("u_core", other)]).is_err());
+}
@@ -1,107 +1,1664 @@
+use crate::error::{Result, StarsError};
 use crate::graph::{SDFEdge, SDFGraph};
-use crate::types::{PinTrans, PinTransMap};
-use rustc_hash::FxHashSet;
+use crate::progress::{Phase, PhaseProgress, Progress};
+use crate::types::{InstanceId, PinId, PinMap, PinSet, PinTrans, PinTransMap, Transition};
+use ordered_float::OrderedFloat;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// Timing constraints of the analysis, usually read from an SDC file (see [`crate::sdc::Sdc::analysis_options`]).
+/// Times are in ns.
+#[derive(Debug, Clone, Default)]
+pub struct AnalysisOptions {
+    /// clock period of the endpoints (register inputs and output ports), None leaves them unconstrained
+    pub period: Option<f32>,
+    /// period of the endpoints captured by another clock than the main one
+    pub endpoint_periods: PinMap<f32>,
+    /// arrival time of the input ports, relative to the clock edge
+    pub input_delays: PinMap<f32>,
+    /// time spent outside of the design after the output ports, it is removed from their required time
+    pub output_delays: PinMap<f32>,
+    /// false paths and multicycle paths
+    pub exceptions: Vec<PathException>,
+    /// propagate the clock through the clock tree (`set_propagated_clock` in SDC), so that the registers are
+    /// launched and captured when the clock reaches them. Otherwise the clock is ideal and reaches them at 0.
+    pub propagated_clock: bool,
+    /// pins the clocks are defined on (`create_clock` in SDC), where the propagated clock starts. Empty starts from
+    /// the clock port of the graph
+    pub clock_sources: PinSet,
+    /// period given to the paths of a group instead of `period`, e.g a budget for the in2out paths. Only used by
+    /// [`SDFGraphAnalyzed::path_groups`]
+    pub group_periods: FxHashMap<PathGroup, f32>,
+    /// reports the levels propagated, see [`AnalysisOptions::progress`]
+    pub progress: Option<Progress>,
+}
+
+/// Timing exception between startpoints and endpoints, like `set_false_path`/`set_multicycle_path` in SDC.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathException {
+    /// glob patterns on the startpoint pins (e.g `sync_*/Q`), empty matches every startpoint
+    pub from: Vec<String>,
+    /// glob patterns on the endpoint pins, empty matches every endpoint
+    pub to: Vec<String>,
+    /// None for a false path, else the number of clock periods the paths are given
+    pub multicycle: Option<u32>,
+}
+
+impl AnalysisOptions {
+    /// Ignore the paths going from the startpoints matching the first pattern to the endpoints matching the second one.
+    /// For example `("sync_*/Q", "*")` removes every path launched by the synchronizers.
+    pub fn false_paths(mut self, paths: Vec<(String, String)>) -> Self {
+        self.exceptions
+            .extend(paths.into_iter().map(|(from, to)| PathException {
+                from: vec![from],
+                to: vec![to],
+                multicycle: None,
+            }));
+        self
+    }
+
+    /// Give `multiplier` clock periods to the paths going from the startpoints matching the first pattern
+    /// to the endpoints matching the second one.
+    pub fn multicycle_paths(mut self, paths: Vec<(String, String, u32)>) -> Self {
+        self.exceptions
+            .extend(paths.into_iter().map(|(from, to, multiplier)| PathException {
+                from: vec![from],
+                to: vec![to],
+                multicycle: Some(multiplier),
+            }));
+        self
+    }
+
+    /// Report the levels of the graph propagated to `progress` as [`Phase::Analysis`], three times each for the
+    /// latest, earliest and required times
+    pub fn progress(mut self, progress: Progress) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+}
 
 pub struct SDFGraphAnalyzed {
     pub max_delay: PinTransMap<f32>,
+    /// earliest arrival time of each node, for the hold checks
+    pub min_delay: PinTransMap<f32>,
     pub max_delay_backwards: PinTransMap<f32>,
+    /// time at which the data has to arrive at the constrained endpoints
+    pub required: PinTransMap<f32>,
+    /// arrival time of the slowest endpoint
+    pub worst_arrival: f32,
+    /// main clock period of the analysis (see [`AnalysisOptions::period`])
+    pub period: Option<f32>,
+    /// latest arrival time of the clock edge at the clock pins of the registers, 0 (ideal clock) when missing.
+    /// It launches the paths of the setup checks and captures the hold checks.
+    pub clock_arrivals: PinTransMap<f32>,
+    /// earliest arrival time of the clock edge at the clock pins of the registers, which captures the setup checks
+    /// and launches the paths of the hold checks
+    pub early_clock_arrivals: PinTransMap<f32>,
+    /// arrival times computed from a part of the startpoints only, for the endpoints with exceptions
+    pub partial_delays: Vec<PinTransMap<f32>>,
+    /// which of `partial_delays` holds the arrival of an endpoint with exceptions, None when all its paths are false
+    pub endpoint_delays: PinTransMap<Option<usize>>,
+    /// constraints of the analysis, kept for [`SDFGraphAnalyzed::update`]
+    pub options: AnalysisOptions,
+    /// level of each node in [`SDFGraph::levels`] and number of levels
+    node_level: PinTransMap<usize>,
+    n_levels: usize,
 }
 
 impl SDFGraphAnalyzed {
     /// Extract the path of transitions that led to the max delay of the given output node.\
     /// The path is a list of (node_transition, delay) tuples, going from the start to the output.\
+    /// The output can be any node of the graph, the path is empty when no signal reaches it.\
     ///
     /// **Note**: The output is _not_ included in the path (since it doesn't do any transitions itself).
     pub fn extract_path(&self, graph: &SDFGraph, output: &PinTrans) -> Vec<(PinTrans, f32)> {
-        let mut path = Vec::new();
+        let max_delay = match self.endpoint_delays.get(output) {
+            Some(Some(partial)) => &self.partial_delays[*partial],
+            _ => &self.max_delay,
+        };
+        trace_path(graph, max_delay, output)
+    }
 
-        let mut node = output.clone();
+    /// Same as [`Self::extract_path`] for the path of the earliest arrival, checked by the hold checks.
+    pub fn extract_min_path(&self, graph: &SDFGraph, output: &PinTrans) -> Vec<(PinTrans, f32)> {
+        trace_path(graph, &self.min_delay, output)
+    }
 
-        loop {
-            let edges = &graph.reverse_graph[&node];
-            let delay = self.max_delay[&node];
-            let mut prev_node_delay = None;
-            for edge in edges {
-                let Some(prev_delay) = self.max_delay.get(&edge.dst).copied() else {
-                    continue;
-                };
+    /// Worst path to `endpoint` split in stages, None if no (true) path reaches it.
+    pub fn path_report(&self, graph: &SDFGraph, endpoint: &PinTrans) -> Option<PathReport> {
+        let total_delay = self.arrival(endpoint)?;
+        let mut path = self.extract_path(graph, endpoint);
+        path.push((*endpoint, total_delay));
+        Some(PathReport {
+            endpoint: *endpoint,
+            total_delay,
+            stages: path_stages(graph, &path),
+        })
+    }
+
+    /// Worst path into any node of the graph split in stages, not only into the endpoints, e.g to look at the
+    /// inputs of a suspicious gate. The last stage ends at `node`.
+    pub fn node_path_report(&self, graph: &SDFGraph, node: &PinTrans) -> Result<PathReport> {
+        let name = || format!("{}{}", graph.symbols.pin_name(node.0), node.1);
+        if !graph.graph.contains_key(node) {
+            return Err(StarsError::UnknownPin(name()));
+        }
+        self.path_report(graph, node)
+            .ok_or_else(|| StarsError::EmptyPath(name()))
+    }
+
+    /// Same as [`Self::node_path_report`] for a pin given by name (e.g `_151_/Y`), through its slowest transition.
+    pub fn pin_path_report(&self, graph: &SDFGraph, pin: &str) -> Result<PathReport> {
+        let id = graph
+            .symbols
+            .pin_id(pin)
+            .ok_or_else(|| StarsError::UnknownPin(pin.to_string()))?;
+        let node = [(id, Transition::Rise), (id, Transition::Fall)]
+            .into_iter()
+            .max_by_key(|node| self.arrival(node).map(OrderedFloat))
+            .unwrap();
+        self.node_path_report(graph, &node)
+    }
+
+    /// Shortest path to `endpoint` split in stages, the one the hold checks are about. None if no signal reaches it.
+    pub fn hold_path_report(&self, graph: &SDFGraph, endpoint: &PinTrans) -> Option<PathReport> {
+        let total_delay = *self.min_delay.get(endpoint)?;
+        let mut path = self.extract_min_path(graph, endpoint);
+        path.push((*endpoint, total_delay));
+        Some(PathReport {
+            endpoint: *endpoint,
+            total_delay,
+            stages: path_stages(graph, &path),
+        })
+    }
+
+    /// Arrival time of an endpoint, taking the exceptions into account. None if no (true) path reaches it.
+    pub fn arrival(&self, endpoint: &PinTrans) -> Option<f32> {
+        match self.endpoint_delays.get(endpoint) {
+            None => self.max_delay.get(endpoint).copied(),
+            Some(None) => None,
+            Some(Some(partial)) => self.partial_delays[*partial].get(endpoint).copied(),
+        }
+    }
+
+    /// Arrival times of the paths launched by `startpoint` alone, for the nodes of its fanout cone.
+    /// The startpoint departs at its arrival time in the analysis (input delay, clock arrival and CLK -> Q delay),
+    /// or at 0 when it isn't a startpoint of the graph, e.g an internal pin. Exceptions are not applied.
+    pub fn arrivals_from(&self, graph: &SDFGraph, startpoint: &PinTrans) -> PinTransMap<f32> {
+        let launch = self.options.input_delays.get(&startpoint.0).copied().unwrap_or(0.0)
+            + graph.launch_clock(startpoint).map_or(0.0, |clock| {
+                self.clock_arrival(&clock) + graph.clock_to_q(startpoint, &clock)
+            });
+        let init = FxHashMap::from_iter([(*startpoint, launch)]);
+
+        // only the fanout cone of the startpoint gets a delay, as in an update after an edit of the startpoint
+        let mut delays = PinTransMap::new();
+        update_pass(
+            &mut delays,
+            &init,
+            std::iter::once(*startpoint),
+            (&self.node_level, self.n_levels),
+            (|node| graph.fanin(node), |node| graph.fanout(node).into()),
+            false,
+            f32::max,
+        );
+        delays
+    }
+
+    /// Arrival time at `node` of the worst path launched by `startpoint`, None if no path goes from one to the
+    /// other. Unlike [`Self::arrival`], which is the worst over all the startpoints.
+    pub fn arrival_from(&self, graph: &SDFGraph, startpoint: &PinTrans, node: &PinTrans) -> Option<f32> {
+        self.arrivals_from(graph, startpoint).get(node).copied()
+    }
+
+    /// Required time of an endpoint. Unconstrained endpoints use the worst arrival time of the design,
+    /// so that the critical path has a slack of 0.
+    pub fn required_time(&self, endpoint: &PinTrans) -> f32 {
+        self.required.get(endpoint).copied().unwrap_or(self.worst_arrival)
+    }
+
+    /// Required minus arrival time of an endpoint, None if no signal reaches it.
+    pub fn slack(&self, endpoint: &PinTrans) -> Option<f32> {
+        Some(self.required_time(endpoint) - self.arrival(endpoint)?)
+    }
+
+    /// Latest arrival time of the clock edge at a clock pin
+    pub fn clock_arrival(&self, clock: &PinTrans) -> f32 {
+        self.clock_arrivals.get(clock).copied().unwrap_or(0.0)
+    }
 
-                if prev_delay + edge.delay == delay {
-                    prev_node_delay = Some((edge.dst.clone(), prev_delay));
+    /// Earliest arrival time of the clock edge at a clock pin
+    pub fn early_clock_arrival(&self, clock: &PinTrans) -> f32 {
+        self.early_clock_arrivals.get(clock).copied().unwrap_or(0.0)
+    }
+
+    /// Hold slack of a register input: earliest arrival of the data minus the time it has to stay stable after
+    /// the clock edge. None when the pin has no hold check or no signal reaches it.
+    ///
+    /// Exceptions are not applied to the hold checks.
+    pub fn hold_slack(&self, graph: &SDFGraph, endpoint: &PinTrans) -> Option<f32> {
+        let check = graph.timing_checks.get(endpoint)?;
+        Some(self.min_delay.get(endpoint)? - (self.clock_arrival(&check.clock) + check.hold?))
+    }
+
+    /// Endpoints with a hold check, sorted from the smallest to the largest hold slack.
+    pub fn endpoints_by_hold_slack(&self, graph: &SDFGraph) -> Vec<(PinTrans, f32)> {
+        let mut endpoints: Vec<_> = graph
+            .outputs
+            .iter()
+            .filter_map(|output| Some((*output, self.hold_slack(graph, output)?)))
+            .collect();
+        endpoints.sort_by_key(|(_, slack)| OrderedFloat(*slack));
+        endpoints
+    }
+
+    /// Slack of the worst path going through a node, against the required time of the endpoint it reaches.
+    pub fn slack_through(&self, graph: &SDFGraph, node: &PinTrans) -> Option<f32> {
+        self.max_delay_backwards.get(node)?;
+        let mut arrival = *self.max_delay.get(node)?;
+        let mut endpoint = *node;
+        for edge in self.trace_forward(graph, *node) {
+            arrival += edge.delay;
+            endpoint = edge.dst;
+        }
+        Some(self.required_time(&endpoint) - arrival)
+    }
+
+    /// Edges explaining the backward delays from `node` up to the endpoint of the worst path leaving it
+    fn trace_forward<'a>(&'a self, graph: &'a SDFGraph, node: PinTrans) -> impl Iterator<Item = &'a SDFEdge> + 'a {
+        let next = move |node: PinTrans| {
+            graph
+                .graph
+                .get(&node)
+                .into_iter()
+                .flatten()
+                .filter_map(|edge| Some((edge, edge.delay + self.max_delay_backwards.get(&edge.dst)?)))
+                .max_by_key(|(edge, delay)| (OrderedFloat(*delay), Reverse(edge.dst)))
+                .map(|(edge, _)| edge)
+        };
+        std::iter::successors(next(node), move |edge| next(edge.dst))
+    }
+
+    /// Worst path going through `pin` (either transition), from its startpoint to its endpoint, stitched from the
+    /// worst path reaching the pin (`max_delay`) and the worst one leaving it (`max_delay_backwards`).
+    /// None if no path goes through the pin. Exceptions are not applied, as in [`Self::slack_through`].
+    pub fn extract_worst_path_through(&self, graph: &SDFGraph, pin: PinId) -> Option<PathReport> {
+        let through = [(pin, Transition::Rise), (pin, Transition::Fall)]
+            .into_iter()
+            .filter_map(|node| Some((node, self.max_delay.get(&node)? + self.max_delay_backwards.get(&node)?)))
+            .max_by_key(|(_, delay)| OrderedFloat(*delay))?
+            .0;
+
+        let mut path = trace_path(graph, &self.max_delay, &through);
+        let mut arrival = self.max_delay[&through];
+        path.push((through, arrival));
+
+        let mut node = through;
+        for edge in self.trace_forward(graph, through) {
+            arrival += edge.delay;
+            path.push((edge.dst, arrival));
+            node = edge.dst;
+        }
+
+        Some(PathReport {
+            endpoint: node,
+            total_delay: arrival,
+            stages: path_stages(graph, &path),
+        })
+    }
+
+    /// Endpoints of the graph that are reached by a signal, sorted from the smallest to the largest slack.
+    pub fn endpoints_by_slack(&self, graph: &SDFGraph) -> Vec<(PinTrans, f32)> {
+        let mut endpoints: Vec<_> = graph
+            .outputs
+            .iter()
+            .filter_map(|output| Some((*output, self.slack(output)?)))
+            .collect();
+        endpoints.sort_by_key(|(_, slack)| OrderedFloat(*slack));
+        endpoints
+    }
+
+    /// Endpoints of the graph that are reached by a signal, sorted from the slowest to the fastest.
+    pub fn endpoints_by_delay(&self, graph: &SDFGraph) -> Vec<(PinTrans, f32)> {
+        let mut endpoints: Vec<_> = graph
+            .outputs
+            .iter()
+            .filter_map(|output| Some((*output, self.arrival(output)?)))
+            .collect();
+        endpoints.sort_by_key(|(_, delay)| Reverse(OrderedFloat(*delay)));
+        endpoints
+    }
+
+    /// Endpoint pins reached by a signal with the slack of their worst transition, sorted from the smallest to the
+    /// largest slack.
+    pub fn endpoint_pins_by_slack(&self, graph: &SDFGraph) -> Vec<(PinTrans, f32)> {
+        let mut seen = PinSet::new();
+        let mut endpoints = self.endpoints_by_slack(graph);
+        endpoints.retain(|(endpoint, _)| seen.insert(endpoint.0));
+        endpoints
+    }
+
+    /// The `n` paths with the smallest slack, worst first. Unlike [`SDFGraphAnalyzed::endpoints_by_slack`],
+    /// an endpoint can appear in several paths, one per way of reaching it.
+    ///
+    /// Paths are enumerated backwards from the endpoints, best first: the arrival times of the analysis
+    /// are the exact delay of the worst way to complete a partial path, so they come out in order.
+    pub fn worst_paths(&self, graph: &SDFGraph, n: usize) -> Vec<TimingPath> {
+        let inputs: FxHashSet<PinTrans> = graph.inputs.iter().copied().collect();
+
+        // partial paths: node, next node towards the endpoint, delay from the node to the endpoint, endpoint
+        let mut partials: Vec<(PinTrans, Option<usize>, f32, PinTrans)> = Vec::new();
+        let mut queue = BinaryHeap::new();
+
+        for endpoint in &graph.outputs {
+            let Some(&arrival) = self
+                .endpoint_delay_map(endpoint)
+                .and_then(|delays| delays.get(endpoint))
+            else {
+                continue;
+            };
+            let slack = self.required_time(endpoint) - arrival;
+            queue.push((Reverse(OrderedFloat(slack)), partials.len()));
+            partials.push((*endpoint, None, 0.0, *endpoint));
+        }
+
+        let mut paths = Vec::new();
+        while paths.len() < n {
+            let Some((Reverse(OrderedFloat(slack)), i)) = queue.pop() else {
+                break;
+            };
+            let (node, _, suffix, endpoint) = partials[i];
+            let delays = self.endpoint_delay_map(&endpoint).unwrap();
+
+            if inputs.contains(&node) {
+                let arrival = delays[&node] + suffix;
+                let mut path = Vec::new();
+                let mut next = Some(i);
+                while let Some(j) = next {
+                    let (node, after, suffix, _) = partials[j];
+                    path.push((node, arrival - suffix));
+                    next = after;
                 }
+                path.pop();
+                paths.push(TimingPath {
+                    endpoint,
+                    arrival,
+                    slack,
+                    path,
+                });
+                continue;
             }
-            let Some((prev_node, delay)) = prev_node_delay else {
-                break;
+
+            // conditional arcs can give several edges between the same nodes, only the slowest makes a path
+            let mut previous: FxHashMap<PinTrans, f32> = FxHashMap::default();
+            for edge in graph.fanin(&node).iter() {
+                if delays.contains_key(&edge.dst) {
+                    let delay = previous.entry(edge.dst).or_insert(edge.delay);
+                    *delay = delay.max(edge.delay);
+                }
+            }
+            for (prev, delay) in previous {
+                let prev_suffix = suffix + delay;
+                let slack = self.required_time(&endpoint) - (delays[&prev] + prev_suffix);
+                queue.push((Reverse(OrderedFloat(slack)), partials.len()));
+                partials.push((prev, Some(i), prev_suffix, endpoint));
+            }
+        }
+        paths
+    }
+
+    /// Arrival times used for the paths to an endpoint, None when all of them are false paths.
+    fn endpoint_delay_map(&self, endpoint: &PinTrans) -> Option<&PinTransMap<f32>> {
+        match self.endpoint_delays.get(endpoint) {
+            None => Some(&self.max_delay),
+            Some(None) => None,
+            Some(Some(partial)) => Some(&self.partial_delays[*partial]),
+        }
+    }
+
+    /// Histogram of the slacks of the endpoints in `bins` bins of the same width, from the worst to the best slack.
+    /// Each endpoint pin is counted once, with its worst transition.
+    pub fn slack_histogram(&self, graph: &SDFGraph, bins: usize) -> SlackHistogram {
+        let slacks: Vec<f32> = self
+            .endpoint_pins_by_slack(graph)
+            .into_iter()
+            .map(|(_, slack)| slack)
+            .collect();
+
+        let bins = bins.max(1);
+        let mut histogram = SlackHistogram {
+            min: slacks.first().copied().unwrap_or(0.0),
+            max: slacks.last().copied().unwrap_or(0.0),
+            counts: vec![0; bins],
+        };
+        let width = histogram.bin_width();
+        for slack in slacks {
+            let bin = if width > 0.0 {
+                ((slack - histogram.min) / width) as usize
+            } else {
+                0
             };
-            path.push((prev_node.clone(), delay));
-            node = prev_node;
+            histogram.counts[bin.min(bins - 1)] += 1;
         }
+        histogram
+    }
+
+    /// Criticality of every instance on a path, from the most to the least critical.
+    ///
+    /// With `top_paths`, also counts how many of the paths of the `top_paths` worst endpoints go through each instance.
+    pub fn criticality(&self, graph: &SDFGraph, top_paths: Option<usize>) -> Vec<InstanceCriticality> {
+        let through = |node: &PinTrans| Some(self.max_delay.get(node)? + self.max_delay_backwards.get(node)?);
+        let critical = graph.outputs.iter().filter_map(&through).fold(0.0, f32::max);
+
+        let mut path_counts = FxHashMap::default();
+        for (endpoint, _) in self.endpoints_by_slack(graph).iter().take(top_paths.unwrap_or(0)) {
+            let instances: FxHashSet<InstanceId> = self
+                .extract_path(graph, endpoint)
+                .iter()
+                .map(|(node, _)| node)
+                .chain([endpoint])
+                .filter_map(|node| graph.symbols.pin_instance(node.0))
+                .collect();
+            for instance in instances {
+                *path_counts.entry(instance).or_insert(0) += 1;
+            }
+        }
+
+        let mut instances: Vec<_> = graph
+            .instance_celltype
+            .keys()
+            .filter_map(|instance| {
+                let pins = graph.instance_ins.get(&instance).into_iter().flatten();
+                let pins = pins.chain(graph.instance_outs.get(&instance).into_iter().flatten());
+                let through_delay = pins
+                    .flat_map(|&pin| [(pin, Transition::Rise), (pin, Transition::Fall)])
+                    .filter_map(|node| through(&node))
+                    .reduce(f32::max)?;
+                Some(InstanceCriticality {
+                    instance,
+                    through_delay,
+                    criticality: if critical > 0.0 {
+                        (through_delay / critical).min(1.0)
+                    } else {
+                        1.0
+                    },
+                    top_paths: path_counts.get(&instance).copied().unwrap_or(0),
+                })
+            })
+            .collect();
+        instances.sort_by_key(|c| (Reverse(OrderedFloat(c.criticality)), Reverse(c.top_paths)));
+        instances
+    }
+}
+
+/// A path from a startpoint to an endpoint (see [`SDFGraphAnalyzed::worst_paths`])
+#[derive(Debug, Clone)]
+pub struct TimingPath {
+    pub endpoint: PinTrans,
+    pub arrival: f32,
+    pub slack: f32,
+    /// nodes of the path with their arrival time, in the format of [`SDFGraphAnalyzed::extract_path`]
+    pub path: Vec<(PinTrans, f32)>,
+}
 
-        path.reverse();
+/// Worst path to an endpoint, as shown by the reports (see [`SDFGraphAnalyzed::path_report`])
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathReport {
+    pub endpoint: PinTrans,
+    /// arrival time at the endpoint
+    pub total_delay: f32,
+    /// from the startpoint to the endpoint, the first stage has no input and the last one no output
+    pub stages: Vec<PathStage>,
+}
+
+impl PathReport {
+    /// Pins of the path in order with their arrival time, the endpoint included
+    pub fn pins(&self) -> impl Iterator<Item = (PinTrans, f32)> + '_ {
+        self.stages.iter().flat_map(|stage| {
+            let input = stage.input.map(|pin| (pin, stage.arrival - stage.cell_delay));
+            input.into_iter().chain(stage.output.map(|pin| (pin, stage.arrival)))
+        })
+    }
+
+    pub fn pin_set(&self) -> PinSet {
+        self.pins().map(|(pin, _)| pin.0).collect()
+    }
 
-        path
+    /// Nets crossed by the path, from the output of a stage to the input of the next one
+    pub fn wires(&self) -> impl Iterator<Item = (PinTrans, PinTrans)> + '_ {
+        self.stages
+            .windows(2)
+            .filter_map(|stages| Some((stages[0].last_pin()?, stages[1].first_pin()?)))
     }
+
+    pub fn startpoint(&self) -> PinTrans {
+        self.stages
+            .first()
+            .and_then(PathStage::first_pin)
+            .unwrap_or(self.endpoint)
+    }
+
+    pub fn cell_delay(&self) -> f32 {
+        self.stages.iter().map(|stage| stage.cell_delay).sum()
+    }
+
+    pub fn net_delay(&self) -> f32 {
+        self.stages.iter().map(|stage| stage.net_delay).sum()
+    }
+}
+
+/// One cell of a path: the net reaching its input then the arc through it (see [`PathReport`]).
+/// Times are in ns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathStage {
+    /// None for the ports
+    pub instance: Option<InstanceId>,
+    pub celltype: Option<String>,
+    /// None for the startpoint, where the path begins
+    pub input: Option<PinTrans>,
+    /// None for the endpoint, where the path ends
+    pub output: Option<PinTrans>,
+    /// delay of the wires from the output of the previous stage to `input`
+    pub net_delay: f32,
+    /// delay of the arc from `input` to `output`
+    pub cell_delay: f32,
+    /// arrival time at the last pin of the stage
+    pub arrival: f32,
+}
+
+impl PathStage {
+    /// Input of the stage, or its output for the startpoint
+    pub fn first_pin(&self) -> Option<PinTrans> {
+        self.input.or(self.output)
+    }
+
+    /// Output of the stage, or its input for the endpoint
+    pub fn last_pin(&self) -> Option<PinTrans> {
+        self.output.or(self.input)
+    }
+}
+
+/// Relative difference under which an arrival time is explained by an arc, the sums of the propagation
+/// may have been rounded differently (e.g after [`SDFGraphAnalyzed::update`])
+const TRACE_TOLERANCE: f32 = 1e-5;
+
+/// Walk back from `output` through the arcs whose delay explains the arrival times of `delays`,
+/// see [`SDFGraphAnalyzed::extract_path`].
+///
+/// The predecessor is the one whose arrival plus the arc delay is the closest to the arrival of the node, within
+/// [`TRACE_TOLERANCE`]. Ties (e.g two inputs of a gate with the same arrival) go to the smallest node, so the
+/// same path is extracted on every run.
+fn trace_path(graph: &SDFGraph, delays: &PinTransMap<f32>, output: &PinTrans) -> Vec<(PinTrans, f32)> {
+    let mut path = Vec::new();
+    let mut node = *output;
+
+    while let Some(&delay) = delays.get(&node) {
+        let tolerance = TRACE_TOLERANCE * delay.abs().max(1.0);
+        let prev_node_delay = graph
+            .fanin(&node)
+            .iter()
+            .filter_map(|edge| {
+                let prev_delay = *delays.get(&edge.dst)?;
+                let error = (prev_delay + edge.delay - delay).abs();
+                (error <= tolerance).then_some((OrderedFloat(error), edge.dst, prev_delay))
+            })
+            .min_by_key(|&(error, prev_node, _)| (error, prev_node));
+        let Some((_, prev_node, delay)) = prev_node_delay else {
+            break;
+        };
+        path.push((prev_node, delay));
+        node = prev_node;
+    }
+
+    path.reverse();
+
+    path
+}
+
+/// Split a path in the format of [`SDFGraphAnalyzed::extract_path`], plus the endpoint, in stages. An arc between two pins of the same
+/// instance is a cell arc, any other is a net arc.
+fn path_stages(graph: &SDFGraph, path: &[(PinTrans, f32)]) -> Vec<PathStage> {
+    let symbols = &graph.symbols;
+    let stage = |instance: Option<InstanceId>, arrival| PathStage {
+        instance,
+        celltype: instance.and_then(|id| graph.instance_celltype.get(&id)).cloned(),
+        input: None,
+        output: None,
+        net_delay: 0.0,
+        cell_delay: 0.0,
+        arrival,
+    };
+
+    let mut stages = Vec::new();
+    let Some(&(start, start_arrival)) = path.first() else {
+        return stages;
+    };
+    stages.push(PathStage {
+        output: Some(start),
+        ..stage(symbols.pin_instance(start.0), start_arrival)
+    });
+
+    // stage whose input was reached but not its output yet
+    let mut current: Option<PathStage> = None;
+    for window in path.windows(2) {
+        let ((from, from_arrival), (to, arrival)) = (window[0], window[1]);
+        let instance = symbols.pin_instance(to.0);
+        let delay = arrival - from_arrival;
+        if instance.is_some() && instance == symbols.pin_instance(from.0) {
+            let mut cell = current.take().unwrap_or_else(|| PathStage {
+                input: Some(from),
+                ..stage(instance, from_arrival)
+            });
+            cell.cell_delay += delay;
+            cell.output = Some(to);
+            cell.arrival = arrival;
+            stages.push(cell);
+        } else {
+            let net_delay = current.map_or(0.0, |current| current.net_delay) + delay;
+            current = Some(PathStage {
+                input: Some(to),
+                net_delay,
+                ..stage(instance, arrival)
+            });
+        }
+    }
+    stages.extend(current);
+    stages
+}
+
+/// Kinds of startpoint and endpoint of a path: register or port
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PathGroup {
+    RegToReg,
+    InToReg,
+    RegToOut,
+    InToOut,
+}
+
+impl PathGroup {
+    pub const ALL: [PathGroup; 4] = [
+        PathGroup::RegToReg,
+        PathGroup::InToReg,
+        PathGroup::RegToOut,
+        PathGroup::InToOut,
+    ];
+
+    /// The paths start at the output of a register, else at an input port
+    pub fn from_register(self) -> bool {
+        matches!(self, PathGroup::RegToReg | PathGroup::RegToOut)
+    }
+
+    /// The paths end at the input of a register, else at an output port
+    pub fn to_register(self) -> bool {
+        matches!(self, PathGroup::RegToReg | PathGroup::InToReg)
+    }
+}
+
+impl Display for PathGroup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PathGroup::RegToReg => write!(f, "reg2reg"),
+            PathGroup::InToReg => write!(f, "in2reg"),
+            PathGroup::RegToOut => write!(f, "reg2out"),
+            PathGroup::InToOut => write!(f, "in2out"),
+        }
+    }
+}
+
+impl FromStr for PathGroup {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "reg2reg" => Ok(PathGroup::RegToReg),
+            "in2reg" => Ok(PathGroup::InToReg),
+            "reg2out" => Ok(PathGroup::RegToOut),
+            "in2out" => Ok(PathGroup::InToOut),
+            _ => Err(format!(
+                "Unknown path group {} (expected reg2reg, in2reg, reg2out or in2out)",
+                s
+            )),
+        }
+    }
+}
+
+/// Endpoints of a path group and its worst path, see [`SDFGraphAnalyzed::path_groups`]
+#[derive(Debug, Clone)]
+pub struct PathGroupSummary {
+    pub group: PathGroup,
+    /// endpoint pins reached by a path of the group
+    pub endpoints: usize,
+    /// endpoint pins with a negative slack on either transition
+    pub violations: usize,
+    /// slack and worst path, None when the group has no path
+    pub worst: Option<(f32, PathReport)>,
+}
+
+/// Distribution of the slacks of the endpoints (see [`SDFGraphAnalyzed::slack_histogram`]). Times are in ns.
+#[derive(Debug, Clone)]
+pub struct SlackHistogram {
+    /// worst slack, lower bound of the first bin
+    pub min: f32,
+    /// best slack, upper bound of the last bin
+    pub max: f32,
+    /// number of endpoints in each bin, from the worst to the best slack
+    pub counts: Vec<usize>,
+}
+
+impl SlackHistogram {
+    pub fn bin_width(&self) -> f32 {
+        (self.max - self.min) / self.counts.len() as f32
+    }
+
+    /// Slack range of the `i`th bin
+    pub fn bin_range(&self, i: usize) -> (f32, f32) {
+        let width = self.bin_width();
+        (self.min + i as f32 * width, self.min + (i + 1) as f32 * width)
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+}
+
+/// How close the worst path through an instance is to the critical path (see [`SDFGraphAnalyzed::criticality`])
+#[derive(Debug, Clone, Copy)]
+pub struct InstanceCriticality {
+    pub instance: InstanceId,
+    /// delay of the slowest path going through the instance, from its startpoint to its endpoint
+    pub through_delay: f32,
+    /// `through_delay` relative to the delay of the critical path, 1 for the instances on it
+    pub criticality: f32,
+    /// number of the top paths going through the instance, 0 when they are not counted
+    pub top_paths: usize,
 }
 
 impl SDFGraphAnalyzed {
     /// Propagate delays through the graph and return the maximum delay for each node.
     /// The maximum delay is the maximum time it takes for a signal to propagate from the inputs to the node.
+    ///
+    /// Nodes are computed level by level (see [`SDFGraph::levels`]), in parallel with the `rayon` feature.
     pub fn analyze(graph: &SDFGraph) -> Self {
-        fn dfs_visit<'b>(
-            max_delay: &mut PinTransMap<f32>,
-            node: &PinTrans,
-            bw_edges_fn: impl for<'c> Fn(&'c PinTrans) -> &'b [SDFEdge] + Copy,
-        ) {
-            let bw_edges = bw_edges_fn(node);
-            if bw_edges.is_empty() {
-                max_delay.insert(node.clone(), f32::NAN);
-                return;
-            }
-
-            let mut max = f32::NAN;
-            for edge in bw_edges {
-                let t_setup = match max_delay.get(&edge.dst) {
-                    Some(delay) => *delay,
-                    None => {
-                        dfs_visit(max_delay, &edge.dst, bw_edges_fn);
-                        max_delay[&edge.dst]
-                    }
-                };
-                max = f32::max(max, t_setup + edge.delay);
+        Self::analyze_with(graph, &AnalysisOptions::default())
+    }
+
+    /// Same as [`SDFGraphAnalyzed::analyze`], with the io delays and clock periods of `options`.
+    pub fn analyze_with(graph: &SDFGraph, options: &AnalysisOptions) -> Self {
+        let levels = graph.levels();
+
+        let [clock_arrivals, early_clock_arrivals] = [f32::max as fn(f32, f32) -> f32, f32::min].map(|worst| {
+            if options.propagated_clock {
+                graph.clock_arrivals(&options.clock_sources, worst)
+            } else {
+                PinTransMap::new()
+            }
+        });
+
+        let progress = PhaseProgress::start(options.progress.as_ref(), Phase::Analysis, 3 * levels.len(), 1);
+        let done = Cell::new(0);
+        let propagated = |_: &&Vec<PinTrans>| {
+            done.set(done.get() + 1);
+            progress.update(done.get());
+        };
+
+        let inputs = startpoint_delays(graph, options, &clock_arrivals);
+        let max_delay = delay_pass(
+            &inputs,
+            levels.iter().inspect(propagated),
+            |node| graph.fanin(node),
+            f32::max,
+        );
+        let min_delay = delay_pass(
+            &startpoint_delays(graph, options, &early_clock_arrivals),
+            levels.iter().inspect(propagated),
+            |node| graph.fanin(node),
+            f32::min,
+        );
+        let max_delay_backwards = delay_pass(
+            &io_delays(&graph.outputs, &options.output_delays),
+            levels.iter().rev().inspect(propagated),
+            |node| graph.fanout(node).into(),
+            f32::max,
+        );
+
+        let mut node_level = PinTransMap::new();
+        for (i, level) in levels.iter().enumerate() {
+            for node in level {
+                node_level.insert(*node, i);
+            }
+        }
+
+        let mut analysis = Self {
+            max_delay,
+            min_delay,
+            max_delay_backwards,
+            required: PinTransMap::new(),
+            worst_arrival: 0.0,
+            period: options.period,
+            clock_arrivals,
+            early_clock_arrivals,
+            partial_delays: Vec::new(),
+            endpoint_delays: PinTransMap::new(),
+            options: options.clone(),
+            node_level,
+            n_levels: levels.len(),
+        };
+        analysis.update_endpoints(graph, &levels);
+        progress.finish();
+
+        analysis
+    }
+
+    /// Update the analysis after the delays of some arcs changed (e.g with [`SDFGraph::swap_cell`]).
+    /// Only the fanout cones (for `max_delay`) and fanin cones (for `max_delay_backwards`) of the nodes in
+    /// [`SDFGraph::dirty`] are propagated again, then `dirty` is cleared.
+    /// The exceptions, if any, are applied again from scratch, and so is the clock tree with a propagated clock.
+    ///
+    /// The arcs of the graph must be the same as when it was analyzed, only their delays can change.
+    pub fn update(&mut self, graph: &mut SDFGraph) {
+        let mut dirty = std::mem::take(&mut graph.dirty);
+        if dirty.is_empty() {
+            return;
+        }
+
+        if self.options.propagated_clock {
+            let clock_arrivals = graph.clock_arrivals(&self.options.clock_sources, f32::max);
+            let early_clock_arrivals = graph.clock_arrivals(&self.options.clock_sources, f32::min);
+            // the outputs of the registers whose clock moved are launched at another time
+            let moved: PinSet = (clock_arrivals.iter())
+                .filter(|(clock, arrival)| self.clock_arrivals.get(clock) != Some(arrival))
+                .chain(
+                    (early_clock_arrivals.iter())
+                        .filter(|(clock, arrival)| self.early_clock_arrivals.get(clock) != Some(arrival)),
+                )
+                .map(|(clock, _)| clock.0)
+                .collect();
+            for pin in moved {
+                let instance = graph.symbols.pin_instance(pin);
+                let outs = instance.and_then(|instance| graph.instance_outs.get(&instance));
+                for &out in outs.into_iter().flatten() {
+                    dirty.insert((out, Transition::Rise));
+                    dirty.insert((out, Transition::Fall));
+                }
             }
+            self.clock_arrivals = clock_arrivals;
+            self.early_clock_arrivals = early_clock_arrivals;
+        }
 
-            max_delay.insert(node.clone(), max);
+        for (delays, clock_arrivals, worst) in [
+            (
+                &mut self.max_delay,
+                &self.clock_arrivals,
+                f32::max as fn(f32, f32) -> f32,
+            ),
+            (&mut self.min_delay, &self.early_clock_arrivals, f32::min),
+        ] {
+            update_pass(
+                delays,
+                &startpoint_delays(graph, &self.options, clock_arrivals),
+                dirty.iter().copied(),
+                (&self.node_level, self.n_levels),
+                (|node| graph.fanin(node), |node| graph.fanout(node).into()),
+                false,
+                worst,
+            );
         }
 
-        fn delay_pass<'b>(
-            init: impl IntoIterator<Item = &'b PinTrans>,
-            all_keys: impl IntoIterator<Item = &'b PinTrans>,
-            bw_edges: impl for<'c> Fn(&'c PinTrans) -> &'b [SDFEdge] + Copy,
-        ) -> PinTransMap<f32> {
-            let init: FxHashSet<_> = init.into_iter().collect();
-            let mut max_delay = PinTransMap::new();
+        // backward delays change from the sources of the arcs going to the dirty nodes
+        let sources = dirty
+            .iter()
+            .flat_map(|node| graph.fanin(node).into_owned())
+            .map(|edge| edge.dst);
+        update_pass(
+            &mut self.max_delay_backwards,
+            &io_delays(&graph.outputs, &self.options.output_delays),
+            sources,
+            (&self.node_level, self.n_levels),
+            (|node| graph.fanout(node).into(), |node| graph.fanin(node)),
+            true,
+            f32::max,
+        );
 
-            for &v in init.iter() {
-                max_delay.insert(v.clone(), 0.0);
+        let levels = if self.options.exceptions.is_empty() {
+            Vec::new()
+        } else {
+            graph.levels()
+        };
+        self.update_endpoints(graph, &levels);
+    }
+
+    /// Required times of the endpoints, exceptions and worst arrival. `levels` is only used with exceptions.
+    fn update_endpoints(&mut self, graph: &SDFGraph, levels: &[Vec<PinTrans>]) {
+        let options = std::mem::take(&mut self.options);
+
+        self.required = PinTransMap::new();
+        for output in &graph.outputs {
+            let period = options.endpoint_periods.get(&output.0).copied().or(options.period);
+            if let Some(period) = period {
+                self.required
+                    .insert(*output, self.required_for_period(graph, &options, output, period));
             }
+        }
+
+        self.partial_delays.clear();
+        self.endpoint_delays = PinTransMap::new();
+        if !options.exceptions.is_empty() {
+            let inputs = startpoint_delays(graph, &options, &self.clock_arrivals);
+            self.apply_exceptions(graph, &options, levels, &inputs);
+        }
 
-            for v in all_keys {
-                if !max_delay.contains_key(v) {
-                    dfs_visit(&mut max_delay, v, bw_edges);
+        self.worst_arrival = graph
+            .outputs
+            .iter()
+            .filter_map(|output| self.arrival(output))
+            .fold(0.0, f32::max);
+
+        self.options = options;
+    }
+
+    /// Time at which the data has to arrive at an endpoint captured with a clock of `period`
+    fn required_for_period(
+        &self,
+        graph: &SDFGraph,
+        options: &AnalysisOptions,
+        endpoint: &PinTrans,
+        period: f32,
+    ) -> f32 {
+        let output_delay = options.output_delays.get(&endpoint.0).copied().unwrap_or(0.0);
+        // register inputs have to arrive a setup time before the clock edge capturing them,
+        // which comes later than the period by the delay of the fastest path of the clock tree
+        let setup = graph
+            .timing_checks
+            .get(endpoint)
+            .and_then(|check| check.setup)
+            .unwrap_or(0.0);
+        let capture = graph
+            .capture_clock(endpoint)
+            .map_or(0.0, |clock| self.early_clock_arrival(&clock));
+        period + capture - output_delay - setup
+    }
+
+    /// Worst path of each path group, like the path groups of the STA tools. The paths of a group are the ones
+    /// between its kinds of startpoints and endpoints only, so the worst in2out path is found even when the same
+    /// output is also reached later from a register.
+    ///
+    /// The groups use [`AnalysisOptions::group_periods`] when they have one, and the exceptions are ignored.
+    /// Paths from or to other pins than registers and ports (e.g antenna diodes) are in no group.
+    pub fn path_groups(&self, graph: &SDFGraph) -> Vec<PathGroupSummary> {
+        // Some(true) for the registers, Some(false) for the ports
+        let is_register = |pin: &PinTrans, clock: Option<PinTrans>| match clock {
+            Some(_) => Some(true),
+            None => graph.symbols.pin_instance(pin.0).is_none().then_some(false),
+        };
+        let levels = graph.levels();
+        let inputs = startpoint_delays(graph, &self.options, &self.clock_arrivals);
+        // arrival times of the paths launched by the registers, and by the input ports
+        let [from_registers, from_inputs] = [true, false].map(|registers| {
+            let init = inputs
+                .iter()
+                .filter(|(input, _)| is_register(input, graph.launch_clock(input)) == Some(registers))
+                .map(|(input, delay)| (*input, *delay))
+                .collect();
+            delay_pass(&init, levels.iter(), |node| graph.fanin(node), f32::max)
+        });
+
+        PathGroup::ALL
+            .into_iter()
+            .map(|group| {
+                let delays = if group.from_register() {
+                    &from_registers
+                } else {
+                    &from_inputs
+                };
+                let period = self.options.group_periods.get(&group).copied();
+                let slacks: Vec<(PinTrans, f32, f32)> = graph
+                    .outputs
+                    .iter()
+                    .filter(|endpoint| {
+                        is_register(endpoint, graph.capture_clock(endpoint)) == Some(group.to_register())
+                    })
+                    .filter_map(|endpoint| {
+                        let arrival = *delays.get(endpoint)?;
+                        let required = match period {
+                            Some(period) => self.required_for_period(graph, &self.options, endpoint, period),
+                            None => self.required_time(endpoint),
+                        };
+                        Some((*endpoint, arrival, required - arrival))
+                    })
+                    .collect();
+                let worst = slacks
+                    .iter()
+                    .min_by_key(|(endpoint, _, slack)| (OrderedFloat(*slack), *endpoint))
+                    .map(|&(endpoint, arrival, slack)| {
+                        let mut path = trace_path(graph, delays, &endpoint);
+                        path.push((endpoint, arrival));
+                        let report = PathReport {
+                            endpoint,
+                            total_delay: arrival,
+                            stages: path_stages(graph, &path),
+                        };
+                        (slack, report)
+                    });
+                // pins rather than transitions, as in the stats of the graph
+                let endpoints: PinSet = slacks.iter().map(|(endpoint, _, _)| endpoint.0).collect();
+                let violations: PinSet = (slacks.iter())
+                    .filter(|(_, _, slack)| *slack < 0.0)
+                    .map(|(endpoint, _, _)| endpoint.0)
+                    .collect();
+                PathGroupSummary {
+                    group,
+                    endpoints: endpoints.len(),
+                    violations: violations.len(),
+                    worst,
                 }
+            })
+            .collect()
+    }
+
+    /// Startpoints are grouped by the exceptions they are the `from` of, and arrival times are computed for each group.
+    /// The arrival of an endpoint is then the one of the group with the worst slack, ignoring the groups
+    /// with a false path to it and giving more time to the ones with a multicycle path.
+    fn apply_exceptions(
+        &mut self,
+        graph: &SDFGraph,
+        options: &AnalysisOptions,
+        levels: &[Vec<PinTrans>],
+        inputs: &FxHashMap<PinTrans, f32>,
+    ) {
+        let exceptions = &options.exceptions;
+        let from: Vec<Option<PinSet>> = exceptions.iter().map(|e| matching_pins(graph, &e.from)).collect();
+        let to: Vec<Option<PinSet>> = exceptions.iter().map(|e| matching_pins(graph, &e.to)).collect();
+
+        let applies = |sets: &[Option<PinSet>], pin: &PinTrans| -> Vec<bool> {
+            sets.iter()
+                .map(|set| set.as_ref().is_none_or(|set| set.contains(&pin.0)))
+                .collect()
+        };
+
+        let mut groups: FxHashMap<Vec<bool>, FxHashMap<PinTrans, f32>> = FxHashMap::default();
+        for (input, delay) in inputs {
+            groups.entry(applies(&from, input)).or_default().insert(*input, *delay);
+        }
+
+        let mut group_from = Vec::new();
+        for (group, init) in groups {
+            self.partial_delays
+                .push(delay_pass(&init, levels.iter(), |node| graph.fanin(node), f32::max));
+            group_from.push(group);
+        }
+
+        for output in &graph.outputs {
+            let output_to = applies(&to, output);
+            let is_excepted = |group: &[bool]| group.iter().zip(&output_to).any(|(from, to)| *from && *to);
+            if !group_from.iter().any(|group| is_excepted(group)) {
+                continue;
             }
 
-            max_delay.retain(|_, delay| !delay.is_nan());
+            let period = options.endpoint_periods.get(&output.0).copied().or(options.period);
+            let required = self.required.get(output).copied();
+
+            // (slack, group, required)
+            let mut worst: Option<(f32, usize, Option<f32>)> = None;
+            for (i, group) in group_from.iter().enumerate() {
+                let Some(&arrival) = self.partial_delays[i].get(output) else {
+                    continue;
+                };
 
-            max_delay
+                let mut multiplier = 1;
+                let mut is_false = false;
+                for (e, exception) in exceptions.iter().enumerate() {
+                    if !(group[e] && output_to[e]) {
+                        continue;
+                    }
+                    match exception.multicycle {
+                        None => is_false = true,
+                        Some(m) => multiplier = multiplier.max(m),
+                    }
+                }
+                if is_false {
+                    continue;
+                }
+
+                let required = required.zip(period).map(|(r, p)| r + p * (multiplier - 1) as f32);
+                // unconstrained endpoints: the slowest group is the worst
+                let slack = required.map_or(-arrival, |r| r - arrival);
+                if worst.is_none_or(|(worst_slack, _, _)| slack < worst_slack) {
+                    worst = Some((slack, i, required));
+                }
+            }
+
+            match worst {
+                Some((_, group, required)) => {
+                    self.endpoint_delays.insert(*output, Some(group));
+                    if let Some(required) = required {
+                        self.required.insert(*output, required);
+                    }
+                }
+                None => {
+                    self.endpoint_delays.insert(*output, None);
+                }
+            }
         }
+    }
+}
 
-        let max_delay = delay_pass(graph.inputs.iter(), graph.graph.keys(), |n| &graph.reverse_graph[n]);
-        let max_delay_backwards = delay_pass(graph.outputs.iter(), graph.reverse_graph.keys(), |n| &graph.graph[n]);
+/// Propagate the arrival times of `init` level by level, following the edges given by `bw_edges` backwards to get
+/// the inputs of a node.
+/// `worst` picks the arrival of a node among the ones through its inputs: `f32::max` for the latest
+/// arrival (setup), `f32::min` for the earliest (hold).
+fn delay_pass<'b, 'e>(
+    init: &FxHashMap<PinTrans, f32>,
+    levels: impl Iterator<Item = &'b Vec<PinTrans>>,
+    bw_edges: impl Fn(&PinTrans) -> Cow<'e, [SDFEdge]> + Sync,
+    worst: fn(f32, f32) -> f32,
+) -> PinTransMap<f32> {
+    let mut max_delay = PinTransMap::new();
 
-        Self {
-            max_delay,
-            max_delay_backwards,
+    for level in levels {
+        let delay_of = |node: &PinTrans| node_delay(node, init, &max_delay, &bw_edges(node), worst);
+
+        #[cfg(feature = "rayon")]
+        let delays: Vec<f32> = level.par_iter().with_min_len(1024).map(delay_of).collect();
+        #[cfg(not(feature = "rayon"))]
+        let delays: Vec<f32> = level.iter().map(delay_of).collect();
+
+        for (node, delay) in level.iter().zip(delays) {
+            if !delay.is_nan() {
+                max_delay.insert(*node, delay);
+            }
         }
     }
+
+    max_delay
+}
+
+/// Delay of a node from the delays of its predecessors through its backward edges `bw_edges`, NaN if none of them
+/// has a delay.
+fn node_delay(
+    node: &PinTrans,
+    init: &FxHashMap<PinTrans, f32>,
+    max_delay: &PinTransMap<f32>,
+    bw_edges: &[SDFEdge],
+    worst: fn(f32, f32) -> f32,
+) -> f32 {
+    if let Some(&delay) = init.get(node) {
+        return delay;
+    }
+    let mut max = f32::NAN;
+    for edge in bw_edges {
+        let t_setup = max_delay.get(&edge.dst).copied().unwrap_or(f32::NAN);
+        max = worst(max, t_setup + edge.delay);
+    }
+    max
+}
+
+/// Recompute the delays of `seeds`, and of the nodes after them through the edges given by `fw_edges` when their
+/// delay changed. Delays are computed from the ones before them through the edges given by `bw_edges`.
+/// Nodes are taken level by level (`(node_level, n_levels)`), from the last one when `reverse` is set.
+fn update_pass<'e>(
+    max_delay: &mut PinTransMap<f32>,
+    init: &FxHashMap<PinTrans, f32>,
+    seeds: impl Iterator<Item = PinTrans>,
+    (node_level, n_levels): (&PinTransMap<usize>, usize),
+    (bw_edges, fw_edges): (
+        impl Fn(&PinTrans) -> Cow<'e, [SDFEdge]>,
+        impl Fn(&PinTrans) -> Cow<'e, [SDFEdge]>,
+    ),
+    reverse: bool,
+    worst: fn(f32, f32) -> f32,
+) {
+    let mut buckets = vec![Vec::new(); n_levels];
+    let mut queued = FxHashSet::default();
+    let mut push = |node: PinTrans, buckets: &mut Vec<Vec<PinTrans>>| {
+        if let Some(&level) = node_level.get(&node) {
+            if queued.insert(node) {
+                buckets[level].push(node);
+            }
+        }
+    };
+    for node in seeds {
+        push(node, &mut buckets);
+    }
+
+    for i in 0..n_levels {
+        let level = if reverse { n_levels - 1 - i } else { i };
+        for node in std::mem::take(&mut buckets[level]) {
+            let delay = node_delay(&node, init, max_delay, &bw_edges(&node), worst);
+            let changed = match max_delay.get(&node) {
+                Some(&old) => old != delay,
+                None => !delay.is_nan(),
+            };
+            if !changed {
+                continue;
+            }
+            if delay.is_nan() {
+                max_delay.remove(&node);
+            } else {
+                max_delay.insert(node, delay);
+            }
+            for edge in fw_edges(&node).iter() {
+                push(edge.dst, &mut buckets);
+            }
+        }
+    }
+}
+
+/// Arrival times of the startpoints: input delay of the ports, arrival of the clock edge launching the registers
+/// plus the delay of their CLK -> Q arc.
+fn startpoint_delays(
+    graph: &SDFGraph,
+    options: &AnalysisOptions,
+    clock_arrivals: &PinTransMap<f32>,
+) -> FxHashMap<PinTrans, f32> {
+    let mut delays = io_delays(&graph.inputs, &options.input_delays);
+    for (startpoint, delay) in &mut delays {
+        if let Some(clock) = graph.launch_clock(startpoint) {
+            *delay += clock_arrivals.get(&clock).copied().unwrap_or(0.0) + graph.clock_to_q(startpoint, &clock);
+        }
+    }
+    delays
+}
+
+fn io_delays(pins: &[PinTrans], delays: &PinMap<f32>) -> FxHashMap<PinTrans, f32> {
+    pins.iter()
+        .map(|pin| (*pin, delays.get(&pin.0).copied().unwrap_or(0.0)))
+        .collect()
+}
+
+/// Pins whose name matches one of the glob patterns, None when there are no patterns (everything matches).
+fn matching_pins(graph: &SDFGraph, patterns: &[String]) -> Option<PinSet> {
+    if patterns.is_empty() {
+        return None;
+    }
+    Some(
+        patterns
+            .iter()
+            .flat_map(|pattern| graph.symbols.pins_matching(pattern))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::SDFGraphOptions;
+    use crate::types::{Corner, Transition};
+
+    /// Two flip-flops clocked through a buffer, the first one launching the data captured by the second one
+    /// through a buffer
+    const TWO_FLOPS: &str = r#"(DELAYFILE
+ (SDFVERSION "3.0")
+ (DESIGN "two_flops")
+ (DIVIDER .)
+ (TIMESCALE 1ns)
+ (CELL
+  (CELLTYPE "two_flops")
+  (INSTANCE)
+  (DELAY
+   (ABSOLUTE
+    (INTERCONNECT clk clkbuf.A (0.050:0.050:0.050) (0.050:0.050:0.050))
+    (INTERCONNECT clkbuf.X _1_.CLK (0.020:0.020:0.020) (0.020:0.020:0.020))
+    (INTERCONNECT clkbuf.X _2_.CLK (0.040:0.040:0.040) (0.040:0.040:0.040))
+    (INTERCONNECT in _1_.D (0.010:0.010:0.010) (0.010:0.010:0.010))
+    (INTERCONNECT _1_.Q buf.A (0.010:0.010:0.010) (0.010:0.010:0.010))
+    (INTERCONNECT buf.X _2_.D (0.020:0.020:0.020) (0.020:0.020:0.020))
+    (INTERCONNECT _2_.Q out (0.010:0.010:0.010) (0.010:0.010:0.010))
+   )
+  )
+ )
+ (CELL
+  (CELLTYPE "sky130_fd_sc_hd__clkbuf_1")
+  (INSTANCE clkbuf)
+  (DELAY
+   (ABSOLUTE
+    (IOPATH A X (0.100:0.100:0.100) (0.100:0.100:0.100))
+   )
+  )
+ )
+ (CELL
+  (CELLTYPE "sky130_fd_sc_hd__dfxtp_1")
+  (INSTANCE _1_)
+  (DELAY
+   (ABSOLUTE
+    (IOPATH (posedge CLK) Q (0.300:0.300:0.300) (0.350:0.350:0.350))
+   )
+  )
+  (TIMINGCHECK
+    (SETUP (posedge D) (posedge CLK) (0.100:0.100:0.100))
+    (SETUP (negedge D) (posedge CLK) (0.100:0.100:0.100))
+  )
+ )
+ (CELL
+  (CELLTYPE "sky130_fd_sc_hd__buf_1")
+  (INSTANCE buf)
+  (DELAY
+   (ABSOLUTE
+    (IOPATH A X (0.050:0.050:0.050) (0.040:0.040:0.040))
+   )
+  )
+ )
+ (CELL
+  (CELLTYPE "sky130_fd_sc_hd__dfxtp_1")
+  (INSTANCE _2_)
+  (DELAY
+   (ABSOLUTE
+    (IOPATH (posedge CLK) Q (0.300:0.300:0.300) (0.350:0.350:0.350))
+   )
+  )
+  (TIMINGCHECK
+    (SETUP (posedge D) (posedge CLK) (0.100:0.100:0.100))
+    (SETUP (negedge D) (posedge CLK) (0.100:0.100:0.100))
+  )
+ )
+)"#;
+
+    fn graph_of(sdf: &str) -> SDFGraph {
+        SDFGraph::new(&sdfparse::SDF::parse_str(sdf).unwrap(), Corner::default()).unwrap()
+    }
+
+    fn two_flops() -> SDFGraph {
+        graph_of(TWO_FLOPS)
+    }
+
+    fn node(graph: &SDFGraph, name: &str, transition: Transition) -> PinTrans {
+        (graph.symbols.pin_id(name).unwrap(), transition)
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-5, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_register_launch() {
+        let graph = two_flops();
+        let q = node(&graph, "_1_/Q", Transition::Rise);
+        let d = node(&graph, "_2_/D", Transition::Rise);
+        assert_eq!(graph.launch_clock(&q), Some(node(&graph, "_1_/CLK", Transition::Rise)));
+        // the D pins are leaves of the graph and register inputs, they are listed once
+        let endpoints: Vec<&str> = graph
+            .outputs
+            .iter()
+            .map(|output| graph.symbols.pin_name(output.0))
+            .collect();
+        assert_eq!(
+            endpoints,
+            ["_1_/CLK", "_1_/D", "_1_/D", "_2_/CLK", "_2_/D", "_2_/D", "out", "out"]
+        );
+
+        // the data leaves the first register a CLK -> Q delay after the ideal clock edge
+        let analysis = SDFGraphAnalyzed::analyze(&graph);
+        assert_close(analysis.max_delay[&q], 0.3);
+        assert_close(analysis.arrival(&d).unwrap(), 0.3 + 0.01 + 0.05 + 0.02);
+        let fall = node(&graph, "_2_/D", Transition::Fall);
+        assert_close(analysis.arrival(&fall).unwrap(), 0.35 + 0.01 + 0.04 + 0.02);
+        assert_close(analysis.arrival_from(&graph, &q, &d).unwrap(), 0.38);
+
+        // and after the clock tree with a propagated clock, which also delays the capture
+        let options = AnalysisOptions {
+            period: Some(1.0),
+            propagated_clock: true,
+            ..Default::default()
+        };
+        let analysis = SDFGraphAnalyzed::analyze_with(&graph, &options);
+        assert_close(analysis.arrival(&d).unwrap(), 0.17 + 0.38);
+        assert_close(analysis.required_time(&d), 1.0 + 0.19 - 0.1);
+        let (startpoint, launch) = analysis.extract_path(&graph, &d)[0];
+        assert_eq!(startpoint, q);
+        assert_close(launch, 0.17 + 0.3);
+    }
+
+    #[test]
+    fn test_clock_sources() {
+        // the clock comes from a port that isn't named clk, and reaches the second register through a shorter
+        // path too
+        let graph = graph_of(&TWO_FLOPS.replace("INTERCONNECT clk clkbuf.A", "INTERCONNECT sysclk clkbuf.A").replace(
+            "    (INTERCONNECT in _1_.D",
+            "    (INTERCONNECT sysclk _2_.CLK (0.010:0.010:0.010) (0.010:0.010:0.010))\n    (INTERCONNECT in _1_.D",
+        ));
+        assert_eq!(graph.clock, None);
+        let d = node(&graph, "_2_/D", Transition::Rise);
+        let clk = node(&graph, "_2_/CLK", Transition::Rise);
+
+        let options = AnalysisOptions {
+            period: Some(1.0),
+            propagated_clock: true,
+            clock_sources: PinSet::from_iter([graph.symbols.pin_id("sysclk").unwrap()]),
+            ..Default::default()
+        };
+        let analysis = SDFGraphAnalyzed::analyze_with(&graph, &options);
+        assert_close(analysis.clock_arrival(&clk), 0.19);
+        assert_close(analysis.early_clock_arrival(&clk), 0.01);
+        // launched by the latest clock edge, captured by the earliest one
+        assert_close(analysis.arrival(&d).unwrap(), 0.17 + 0.38);
+        assert_close(analysis.required_time(&d), 1.0 + 0.01 - 0.1);
+    }
+
+    #[test]
+    fn test_slack_through() {
+        let graph = two_flops();
+        let d = node(&graph, "_2_/D", Transition::Rise);
+        let options = AnalysisOptions {
+            period: Some(1.0),
+            propagated_clock: true,
+            ..Default::default()
+        };
+        let analysis = SDFGraphAnalyzed::analyze_with(&graph, &options);
+        // the worst path through the buffer is the one to the second register, with its clock tree and setup time
+        let through = analysis.slack_through(&graph, &node(&graph, "buf/X", Transition::Rise));
+        assert_close(through.unwrap(), (1.0 + 0.19 - 0.1) - (0.17 + 0.38));
+        assert_close(through.unwrap(), analysis.slack(&d).unwrap());
+        assert_close(analysis.slack_through(&graph, &d).unwrap(), analysis.slack(&d).unwrap());
+    }
+
+    #[test]
+    fn test_false_path() {
+        // the input port also reaches the buffer, later than the first register
+        let graph = graph_of(&TWO_FLOPS.replace(
+            "    (INTERCONNECT in _1_.D",
+            "    (INTERCONNECT in buf.A (0.500:0.500:0.500) (0.500:0.500:0.500))\n    (INTERCONNECT in _1_.D",
+        ));
+        let d = node(&graph, "_2_/D", Transition::Rise);
+        let input = node(&graph, "in", Transition::Rise);
+
+        let analysis = SDFGraphAnalyzed::analyze(&graph);
+        assert_close(analysis.arrival(&d).unwrap(), 0.5 + 0.05 + 0.02);
+        assert_eq!(analysis.extract_path(&graph, &d)[0].0, input);
+
+        // the path from the port doesn't count anymore, the one from the register is the worst
+        let options = AnalysisOptions::default().false_paths(vec![("in".to_string(), "_2_/D".to_string())]);
+        let analysis = SDFGraphAnalyzed::analyze_with(&graph, &options);
+        assert_close(analysis.arrival(&d).unwrap(), 0.38);
+        assert_eq!(
+            analysis.extract_path(&graph, &d)[0].0,
+            node(&graph, "_1_/Q", Transition::Rise)
+        );
+        // other endpoints of the port are still reached
+        assert_close(
+            analysis.arrival(&node(&graph, "_1_/D", Transition::Rise)).unwrap(),
+            0.01,
+        );
+
+        // no path left at all
+        let options = AnalysisOptions::default().false_paths(vec![("*".to_string(), "_2_/D".to_string())]);
+        let analysis = SDFGraphAnalyzed::analyze_with(&graph, &options);
+        assert_eq!(analysis.arrival(&d), None);
+        assert_eq!(analysis.slack(&d), None);
+    }
+
+    #[test]
+    fn test_multicycle_path() {
+        let graph = two_flops();
+        let (d, first_d) = (
+            node(&graph, "_2_/D", Transition::Rise),
+            node(&graph, "_1_/D", Transition::Rise),
+        );
+        let options = AnalysisOptions {
+            period: Some(1.0),
+            ..Default::default()
+        };
+
+        let analysis = SDFGraphAnalyzed::analyze_with(&graph, &options);
+        assert_close(analysis.required_time(&d), 1.0 - 0.1);
+        assert_close(analysis.slack(&d).unwrap(), 0.9 - 0.38);
+
+        // a second period for the paths between the two registers, the arrival doesn't move
+        let options = options.multicycle_paths(vec![("_1_/Q".to_string(), "_2_/D".to_string(), 2)]);
+        let analysis = SDFGraphAnalyzed::analyze_with(&graph, &options);
+        assert_close(analysis.arrival(&d).unwrap(), 0.38);
+        assert_close(analysis.required_time(&d), 2.0 - 0.1);
+        assert_close(analysis.slack(&d).unwrap(), 1.9 - 0.38);
+        assert_close(analysis.required_time(&first_d), 0.9);
+    }
+
+    #[test]
+    fn test_path_groups() {
+        let graph = two_flops();
+        let analysis = SDFGraphAnalyzed::analyze(&graph);
+        let groups = analysis.path_groups(&graph);
+        // one pin each, reached on both transitions
+        let endpoints: Vec<(PathGroup, usize)> = groups.iter().map(|group| (group.group, group.endpoints)).collect();
+        assert_eq!(
+            endpoints,
+            [
+                (PathGroup::RegToReg, 1),
+                (PathGroup::InToReg, 1),
+                (PathGroup::RegToOut, 1),
+                (PathGroup::InToOut, 0)
+            ]
+        );
+    }
+
+    fn sorted(delays: &PinTransMap<f32>) -> Vec<(PinTrans, f32)> {
+        delays.iter().map(|(node, delay)| (node, *delay)).collect()
+    }
+
+    #[test]
+    fn test_update() {
+        let mut graph = two_flops();
+        let options = AnalysisOptions {
+            period: Some(1.0),
+            propagated_clock: true,
+            ..Default::default()
+        };
+        let mut analysis = SDFGraphAnalyzed::analyze_with(&graph, &options);
+        let d = node(&graph, "_2_/D", Transition::Rise);
+        let before = analysis.arrival(&d).unwrap();
+
+        // a data cell, a register (its CLK -> Q arc), the clock tree, and an interconnect edited by hand
+        for (instance, celltype) in [
+            ("buf", "sky130_fd_sc_hd__buf_2"),
+            ("_1_", "sky130_fd_sc_hd__dfxtp_2"),
+            ("clkbuf", "sky130_fd_sc_hd__clkbuf_4"),
+        ] {
+            let instance = graph.symbols.instance_id(instance).unwrap();
+            graph.swap_cell(instance, celltype, None);
+        }
+        let (q, a) = (
+            node(&graph, "_1_/Q", Transition::Fall),
+            node(&graph, "buf/A", Transition::Fall),
+        );
+        for edge in graph.graph[&q].iter_mut().filter(|edge| edge.dst == a) {
+            edge.delay = 0.1;
+        }
+        for edge in graph.reverse_edges_mut(&a).filter(|edge| edge.dst == q) {
+            edge.delay = 0.1;
+        }
+        graph.dirty.insert(a);
+
+        analysis.update(&mut graph);
+        assert!(graph.dirty.is_empty());
+        assert_ne!(analysis.arrival(&d).unwrap(), before);
+
+        let fresh = SDFGraphAnalyzed::analyze_with(&graph, &options);
+        assert_eq!(sorted(&analysis.max_delay), sorted(&fresh.max_delay));
+        assert_eq!(sorted(&analysis.min_delay), sorted(&fresh.min_delay));
+        assert_eq!(
+            sorted(&analysis.max_delay_backwards),
+            sorted(&fresh.max_delay_backwards)
+        );
+        assert_eq!(sorted(&analysis.required), sorted(&fresh.required));
+        assert_eq!(sorted(&analysis.clock_arrivals), sorted(&fresh.clock_arrivals));
+        assert_eq!(
+            sorted(&analysis.early_clock_arrivals),
+            sorted(&fresh.early_clock_arrivals)
+        );
+        assert_eq!(analysis.worst_arrival, fresh.worst_arrival);
+    }
+
+    #[test]
+    fn test_low_memory() {
+        let graph = two_flops();
+        let sdf = sdfparse::SDF::parse_str(TWO_FLOPS).unwrap();
+        let low = SDFGraph::from_sdf_with(sdf, &SDFGraphOptions::default().low_memory(true)).unwrap();
+        assert!(low.low_memory && !graph.low_memory);
+
+        // only the backward edges of the output port, which can't be derived, are left. The offsets of the
+        // nodes are the most of what remains on such a small graph
+        let (stats, low_stats) = (graph.memory_stats(), low.memory_stats());
+        assert_eq!(low_stats.graph, stats.graph);
+        assert!(low_stats.reverse_graph < stats.reverse_graph / 2);
+        assert_eq!(low.reverse_graph.n_edges(), 2);
+        let kept: Vec<PinTrans> = low.reverse_graph.keys().collect();
+        assert_eq!(
+            kept,
+            [node(&low, "out", Transition::Rise), node(&low, "out", Transition::Fall)]
+        );
+
+        for node in graph.graph.keys() {
+            assert_eq!(
+                graph.fanin(&node),
+                low.fanin(&node),
+                "{}",
+                graph.symbols.pin_name(node.0)
+            );
+        }
+
+        let options = AnalysisOptions {
+            period: Some(1.0),
+            propagated_clock: true,
+            ..Default::default()
+        };
+        let (analysis, low_analysis) = (
+            SDFGraphAnalyzed::analyze_with(&graph, &options),
+            SDFGraphAnalyzed::analyze_with(&low, &options),
+        );
+        assert_eq!(sorted(&analysis.max_delay), sorted(&low_analysis.max_delay));
+        assert_eq!(sorted(&analysis.min_delay), sorted(&low_analysis.min_delay));
+        assert_eq!(
+            sorted(&analysis.max_delay_backwards),
+            sorted(&low_analysis.max_delay_backwards)
+        );
+        assert_eq!(sorted(&analysis.required), sorted(&low_analysis.required));
+        let d = node(&graph, "_2_/D", Transition::Rise);
+        assert_eq!(analysis.path_report(&graph, &d), low_analysis.path_report(&low, &d));
+    }
+
+    #[test]
+    fn test_two_value_timing_checks() {
+        let checks = "    (SETUP (posedge D) (posedge CLK) (0.100:0.100:0.100))
+    (SETUP (negedge D) (posedge CLK) (0.100:0.100:0.100))";
+        let sdf = TWO_FLOPS.replace(
+            checks,
+            "    (SETUPHOLD (posedge D) (posedge CLK) (0.120:0.120:0.120) (-0.040:-0.040:-0.040))
+    (RECREM (posedge RESET_B) (posedge CLK) (0.200:0.200:0.200) (0.300:0.300:0.300))",
+        );
+        let graph = graph_of(&sdf);
+        let clock = node(&graph, "_2_/CLK", Transition::Rise);
+
+        let check = graph.timing_checks[&node(&graph, "_2_/D", Transition::Rise)];
+        assert_eq!((check.clock, check.setup, check.hold), (clock, Some(0.12), Some(-0.04)));
+        assert!(!graph
+            .timing_checks
+            .contains_key(&node(&graph, "_2_/D", Transition::Fall)));
+        let check = graph.timing_checks[&node(&graph, "_2_/RESET_B", Transition::Rise)];
+        assert_eq!((check.clock, check.setup, check.hold), (clock, Some(0.2), Some(0.3)));
+    }
 }
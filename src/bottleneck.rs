@@ -0,0 +1,113 @@
+//! Bottlenecks of the worst paths: the instances and the chains of pins that many of them go through,
+//! where a single fix improves all of these paths at once.
+
+use crate::analysis::{SDFGraphAnalyzed, TimingPath};
+use crate::graph::SDFGraph;
+use crate::types::{InstanceId, PinTrans};
+use ordered_float::OrderedFloat;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::cmp::Reverse;
+
+/// Instance appearing in several of the worst paths
+#[derive(Debug, Clone)]
+pub struct SharedInstance {
+    pub instance: InstanceId,
+    /// number of paths going through the instance
+    pub paths: usize,
+    /// slack of the worst of these paths
+    pub worst_slack: f32,
+}
+
+/// Chain of pins that several of the worst paths go through, the longest such chain for that number of paths
+#[derive(Debug, Clone)]
+pub struct SharedSegment {
+    /// pins of the chain, from its start to its end
+    pub pins: Vec<PinTrans>,
+    /// delay from the start to the end of the chain
+    pub delay: f32,
+    /// number of paths going through the chain
+    pub paths: usize,
+}
+
+pub struct Bottlenecks {
+    /// worst paths of the design, worst first (see [`SDFGraphAnalyzed::worst_paths`])
+    pub paths: Vec<TimingPath>,
+    /// instances shared by at least two paths, the most shared first
+    pub instances: Vec<SharedInstance>,
+    /// segments shared by at least two paths, the most shared first
+    pub segments: Vec<SharedSegment>,
+}
+
+impl Bottlenecks {
+    /// Find what the `n` worst paths have in common
+    pub fn new(graph: &SDFGraph, analysis: &SDFGraphAnalyzed, n: usize) -> Self {
+        let paths = analysis.worst_paths(graph, n);
+
+        // full paths, endpoint included
+        let nodes: Vec<Vec<(PinTrans, f32)>> = paths
+            .iter()
+            .map(|path| {
+                let mut nodes = path.path.clone();
+                nodes.push((path.endpoint, path.arrival));
+                nodes
+            })
+            .collect();
+
+        let mut instances: FxHashMap<InstanceId, SharedInstance> = FxHashMap::default();
+        let mut edge_paths: FxHashMap<(PinTrans, PinTrans), usize> = FxHashMap::default();
+
+        for (path, nodes) in paths.iter().zip(&nodes) {
+            let path_instances: FxHashSet<InstanceId> = nodes
+                .iter()
+                .filter_map(|(node, _)| graph.symbols.pin_instance(node.0))
+                .collect();
+            for instance in path_instances {
+                let shared = instances.entry(instance).or_insert(SharedInstance {
+                    instance,
+                    paths: 0,
+                    worst_slack: path.slack,
+                });
+                shared.paths += 1;
+                shared.worst_slack = shared.worst_slack.min(path.slack);
+            }
+
+            let edges: FxHashSet<(PinTrans, PinTrans)> = nodes.windows(2).map(|w| (w[0].0, w[1].0)).collect();
+            for edge in edges {
+                *edge_paths.entry(edge).or_insert(0) += 1;
+            }
+        }
+
+        let mut instances: Vec<SharedInstance> = instances.into_values().filter(|shared| shared.paths >= 2).collect();
+        instances.sort_by_key(|shared| (Reverse(shared.paths), OrderedFloat(shared.worst_slack)));
+
+        // runs of consecutive edges of a path shared by the same number of paths
+        let mut seen = FxHashSet::default();
+        let mut segments = Vec::new();
+        for nodes in &nodes {
+            let mut start = 0;
+            while start + 1 < nodes.len() {
+                let count = edge_paths[&(nodes[start].0, nodes[start + 1].0)];
+                let mut end = start + 1;
+                while end + 1 < nodes.len() && edge_paths[&(nodes[end].0, nodes[end + 1].0)] == count {
+                    end += 1;
+                }
+                let pins: Vec<PinTrans> = nodes[start..=end].iter().map(|(node, _)| *node).collect();
+                if count >= 2 && seen.insert(pins.clone()) {
+                    segments.push(SharedSegment {
+                        pins,
+                        delay: nodes[end].1 - nodes[start].1,
+                        paths: count,
+                    });
+                }
+                start = end;
+            }
+        }
+        segments.sort_by_key(|segment| (Reverse(segment.paths), Reverse(OrderedFloat(segment.delay))));
+
+        Self {
+            paths,
+            instances,
+            segments,
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! Comparison of two analyses of the same design, e.g. before and after an ECO: how the slack of the worst endpoints
+//! and of the instances changed, and where the worst paths took another route.
+//!
+//! The graphs of the two analyses have their own ids, pins and instances are matched by their name.
+
+use crate::analysis::SDFGraphAnalyzed;
+use crate::graph::SDFGraph;
+use crate::types::{PinTrans, Transition};
+use ordered_float::OrderedFloat;
+use rustc_hash::FxHashMap;
+use std::cmp::Reverse;
+
+/// Worst path to an endpoint in both analyses, None where the endpoint isn't reached
+#[derive(Debug, Clone)]
+pub struct EndpointDelta {
+    pub endpoint: String,
+    pub transition: Transition,
+    /// (before, after) slacks
+    pub slack: (Option<f32>, Option<f32>),
+    /// (before, after) arrival times
+    pub delay: (Option<f32>, Option<f32>),
+    /// pins of the worst path before, from the startpoint to the endpoint, e.g `_123_/A↗`
+    pub path_before: Vec<String>,
+    /// pins of the worst path after
+    pub path_after: Vec<String>,
+    /// number of pins both paths start with, the paths diverge after them
+    pub common: usize,
+}
+
+impl EndpointDelta {
+    /// Change of the slack, positive when the endpoint got better
+    pub fn slack_delta(&self) -> Option<f32> {
+        Some(self.slack.1? - self.slack.0?)
+    }
+
+    /// Last pin the two paths share before taking different routes, None when they are the same path or when
+    /// they don't even share their startpoint
+    pub fn divergence(&self) -> Option<&str> {
+        if self.path_before == self.path_after {
+            return None;
+        }
+        self.common.checked_sub(1).map(|i| &*self.path_before[i])
+    }
+}
+
+/// Worst slack of the paths going through an instance in both analyses, None where it doesn't exist
+#[derive(Debug, Clone)]
+pub struct InstanceDelta {
+    pub instance: String,
+    /// (before, after) celltypes, they differ when the instance was resized
+    pub celltype: (Option<String>, Option<String>),
+    /// (before, after) slacks
+    pub slack: (Option<f32>, Option<f32>),
+}
+
+impl InstanceDelta {
+    pub fn slack_delta(&self) -> Option<f32> {
+        Some(self.slack.1? - self.slack.0?)
+    }
+}
+
+pub struct AnalysisComparison {
+    /// the worst endpoints of both analyses, the worst one (before or after) first
+    pub endpoints: Vec<EndpointDelta>,
+    /// instances whose slack or celltype changed, added or removed, the largest change first
+    pub instances: Vec<InstanceDelta>,
+}
+
+impl AnalysisComparison {
+    /// Compare the `top` endpoints with the smallest slack of each analysis, and every instance.
+    /// Instance slacks changing by less than `tolerance` (ns) are ignored.
+    pub fn new(
+        before: (&SDFGraph, &SDFGraphAnalyzed),
+        after: (&SDFGraph, &SDFGraphAnalyzed),
+        top: usize,
+        tolerance: f32,
+    ) -> Self {
+        let mut names: Vec<(String, Transition)> = Vec::new();
+        for (graph, analysis) in [before, after] {
+            for (endpoint, _) in analysis.endpoints_by_slack(graph).into_iter().take(top) {
+                let name = (graph.symbols.pin_name(endpoint.0).to_string(), endpoint.1);
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+
+        let mut endpoints: Vec<EndpointDelta> = names
+            .into_iter()
+            .map(|(endpoint, transition)| {
+                let (slack_before, delay_before, path_before) = worst_path(before, &endpoint, transition);
+                let (slack_after, delay_after, path_after) = worst_path(after, &endpoint, transition);
+                let common = (path_before.iter().zip(&path_after))
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                EndpointDelta {
+                    endpoint,
+                    transition,
+                    slack: (slack_before, slack_after),
+                    delay: (delay_before, delay_after),
+                    path_before,
+                    path_after,
+                    common,
+                }
+            })
+            .collect();
+        endpoints.sort_by_key(|delta| {
+            let worst = [delta.slack.0, delta.slack.1].into_iter().flatten().reduce(f32::min);
+            OrderedFloat(worst.unwrap_or(f32::INFINITY))
+        });
+
+        let slacks_before = instance_slacks(before.0, before.1);
+        let mut slacks_after = instance_slacks(after.0, after.1);
+        let mut instances: Vec<InstanceDelta> = slacks_before
+            .into_iter()
+            .map(|(instance, (celltype, slack))| {
+                let (celltype_after, slack_after) = slacks_after.remove(&instance).unzip();
+                InstanceDelta {
+                    instance,
+                    celltype: (Some(celltype), celltype_after),
+                    slack: (slack, slack_after.flatten()),
+                }
+            })
+            .collect();
+        instances.extend(
+            slacks_after
+                .into_iter()
+                .map(|(instance, (celltype, slack))| InstanceDelta {
+                    instance,
+                    celltype: (None, Some(celltype)),
+                    slack: (None, slack),
+                }),
+        );
+        instances.retain(|delta| {
+            delta.celltype.0 != delta.celltype.1
+                || delta.slack.0.is_some() != delta.slack.1.is_some()
+                || delta.slack_delta().is_some_and(|delta| delta.abs() > tolerance)
+        });
+        instances.sort_by(|a, b| {
+            let change =
+                |delta: &InstanceDelta| Reverse(OrderedFloat(delta.slack_delta().map_or(f32::INFINITY, f32::abs)));
+            change(a).cmp(&change(b)).then_with(|| a.instance.cmp(&b.instance))
+        });
+
+        Self { endpoints, instances }
+    }
+}
+
+/// Slack, arrival and pins of the worst path to an endpoint given by its name
+fn worst_path(
+    (graph, analysis): (&SDFGraph, &SDFGraphAnalyzed),
+    endpoint: &str,
+    transition: Transition,
+) -> (Option<f32>, Option<f32>, Vec<String>) {
+    let Some(pin) = graph.symbols.pin_id(endpoint) else {
+        return (None, None, Vec::new());
+    };
+    let endpoint: PinTrans = (pin, transition);
+    let pins = analysis.path_report(graph, &endpoint).map_or(Vec::new(), |report| {
+        report
+            .pins()
+            .map(|(pin, _)| format!("{}{}", graph.symbols.pin_name(pin.0), pin.1))
+            .collect()
+    });
+    (analysis.slack(&endpoint), analysis.arrival(&endpoint), pins)
+}
+
+/// Celltype and worst slack through the pins of every instance of the design, by name
+fn instance_slacks(graph: &SDFGraph, analysis: &SDFGraphAnalyzed) -> FxHashMap<String, (String, Option<f32>)> {
+    graph
+        .instance_celltype
+        .iter()
+        .filter(|(instance, _)| !graph.symbols.instance_name(*instance).is_empty())
+        .map(|(instance, celltype)| {
+            let pins = graph.instance_ins.get(&instance).into_iter().flatten();
+            let pins = pins.chain(graph.instance_outs.get(&instance).into_iter().flatten());
+            let slack = pins
+                .flat_map(|&pin| [(pin, Transition::Rise), (pin, Transition::Fall)])
+                .filter_map(|node| analysis.slack_through(graph, &node))
+                .reduce(f32::min);
+            let name = graph.symbols.instance_name(instance).to_string();
+            (name, (celltype.clone(), slack))
+        })
+        .collect()
+}
@@ -0,0 +1,396 @@
+//! Edges of the graph in compressed sparse row (CSR) form: the edges of every node in one `Vec`, each node owning a
+//! range of it. It avoids the allocation per node of a `PinTransMap<Vec<SDFEdge>>`, a few times larger than the
+//! edges themselves on multi-million-node designs, and keeps the edges the propagation passes go through next to
+//! each other.
+
+use std::ops::{Index, IndexMut, Range};
+
+use crate::graph::SDFEdge;
+use crate::memory::HeapSize;
+use crate::types::{Id, PinTrans, PinTransMap};
+
+/// Adjacency of the nodes, node `i` (see [`Id::index`]) owning `edges[offsets[i]..offsets[i + 1]]`.
+///
+/// Reads like the `PinTransMap<Vec<SDFEdge>>` it replaces: a node of the graph maps to its edges, possibly none,
+/// and the other nodes aren't keys. The delays of the edges can be changed in place, adding or removing edges goes
+/// through [`CsrEdges::to_map`] and [`CsrEdges::from_map`].
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CsrEdges {
+    /// one more than the nodes, or empty
+    offsets: Vec<u32>,
+    edges: Vec<SDFEdge>,
+    /// whether each node is a key, with or without edges
+    nodes: Vec<bool>,
+    len: usize,
+}
+
+impl CsrEdges {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_map(map: &PinTransMap<Vec<SDFEdge>>) -> Self {
+        let n_nodes = map.keys().last().map_or(0, |node| node.index() + 1);
+        let mut csr = Self {
+            offsets: Vec::with_capacity(n_nodes + 1),
+            edges: Vec::with_capacity(map.values().map(Vec::len).sum()),
+            nodes: vec![false; n_nodes],
+            len: map.len(),
+        };
+        csr.offsets.push(0);
+        for i in 0..n_nodes {
+            if let Some(edges) = map.get(&PinTrans::from_index(i)) {
+                csr.edges.extend_from_slice(edges);
+                csr.nodes[i] = true;
+            }
+            csr.offsets.push(csr.edges.len() as u32);
+        }
+        csr
+    }
+
+    /// Map of the edges, to add or remove some
+    pub fn to_map(&self) -> PinTransMap<Vec<SDFEdge>> {
+        self.iter().map(|(node, edges)| (node, edges.to_vec())).collect()
+    }
+
+    /// Number of nodes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of edges
+    pub fn n_edges(&self) -> usize {
+        self.edges.len()
+    }
+
+    fn range(&self, i: usize) -> Option<Range<usize>> {
+        match self.nodes.get(i) {
+            Some(true) => Some(self.offsets[i] as usize..self.offsets[i + 1] as usize),
+            _ => None,
+        }
+    }
+
+    pub fn get(&self, node: &PinTrans) -> Option<&[SDFEdge]> {
+        let range = self.range(node.index())?;
+        Some(&self.edges[range])
+    }
+
+    pub fn get_mut(&mut self, node: &PinTrans) -> Option<&mut [SDFEdge]> {
+        let range = self.range(node.index())?;
+        Some(&mut self.edges[range])
+    }
+
+    pub fn contains_key(&self, node: &PinTrans) -> bool {
+        self.nodes.get(node.index()).copied().unwrap_or(false)
+    }
+
+    /// Edges of `node`, empty for a node without edges or outside of the graph
+    pub fn edges(&self, node: &PinTrans) -> &[SDFEdge] {
+        self.get(node).unwrap_or_default()
+    }
+
+    /// Sort the edges of each node by the node they go to, the edges to the same node keeping their order
+    pub fn sort_edges(&mut self) {
+        for range in self.offsets.windows(2) {
+            self.edges[range[0] as usize..range[1] as usize].sort_by_key(|edge| edge.dst.index());
+        }
+    }
+
+    /// Nodes and their edges, in increasing id order
+    pub fn iter(&self) -> impl Iterator<Item = (PinTrans, &[SDFEdge])> {
+        (self.offsets.windows(2).enumerate())
+            .filter(|&(i, _)| self.nodes[i])
+            .map(|(i, range)| {
+                (
+                    PinTrans::from_index(i),
+                    &self.edges[range[0] as usize..range[1] as usize],
+                )
+            })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (PinTrans, &mut [SDFEdge])> {
+        let mut rest = &mut self.edges[..];
+        (self.offsets.windows(2).zip(&self.nodes).enumerate()).filter_map(move |(i, (range, &node))| {
+            let (edges, tail) = std::mem::take(&mut rest).split_at_mut((range[1] - range[0]) as usize);
+            rest = tail;
+            node.then(|| (PinTrans::from_index(i), edges))
+        })
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = PinTrans> + '_ {
+        self.iter().map(|(node, _)| node)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &[SDFEdge]> {
+        self.iter().map(|(_, edges)| edges)
+    }
+}
+
+impl HeapSize for CsrEdges {
+    fn heap_size(&self) -> usize {
+        self.offsets.heap_size() + self.edges.heap_size() + self.nodes.heap_size()
+    }
+}
+
+impl Index<&PinTrans> for CsrEdges {
+    type Output = [SDFEdge];
+
+    fn index(&self, node: &PinTrans) -> &[SDFEdge] {
+        self.get(node).expect("node not in CsrEdges")
+    }
+}
+
+impl IndexMut<&PinTrans> for CsrEdges {
+    fn index_mut(&mut self, node: &PinTrans) -> &mut [SDFEdge] {
+        self.get_mut(node).expect("node not in CsrEdges")
+    }
+}
+
+impl FromIterator<(PinTrans, Vec<SDFEdge>)> for CsrEdges {
+    fn from_iter<T: IntoIterator<Item = (PinTrans, Vec<SDFEdge>)>>(iter: T) -> Self {
+        Self::from_map(&iter.into_iter().collect())
+    }
+}
+
+impl<'a> IntoIterator for &'a CsrEdges {
+    type Item = (PinTrans, &'a [SDFEdge]);
+    type IntoIter = Box<dyn Iterator<Item = (PinTrans, &'a [SDFEdge])> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter())
+    }
+}
+
+/// Arcs read from the SDF, kept in one `Vec` in the order of the SDF until every cell is read. The edges of each
+/// node are then built at once, with their final size, see [`EdgeArena::finish`].
+#[derive(Default)]
+pub(crate) struct EdgeArena {
+    arcs: Vec<ArenaArc>,
+}
+
+struct ArenaArc {
+    src: PinTrans,
+    dst: PinTrans,
+    delay: f32,
+    cond: Option<u32>,
+    increment: bool,
+    /// keep only the worst delay when the arc already exists, see [`EdgeArena::add`]
+    merge: bool,
+}
+
+impl EdgeArena {
+    /// Add the `src -> dst` arc. When `merge` is set and the arc already exists, only the worst delay is kept.
+    pub(crate) fn add(&mut self, src: PinTrans, dst: PinTrans, delay: f32, cond: Option<u32>, merge: bool) {
+        self.arcs.push(ArenaArc {
+            src,
+            dst,
+            delay,
+            cond,
+            increment: false,
+            merge,
+        });
+    }
+
+    /// Add `delay` to the `src -> dst` arcs with the given condition, for an INCREMENT entry.
+    /// The arc is created when there is no previous value.
+    pub(crate) fn increment(&mut self, src: PinTrans, dst: PinTrans, delay: f32, cond: Option<u32>) {
+        self.arcs.push(ArenaArc {
+            src,
+            dst,
+            delay,
+            cond,
+            increment: true,
+            merge: false,
+        });
+    }
+
+    /// Forward and reverse graphs of the arcs. Both ends of each arc are nodes of the two graphs.
+    pub(crate) fn finish(self) -> (CsrEdges, CsrEdges) {
+        let graph = self.fold(|arc| (arc.src, arc.dst));
+        let reverse_graph = self.fold(|arc| (arc.dst, arc.src));
+        (graph, reverse_graph)
+    }
+
+    /// Edges of each node, `ends` giving the node of an arc and the other end of its edge. The arcs of a node are
+    /// applied in the order they were read, as the INCREMENTs go on top of the values seen before them.
+    fn fold(&self, ends: impl Fn(&ArenaArc) -> (PinTrans, PinTrans)) -> CsrEdges {
+        let n_nodes = (self.arcs.iter())
+            .map(|arc| arc.src.index().max(arc.dst.index()) + 1)
+            .max()
+            .unwrap_or(0);
+
+        // counting sort of the arcs by node, stable
+        let mut offsets = vec![0u32; n_nodes + 1];
+        for arc in &self.arcs {
+            offsets[ends(arc).0.index() + 1] += 1;
+        }
+        for i in 0..n_nodes {
+            offsets[i + 1] += offsets[i];
+        }
+        let mut next = offsets.clone();
+        let mut order = vec![0u32; self.arcs.len()];
+        for (i, arc) in self.arcs.iter().enumerate() {
+            let slot = &mut next[ends(arc).0.index()];
+            order[*slot as usize] = i as u32;
+            *slot += 1;
+        }
+
+        let mut nodes = vec![false; n_nodes];
+        for arc in &self.arcs {
+            nodes[arc.src.index()] = true;
+            nodes[arc.dst.index()] = true;
+        }
+        let mut csr = CsrEdges {
+            offsets: Vec::with_capacity(n_nodes + 1),
+            edges: Vec::with_capacity(self.arcs.len()),
+            len: nodes.iter().filter(|&&node| node).count(),
+            nodes,
+        };
+        csr.offsets.push(0);
+        for range in offsets.windows(2) {
+            let start = csr.edges.len();
+            for arc in order[range[0] as usize..range[1] as usize]
+                .iter()
+                .map(|&arc| &self.arcs[arc as usize])
+            {
+                let (_, other) = ends(arc);
+                let edges = &mut csr.edges;
+                if arc.increment {
+                    let mut found = false;
+                    for edge in edges[start..]
+                        .iter_mut()
+                        .filter(|e| e.dst == other && e.cond == arc.cond)
+                    {
+                        edge.delay += arc.delay;
+                        found = true;
+                    }
+                    if found {
+                        continue;
+                    }
+                }
+                match edges[start..].iter_mut().find(|e| arc.merge && e.dst == other) {
+                    Some(edge) => edge.delay = edge.delay.max(arc.delay),
+                    None => edges.push(SDFEdge {
+                        dst: other,
+                        delay: arc.delay,
+                        cond: arc.cond,
+                    }),
+                }
+            }
+            csr.offsets.push(csr.edges.len() as u32);
+        }
+        csr.edges.shrink_to_fit();
+        csr
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(index: usize) -> PinTrans {
+        PinTrans::from_index(index)
+    }
+
+    fn edge(dst: usize, delay: f32, cond: Option<u32>) -> SDFEdge {
+        SDFEdge {
+            dst: node(dst),
+            delay,
+            cond,
+        }
+    }
+
+    fn edges_of(csr: &CsrEdges) -> Vec<(PinTrans, Vec<SDFEdge>)> {
+        csr.iter().map(|(node, edges)| (node, edges.to_vec())).collect()
+    }
+
+    #[test]
+    fn test_edge_arena() {
+        let mut arena = EdgeArena::default();
+        arena.add(node(4), node(1), 0.2, None, false);
+        // merged into the worst delay
+        arena.add(node(0), node(2), 0.1, None, true);
+        arena.add(node(0), node(2), 0.3, None, true);
+        arena.add(node(0), node(2), 0.2, None, true);
+        // parallel arcs of two conditions, the INCREMENT only goes to its own
+        arena.add(node(0), node(4), 0.1, Some(0), false);
+        arena.add(node(0), node(4), 0.2, Some(1), false);
+        arena.increment(node(0), node(4), 0.05, Some(1));
+        // an INCREMENT without previous value creates the arc
+        arena.increment(node(6), node(2), 0.4, None);
+
+        let (graph, reverse_graph) = arena.finish();
+        assert_eq!(
+            edges_of(&graph),
+            [
+                (
+                    node(0),
+                    vec![edge(2, 0.3, None), edge(4, 0.1, Some(0)), edge(4, 0.25, Some(1))]
+                ),
+                (node(1), vec![]),
+                (node(2), vec![]),
+                (node(4), vec![edge(1, 0.2, None)]),
+                (node(6), vec![edge(2, 0.4, None)]),
+            ]
+        );
+        assert_eq!(
+            edges_of(&reverse_graph),
+            [
+                (node(0), vec![]),
+                (node(1), vec![edge(4, 0.2, None)]),
+                (node(2), vec![edge(0, 0.3, None), edge(6, 0.4, None)]),
+                (node(4), vec![edge(0, 0.1, Some(0)), edge(0, 0.25, Some(1))]),
+                (node(6), vec![]),
+            ]
+        );
+        assert_eq!((graph.len(), graph.n_edges()), (5, 5));
+        // the nodes in between aren't keys
+        assert!(!graph.contains_key(&node(3)));
+    }
+
+    #[test]
+    fn test_csr_against_map() {
+        let mut map: PinTransMap<Vec<SDFEdge>> = PinTransMap::new();
+        map.insert(node(1), vec![edge(5, 0.2, None), edge(2, 0.1, None)]);
+        map.insert(node(2), vec![]);
+        map.insert(node(5), vec![edge(2, 0.3, Some(0))]);
+        let mut csr = CsrEdges::from_map(&map);
+
+        // the same keys and edges, in the same order
+        let from_map: Vec<(PinTrans, Vec<SDFEdge>)> = map.iter().map(|(node, edges)| (node, edges.clone())).collect();
+        assert_eq!(edges_of(&csr), from_map);
+        assert_eq!(edges_of(&CsrEdges::from_map(&csr.to_map())), from_map);
+        assert_eq!(edges_of(&from_map.iter().cloned().collect()), from_map);
+        assert_eq!((csr.len(), csr.n_edges(), csr.is_empty()), (3, 3, false));
+        assert!(CsrEdges::new().is_empty());
+
+        // a key without edges isn't a missing node
+        for i in 0..8 {
+            assert_eq!(csr.contains_key(&node(i)), map.get(&node(i)).is_some());
+            assert_eq!(csr.get(&node(i)), map.get(&node(i)).map(Vec::as_slice));
+            assert_eq!(csr.edges(&node(i)), map.get(&node(i)).map_or(&[][..], Vec::as_slice));
+        }
+        assert_eq!(csr.keys().collect::<Vec<_>>(), [node(1), node(2), node(5)]);
+        assert_eq!(csr.values().map(<[SDFEdge]>::len).collect::<Vec<_>>(), [2, 0, 1]);
+        assert_eq!((&csr).into_iter().count(), 3);
+        assert_eq!(csr[&node(5)], [edge(2, 0.3, Some(0))]);
+
+        // the delays are changed in place
+        csr[&node(1)][0].delay = 0.4;
+        csr.get_mut(&node(5)).unwrap()[0].delay = 0.5;
+        for (_, edges) in csr.iter_mut() {
+            for edge in edges {
+                edge.delay *= 2.0;
+            }
+        }
+        csr.sort_edges();
+        assert_eq!(csr[&node(1)], [edge(2, 0.2, None), edge(5, 0.8, None)]);
+        assert_eq!(csr[&node(5)], [edge(2, 1.0, Some(0))]);
+        assert!(csr.get_mut(&node(3)).is_none());
+    }
+}
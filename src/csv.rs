@@ -0,0 +1,90 @@
+//! CSV tables of the analysis for spreadsheets: one row per endpoint, and one row per stage of the worst paths.
+//! Times are in ns, transitions are `rise` or `fall` as in the JSON output.
+
+use crate::analysis::SDFGraphAnalyzed;
+use crate::graph::SDFGraph;
+use crate::types::{PinTrans, Transition};
+use std::fmt::Write;
+
+/// Quote a field when it contains a separator, a quote or a line break
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn transition(transition: Transition) -> &'static str {
+    match transition {
+        Transition::Rise => "rise",
+        Transition::Fall => "fall",
+    }
+}
+
+fn optional(value: Option<f32>) -> String {
+    value.map_or(String::new(), |value| format!("{:.3}", value))
+}
+
+/// Every reachable endpoint, smallest slack first. `required` is empty for the unconstrained endpoints.
+pub fn endpoints_csv(graph: &SDFGraph, analysis: &SDFGraphAnalyzed) -> String {
+    let symbols = &graph.symbols;
+    let mut csv = String::from("endpoint,transition,startpoint,arrival,required,slack\n");
+    for (endpoint, slack) in analysis.endpoints_by_slack(graph) {
+        let startpoint = analysis
+            .path_report(graph, &endpoint)
+            .map(|report| symbols.pin_name(report.startpoint().0).to_string());
+        writeln!(
+            &mut csv,
+            "{},{},{},{},{},{:.3}",
+            field(symbols.pin_name(endpoint.0)),
+            transition(endpoint.1),
+            field(startpoint.as_deref().unwrap_or("")),
+            optional(analysis.arrival(&endpoint)),
+            optional(analysis.required.get(&endpoint).copied()),
+            slack
+        )
+        .unwrap();
+    }
+    csv
+}
+
+/// Stages of the worst path of the `top` endpoints with the smallest slack, from the startpoint (stage 0) to the
+/// endpoint. The ports have no instance nor celltype, and `fanout` is the number of pins driven by the output.
+pub fn stages_csv(graph: &SDFGraph, analysis: &SDFGraphAnalyzed, top: usize) -> String {
+    let symbols = &graph.symbols;
+    let pin_name = |pin: Option<PinTrans>| pin.map_or(String::new(), |pin| field(symbols.pin_name(pin.0)));
+    let mut csv = String::from(
+        "endpoint,transition,slack,stage,instance,celltype,input,output,cell_delay,net_delay,arrival,fanout\n",
+    );
+    for (endpoint, slack) in analysis.endpoints_by_slack(graph).into_iter().take(top) {
+        let Some(report) = analysis.path_report(graph, &endpoint) else {
+            continue;
+        };
+        for (i, stage) in report.stages.iter().enumerate() {
+            let fanout = stage
+                .output
+                .map_or(String::new(), |output| graph.graph.edges(&output).len().to_string());
+            writeln!(
+                &mut csv,
+                "{},{},{:.3},{},{},{},{},{},{:.3},{:.3},{:.3},{}",
+                field(symbols.pin_name(endpoint.0)),
+                transition(endpoint.1),
+                slack,
+                i,
+                stage
+                    .instance
+                    .map_or(String::new(), |instance| field(symbols.instance_name(instance))),
+                stage.celltype.as_deref().map_or(String::new(), field),
+                pin_name(stage.input),
+                pin_name(stage.output),
+                stage.cell_delay,
+                stage.net_delay,
+                stage.arrival,
+                fanout
+            )
+            .unwrap();
+        }
+    }
+    csv
+}
@@ -0,0 +1,222 @@
+//! Placement of the design read from a DEF file, to tell the paths crossing the die from the local ones.
+//!
+//! Only the locations of the COMPONENTS and of the PINS are read. Without the LEF of the cells, an instance is at
+//! the origin of its placement (its lower left corner before the orientation is applied), which is close enough
+//! to compare wire lengths of several µm.
+
+use crate::analysis::PathReport;
+use crate::graph::SDFGraph;
+use crate::types::PinId;
+use rustc_hash::FxHashMap;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug)]
+pub struct DefError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for DefError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DEF line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for DefError {}
+
+/// Locations in µm, keyed by the names of the graph (`/` between the levels of hierarchy)
+#[derive(Debug, Clone, Default)]
+pub struct Placement {
+    /// lower left and upper right corners of the die, None when the DEF has no DIEAREA
+    pub die_area: Option<((f32, f32), (f32, f32))>,
+    pub instances: FxHashMap<String, (f32, f32)>,
+    pub ports: FxHashMap<String, (f32, f32)>,
+}
+
+/// Section of the DEF whose statements are read
+#[derive(Clone, Copy, PartialEq)]
+enum Section {
+    Components,
+    Pins,
+    Other,
+}
+
+impl Placement {
+    pub fn parse(content: &str) -> Result<Self, DefError> {
+        let mut tokens = content.lines().enumerate().flat_map(|(i, line)| {
+            let line_tokens = line.split('#').next().unwrap_or("").split_whitespace();
+            line_tokens.map(move |token| (i + 1, token))
+        });
+
+        let mut placement = Placement::default();
+        let mut units = 1.0;
+        let mut divider = '/';
+        let mut section = Section::Other;
+        // tokens of the statement being read, up to its `;`
+        let mut statement: Vec<(usize, &str)> = Vec::new();
+
+        while let Some((line, token)) = tokens.next() {
+            // `END COMPONENTS` has no `;`
+            if token == "END" && statement.is_empty() {
+                tokens.next();
+                section = Section::Other;
+                continue;
+            }
+            if token != ";" {
+                statement.push((line, token));
+                continue;
+            }
+            let words: Vec<&str> = statement.iter().map(|(_, word)| *word).collect();
+            let error = |message: String| DefError {
+                line: statement.first().map_or(line, |(line, _)| *line),
+                message,
+            };
+
+            match words.as_slice() {
+                ["DIVIDERCHAR", char] => {
+                    divider = char.trim_matches('"').chars().next().unwrap_or('/');
+                }
+                ["UNITS", "DISTANCE", "MICRONS", value] => {
+                    units = value
+                        .parse()
+                        .map_err(|_| error(format!("invalid distance units {}", value)))?;
+                }
+                // a rectangle or the vertices of a polygon, whose bounding box is kept
+                ["DIEAREA", points @ ..] => {
+                    let points: Vec<(f32, f32)> = points
+                        .chunks(4)
+                        .map(|point| match point {
+                            ["(", x, y, ")"] => Some((x.parse::<f32>().ok()? / units, y.parse::<f32>().ok()? / units)),
+                            _ => None,
+                        })
+                        .collect::<Option<_>>()
+                        .ok_or_else(|| error("invalid DIEAREA".to_string()))?;
+                    placement.die_area = points.iter().fold(None, |area, &(x, y)| match area {
+                        None => Some(((x, y), (x, y))),
+                        Some(((x0, y0), (x1, y1))) => Some(((x0.min(x), y0.min(y)), (x1.max(x), y1.max(y)))),
+                    });
+                }
+                ["COMPONENTS", ..] => section = Section::Components,
+                ["PINS", ..] => section = Section::Pins,
+                _ => {}
+            }
+            if section != Section::Other && words.first() == Some(&"-") {
+                let name = words
+                    .get(1)
+                    .map(|name| flat_name(name, divider))
+                    .ok_or_else(|| error("statement without a name".to_string()))?;
+                let location = words
+                    .iter()
+                    .position(|word| matches!(*word, "PLACED" | "FIXED" | "COVER"))
+                    .map(|i| match words.get(i + 1..i + 5) {
+                        Some(["(", x, y, ")"]) => match (x.parse::<f32>(), y.parse::<f32>()) {
+                            (Ok(x), Ok(y)) => Ok((x / units, y / units)),
+                            _ => Err(error(format!("invalid location of {}", name))),
+                        },
+                        _ => Err(error(format!("invalid location of {}", name))),
+                    })
+                    .transpose()?;
+                if let Some(location) = location {
+                    match section {
+                        Section::Components => placement.instances.insert(name, location),
+                        _ => placement.ports.insert(name, location),
+                    };
+                }
+            }
+            statement.clear();
+        }
+
+        Ok(placement)
+    }
+
+    /// Die area, or the bounding box of the placed instances and ports when the DEF has no DIEAREA
+    pub fn bounds(&self) -> Option<((f32, f32), (f32, f32))> {
+        self.die_area.or_else(|| {
+            let mut locations = self.instances.values().chain(self.ports.values());
+            let &first = locations.next()?;
+            Some(locations.fold((first, first), |((x0, y0), (x1, y1)), &(x, y)| {
+                ((x0.min(x), y0.min(y)), (x1.max(x), y1.max(y)))
+            }))
+        })
+    }
+
+    /// Location of the instance of a pin, or of the port
+    pub fn pin_location(&self, graph: &SDFGraph, pin: PinId) -> Option<(f32, f32)> {
+        match graph.symbols.pin_instance(pin) {
+            Some(instance) => self.instances.get(graph.symbols.instance_name(instance)).copied(),
+            None => self.ports.get(graph.symbols.pin_name(pin)).copied(),
+        }
+    }
+
+    /// Manhattan length of the nets of a path, and the distance from its startpoint to its endpoint.
+    /// The nets with a pin that isn't placed are skipped
+    pub fn path_length(&self, graph: &SDFGraph, report: &PathReport) -> (f32, Option<f32>) {
+        let length = report
+            .wires()
+            .filter_map(|(from, to)| {
+                let from = self.pin_location(graph, from.0)?;
+                let to = self.pin_location(graph, to.0)?;
+                Some(manhattan(from, to))
+            })
+            .sum();
+        let direct = self
+            .pin_location(graph, report.startpoint().0)
+            .zip(self.pin_location(graph, report.endpoint.0))
+            .map(|(from, to)| manhattan(from, to));
+        (length, direct)
+    }
+}
+
+fn manhattan(a: (f32, f32), b: (f32, f32)) -> f32 {
+    (a.0 - b.0).abs() + (a.1 - b.1).abs()
+}
+
+/// Name of the graph for a DEF name, the divider becoming `/`. A backslash escapes the next character
+fn flat_name(name: &str, divider: char) -> String {
+    let mut flat = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => flat.extend(chars.next()),
+            c if c == divider => flat.push('/'),
+            c => flat.push(c),
+        }
+    }
+    flat
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let def = r#"VERSION 5.8 ;
+DIVIDERCHAR "|" ;
+BUSBITCHARS "[]" ;
+DESIGN top ;
+UNITS DISTANCE MICRONS 1000 ;
+DIEAREA ( 0 0 ) ( 200000 200000 ) ;
+COMPONENTS 3 ;
+    - _1_ sky130_fd_sc_hd__inv_1 + PLACED ( 10000 20000 ) N ;
+    - core|u\[2\] sky130_fd_sc_hd__buf_2
+      + FIXED ( 5500 0 ) FS ;
+    - _3_ sky130_fd_sc_hd__inv_1 + UNPLACED ;
+END COMPONENTS
+PINS 1 ;
+    - data[0] + NET data[0] + DIRECTION INPUT + USE SIGNAL
+      + PORT + LAYER met2 ( -140 -2000 ) ( 140 2000 ) + PLACED ( 0 100000 ) N ;
+END PINS
+END DESIGN
+"#;
+        let placement = Placement::parse(def).unwrap();
+        assert_eq!(placement.die_area, Some(((0.0, 0.0), (200.0, 200.0))));
+        assert_eq!(placement.instances.len(), 2);
+        assert_eq!(placement.instances["_1_"], (10.0, 20.0));
+        assert_eq!(placement.instances["core/u[2]"], (5.5, 0.0));
+        assert_eq!(placement.ports["data[0]"], (0.0, 100.0));
+
+        let err = Placement::parse("COMPONENTS 1 ;\n- _1_ inv + PLACED ( 1 ) N ;\nEND COMPONENTS\n").unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+}
@@ -0,0 +1,171 @@
+//! Cell delay calculation from the NLDM tables of a Liberty library, to update the delays of the SDF
+//! after editing the design (e.g resizing a cell) without going through the whole P&R flow again.
+
+use crate::graph::SDFGraph;
+use crate::liberty::{Library, TimingArc};
+#[cfg(feature = "spef")]
+use crate::parasitics::Parasitics;
+use crate::types::{PinId, PinTrans, PinTransMap, Transition};
+use rustc_hash::FxHashMap;
+
+pub struct DelayCalculator<'a> {
+    pub library: &'a Library,
+    /// wire capacitances, only the pin capacitances are counted in the loads without them
+    #[cfg(feature = "spef")]
+    pub parasitics: Option<&'a Parasitics>,
+    /// transition time at the input ports, in ns
+    pub input_transition: f32,
+}
+
+impl<'a> DelayCalculator<'a> {
+    pub fn new(library: &'a Library) -> Self {
+        Self {
+            library,
+            #[cfg(feature = "spef")]
+            parasitics: None,
+            input_transition: 0.05,
+        }
+    }
+
+    /// Count the capacitance of the wires in the loads
+    #[cfg(feature = "spef")]
+    pub fn with_parasitics(mut self, parasitics: Option<&'a Parasitics>) -> Self {
+        self.parasitics = parasitics;
+        self
+    }
+
+    /// Capacitance driven by `driver` in pF: input pins of the fanout and the wire if known.
+    pub fn load(&self, graph: &SDFGraph, driver: PinId) -> f32 {
+        let symbols = &graph.symbols;
+        let driver_instance = symbols.pin_instance(driver);
+
+        let pins: f32 = graph
+            .graph
+            .get(&(driver, Transition::Rise))
+            .into_iter()
+            .flatten()
+            .filter(|edge| driver_instance.is_none() || symbols.pin_instance(edge.dst.0) != driver_instance)
+            .filter_map(|edge| {
+                let instance = symbols.pin_instance(edge.dst.0)?;
+                let cell = self.library.cells.get(graph.instance_celltype.get(&instance)?)?;
+                cell.pin(symbols.pin_short_name(edge.dst.0))?.capacitance
+            })
+            .sum::<f32>()
+            * self.library.capacitive_load_unit;
+
+        #[cfg(not(feature = "spef"))]
+        let wire = 0.0;
+        #[cfg(feature = "spef")]
+        let wire = self
+            .parasitics
+            .and_then(|parasitics| parasitics.net_of(symbols.pin_name(driver)))
+            .map_or(0.0, |(net, _)| net.total_cap() as f32 * 1e12);
+
+        pins + wire
+    }
+
+    /// Delay and output transition in ns of the arc of `celltype` from `input` to the `output` transition,
+    /// None if the library has no table for it. The worst of the conditional arcs is taken.
+    pub fn arc_delay(
+        &self,
+        celltype: &str,
+        input: &str,
+        output: (&str, Transition),
+        input_transition: f32,
+        load: f32,
+    ) -> Option<(f32, f32)> {
+        let cell = self.library.cells.get(celltype)?;
+        let pin = cell.pin(output.0)?;
+
+        let time_unit = self.library.time_unit;
+        let slew = input_transition / time_unit;
+        let load = load / self.library.capacitive_load_unit;
+
+        let tables = |arc: &'a TimingArc| match output.1 {
+            Transition::Rise => (arc.cell_rise.as_ref(), arc.rise_transition.as_ref()),
+            Transition::Fall => (arc.cell_fall.as_ref(), arc.fall_transition.as_ref()),
+        };
+
+        pin.timing
+            .iter()
+            .filter(|arc| arc.related_pin == input && !is_check(arc))
+            .filter_map(|arc| {
+                let (delay, transition) = tables(arc);
+                let delay = delay?.delay(slew, load) * time_unit;
+                let transition = transition.map_or(input_transition, |t| t.delay(slew, load) * time_unit);
+                Some((delay, transition))
+            })
+            .reduce(|(d1, t1), (d2, t2)| (d1.max(d2), t1.max(t2)))
+    }
+
+    /// Recompute the delays of the cell arcs of the graph, propagating the transition times from the inputs.
+    /// Arcs of cells that are not in the library keep their SDF delay. Returns the number of updated arcs.
+    pub fn update_graph(&self, graph: &mut SDFGraph) -> usize {
+        let symbols = &graph.symbols;
+
+        let mut transitions: PinTransMap<f32> = PinTransMap::new();
+        let mut loads: FxHashMap<PinId, f32> = FxHashMap::default();
+        let mut delays: FxHashMap<(PinTrans, PinTrans), f32> = FxHashMap::default();
+
+        for node in graph.levels().into_iter().flatten() {
+            let instance = symbols.pin_instance(node.0);
+            let mut transition = None;
+
+            for edge in graph.fanin(&node).iter() {
+                let src_transition = transitions.get(&edge.dst).copied().unwrap_or(self.input_transition);
+                // interconnects keep their delay and the transition of their driver
+                if instance.is_none() || symbols.pin_instance(edge.dst.0) != instance {
+                    transition = Some(transition.map_or(src_transition, |t: f32| t.max(src_transition)));
+                    continue;
+                }
+                let Some(celltype) = instance.and_then(|id| graph.instance_celltype.get(&id)) else {
+                    continue;
+                };
+                let load = *loads.entry(node.0).or_insert_with(|| self.load(graph, node.0));
+                let Some((delay, out_transition)) = self.arc_delay(
+                    celltype,
+                    symbols.pin_short_name(edge.dst.0),
+                    (symbols.pin_short_name(node.0), node.1),
+                    src_transition,
+                    load,
+                ) else {
+                    continue;
+                };
+                delays.insert((edge.dst, node), delay);
+                transition = Some(transition.map_or(out_transition, |t: f32| t.max(out_transition)));
+            }
+
+            if let Some(transition) = transition {
+                transitions.insert(node, transition);
+            }
+        }
+
+        for ((src, dst), delay) in &delays {
+            for edge in graph.graph[src].iter_mut().filter(|edge| edge.dst == *dst) {
+                edge.delay = *delay;
+            }
+            for edge in graph.reverse_edges_mut(dst).filter(|edge| edge.dst == *src) {
+                edge.delay = *delay;
+            }
+        }
+
+        delays.len()
+    }
+}
+
+/// Setup/hold and other timing checks, which are not delays
+fn is_check(arc: &TimingArc) -> bool {
+    arc.timing_type.as_deref().is_some_and(|timing_type| {
+        [
+            "setup",
+            "hold",
+            "recovery",
+            "removal",
+            "skew",
+            "min_pulse_width",
+            "minimum_period",
+        ]
+        .iter()
+        .any(|check| timing_type.starts_with(check))
+    })
+}
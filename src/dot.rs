@@ -0,0 +1,81 @@
+use crate::graph::SDFGraph;
+use crate::types::{PinTrans, PinTransSet, SDFPin};
+use std::io::Write;
+
+#[derive(Debug, Default, Clone)]
+pub struct DotOptions {
+    /// Only emit the fanin and fanout cone of this pin (both transitions). `None` emits the whole graph.
+    pub cone_of: Option<SDFPin>,
+    /// Maximum number of arcs between `cone_of` and the nodes of its cone, see [`SDFGraph::fanout_cone`].
+    /// `None` goes up to the startpoints and endpoints.
+    pub cone_depth: Option<usize>,
+    /// Nodes of a path (usually from `extract_path` plus the endpoint), in order. Edges between consecutive nodes are highlighted.
+    pub highlight_path: Vec<PinTrans>,
+}
+
+fn dot_id(graph: &SDFGraph, node: &PinTrans) -> String {
+    let name = graph.symbols.pin_name(node.0);
+    format!("\"{}{}\"", name.replace('\\', "\\\\").replace('"', "\\\""), node.1)
+}
+
+impl SDFGraph {
+    /// Write the pin-transition graph in the Graphviz DOT format, with delays as edge labels.
+    pub fn to_dot(&self, mut w: impl Write, options: &DotOptions) -> std::io::Result<()> {
+        let cone = options.cone_of.as_ref().map(|pin| match self.symbols.pin_id(pin) {
+            Some(pin) => {
+                let mut cone = self.fanout_cone(pin, options.cone_depth);
+                cone.extend(self.fanin_cone(pin, options.cone_depth));
+                cone
+            }
+            None => PinTransSet::new(),
+        });
+        let keep = |node: &PinTrans| cone.as_ref().is_none_or(|cone| cone.contains(node));
+
+        let highlighted: PinTransSet = options.highlight_path.iter().copied().collect();
+        let is_highlighted_edge =
+            |src: &PinTrans, dst: &PinTrans| options.highlight_path.windows(2).any(|w| &w[0] == src && &w[1] == dst);
+
+        writeln!(w, "digraph timing {{")?;
+        writeln!(w, "    rankdir=LR;")?;
+        writeln!(w, "    node [shape=box, fontname=monospace];")?;
+
+        for node in self.graph.keys() {
+            if !keep(&node) {
+                continue;
+            }
+            if highlighted.contains(&node) {
+                writeln!(w, "    {} [color=red, penwidth=2];", dot_id(self, &node))?;
+            } else {
+                writeln!(w, "    {};", dot_id(self, &node))?;
+            }
+        }
+
+        for (src, edges) in &self.graph {
+            if !keep(&src) {
+                continue;
+            }
+            for edge in edges {
+                if !keep(&edge.dst) {
+                    continue;
+                }
+                let style = if is_highlighted_edge(&src, &edge.dst) {
+                    ", color=red, penwidth=2"
+                } else {
+                    ""
+                };
+                writeln!(
+                    w,
+                    "    {} -> {} [label=\"{:.3}\"{}];",
+                    dot_id(self, &src),
+                    dot_id(self, &edge.dst),
+                    edge.delay,
+                    style
+                )?;
+            }
+        }
+
+        writeln!(w, "}}")?;
+
+        Ok(())
+    }
+}
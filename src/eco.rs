@@ -0,0 +1,415 @@
+//! Suggestions of ECOs (engineering change orders) for the worst paths: upsizing the cells of the path,
+//! downsizing the cells loading it and buffering its high fanout nets.
+//!
+//! Gains are estimated with an RC model of each stage: the resistance of the drivers comes from the transistors
+//! of their SUBCKT (see [`crate::subckt::Drive`]) and is scaled by the drive strength for the other sizes,
+//! the capacitance of the pins comes from their gate area (see [`crate::subckt::Load`]), and a stage takes `ln(2) R C`.
+
+use crate::analysis::SDFGraphAnalyzed;
+use crate::graph::{drive_strength, SDFGraph};
+use crate::pdk::PdkConfig;
+use crate::subckt::{Subckt, SubcktData};
+use crate::types::{InstanceId, PinId, PinTrans, Transition};
+use ordered_float::OrderedFloat;
+use rustc_hash::FxHashSet;
+use std::cmp::Reverse;
+
+/// Fanout from which buffering the off-path sinks of a net is considered
+const HIGH_FANOUT: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EcoAction {
+    /// Replace a cell of the path by a stronger one
+    Upsize {
+        instance: InstanceId,
+        from: String,
+        to: String,
+    },
+    /// Replace a cell loading the path (but not on it) by one with a smaller input capacitance
+    DownsizeLoad {
+        instance: InstanceId,
+        from: String,
+        to: String,
+    },
+    /// Drive the off-path sinks of a net through a new buffer
+    BufferFanout {
+        driver: PinId,
+        sinks: Vec<PinId>,
+        buffer: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EcoSuggestion {
+    pub action: EcoAction,
+    /// estimated decrease of the arrival time at `endpoint`, in ns
+    pub gain: f32,
+    /// worst endpoint whose path goes through the change
+    pub endpoint: PinTrans,
+}
+
+impl EcoSuggestion {
+    pub fn describe(&self, graph: &SDFGraph) -> String {
+        let symbols = &graph.symbols;
+        let action = match &self.action {
+            EcoAction::Upsize { instance, from, to } => {
+                format!("upsize {} {} -> {}", symbols.instance_name(*instance), from, to)
+            }
+            EcoAction::DownsizeLoad { instance, from, to } => {
+                format!(
+                    "downsize off-path load {} {} -> {}",
+                    symbols.instance_name(*instance),
+                    from,
+                    to
+                )
+            }
+            EcoAction::BufferFanout { driver, sinks, buffer } => format!(
+                "buffer {} off-path sinks of {} with {}",
+                sinks.len(),
+                symbols.pin_name(*driver),
+                buffer
+            ),
+        };
+        format!(
+            "{:<70} gain {:.3} ns (endpoint {}{})",
+            action,
+            self.gain,
+            symbols.pin_name(self.endpoint.0),
+            self.endpoint.1
+        )
+    }
+}
+
+/// Equivalent resistance of an output for a transition, in Ohm
+fn drive_resistance(pdk: &PdkConfig, subckt: &Subckt, pin: &str, transition: Transition) -> Option<f32> {
+    let drive = subckt.output_pin_drive.get(pin)?;
+    Some(match transition {
+        Transition::Rise => pdk.pfet.eq_resistance * drive.rise_lw,
+        Transition::Fall => pdk.nfet.eq_resistance * drive.fall_lw,
+    })
+}
+
+/// Capacitance of an input, in pF
+fn input_capacitance(pdk: &PdkConfig, subckt: &Subckt, pin: &str) -> Option<f32> {
+    Some(subckt.input_pin_load.get(pin)?.capacitance(pdk))
+}
+
+/// Resistance of the output of `from` once resized to `to`, scaled by the ratio of the drive strengths.
+/// The resistance of the SUBCKT only counts the worst series path of transistors and misses the fingers
+/// of the bigger sizes, so it can't be used to compare two sizes.
+fn resized_resistance(res: f32, from: &str, to: &str) -> Option<f32> {
+    Some(res * drive_strength(from)? / drive_strength(to)?)
+}
+
+/// Delay in ns of a resistance (Ohm) charging a capacitance (pF)
+fn rc_delay(res: f32, cap: f32) -> f32 {
+    std::f32::consts::LN_2 * res * cap * 1e-3
+}
+
+/// Stronger or weaker sizes of a cell: same name up to the drive strength (`_1`, `_2`, ...) and same pins
+fn variants<'a>(subckts: &'a SubcktData, celltype: &str, stronger: bool) -> Vec<(&'a str, &'a Subckt)> {
+    let (Some((base, _)), Some(strength), Some(cell)) = (
+        celltype.rsplit_once('_'),
+        drive_strength(celltype),
+        subckts.data.get(celltype),
+    ) else {
+        return Vec::new();
+    };
+    let right_size =
+        |name: &str| drive_strength(name).is_some_and(|s| if stronger { s > strength } else { s < strength });
+    let same_pins = |other: &Subckt| {
+        other.input_pin_load.len() == cell.input_pin_load.len()
+            && cell
+                .input_pin_load
+                .keys()
+                .all(|pin| other.input_pin_load.contains_key(pin))
+            && other.output_pin_drive.len() == cell.output_pin_drive.len()
+            && cell
+                .output_pin_drive
+                .keys()
+                .all(|pin| other.output_pin_drive.contains_key(pin))
+    };
+    subckts
+        .data
+        .iter()
+        .filter(|(name, other)| {
+            name.rsplit_once('_').is_some_and(|(b, _)| b == base) && right_size(name) && same_pins(other)
+        })
+        .map(|(name, other)| (&**name, other))
+        .collect()
+}
+
+struct Eco<'a> {
+    graph: &'a SDFGraph,
+    analysis: &'a SDFGraphAnalyzed,
+    subckts: &'a SubcktData,
+}
+
+impl<'a> Eco<'a> {
+    fn subckt_of(&self, instance: InstanceId) -> Option<(&'a str, &'a Subckt)> {
+        let celltype = self.graph.instance_celltype.get(&instance)?;
+        Some((celltype, self.subckts.data.get(celltype)?))
+    }
+
+    fn is_interconnect(&self, src: PinId, dst: PinId) -> bool {
+        let src_instance = self.graph.symbols.pin_instance(src);
+        src_instance.is_none() || self.graph.symbols.pin_instance(dst) != src_instance
+    }
+
+    /// Pins on the net driven by `driver`
+    fn sinks(&self, driver: PinId) -> Vec<PinId> {
+        (self.graph.net_of(driver))
+            .filter(|net| net.driver == driver)
+            .map_or(Vec::new(), |net| net.sinks)
+    }
+
+    /// Capacitance of an input pin in pF, 0 for ports and unknown cells
+    fn pin_capacitance(&self, pin: PinId) -> f32 {
+        let pdk = &self.subckts.pdk;
+        let symbols = &self.graph.symbols;
+        symbols
+            .pin_instance(pin)
+            .and_then(|instance| self.subckt_of(instance))
+            .and_then(|(_, subckt)| input_capacitance(pdk, subckt, symbols.pin_short_name(pin)))
+            .unwrap_or(0.0)
+    }
+
+    fn net_load(&self, driver: PinId) -> f32 {
+        self.sinks(driver)
+            .into_iter()
+            .map(|sink| self.pin_capacitance(sink))
+            .sum()
+    }
+
+    fn driver_resistance(&self, pin: &PinTrans) -> Option<f32> {
+        let (_, subckt) = self.subckt_of(self.graph.symbols.pin_instance(pin.0)?)?;
+        drive_resistance(
+            &self.subckts.pdk,
+            subckt,
+            self.graph.symbols.pin_short_name(pin.0),
+            pin.1,
+        )
+    }
+
+    /// Smallest slack through the outputs of an instance minus the slack of the path, None if not reached
+    fn margin(&self, instance: InstanceId, path_slack: f32) -> Option<f32> {
+        let outs = self.graph.instance_outs.get(&instance)?;
+        outs.iter()
+            .flat_map(|&pin| [(pin, Transition::Rise), (pin, Transition::Fall)])
+            .filter_map(|pin| self.analysis.slack_through(self.graph, &pin))
+            .reduce(f32::min)
+            .map(|slack| slack - path_slack)
+    }
+
+    /// Stronger version of the cell of the `input -> output` arc, `upstream` being the pin driving `input`
+    fn upsize(&self, upstream: Option<&PinTrans>, input: &PinTrans, output: &PinTrans) -> Option<(EcoAction, f32)> {
+        let pdk = &self.subckts.pdk;
+        let symbols = &self.graph.symbols;
+        let instance = symbols.pin_instance(output.0)?;
+        let (celltype, cell) = self.subckt_of(instance)?;
+        let (in_name, out_name) = (symbols.pin_short_name(input.0), symbols.pin_short_name(output.0));
+
+        let res = drive_resistance(pdk, cell, out_name, output.1)?;
+        let cap = input_capacitance(pdk, cell, in_name)?;
+        let load = self.net_load(output.0);
+        let upstream_res = upstream.and_then(|pin| self.driver_resistance(pin)).unwrap_or(0.0);
+
+        variants(self.subckts, celltype, true)
+            .into_iter()
+            .filter_map(|(name, other)| {
+                let new_res = resized_resistance(res, celltype, name)?;
+                let new_cap = input_capacitance(pdk, other, in_name)?;
+                // the stronger cell is faster but loads its driver more
+                let gain = rc_delay(res - new_res, load) - rc_delay(upstream_res, new_cap - cap);
+                Some((name, gain))
+            })
+            .filter(|(_, gain)| *gain > 0.0)
+            .max_by_key(|(_, gain)| OrderedFloat(*gain))
+            .map(|(name, gain)| {
+                let action = EcoAction::Upsize {
+                    instance,
+                    from: celltype.to_string(),
+                    to: name.to_string(),
+                };
+                (action, gain)
+            })
+    }
+
+    /// Weaker versions of the off-path sinks of the net from `driver` to `next`, as long as they don't become critical
+    fn downsize_loads(
+        &self,
+        driver: &PinTrans,
+        next: &PinTrans,
+        path: &FxHashSet<InstanceId>,
+        path_slack: f32,
+    ) -> Vec<(EcoAction, f32)> {
+        let pdk = &self.subckts.pdk;
+        let symbols = &self.graph.symbols;
+        let Some(driver_res) = self.driver_resistance(driver) else {
+            return Vec::new();
+        };
+
+        let mut actions = Vec::new();
+        for sink in self.sinks(driver.0) {
+            let Some(instance) = symbols.pin_instance(sink) else {
+                continue;
+            };
+            if sink == next.0 || path.contains(&instance) {
+                continue;
+            }
+            let (Some((celltype, cell)), Some(margin)) = (self.subckt_of(instance), self.margin(instance, path_slack))
+            else {
+                continue;
+            };
+            let pin = symbols.pin_short_name(sink);
+            let Some(cap) = input_capacitance(pdk, cell, pin) else {
+                continue;
+            };
+            let outs: Vec<_> = self
+                .graph
+                .instance_outs
+                .get(&instance)
+                .into_iter()
+                .flatten()
+                .copied()
+                .collect();
+
+            let best = variants(self.subckts, celltype, false)
+                .into_iter()
+                .filter_map(|(name, other)| {
+                    let new_cap = input_capacitance(pdk, other, pin)?;
+                    // the weaker cell slows down its own fanout
+                    let penalty = outs
+                        .iter()
+                        .flat_map(|&out| [Transition::Rise, Transition::Fall].map(|t| (out, t)))
+                        .filter_map(|(out, t)| {
+                            let out_name = symbols.pin_short_name(out);
+                            let res = drive_resistance(pdk, cell, out_name, t)?;
+                            let new_res = resized_resistance(res, celltype, name)?;
+                            Some(rc_delay(new_res - res, self.net_load(out)))
+                        })
+                        .fold(0.0, f32::max);
+                    (penalty < margin).then(|| (name, rc_delay(driver_res, cap - new_cap)))
+                })
+                .filter(|(_, gain)| *gain > 0.0)
+                .max_by_key(|(_, gain)| OrderedFloat(*gain));
+
+            if let Some((name, gain)) = best {
+                let action = EcoAction::DownsizeLoad {
+                    instance,
+                    from: celltype.to_string(),
+                    to: name.to_string(),
+                };
+                actions.push((action, gain));
+            }
+        }
+        actions
+    }
+
+    /// Buffer the off-path sinks of a high fanout net, with the smallest buffer that keeps them off the critical path
+    fn buffer_fanout(&self, driver: &PinTrans, next: &PinTrans, path_slack: f32) -> Option<(EcoAction, f32)> {
+        let pdk = &self.subckts.pdk;
+        let sinks = self.sinks(driver.0);
+        if sinks.len() < HIGH_FANOUT {
+            return None;
+        }
+        let driver_res = self.driver_resistance(driver)?;
+
+        let off_path: Vec<PinId> = sinks.into_iter().filter(|&sink| sink != next.0).collect();
+        let off_path_cap: f32 = off_path.iter().map(|&sink| self.pin_capacitance(sink)).sum();
+        let margin = off_path
+            .iter()
+            .flat_map(|&sink| [(sink, Transition::Rise), (sink, Transition::Fall)])
+            .filter_map(|pin| self.analysis.slack_through(self.graph, &pin))
+            .reduce(f32::min)?
+            - path_slack;
+
+        let mut buffers: Vec<(&str, f32, f32)> = self
+            .subckts
+            .data
+            .iter()
+            .filter(|(name, _)| name.rsplit_once('_').is_some_and(|(base, _)| base.ends_with("__buf")))
+            .filter_map(|(name, subckt)| {
+                let res = drive_resistance(pdk, subckt, "X", Transition::Rise)?.max(drive_resistance(
+                    pdk,
+                    subckt,
+                    "X",
+                    Transition::Fall,
+                )?);
+                Some((&**name, input_capacitance(pdk, subckt, "A")?, res))
+            })
+            .collect();
+        buffers.sort_by_key(|(_, cap, _)| OrderedFloat(*cap));
+
+        let (buffer, cap, _) = buffers
+            .into_iter()
+            .find(|(_, _, res)| rc_delay(*res, off_path_cap) < margin)?;
+        let gain = rc_delay(driver_res, off_path_cap - cap);
+        if gain <= 0.0 {
+            return None;
+        }
+
+        Some((
+            EcoAction::BufferFanout {
+                driver: driver.0,
+                sinks: off_path,
+                buffer: buffer.to_string(),
+            },
+            gain,
+        ))
+    }
+}
+
+/// Suggest changes for the worst paths of the `top` endpoints with the smallest slack (one per pin),
+/// sorted by decreasing estimated gain. A change helping several paths is only listed once, with its best gain.
+pub fn suggest(graph: &SDFGraph, analysis: &SDFGraphAnalyzed, subckts: &SubcktData, top: usize) -> Vec<EcoSuggestion> {
+    let eco = Eco {
+        graph,
+        analysis,
+        subckts,
+    };
+    let symbols = &graph.symbols;
+
+    let mut suggestions: Vec<EcoSuggestion> = Vec::new();
+    let mut add = |action: EcoAction, gain: f32, endpoint: PinTrans| match suggestions
+        .iter_mut()
+        .find(|suggestion| suggestion.action == action)
+    {
+        Some(suggestion) if suggestion.gain < gain => {
+            suggestion.gain = gain;
+            suggestion.endpoint = endpoint;
+        }
+        Some(_) => {}
+        None => suggestions.push(EcoSuggestion { action, gain, endpoint }),
+    };
+
+    for (endpoint, slack) in analysis.endpoint_pins_by_slack(graph).into_iter().take(top) {
+        let mut path: Vec<PinTrans> = analysis
+            .extract_path(graph, &endpoint)
+            .into_iter()
+            .map(|(pin, _)| pin)
+            .collect();
+        path.push(endpoint);
+        let instances: FxHashSet<InstanceId> = path.iter().filter_map(|pin| symbols.pin_instance(pin.0)).collect();
+
+        for (i, stage) in path.windows(2).enumerate() {
+            let (from, to) = (&stage[0], &stage[1]);
+            if eco.is_interconnect(from.0, to.0) {
+                for (action, gain) in eco.downsize_loads(from, to, &instances, slack) {
+                    add(action, gain, endpoint);
+                }
+                if let Some((action, gain)) = eco.buffer_fanout(from, to, slack) {
+                    add(action, gain, endpoint);
+                }
+            } else {
+                let upstream = i.checked_sub(1).map(|j| &path[j]);
+                if let Some((action, gain)) = eco.upsize(upstream, from, to) {
+                    add(action, gain, endpoint);
+                }
+            }
+        }
+    }
+
+    suggestions.sort_by_key(|suggestion| Reverse(OrderedFloat(suggestion.gain)));
+    suggestions
+}
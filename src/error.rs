@@ -0,0 +1,86 @@
+//! Errors of the crate, returned instead of panicking so stars can be embedded in a long-running tool.
+
+use crate::def::DefError;
+use crate::liberty::LibertyError;
+use crate::opensta::StaReportError;
+#[cfg(feature = "spef")]
+use crate::parasitics::ParasiticsError;
+use crate::power::ActivityError;
+use crate::sdc::SdcError;
+use sdfparse::SDFSpan;
+
+#[derive(Debug, thiserror::Error)]
+pub enum StarsError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Unsupported SDF: {0}")]
+    UnsupportedSdf(String),
+    #[error("No unateness data for celltype {0}")]
+    UnknownCellType(String),
+    #[error("No unateness data for pin {pin} of celltype {celltype}")]
+    UnknownCellPin { celltype: String, pin: String },
+    #[error("Unknown instance {0}")]
+    UnknownInstance(String),
+    #[error("Unknown pin {0}")]
+    UnknownPin(String),
+    #[error("No subckt for celltype {0}")]
+    UnknownSubckt(String),
+    #[error("Invalid subckt line `{0}`")]
+    InvalidSubckt(String),
+    #[error("Missing value for pin {pin} for instance {instance}({celltype}), given values for [{available}]")]
+    MissingPinValue {
+        pin: String,
+        instance: String,
+        celltype: String,
+        /// pins that had a value, comma separated
+        available: String,
+    },
+    #[error("No subckt for the celltypes {} used in the design", .0.join(", "))]
+    MissingSubckts(Vec<String>),
+    #[error("SDF {span}: {source}")]
+    AtSdf { span: SDFSpan, source: Box<StarsError> },
+    #[error("Invalid PDK configuration: {0}")]
+    InvalidPdk(String),
+    #[error("Empty path to {0}")]
+    EmptyPath(String),
+    #[error("The path to {0} starts at the same instance")]
+    LoopPath(String),
+    #[error("Floating node {node} at {element} in the SPICE deck")]
+    FloatingNode { node: String, element: String },
+    #[cfg(feature = "html")]
+    #[error("HTML template: {0}")]
+    Template(#[from] minijinja::Error),
+    #[error("ngspice failed: {0}")]
+    Simulation(String),
+    #[cfg(feature = "spef")]
+    #[error(transparent)]
+    Parasitics(#[from] ParasiticsError),
+    #[error(transparent)]
+    Liberty(#[from] LibertyError),
+    #[error(transparent)]
+    Sdc(#[from] SdcError),
+    #[error(transparent)]
+    Def(#[from] DefError),
+    #[error(transparent)]
+    Activity(#[from] ActivityError),
+    #[error(transparent)]
+    StaReport(#[from] StaReportError),
+    #[cfg(feature = "serde")]
+    #[error("Invalid graph file: {0}")]
+    GraphFile(#[from] bincode::Error),
+}
+
+impl StarsError {
+    /// Point the error at its location in the SDF, when known
+    pub fn at(self, span: Option<SDFSpan>) -> Self {
+        match span {
+            Some(span) => Self::AtSdf {
+                span,
+                source: Box::new(self),
+            },
+            None => self,
+        }
+    }
+}
+
+pub type Result<T, E = StarsError> = std::result::Result<T, E>;
@@ -1,18 +1,134 @@
+use crate::csr::{CsrEdges, EdgeArena};
+use crate::delaycalc::DelayCalculator;
+use crate::error::{Result, StarsError};
+#[cfg(feature = "spef")]
+use crate::parasitics::{elmore_delay, Parasitics};
+use crate::progress::{Phase, PhaseProgress, Progress};
 use crate::types::{
-    InstanceMap, PinSet, PinTrans, PinTransMap, SDFCellType, SDFInstance, SDFPin, Transition, TriUnate,
+    Corner, InstanceId, InstanceMap, PinId, PinSet, PinTrans, PinTransMap, PinTransSet, SDFCellType, SDFPin,
+    SymbolTable, Transition, TriUnate,
 };
-use rustc_hash::FxHashMap;
-use sdfparse::{SDFBus, SDFDelay, SDFIOPathCond, SDFPath, SDFPort, SDFPortEdge, SDFValue};
+#[cfg(feature = "spef")]
+use ordered_float::OrderedFloat;
+use rustc_hash::{FxHashMap, FxHashSet};
+use sdfparse::{
+    SDFBus, SDFCell, SDFDelay, SDFIOPathCond, SDFPath, SDFPort, SDFPortEdge, SDFSpan, SDFTimingCheckKind, SDFValue,
+};
+use std::borrow::{Borrow, Cow};
+#[cfg(feature = "spef")]
+use std::cmp::Reverse;
+use std::sync::Arc;
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SDFEdge {
     pub dst: PinTrans,
     pub delay: f32,
+    /// Index in `SDFGraph::conditions` when the arc only exists under a COND (see `CondArcPolicy::Parallel`)
+    pub cond: Option<u32>,
+}
+
+/// What to do with IOPATHs that are only valid under a condition (COND/CONDELSE), common on muxes and AOI gates.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum CondArcPolicy {
+    /// Merge the arcs between the same pins into one edge with the worst (max) delay
+    #[default]
+    Worst,
+    /// Keep one parallel edge per condition, tagged with `SDFEdge::cond`
+    Parallel,
+}
+
+/// What to do with the interconnect delays of the SDF when the parasitics are known, see [`SDFGraph::apply_parasitics`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum InterconnectDelays {
+    /// Keep the SDF delays
+    #[default]
+    Sdf,
+    /// Keep the SDF delays, only compute their Elmore estimate to compare them
+    CrossCheck,
+    /// Replace the SDF delays by their Elmore estimate
+    Elmore,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SDFGraphOptions {
+    /// corner to read the delays at
+    pub corner: Corner,
+    pub conditional_arcs: CondArcPolicy,
+    /// alias of the instances, see [`SDFGraphOptions::rename`]
+    pub rename: Option<Rename>,
+    /// reports the cells read, see [`SDFGraphOptions::progress`]
+    pub progress: Option<Progress>,
+    /// drop the reverse graph once built, see [`SDFGraph::drop_reverse_graph`]
+    pub low_memory: bool,
 }
 
+impl SDFGraphOptions {
+    /// Give the instances the alias returned for their SDF name (e.g. to shorten the generated names of a flow),
+    /// kept in [`SDFGraph::aliases`]. The graph still uses the SDF names, so the SPEF, SPICE and SDC keep matching.
+    pub fn rename(mut self, rename: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        self.rename = Some(Rename(Arc::new(rename)));
+        self
+    }
+
+    /// Report the cells read to `progress` as [`Phase::GraphBuild`]
+    pub fn progress(mut self, progress: Progress) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Build the graph without its reverse graph, for the designs whose graph doesn't fit in memory,
+    /// see [`SDFGraph::drop_reverse_graph`]
+    pub fn low_memory(mut self, low_memory: bool) -> Self {
+        self.low_memory = low_memory;
+        self
+    }
+}
+
+/// Hook of [`SDFGraphOptions::rename`]
+#[derive(Clone)]
+pub struct Rename(Arc<dyn Fn(&str) -> String + Send + Sync>);
+
+impl std::fmt::Debug for Rename {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Rename(..)")
+    }
+}
+
+/// Setup and hold times (ns) of a data pin transition against the clock edge capturing it, from the TIMINGCHECK
+/// of the SDF, SETUPHOLD giving both. RECOVERY, REMOVAL and RECREM checks of the asynchronous pins are counted as
+/// setup and hold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimingCheck {
+    /// clock pin and edge of the check
+    pub clock: PinTrans,
+    pub setup: Option<f32>,
+    pub hold: Option<f32>,
+}
+
+/// Flip-flop or latch of the design
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Register {
+    /// clock pin (CLK, CLK_N, GATE or GATE_N) and the transition launching the data: Rise for posedge flops and
+    /// active-high latches, Fall for negedge flops and active-low latches
+    pub clock: PinTrans,
+    /// data input (D), an endpoint of the timing paths
+    pub data: PinId,
+    /// level-sensitive latch, opened by the `clock` transition.
+    /// Its D -> Q arcs are left out of the graph: paths end at D and start again at Q, without time borrowing.
+    pub latch: bool,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SDFGraph {
-    pub graph: PinTransMap<Vec<SDFEdge>>,
-    pub reverse_graph: PinTransMap<Vec<SDFEdge>>,
+    /// names of the pins and instances used as keys in the maps below
+    pub symbols: SymbolTable,
+    pub graph: CsrEdges,
+    /// edges of `graph` reversed, going from each node to the nodes with an arc to it. Read through
+    /// [`SDFGraph::fanin`], as it only keeps some of them in the low-memory mode.
+    pub reverse_graph: CsrEdges,
     pub instance_celltype: InstanceMap<String>,
     /// list of pin of input of the instance (e.g A)
     pub instance_ins: InstanceMap<PinSet>,
@@ -20,8 +136,55 @@ pub struct SDFGraph {
     pub instance_outs: InstanceMap<PinSet>,
     /// list of (input) pins that are connected to the output of this instance
     pub instance_fanout: InstanceMap<PinSet>,
+    /// list of (output) pins and ports that are connected to the inputs of this instance
+    pub instance_fanin: InstanceMap<PinSet>,
+    /// flip-flops and latches, found from their clock to output arcs
+    pub registers: InstanceMap<Register>,
+    /// setup and hold times of the data pins of the registers
+    pub timing_checks: PinTransMap<TimingCheck>,
+    /// clock port of the design, left out of the inputs (see [`SDFGraph::clock_arrivals`])
+    pub clock: Option<PinId>,
     pub inputs: Vec<PinTrans>,
     pub outputs: Vec<PinTrans>,
+    /// corner the delays were read at
+    pub corner: Corner,
+    /// COND expressions of the conditional arcs, indexed by `SDFEdge::cond`
+    pub conditions: Vec<String>,
+    /// nodes whose incoming delays were changed by an edit of the graph (e.g [`SDFGraph::swap_cell`])
+    pub dirty: PinTransSet,
+    /// pins of the cells connected on one side only, left out of the inputs and outputs
+    pub diagnostics: GraphDiagnostics,
+    /// names shown in the reports for the renamed instances, see [`SDFGraph::display_instance_name`]
+    pub aliases: InstanceMap<String>,
+    /// the reverse graph was dropped, see [`SDFGraph::drop_reverse_graph`]
+    pub low_memory: bool,
+}
+
+/// Pins of the cells that the SDF leaves half connected, see [`SDFGraph::diagnostics`].
+/// They would otherwise be taken for startpoints or endpoints, e.g a cell input without a net is a startpoint
+/// arriving at 0 and hides the real paths through the cell.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GraphDiagnostics {
+    /// IOPATH inputs driven by no INTERCONNECT
+    pub undriven: Vec<PinId>,
+    /// IOPATH outputs driving no INTERCONNECT
+    pub unloaded: Vec<PinId>,
+    /// INTERCONNECT sinks on a cell without IOPATH nor timing check from them, e.g antenna diodes
+    pub dangling: Vec<PinId>,
+}
+
+impl GraphDiagnostics {
+    pub fn is_empty(&self) -> bool {
+        self.undriven.is_empty() && self.unloaded.is_empty() && self.dangling.is_empty()
+    }
+
+    /// All the half connected pins, sorted
+    pub fn pins(&self) -> PinSet {
+        (self.undriven.iter().chain(&self.unloaded).chain(&self.dangling))
+            .copied()
+            .collect()
+    }
 }
 
 struct UnatenessData {
@@ -38,22 +201,20 @@ impl UnatenessData {
     }
 }
 
-fn extract_delay(value: &SDFValue) -> f32 {
+fn extract_delay(value: &SDFValue, corner: Corner) -> f32 {
     match *value {
         SDFValue::None => 0.0,
         SDFValue::Single(v) => v,
-        SDFValue::Multi(v, _, _) => v.unwrap_or(0.0),
+        SDFValue::Multi(min, typ, max) => corner.select(min, typ, max).unwrap_or(0.0),
     }
 }
 
-fn unique_name(path: &SDFPath, renaming: &FxHashMap<String, String>) -> SDFPin {
+/// Name of a SDF path in the graph, with `/` between the levels of hierarchy whatever the divider of the SDF.
+/// The other inputs (SPEF, SDC) are read with the same canonical divider.
+pub(crate) fn unique_name(path: &SDFPath) -> Result<SDFPin> {
     let mut name = String::new();
     for part in &path.path {
-        if let Some(v) = renaming.get(part.as_str()) {
-            name.push_str(&v);
-        } else {
-            name.push_str(part);
-        }
+        name.push_str(part);
         name.push('/');
     }
     name.pop();
@@ -64,14 +225,17 @@ fn unique_name(path: &SDFPath, renaming: &FxHashMap<String, String>) -> SDFPin {
             name.push_str(&b.to_string());
             name.push(']');
         }
-        SDFBus::BitRange(_, _) => {
-            unimplemented!("SDFBus::BitRange");
+        SDFBus::BitRange(from, to) => {
+            return Err(StarsError::UnsupportedSdf(format!(
+                "bit range {}[{}:{}]",
+                name, from, to
+            )));
         }
     }
-    name
+    Ok(name)
 }
 
-fn unique_name_port(cell_name: &SDFPin, port: &SDFPort) -> SDFPin {
+pub(crate) fn unique_name_port(cell_name: &SDFPin, port: &SDFPort) -> Result<SDFPin> {
     let mut name = cell_name.clone();
     name.push('/');
     name.push_str(&port.port_name);
@@ -82,365 +246,1714 @@ fn unique_name_port(cell_name: &SDFPin, port: &SDFPort) -> SDFPin {
             name.push_str(&b.to_string());
             name.push(']');
         }
-        SDFBus::BitRange(_, _) => {
-            unimplemented!("SDFBus::BitRange");
+        SDFBus::BitRange(from, to) => {
+            return Err(StarsError::UnsupportedSdf(format!(
+                "bit range {}[{}:{}]",
+                name, from, to
+            )));
         }
     }
-    name
+    Ok(name)
+}
+
+/// Whether an IOPATH from `input` to `output` is the clock to output arc of a register: Some(true) for a latch,
+/// Some(false) for a flip-flop, None if it isn't
+fn register_arc(input: &str, output: &str) -> Option<bool> {
+    if output != "Q" && output != "Q_N" {
+        return None;
+    }
+    match input {
+        "CLK" | "CLK_N" => Some(false),
+        "GATE" | "GATE_N" => Some(true),
+        _ => None,
+    }
 }
-fn parse_delays(value: &[SDFValue]) -> (f32, f32) {
+
+/// Transition of an edge specification, None when both transitions apply
+pub(crate) fn edge_transition(edge: &SDFPortEdge) -> Option<Transition> {
+    match edge {
+        SDFPortEdge::None => None,
+        SDFPortEdge::Posedge | SDFPortEdge::T01 | SDFPortEdge::T0Z | SDFPortEdge::TZ1 => Some(Transition::Rise),
+        SDFPortEdge::Negedge | SDFPortEdge::T10 | SDFPortEdge::T1Z | SDFPortEdge::TZ0 => Some(Transition::Fall),
+    }
+}
+
+pub(crate) fn parse_delays(value: &[SDFValue], corner: Corner) -> Result<(f32, f32)> {
     match value {
         [updown] => {
-            let v = extract_delay(updown);
-            (v, v)
+            let v = extract_delay(updown, corner);
+            Ok((v, v))
         }
-        [up, down] => (extract_delay(up), extract_delay(down)),
-        _ => panic!(
-            "Interconnect delay is not of length 1 or 2 (up, down), but {:?}",
+        [up, down] => Ok((extract_delay(up, corner), extract_delay(down, corner))),
+        _ => Err(StarsError::UnsupportedSdf(format!(
+            "delay is not of length 1 or 2 (up, down), but {}",
             value.len()
-        ),
+        ))),
+    }
+}
+
+/// Add the `src -> dst` arc to both the forward and reverse graphs.
+/// When `merge` is set and the arc already exists, only the worst delay is kept.
+fn add_arc(
+    graph: &mut PinTransMap<Vec<SDFEdge>>,
+    reverse_graph: &mut PinTransMap<Vec<SDFEdge>>,
+    src: PinTrans,
+    dst: PinTrans,
+    delay: f32,
+    cond: Option<u32>,
+    merge: bool,
+) {
+    let edges = graph.entry(src).or_default();
+    match edges.iter_mut().find(|e| merge && e.dst == dst) {
+        Some(edge) => edge.delay = edge.delay.max(delay),
+        None => edges.push(SDFEdge { dst, delay, cond }),
+    }
+
+    let edges = reverse_graph.entry(dst).or_default();
+    match edges.iter_mut().find(|e| merge && e.dst == src) {
+        Some(edge) => edge.delay = edge.delay.max(delay),
+        None => edges.push(SDFEdge { dst: src, delay, cond }),
+    }
+
+    graph.entry(dst).or_default();
+    reverse_graph.entry(src).or_default();
+}
+
+/// Full name of a path given inside a cell, e.g `A` in the cell of `u1` is `u1/A`
+pub(crate) fn cell_path(cell_name: &str, path: &SDFPath) -> Result<SDFPin> {
+    let name = unique_name(path)?;
+    if cell_name.is_empty() {
+        return Ok(name);
+    }
+    Ok(format!("{}/{}", cell_name, name))
+}
+
+/// Add the (up, down) delay of a PORT (`incoming`) or NETDELAY entry to the interconnects
+/// ending at `pin` or starting from it.
+fn add_wire_delay(
+    graph: &mut CsrEdges,
+    reverse_graph: &mut CsrEdges,
+    symbols: &SymbolTable,
+    pin: PinId,
+    (up, down): (f32, f32),
+    incoming: bool,
+) {
+    let instance = symbols.pin_instance(pin);
+    let is_wire = |other: PinId| instance.is_none() || symbols.pin_instance(other) != instance;
+
+    for (transition, delay) in [(Transition::Rise, up), (Transition::Fall, down)] {
+        let node = (pin, transition);
+        let (edges, other_edges) = if incoming {
+            (&mut *reverse_graph, &mut *graph)
+        } else {
+            (&mut *graph, &mut *reverse_graph)
+        };
+        let Some(edges) = edges.get_mut(&node) else {
+            continue;
+        };
+        for edge in edges.iter_mut().filter(|edge| is_wire(edge.dst.0)) {
+            edge.delay += delay;
+            for other in other_edges[&edge.dst].iter_mut().filter(|other| other.dst == node) {
+                other.delay += delay;
+            }
+        }
+    }
+}
+
+/// Set the delay of the arcs of its cell ending at the output `pin` for a DEVICE entry,
+/// or add to it when `increment` is set.
+fn set_device_delay(
+    graph: &mut CsrEdges,
+    reverse_graph: &mut CsrEdges,
+    symbols: &SymbolTable,
+    pin: PinId,
+    (up, down): (f32, f32),
+    increment: bool,
+) {
+    let instance = symbols.pin_instance(pin);
+
+    for (transition, delay) in [(Transition::Rise, up), (Transition::Fall, down)] {
+        let node = (pin, transition);
+        let Some(edges) = reverse_graph.get_mut(&node) else {
+            continue;
+        };
+        for edge in edges
+            .iter_mut()
+            .filter(|edge| instance.is_some() && symbols.pin_instance(edge.dst.0) == instance)
+        {
+            edge.delay = if increment { edge.delay + delay } else { delay };
+            for other in graph[&edge.dst].iter_mut().filter(|other| other.dst == node) {
+                other.delay = edge.delay;
+            }
+        }
+    }
+}
+
+/// Location of each delay of a cell, None when the spans weren't recorded
+fn spans(spans: &[SDFSpan]) -> impl Iterator<Item = Option<SDFSpan>> + '_ {
+    spans.iter().copied().map(Some).chain(std::iter::repeat(None))
+}
+
+/// Instance of the design-level CELL when the SDF writer gave it one, e.g. `(CELLTYPE "top") (INSTANCE top)`:
+/// the first cell, named after its celltype or the DESIGN of the header. Its paths are relative to the top.
+fn design_instance(sdf: &sdfparse::SDF) -> Option<String> {
+    let cell = sdf.cells.first()?;
+    let [name] = &cell.instance.as_ref()?.path[..] else {
+        return None;
+    };
+    (*name == cell.celltype || sdf.header.design_name.as_ref() == Some(name)).then(|| name.to_string())
+}
+
+/// Instance path of a cell from the top of the design, without the design-level instance some writers put
+/// in front of every instance. Empty for the design-level cell.
+fn cell_scope(cell: &SDFCell, design: Option<&str>) -> SDFPath {
+    let Some(instance) = &cell.instance else {
+        return SDFPath {
+            path: vec![],
+            bus: SDFBus::None,
+        };
+    };
+    let mut path = instance.path.clone();
+    if design.is_some() && path.first().map(|name| name.as_str()) == design {
+        path.remove(0);
+    }
+    SDFPath {
+        path,
+        bus: instance.bus,
     }
 }
 
-static DO_RENAMING: bool = false;
+/// Hierarchical instances of the design: the cells whose instance contains other instances and that have no
+/// arc of their own, e.g. `u_core` for `u_core/u1`. Their CELL only connects their ports to the cells inside.
+fn hierarchical_instances(
+    symbols: &SymbolTable,
+    instance_celltype: &InstanceMap<SDFCellType>,
+    (instance_ins, instance_outs): (&InstanceMap<PinSet>, &InstanceMap<PinSet>),
+) -> Vec<InstanceId> {
+    let mut parents = FxHashSet::default();
+    for (_, name) in symbols.instances().iter() {
+        parents.extend(name.match_indices('/').map(|(i, _)| &name[..i]));
+    }
+    instance_celltype
+        .keys()
+        .filter(|&instance| !instance_ins.contains_key(&instance) && !instance_outs.contains_key(&instance))
+        .filter(|&instance| parents.contains(symbols.instance_name(instance)))
+        .collect()
+}
+
+/// Replace the ports of the hierarchical instances by direct arcs from their drivers to their sinks, the delay
+/// of the interconnects on both sides of the port adding up, so the graph is the one of the flat netlist.
+/// The ports driven or loaded from outside the SDF (e.g. the SDF of one block) are kept.
+fn flatten_hierarchy(
+    (graph_edges, reverse_edges): (&mut CsrEdges, &mut CsrEdges),
+    symbols: &SymbolTable,
+    (instance_fanout, instance_fanin): (&mut InstanceMap<PinSet>, &mut InstanceMap<PinSet>),
+    hierarchical: &[InstanceId],
+) {
+    let hierarchical: FxHashSet<InstanceId> = hierarchical.iter().copied().collect();
+    let ports: Vec<PinId> = (symbols.pins().iter())
+        .map(|(pin, _)| pin)
+        .filter(|&pin| {
+            symbols
+                .pin_instance(pin)
+                .is_some_and(|instance| hierarchical.contains(&instance))
+        })
+        .collect();
+    // arcs are removed and added, on maps until the end
+    let (mut graph, mut reverse_graph) = (graph_edges.to_map(), reverse_edges.to_map());
+
+    for port in ports {
+        let nodes = [(port, Transition::Rise), (port, Transition::Fall)];
+        let connected = |edges: &PinTransMap<Vec<SDFEdge>>| {
+            nodes
+                .iter()
+                .any(|node| edges.get(node).is_some_and(|edges| !edges.is_empty()))
+        };
+        if !connected(&graph) || !connected(&reverse_graph) {
+            continue;
+        }
+        let mut driver_pins = PinSet::new();
+        let mut sink_pins = PinSet::new();
+        for node in nodes {
+            let drivers = reverse_graph.remove(&node).unwrap_or_default();
+            let sinks = graph.remove(&node).unwrap_or_default();
+            for driver in &drivers {
+                graph[&driver.dst].retain(|edge| edge.dst != node);
+            }
+            for sink in &sinks {
+                reverse_graph[&sink.dst].retain(|edge| edge.dst != node);
+            }
+            for driver in &drivers {
+                for sink in &sinks {
+                    let delay = driver.delay + sink.delay;
+                    add_arc(&mut graph, &mut reverse_graph, driver.dst, sink.dst, delay, None, true);
+                }
+            }
+            driver_pins.extend(drivers.iter().map(|edge| edge.dst.0));
+            sink_pins.extend(sinks.iter().map(|edge| edge.dst.0));
+        }
+
+        for &driver in &driver_pins {
+            if let Some(fanout) = symbols.pin_instance(driver).and_then(|i| instance_fanout.get_mut(&i)) {
+                fanout.remove(&port);
+                fanout.extend(&sink_pins);
+            }
+        }
+        for &sink in &sink_pins {
+            if let Some(fanin) = symbols.pin_instance(sink).and_then(|i| instance_fanin.get_mut(&i)) {
+                fanin.remove(&port);
+                fanin.extend(&driver_pins);
+            }
+        }
+    }
+
+    for instance in hierarchical {
+        instance_fanout.remove(&instance);
+        instance_fanin.remove(&instance);
+    }
+    *graph_edges = CsrEdges::from_map(&graph);
+    *reverse_edges = CsrEdges::from_map(&reverse_graph);
+}
 
 impl SDFGraph {
-    pub fn new(sdf: &sdfparse::SDF) -> Self {
-        let mut graph: PinTransMap<_> = Default::default();
-        let mut reverse_graph: PinTransMap<_> = Default::default();
+    /// Build the timing graph of the design, reading every delay triple at the given corner.
+    /// Fails on cells and pins without unateness data and on unsupported SDF constructs (e.g bus ranges).
+    pub fn new(sdf: &sdfparse::SDF, corner: Corner) -> Result<Self> {
+        Self::with_options(
+            sdf,
+            &SDFGraphOptions {
+                corner,
+                ..Default::default()
+            },
+        )
+    }
+
+    pub fn with_options(sdf: &sdfparse::SDF, options: &SDFGraphOptions) -> Result<Self> {
+        let design = design_instance(sdf);
+        Self::from_cells(&sdf.cells, design.as_deref(), options)
+    }
+
+    /// Same as [SDFGraph::new] but consumes the SDF, each cell is dropped as soon as its arcs are in the graph
+    /// so the whole AST and the graph are never held at the same time.
+    pub fn from_sdf(sdf: sdfparse::SDF, corner: Corner) -> Result<Self> {
+        Self::from_sdf_with(
+            sdf,
+            &SDFGraphOptions {
+                corner,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Same as [SDFGraph::from_sdf] with the given options
+    pub fn from_sdf_with(sdf: sdfparse::SDF, options: &SDFGraphOptions) -> Result<Self> {
+        let design = design_instance(&sdf);
+        Self::from_cells(sdf.cells, design.as_deref(), options)
+    }
+
+    fn from_cells<C: Borrow<SDFCell>, I: IntoIterator<Item = C>>(
+        cells: I,
+        design: Option<&str>,
+        options: &SDFGraphOptions,
+    ) -> Result<Self>
+    where
+        I::IntoIter: ExactSizeIterator,
+    {
+        let corner = options.corner;
+        // IOPATH and INTERCONNECT arcs, the graphs are built once every cell is read
+        let mut arena = EdgeArena::default();
         let mut instance_celltype: InstanceMap<_> = Default::default();
         let mut instance_ins: InstanceMap<_> = Default::default();
         let mut instance_outs: InstanceMap<_> = Default::default();
         let mut instance_fanout: InstanceMap<_> = Default::default();
+        let mut instance_fanin: InstanceMap<_> = Default::default();
+        let mut registers: InstanceMap<Register> = Default::default();
+        let mut register_outs = PinSet::new();
+        let mut timing_checks: PinTransMap<TimingCheck> = Default::default();
         let mut regs_d = vec![];
         let mut regs_q = vec![];
-        let mut renaming_map: FxHashMap<SDFInstance, String> = Default::default();
+        let mut conditions = Vec::new();
+        // first condition of the arcs from a pin with the given text, where its INCREMENTs go
+        let mut cond_ids: FxHashMap<(PinId, String), u32> = FxHashMap::default();
+        let mut symbols = SymbolTable::default();
+        // PORT and NETDELAY entries: (pin or net, (up, down), true for a PORT)
+        let mut wire_delays: Vec<(SDFPin, (f32, f32), bool)> = Vec::new();
+        // DEVICE entries: (instance, output pin or all of them, (up, down), true for an INCREMENT)
+        let mut device_delays = Vec::new();
 
         let unate = UnatenessData::new();
 
-        if DO_RENAMING {
-            let mut renaming_counter: FxHashMap<SDFInstance, usize> = Default::default();
-            for cell in &sdf.cells {
-                let old_cell_name = unique_name(
-                    cell.instance.as_ref().unwrap_or(&SDFPath {
-                        path: vec![],
-                        bus: SDFBus::None,
-                    }),
-                    &FxHashMap::default(),
-                );
-                let celltype_short = crate::celltype_short_with_size(&cell.celltype);
-                let rename_i = renaming_counter.entry(celltype_short.to_string()).or_insert(0);
-                *rename_i += 1;
-                let cell_name = format!("{rename_i:03}_{celltype_short}");
-                renaming_map.insert(old_cell_name, cell_name);
-            }
-        }
+        let cells = cells.into_iter();
+        let progress = PhaseProgress::start(options.progress.as_ref(), Phase::GraphBuild, cells.len(), 4096);
+        for (i, cell) in cells.enumerate() {
+            progress.update(i);
+            let cell = cell.borrow();
+            let cell_name = unique_name(&cell_scope(cell, design)).map_err(|e| e.at(cell.span))?;
+            let cell_id = symbols.intern_instance(&cell_name);
+            instance_celltype.insert(cell_id, cell.celltype.to_string());
 
-        for cell in &sdf.cells {
-            let cell_name = unique_name(
-                cell.instance.as_ref().unwrap_or(&SDFPath {
-                    path: vec![],
-                    bus: SDFBus::None,
-                }),
-                &renaming_map,
-            );
-            instance_celltype.insert(cell_name.clone(), cell.celltype.to_string());
+            let is_latch = cell.delays.iter().any(|delay| {
+                matches!(delay, SDFDelay::IOPath(_, io) if register_arc(&io.a.port.port_name, &io.b.port_name) == Some(true))
+            });
 
-            for delay in &cell.delays {
+            // INCREMENT sections apply on top of the ABSOLUTE values seen so far
+            let absolutes = cell.delays.iter().zip(spans(&cell.delay_spans));
+            let increments = cell.increments.iter().zip(spans(&cell.increment_spans));
+            let delays = (absolutes.map(|(delay, span)| (delay, span, false)))
+                .chain(increments.map(|(delay, span)| (delay, span, true)));
+            for (delay, span, increment) in delays {
+                let at = |e: StarsError| e.at(span);
                 match delay {
+                    SDFDelay::Port(port) => {
+                        let name = cell_path(&cell_name, &port.port).map_err(at)?;
+                        wire_delays.push((name, parse_delays(&port.delay, corner).map_err(at)?, true));
+                    }
+                    SDFDelay::NetDelay(net) => {
+                        let name = cell_path(&cell_name, &net.net).map_err(at)?;
+                        wire_delays.push((name, parse_delays(&net.delay, corner).map_err(at)?, false));
+                    }
+                    SDFDelay::Device { port, delay } => {
+                        let name = port
+                            .as_ref()
+                            .map(|port| unique_name_port(&cell_name, port))
+                            .transpose()
+                            .map_err(at)?;
+                        device_delays.push((cell_id, name, parse_delays(delay, corner).map_err(at)?, increment));
+                    }
                     SDFDelay::Interconnect(inter) => {
-                        let (up, down) = parse_delays(&inter.delay);
+                        let (up, down) = parse_delays(&inter.delay, corner).map_err(at)?;
 
-                        let a_name = unique_name(&inter.a, &renaming_map);
-                        let b_name = unique_name(&inter.b, &renaming_map);
+                        let a_name = symbols.intern_pin(&cell_path(&cell_name, &inter.a).map_err(at)?);
+                        let b_name = symbols.intern_pin(&cell_path(&cell_name, &inter.b).map_err(at)?);
 
-                        if let Some((instance_a, _)) = a_name.rsplit_once('/') {
+                        if let Some(instance_a) = symbols.pin_instance(a_name) {
                             instance_fanout
-                                .entry(instance_a.to_string())
+                                .entry(instance_a)
                                 .or_insert_with(PinSet::new)
-                                .insert(b_name.clone());
+                                .insert(b_name);
+                        }
+                        if let Some(instance_b) = symbols.pin_instance(b_name) {
+                            instance_fanin
+                                .entry(instance_b)
+                                .or_insert_with(PinSet::new)
+                                .insert(a_name);
                         }
 
-                        graph
-                            .entry((a_name.clone(), Transition::Rise))
-                            .or_insert_with(Vec::new)
-                            .push(SDFEdge {
-                                dst: (b_name.clone(), Transition::Rise),
-                                delay: up,
-                            });
-                        graph
-                            .entry((a_name.clone(), Transition::Fall))
-                            .or_insert_with(Vec::new)
-                            .push(SDFEdge {
-                                dst: (b_name.clone(), Transition::Fall),
-                                delay: down,
-                            });
-                        graph.entry((b_name.clone(), Transition::Rise)).or_insert_with(Vec::new);
-                        graph.entry((b_name.clone(), Transition::Fall)).or_insert_with(Vec::new);
-
-                        reverse_graph
-                            .entry((b_name.clone(), Transition::Rise))
-                            .or_insert_with(Vec::new)
-                            .push(SDFEdge {
-                                dst: (a_name.clone(), Transition::Rise),
-                                delay: up,
-                            });
-                        reverse_graph
-                            .entry((a_name.clone(), Transition::Rise))
-                            .or_insert_with(Vec::new);
-                        reverse_graph
-                            .entry((b_name.clone(), Transition::Fall))
-                            .or_insert_with(Vec::new)
-                            .push(SDFEdge {
-                                dst: (a_name.clone(), Transition::Fall),
-                                delay: down,
-                            });
-                        reverse_graph
-                            .entry((a_name.clone(), Transition::Fall))
-                            .or_insert_with(Vec::new);
-                        reverse_graph
-                            .entry((b_name.clone(), Transition::Rise))
-                            .or_insert_with(Vec::new);
+                        for (transition, delay) in [(Transition::Rise, up), (Transition::Fall, down)] {
+                            let (a, b) = ((a_name, transition), (b_name, transition));
+                            match increment {
+                                true => arena.increment(a, b, delay, None),
+                                false => arena.add(a, b, delay, None, false),
+                            }
+                        }
                     }
                     SDFDelay::IOPath(cond, io) => {
-                        let celltype_short = crate::celltype_short(&cell.celltype);
-                        let unate_pins = unate.data.get(celltype_short).unwrap_or_else(|| {
-                            panic!("No unateness data for celltype {}", celltype_short);
-                        });
+                        // (posedge CLK) and friends: only the given input transition launches the arc
+                        let input_edge = edge_transition(&io.a.edge_type);
 
-                        let SDFIOPathCond::None = cond else {
-                            panic!("IOPathCond is not None for {:?}", cell.instance);
-                        };
+                        let a_name = symbols.intern_pin(&unique_name_port(&cell_name, &io.a.port).map_err(at)?);
+                        let b_name = symbols.intern_pin(&unique_name_port(&cell_name, &io.b).map_err(at)?);
+
+                        instance_ins.entry(cell_id).or_insert_with(PinSet::new).insert(a_name);
+                        instance_outs.entry(cell_id).or_insert_with(PinSet::new).insert(b_name);
 
-                        if !matches!(io.a.edge_type, SDFPortEdge::None) {
-                            panic!("edge_type is not None for {:?}", cell.instance);
+                        if let Some(latch) = register_arc(&io.a.port.port_name, &io.b.port_name) {
+                            let d = symbols.intern_pin(&(cell_name.clone() + "/D"));
+                            // CLK_N and GATE_N are active low
+                            let edge = input_edge.unwrap_or(if io.a.port.port_name.ends_with("_N") {
+                                Transition::Fall
+                            } else {
+                                Transition::Rise
+                            });
+                            let register = Register {
+                                clock: (a_name, edge),
+                                data: d,
+                                latch,
+                            };
+                            if registers.insert(cell_id, register).is_none() {
+                                regs_d.push((d, Transition::Rise));
+                                regs_d.push((d, Transition::Fall));
+                            }
+                            if register_outs.insert(b_name) {
+                                regs_q.push((b_name, Transition::Rise));
+                                regs_q.push((b_name, Transition::Fall));
+                            }
                         }
 
-                        let a_name = unique_name_port(&cell_name, &io.a.port);
-                        let b_name = unique_name_port(&cell_name, &io.b);
-
-                        instance_ins
-                            .entry(cell_name.clone())
-                            .or_insert_with(PinSet::new)
-                            .insert(a_name.clone());
-                        instance_outs
-                            .entry(cell_name.clone())
-                            .or_insert_with(PinSet::new)
-                            .insert(b_name.clone());
-
-                        if io.a.port.port_name == "CLK" && io.b.port_name == "Q" {
-                            regs_d.push((cell_name.clone() + "/D", Transition::Rise));
-                            regs_d.push((cell_name.clone() + "/D", Transition::Fall));
-                            regs_q.push((cell_name.clone() + "/Q", Transition::Rise));
-                            regs_q.push((cell_name.clone() + "/Q", Transition::Fall));
+                        // the transparent path of the latches isn't timed, see `Register::latch`
+                        if is_latch && io.a.port.port_name == "D" {
+                            continue;
                         }
 
-                        let (up, down) = parse_delays(&io.delay);
-
-                        let unate = unate_pins.get(&io.a.port.port_name.to_string()).unwrap_or_else(|| {
-                            panic!(
-                                "No unateness data for pin {} of celltype {}",
-                                io.a.port.port_name, celltype_short
-                            );
-                        });
-
-                        match unate {
-                            TriUnate::Positive => {
-                                graph
-                                    .entry((a_name.clone(), Transition::Rise))
-                                    .or_insert_with(Vec::new)
-                                    .push(SDFEdge {
-                                        dst: (b_name.clone(), Transition::Rise),
-                                        delay: up,
-                                    });
-                                graph
-                                    .entry((a_name.clone(), Transition::Fall))
-                                    .or_insert_with(Vec::new)
-                                    .push(SDFEdge {
-                                        dst: (b_name.clone(), Transition::Fall),
-                                        delay: down,
-                                    });
-
-                                reverse_graph
-                                    .entry((b_name.clone(), Transition::Rise))
-                                    .or_insert_with(Vec::new)
-                                    .push(SDFEdge {
-                                        dst: (a_name.clone(), Transition::Rise),
-                                        delay: up,
-                                    });
-                                reverse_graph
-                                    .entry((b_name.clone(), Transition::Fall))
-                                    .or_insert_with(Vec::new)
-                                    .push(SDFEdge {
-                                        dst: (a_name.clone(), Transition::Fall),
-                                        delay: down,
-                                    });
+                        let (up, down) = parse_delays(&io.delay, corner).map_err(at)?;
+
+                        let unate = match input_edge {
+                            Some(_) => &TriUnate::Non,
+                            None => {
+                                let celltype_short = crate::celltype_short(&cell.celltype);
+                                let unate_pins = unate
+                                    .data
+                                    .get(celltype_short)
+                                    .ok_or_else(|| at(StarsError::UnknownCellType(celltype_short.to_string())))?;
+                                unate_pins.get(&io.a.port.port_name.to_string()).ok_or_else(|| {
+                                    at(StarsError::UnknownCellPin {
+                                        celltype: celltype_short.to_string(),
+                                        pin: io.a.port.port_name.to_string(),
+                                    })
+                                })?
+                            }
+                        };
+
+                        let arcs: &[(Transition, Transition, f32)] = match unate {
+                            TriUnate::Positive => &[
+                                (Transition::Rise, Transition::Rise, up),
+                                (Transition::Fall, Transition::Fall, down),
+                            ],
+                            TriUnate::Negative => &[
+                                (Transition::Rise, Transition::Fall, down),
+                                (Transition::Fall, Transition::Rise, up),
+                            ],
+                            TriUnate::Non => &[
+                                (Transition::Rise, Transition::Rise, up),
+                                (Transition::Fall, Transition::Fall, down),
+                                (Transition::Rise, Transition::Fall, down),
+                                (Transition::Fall, Transition::Rise, up),
+                            ],
+                        };
+
+                        let (cond, merge) = match (cond, options.conditional_arcs) {
+                            (SDFIOPathCond::None, _) => (None, false),
+                            (_, CondArcPolicy::Worst) => (None, true),
+                            (cond, CondArcPolicy::Parallel) => {
+                                let text = match cond {
+                                    SDFIOPathCond::Cond(_, expr) => expr.to_string(),
+                                    _ => "CONDELSE".to_string(),
+                                };
+                                // an INCREMENT goes to the arcs of the same condition
+                                let key = (a_name, text);
+                                let id = match cond_ids.get(&key) {
+                                    Some(&id) if increment => id,
+                                    _ => {
+                                        conditions.push(key.1.clone());
+                                        conditions.len() as u32 - 1
+                                    }
+                                };
+                                cond_ids.entry(key).or_insert(id);
+                                (Some(id), false)
                             }
-                            TriUnate::Negative => {
-                                graph
-                                    .entry((a_name.clone(), Transition::Rise))
-                                    .or_insert_with(Vec::new)
-                                    .push(SDFEdge {
-                                        dst: (b_name.clone(), Transition::Fall),
-                                        delay: down,
-                                    });
-                                graph
-                                    .entry((a_name.clone(), Transition::Fall))
-                                    .or_insert_with(Vec::new)
-                                    .push(SDFEdge {
-                                        dst: (b_name.clone(), Transition::Rise),
-                                        delay: up,
-                                    });
-
-                                reverse_graph
-                                    .entry((b_name.clone(), Transition::Rise))
-                                    .or_insert_with(Vec::new)
-                                    .push(SDFEdge {
-                                        dst: (a_name.clone(), Transition::Fall),
-                                        delay: up,
-                                    });
-
-                                reverse_graph
-                                    .entry((b_name.clone(), Transition::Fall))
-                                    .or_insert_with(Vec::new)
-                                    .push(SDFEdge {
-                                        dst: (a_name.clone(), Transition::Rise),
-                                        delay: down,
-                                    });
-                                reverse_graph
-                                    .entry((a_name.clone(), Transition::Rise))
-                                    .or_insert_with(Vec::new);
-                                reverse_graph
-                                    .entry((a_name.clone(), Transition::Fall))
-                                    .or_insert_with(Vec::new);
+                        };
+
+                        for &(t_a, t_b, delay) in arcs {
+                            if input_edge.is_some_and(|edge| edge != t_a) {
+                                continue;
                             }
-                            TriUnate::Non => {
-                                graph
-                                    .entry((a_name.clone(), Transition::Rise))
-                                    .or_insert_with(Vec::new)
-                                    .push(SDFEdge {
-                                        dst: (b_name.clone(), Transition::Rise),
-                                        delay: up,
-                                    });
-                                graph
-                                    .entry((a_name.clone(), Transition::Fall))
-                                    .or_insert_with(Vec::new)
-                                    .push(SDFEdge {
-                                        dst: (b_name.clone(), Transition::Fall),
-                                        delay: down,
-                                    });
-                                graph
-                                    .entry((a_name.clone(), Transition::Rise))
-                                    .or_insert_with(Vec::new)
-                                    .push(SDFEdge {
-                                        dst: (b_name.clone(), Transition::Fall),
-                                        delay: down,
-                                    });
-                                graph
-                                    .entry((a_name.clone(), Transition::Fall))
-                                    .or_insert_with(Vec::new)
-                                    .push(SDFEdge {
-                                        dst: (b_name.clone(), Transition::Rise),
-                                        delay: up,
-                                    });
-
-                                reverse_graph
-                                    .entry((b_name.clone(), Transition::Rise))
-                                    .or_insert_with(Vec::new)
-                                    .push(SDFEdge {
-                                        dst: (a_name.clone(), Transition::Rise),
-                                        delay: up,
-                                    });
-                                reverse_graph
-                                    .entry((b_name.clone(), Transition::Fall))
-                                    .or_insert_with(Vec::new)
-                                    .push(SDFEdge {
-                                        dst: (a_name.clone(), Transition::Fall),
-                                        delay: down,
-                                    });
-                                reverse_graph
-                                    .entry((b_name.clone(), Transition::Rise))
-                                    .or_insert_with(Vec::new)
-                                    .push(SDFEdge {
-                                        dst: (a_name.clone(), Transition::Fall),
-                                        delay: up,
-                                    });
-                                reverse_graph
-                                    .entry((b_name.clone(), Transition::Fall))
-                                    .or_insert_with(Vec::new)
-                                    .push(SDFEdge {
-                                        dst: (a_name.clone(), Transition::Rise),
-                                        delay: down,
-                                    });
+                            match increment {
+                                true => arena.increment((a_name, t_a), (b_name, t_b), delay, cond),
+                                false => arena.add((a_name, t_a), (b_name, t_b), delay, cond, merge),
                             }
                         }
+                    }
+                }
+            }
 
-                        graph.entry((b_name.clone(), Transition::Rise)).or_insert_with(Vec::new);
-                        graph.entry((b_name.clone(), Transition::Fall)).or_insert_with(Vec::new);
+            for check in &cell.timing_checks {
+                // the setup (or recovery) and hold (or removal) values of the check
+                let (setup, hold) = match check.kind {
+                    SDFTimingCheckKind::Setup | SDFTimingCheckKind::Recovery => (check.values.first(), None),
+                    SDFTimingCheckKind::Hold | SDFTimingCheckKind::Removal => (None, check.values.first()),
+                    SDFTimingCheckKind::Setuphold | SDFTimingCheckKind::Recrem => {
+                        (check.values.first(), check.values.get(1))
+                    }
+                    _ => continue,
+                };
+                let [data, clock] = &check.ports[..] else {
+                    continue;
+                };
+                if setup.is_none() && hold.is_none() {
+                    continue;
+                }
+                let data_pin =
+                    symbols.intern_pin(&unique_name_port(&cell_name, &data.spec.port).map_err(|e| e.at(cell.span))?);
+                let clock_pin =
+                    symbols.intern_pin(&unique_name_port(&cell_name, &clock.spec.port).map_err(|e| e.at(cell.span))?);
+                let clock = (
+                    clock_pin,
+                    edge_transition(&clock.spec.edge_type).unwrap_or(Transition::Rise),
+                );
 
-                        reverse_graph
-                            .entry((a_name.clone(), Transition::Rise))
-                            .or_insert_with(Vec::new);
-                        reverse_graph
-                            .entry((a_name.clone(), Transition::Fall))
-                            .or_insert_with(Vec::new);
+                let transitions = match edge_transition(&data.spec.edge_type) {
+                    Some(transition) => vec![transition],
+                    None => vec![Transition::Rise, Transition::Fall],
+                };
+                for transition in transitions {
+                    let entry = timing_checks.entry((data_pin, transition)).or_insert(TimingCheck {
+                        clock,
+                        setup: None,
+                        hold: None,
+                    });
+                    // conditional checks: the worst one is kept
+                    for (value, time) in [(setup, &mut entry.setup), (hold, &mut entry.hold)] {
+                        if let Some(value) = value {
+                            let value = extract_delay(value, corner);
+                            *time = Some(time.map_or(value, |time| time.max(value)));
+                        }
                     }
                 }
             }
         }
 
+        let (mut graph, mut reverse_graph) = arena.finish();
+
+        for (name, delays, is_port) in wire_delays {
+            let Some(pin) = symbols.pin_id(&name) else {
+                log::warn!(
+                    "SDF: {} of the PORT/NETDELAY is not a pin of the design, ignoring it",
+                    name
+                );
+                continue;
+            };
+            add_wire_delay(&mut graph, &mut reverse_graph, &symbols, pin, delays, is_port);
+        }
+
+        // after the IOPATHs, which may come later in the cell
+        for (instance, name, delays, increment) in device_delays {
+            let pins: Vec<PinId> = match name {
+                Some(name) => match symbols.pin_id(&name) {
+                    Some(pin) => vec![pin],
+                    None => {
+                        log::warn!("SDF: {} of the DEVICE has no IOPATH, ignoring it", name);
+                        continue;
+                    }
+                },
+                None => instance_outs.get(&instance).into_iter().flatten().copied().collect(),
+            };
+            for pin in pins {
+                set_device_delay(&mut graph, &mut reverse_graph, &symbols, pin, delays, increment);
+            }
+        }
+
+        let hierarchical = hierarchical_instances(&symbols, &instance_celltype, (&instance_ins, &instance_outs));
+        if !hierarchical.is_empty() {
+            flatten_hierarchy(
+                (&mut graph, &mut reverse_graph),
+                &symbols,
+                (&mut instance_fanout, &mut instance_fanin),
+                &hierarchical,
+            );
+            for instance in &hierarchical {
+                instance_celltype.remove(instance);
+            }
+        }
+        // in the order of the edges derived by `fanin` in the low-memory mode, for the same paths in both modes
+        reverse_graph.sort_edges();
+
+        let diagnostics = GraphDiagnostics::new(
+            &symbols,
+            (&graph, &reverse_graph),
+            (&instance_ins, &instance_outs),
+            &registers,
+            &timing_checks,
+        );
+        if !diagnostics.is_empty() {
+            log::warn!(
+                "SDF: {} undriven cell inputs, {} unloaded cell outputs and {} dangling sinks are not startpoints \
+                 nor endpoints, see SDFGraph::diagnostics",
+                diagnostics.undriven.len(),
+                diagnostics.unloaded.len(),
+                diagnostics.dangling.len()
+            );
+        }
+        let half_connected = diagnostics.pins();
+
         let mut outputs: Vec<PinTrans> = Vec::new();
         let mut inputs: Vec<PinTrans> = Vec::new();
 
         for (key, edges) in &graph {
-            if edges.is_empty() {
-                outputs.push(key.clone());
+            if edges.is_empty() && !half_connected.contains(&key.0) {
+                outputs.push(key);
             }
         }
 
         for (key, edges) in &reverse_graph {
-            if edges.is_empty() {
-                inputs.push(key.clone());
+            if edges.is_empty() && !half_connected.contains(&key.0) {
+                inputs.push(key);
             }
         }
 
-        inputs.sort_unstable();
-        outputs.sort_unstable();
+        inputs.sort_unstable_by(|a, b| (symbols.pin_name(a.0), a.1).cmp(&(symbols.pin_name(b.0), b.1)));
 
-        let mut clk = None;
-        if graph.contains_key(&("clk".to_string(), Transition::Rise)) {
-            clk = Some("clk".to_string());
-        } else if graph.contains_key(&("clock".to_string(), Transition::Rise)) {
-            clk = Some("clock".to_string());
-        } else {
-            eprintln!("Warning: No clock (clk) signal found");
+        let find_port = |names: &[&str]| {
+            names
+                .iter()
+                .filter_map(|name| symbols.pin_id(name))
+                .find(|&pin| graph.contains_key(&(pin, Transition::Rise)))
+        };
+
+        let clk = find_port(&["clk", "clock"]);
+        if clk.is_none() {
+            log::warn!("No clock (clk) signal found");
         }
 
-        let mut rst = None;
-        if graph.contains_key(&("rst".to_string(), Transition::Rise)) {
-            rst = Some("rst".to_string());
-        } else if graph.contains_key(&("reset".to_string(), Transition::Rise)) {
-            rst = Some("reset".to_string());
-        } else if graph.contains_key(&("resetn".to_string(), Transition::Rise)) {
-            rst = Some("resetn".to_string());
-        } else {
-            eprintln!("Warning: No reset (rst) signal found");
+        let rst = find_port(&["rst", "reset", "resetn"]);
+        if rst.is_none() {
+            log::warn!("No reset (rst) signal found");
         }
 
-        inputs.retain(|v| Some(&v.0) != clk.as_ref() && Some(&v.0) != rst.as_ref());
+        inputs.retain(|v| Some(v.0) != clk && Some(v.0) != rst);
         inputs.extend(regs_q.iter().cloned());
 
+        // the D pins of the registers are leaves of the graph too, each endpoint is listed once
         outputs.extend(regs_d.iter().cloned());
+        outputs.sort_unstable_by(|a, b| (symbols.pin_name(a.0), a.1).cmp(&(symbols.pin_name(b.0), b.1)));
+        outputs.dedup();
 
-        SDFGraph {
+        let aliases = match &options.rename {
+            Some(Rename(rename)) => (instance_celltype.keys())
+                .filter_map(|instance| {
+                    let name = symbols.instance_name(instance);
+                    let alias = rename(name);
+                    (!name.is_empty() && alias != name).then_some((instance, alias))
+                })
+                .collect(),
+            None => InstanceMap::default(),
+        };
+        progress.finish();
+
+        Ok(SDFGraph {
+            symbols,
             graph,
             reverse_graph,
             instance_celltype,
             instance_ins,
             instance_outs,
             instance_fanout,
+            instance_fanin,
+            registers,
+            timing_checks,
+            clock: clk,
             inputs,
             outputs,
+            corner,
+            conditions,
+            dirty: PinTransSet::new(),
+            diagnostics,
+            aliases,
+            low_memory: false,
+        }
+        .with_low_memory(options.low_memory))
+    }
+}
+
+impl GraphDiagnostics {
+    /// Look for the half connected pins of the cells, once all the arcs of the SDF are in the graph
+    fn new(
+        symbols: &SymbolTable,
+        (graph, reverse_graph): (&CsrEdges, &CsrEdges),
+        (instance_ins, instance_outs): (&InstanceMap<PinSet>, &InstanceMap<PinSet>),
+        registers: &InstanceMap<Register>,
+        timing_checks: &PinTransMap<TimingCheck>,
+    ) -> Self {
+        fn edges_of(edges: &CsrEdges, pin: PinId) -> impl Iterator<Item = &SDFEdge> {
+            [Transition::Rise, Transition::Fall]
+                .into_iter()
+                .flat_map(move |transition| edges.get(&(pin, transition)).into_iter().flatten())
+        }
+        let has_net =
+            |edges: &CsrEdges, pin: PinId| edges_of(edges, pin).any(|edge| is_interconnect(symbols, pin, edge.dst.0));
+
+        let mut diagnostics = GraphDiagnostics::default();
+        // pins where a path legitimately starts or ends inside a cell
+        let mut cell_pins: PinSet = instance_ins
+            .values()
+            .chain(instance_outs.values())
+            .flatten()
+            .copied()
+            .collect();
+        cell_pins.extend(registers.values().map(|register| register.data));
+        for pins in instance_ins.values() {
+            diagnostics
+                .undriven
+                .extend(pins.iter().filter(|&&pin| !has_net(reverse_graph, pin)));
+        }
+        for pins in instance_outs.values() {
+            diagnostics
+                .unloaded
+                .extend(pins.iter().filter(|&&pin| !has_net(graph, pin)));
+        }
+        for (pin, _) in symbols.pins().iter() {
+            let dangling = symbols.pin_instance(pin).is_some()
+                && !cell_pins.contains(&pin)
+                && edges_of(graph, pin).next().is_none()
+                && edges_of(reverse_graph, pin).next().is_some()
+                && !timing_checks.contains_key(&(pin, Transition::Rise))
+                && !timing_checks.contains_key(&(pin, Transition::Fall));
+            if dangling {
+                diagnostics.dangling.push(pin);
+            }
+        }
+
+        diagnostics.undriven.sort_unstable();
+        diagnostics.undriven.dedup();
+        diagnostics.unloaded.sort_unstable();
+        diagnostics.unloaded.dedup();
+        diagnostics
+    }
+}
+
+impl SDFGraph {
+    /// Clock pin and edge of a register
+    pub fn register_clock(&self, instance: InstanceId) -> Option<PinTrans> {
+        Some(self.registers.get(&instance)?.clock)
+    }
+
+    /// Whether the pin is the clock pin of a register
+    pub fn is_register_clock(&self, pin: PinId) -> bool {
+        self.symbols
+            .pin_instance(pin)
+            .and_then(|instance| self.registers.get(&instance))
+            .is_some_and(|register| register.clock.0 == pin)
+    }
+
+    /// Output pin or input port driving the net of a pin, None for the input ports and the undriven pins
+    pub fn driver(&self, pin: PinId) -> Option<PinId> {
+        let fanin = self.fanin(&(pin, Transition::Rise));
+        let mut edges = fanin.iter();
+        match self.symbols.pin_instance(pin) {
+            Some(instance) => {
+                let drivers = self.instance_fanin.get(&instance)?;
+                edges.map(|edge| edge.dst.0).find(|driver| drivers.contains(driver))
+            }
+            // output ports are only reached through their net
+            None => edges.next().map(|edge| edge.dst.0),
+        }
+    }
+
+    /// Name of the instance in the reports: its alias (see [`SDFGraphOptions::rename`]) or its SDF name
+    pub fn display_instance_name(&self, instance: InstanceId) -> &str {
+        match self.aliases.get(&instance) {
+            Some(alias) => alias,
+            None => self.symbols.instance_name(instance),
+        }
+    }
+
+    /// Name of the pin in the reports, with the alias of its instance, e.g `007_nand2_1/A`
+    pub fn display_pin_name(&self, pin: PinId) -> Cow<'_, str> {
+        match self
+            .symbols
+            .pin_instance(pin)
+            .and_then(|instance| self.aliases.get(&instance))
+        {
+            Some(alias) => Cow::Owned(format!("{}/{}", alias, self.symbols.pin_short_name(pin))),
+            None => Cow::Borrowed(self.symbols.pin_name(pin)),
+        }
+    }
+
+    /// Alias every instance after its cell and its rank among the instances of that cell, e.g `007_nand2_1`,
+    /// for designs whose instance names are too long to read in the reports
+    pub fn alias_by_celltype(&mut self) {
+        let mut counts: FxHashMap<&str, usize> = FxHashMap::default();
+        for (instance, celltype) in self.instance_celltype.iter() {
+            if self.symbols.instance_name(instance).is_empty() {
+                continue;
+            }
+            let celltype = crate::celltype_short_with_size(celltype);
+            let count = counts.entry(celltype).or_insert(0);
+            *count += 1;
+            self.aliases.insert(instance, format!("{count:03}_{celltype}"));
+        }
+    }
+
+    /// Clock edge launching the data of a startpoint, None if it isn't the output of a register
+    pub fn launch_clock(&self, startpoint: &PinTrans) -> Option<PinTrans> {
+        let instance = self.symbols.pin_instance(startpoint.0)?;
+        if !self.instance_outs.get(&instance)?.contains(&startpoint.0) {
+            return None;
         }
+        self.register_clock(instance)
+    }
+
+    /// Delay of the arc from the clock edge `clock` of a register to its output `q`, 0 without such an arc
+    pub fn clock_to_q(&self, q: &PinTrans, clock: &PinTrans) -> f32 {
+        self.fanin(q)
+            .iter()
+            .filter(|edge| edge.dst == *clock)
+            .map(|edge| edge.delay)
+            .reduce(f32::max)
+            .unwrap_or(0.0)
+    }
+
+    /// Clock edge capturing the data of an endpoint, None if it isn't the input of a register
+    pub fn capture_clock(&self, endpoint: &PinTrans) -> Option<PinTrans> {
+        if let Some(check) = self.timing_checks.get(endpoint) {
+            return Some(check.clock);
+        }
+        self.register_clock(self.symbols.pin_instance(endpoint.0)?)
+    }
+
+    /// Propagate the clocks from `sources` (the clock port of the graph when empty) through the clock tree, up to
+    /// the clock pins of the registers. Returns the latest (`f32::max`) or earliest (`f32::min`) arrival time of the
+    /// clock at each of these pins, for both edges.
+    pub fn clock_arrivals(&self, sources: &PinSet, worst: fn(f32, f32) -> f32) -> PinTransMap<f32> {
+        let mut arrivals: PinTransMap<f32> = PinTransMap::new();
+        let sources = match (sources.is_empty(), self.clock) {
+            (false, _) => Cow::Borrowed(sources),
+            (true, Some(clock)) => Cow::Owned(PinSet::from_iter([clock])),
+            (true, None) => return arrivals,
+        };
+
+        let register_clocks: PinSet = self.registers.values().map(|register| register.clock.0).collect();
+
+        let mut clock_tree: PinTransMap<f32> = PinTransMap::new();
+        for node in self.levels().into_iter().flatten() {
+            let arrival = if sources.contains(&node.0) {
+                Some(0.0)
+            } else {
+                // the clock stops at the registers, it doesn't go through their CLK -> Q arcs
+                self.fanin(&node)
+                    .iter()
+                    .filter(|edge| !register_clocks.contains(&edge.dst.0))
+                    .filter_map(|edge| Some(clock_tree.get(&edge.dst)? + edge.delay))
+                    .reduce(worst)
+            };
+            let Some(arrival) = arrival else {
+                continue;
+            };
+            clock_tree.insert(node, arrival);
+            if register_clocks.contains(&node.0) {
+                arrivals.insert(node, arrival);
+            }
+        }
+        arrivals
+    }
+
+    /// Nodes reachable from `pin` (both transitions) following the arcs forward, `pin` included.
+    /// `depth` limits the number of arcs from `pin`, a cell and the net reaching it being two arcs. None goes up to
+    /// the endpoints.
+    pub fn fanout_cone(&self, pin: PinId, depth: Option<usize>) -> PinTransSet {
+        cone(self, pin, depth, |node| self.fanout(node).into())
+    }
+
+    /// Nodes reaching `pin` (both transitions) following the arcs backward, `pin` included.
+    /// See [`SDFGraph::fanout_cone`] for `depth`.
+    pub fn fanin_cone(&self, pin: PinId, depth: Option<usize>) -> PinTransSet {
+        cone(self, pin, depth, |node| self.fanin(node))
+    }
+
+    /// Graph of the paths from `startpoints` to `endpoints` only, to analyze one block of a large design without
+    /// propagating the delays through the rest of it. An empty set stands for all the startpoints (or endpoints)
+    /// of the graph, and internal pins can be given to cut the paths there.
+    ///
+    /// The side inputs of the cells on these paths are left out, as well as anything after the endpoints. The
+    /// clock tree of the registers kept is kept too, for the propagated clocks. Pins and instances keep their ids.
+    pub fn subgraph(&self, startpoints: &[PinId], endpoints: &[PinId]) -> SDFGraph {
+        let nodes_of = |pins: &[PinId], default: &[PinTrans]| -> Vec<PinTrans> {
+            if pins.is_empty() {
+                return default.to_vec();
+            }
+            (pins.iter())
+                .flat_map(|&pin| [(pin, Transition::Rise), (pin, Transition::Fall)])
+                .filter(|node| self.graph.contains_key(node))
+                .collect()
+        };
+        let inputs = nodes_of(startpoints, &self.inputs);
+        let outputs = nodes_of(endpoints, &self.outputs);
+
+        let mut fanout = PinTransSet::new();
+        for node in &inputs {
+            if !fanout.contains(node) {
+                fanout.extend(self.fanout_cone(node.0, None));
+            }
+        }
+        let mut keep = PinTransSet::new();
+        for node in &outputs {
+            if !keep.contains(node) {
+                keep.extend(self.fanin_cone(node.0, None).intersection(&fanout));
+            }
+        }
+        let instances: FxHashSet<InstanceId> = keep
+            .iter()
+            .filter_map(|node| self.symbols.pin_instance(node.0))
+            .collect();
+        let registers: InstanceMap<Register> = (self.registers.iter())
+            .filter(|(instance, _)| instances.contains(instance))
+            .map(|(instance, register)| (instance, *register))
+            .collect();
+        for register in registers.values() {
+            keep.extend(self.fanin_cone(register.clock.0, None));
+        }
+        // with the buffers of the clock tree
+        let instances: FxHashSet<InstanceId> = keep
+            .iter()
+            .filter_map(|node| self.symbols.pin_instance(node.0))
+            .collect();
+        let keep_pin =
+            |pin: &PinId| keep.contains(&(*pin, Transition::Rise)) || keep.contains(&(*pin, Transition::Fall));
+
+        let edges_within = |reverse: bool| -> CsrEdges {
+            (keep.iter())
+                .map(|node| {
+                    let edges = if reverse {
+                        self.fanin(node)
+                    } else {
+                        self.fanout(node).into()
+                    };
+                    (
+                        *node,
+                        edges.iter().filter(|edge| keep.contains(&edge.dst)).cloned().collect(),
+                    )
+                })
+                .collect()
+        };
+        let pins_within = |map: &InstanceMap<PinSet>| -> InstanceMap<PinSet> {
+            (map.iter())
+                .map(|(instance, pins)| (instance, pins.iter().copied().filter(keep_pin).collect::<PinSet>()))
+                .filter(|(_, pins)| !pins.is_empty())
+                .collect()
+        };
+        let keep_all = |pins: &[PinId]| pins.iter().copied().filter(keep_pin).collect();
+
+        SDFGraph {
+            symbols: self.symbols.clone(),
+            graph: edges_within(false),
+            reverse_graph: edges_within(true),
+            instance_celltype: (self.instance_celltype.iter())
+                .filter(|(instance, _)| instances.contains(instance))
+                .map(|(instance, celltype)| (instance, celltype.clone()))
+                .collect(),
+            instance_ins: pins_within(&self.instance_ins),
+            instance_outs: pins_within(&self.instance_outs),
+            instance_fanout: pins_within(&self.instance_fanout),
+            instance_fanin: pins_within(&self.instance_fanin),
+            registers,
+            timing_checks: (self.timing_checks.iter())
+                .filter(|(node, _)| keep.contains(node))
+                .map(|(node, check)| (node, *check))
+                .collect(),
+            clock: self.clock.filter(keep_pin),
+            inputs: inputs.into_iter().filter(|node| keep.contains(node)).collect(),
+            outputs: outputs.into_iter().filter(|node| keep.contains(node)).collect(),
+            corner: self.corner,
+            conditions: self.conditions.clone(),
+            dirty: PinTransSet::new(),
+            diagnostics: GraphDiagnostics {
+                undriven: keep_all(&self.diagnostics.undriven),
+                unloaded: keep_all(&self.diagnostics.unloaded),
+                dangling: keep_all(&self.diagnostics.dangling),
+            },
+            aliases: (self.aliases.iter())
+                .filter(|(instance, _)| instances.contains(instance))
+                .map(|(instance, alias)| (instance, alias.clone()))
+                .collect(),
+            low_memory: false,
+        }
+        .with_low_memory(self.low_memory)
+    }
+
+    /// Group the nodes by level: every node comes after the nodes its `reverse_graph` edges point to,
+    /// so all the nodes of a level can be computed at once from the previous levels.
+    /// Going through the levels backwards gives the same guarantee for the `graph` edges.
+    ///
+    /// Nodes that are part of a combinational loop are left out.
+    pub fn levels(&self) -> Vec<Vec<PinTrans>> {
+        // inputs of each node, counted from the forward edges as the reverse graph may be dropped
+        let mut remaining: PinTransMap<usize> = self.graph.keys().map(|node| (node, 0)).collect();
+        for edge in self.graph.values().flatten() {
+            remaining[&edge.dst] += 1;
+        }
+
+        let mut level: Vec<PinTrans> = remaining
+            .iter()
+            .filter(|(_, &n)| n == 0)
+            .map(|(node, _)| node)
+            .collect();
+        let mut levels = Vec::new();
+
+        while !level.is_empty() {
+            let mut next = Vec::new();
+            for node in &level {
+                for edge in self.graph.get(node).into_iter().flatten() {
+                    let Some(n) = remaining.get_mut(&edge.dst) else {
+                        continue;
+                    };
+                    *n -= 1;
+                    if *n == 0 {
+                        next.push(edge.dst);
+                    }
+                }
+            }
+            levels.push(level);
+            level = next;
+        }
+
+        levels
+    }
+
+    /// Edges from `node` to the nodes it has an arc to, empty for a node outside of the graph
+    pub fn fanout(&self, node: &PinTrans) -> &[SDFEdge] {
+        self.graph.edges(node)
+    }
+
+    /// Edges from `node` back to the nodes with an arc to it, empty for a node outside of the graph.
+    /// Derived from the forward edges in the low-memory mode, see [`SDFGraph::drop_reverse_graph`].
+    pub fn fanin(&self, node: &PinTrans) -> Cow<'_, [SDFEdge]> {
+        match self.reverse_graph.get(node) {
+            Some(edges) => Cow::Borrowed(edges),
+            None if self.low_memory => Cow::Owned(self.derive_fanin(node)),
+            None => Cow::Borrowed(&[]),
+        }
+    }
+
+    /// Backward edges of `node` from the forward edges of the pins that may have an arc to it: the inputs of its
+    /// cell (cell arcs) and the pins connected to them (nets). The ports have none, their fanin is kept.
+    fn derive_fanin(&self, node: &PinTrans) -> Vec<SDFEdge> {
+        let Some(instance) = self.symbols.pin_instance(node.0) else {
+            return Vec::new();
+        };
+        let mut sources: Vec<PinId> = (self.instance_ins.get(&instance).into_iter().flatten())
+            .chain(self.instance_fanin.get(&instance).into_iter().flatten())
+            .copied()
+            .collect();
+        sources.sort_unstable();
+        sources.dedup();
+        let mut fanin = Vec::new();
+        for src in sources
+            .into_iter()
+            .flat_map(|pin| [(pin, Transition::Rise), (pin, Transition::Fall)])
+        {
+            let edges = self.graph.edges(&src).iter().filter(|edge| edge.dst == *node);
+            fanin.extend(edges.map(|edge| SDFEdge { dst: src, ..*edge }));
+        }
+        fanin
+    }
+
+    /// Backward edges of `node` kept in the reverse graph, to update along with the forward ones
+    pub(crate) fn reverse_edges_mut(&mut self, node: &PinTrans) -> impl Iterator<Item = &mut SDFEdge> {
+        self.reverse_graph.get_mut(node).into_iter().flatten()
+    }
+
+    /// Low-memory mode: drop the reverse graph, which takes as much memory as the graph itself, and derive the
+    /// backward edges from the forward ones when they are needed (see [`SDFGraph::fanin`]). Only the backward
+    /// edges that can't be derived are kept, mostly the ones of the output ports.
+    ///
+    /// The analysis and the reports give the same results, a bit slower as every node looks for its inputs
+    /// through the outputs of its cell's neighbors.
+    pub fn drop_reverse_graph(&mut self) {
+        if self.low_memory {
+            return;
+        }
+        self.low_memory = true;
+        let reverse_graph = std::mem::take(&mut self.reverse_graph);
+        let kept: PinTransMap<Vec<SDFEdge>> = (reverse_graph.iter())
+            .filter(|&(node, edges)| self.derive_fanin(&node) != edges)
+            .map(|(node, edges)| (node, edges.to_vec()))
+            .collect();
+        self.reverse_graph = CsrEdges::from_map(&kept);
+    }
+
+    fn with_low_memory(mut self, low_memory: bool) -> Self {
+        if low_memory {
+            self.drop_reverse_graph();
+        }
+        self
+    }
+}
+
+/// Breadth first search from both transitions of `pin` through the edges of each node given by `edges_of`, at
+/// most `depth` arcs away
+fn cone<'a>(
+    graph: &SDFGraph,
+    pin: PinId,
+    depth: Option<usize>,
+    edges_of: impl Fn(&PinTrans) -> Cow<'a, [SDFEdge]>,
+) -> PinTransSet {
+    let mut cone: PinTransSet = [(pin, Transition::Rise), (pin, Transition::Fall)]
+        .into_iter()
+        .filter(|node| graph.graph.contains_key(node))
+        .collect();
+    let mut frontier: Vec<PinTrans> = cone.iter().copied().collect();
+    let mut distance = 0;
+    while !frontier.is_empty() && depth.is_none_or(|depth| distance < depth) {
+        let mut next = Vec::new();
+        for node in frontier {
+            for edge in edges_of(&node).iter() {
+                if cone.insert(edge.dst) {
+                    next.push(edge.dst);
+                }
+            }
+        }
+        frontier = next;
+        distance += 1;
+    }
+    cone
+}
+
+/// SDF and Elmore delays of an interconnect arc, see [`SDFGraph::apply_parasitics`]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct InterconnectCheck {
+    pub src: PinTrans,
+    pub dst: PinTrans,
+    /// ns
+    pub sdf: f32,
+    /// ns
+    pub elmore: f32,
+}
+
+impl InterconnectCheck {
+    /// Whether the estimate is too far from the SDF delay to be explained by a different extraction.
+    /// A few ps of difference are just rounding.
+    pub fn is_mismatch(&self) -> bool {
+        (self.elmore - self.sdf).abs() > 0.005 + 0.5 * self.sdf
+    }
+}
+
+#[cfg(feature = "spef")]
+impl SDFGraph {
+    /// Estimate the interconnect delays with the Elmore delays of the parasitics,
+    /// and replace the SDF delays by them with [`InterconnectDelays::Elmore`].
+    /// Returns the arcs that had an estimate, arcs of nets missing from the parasitics keep their SDF delay.
+    pub fn apply_parasitics(&mut self, parasitics: &Parasitics, mode: InterconnectDelays) -> Vec<InterconnectCheck> {
+        if mode == InterconnectDelays::Sdf {
+            return Vec::new();
+        }
+        let symbols = &self.symbols;
+
+        let mut checks = Vec::new();
+        for (src, edges) in self.graph.iter_mut() {
+            for edge in edges {
+                if !is_interconnect(symbols, src.0, edge.dst.0) {
+                    continue;
+                }
+                let (src_name, dst_name) = (symbols.pin_name(src.0), symbols.pin_name(edge.dst.0));
+                let Some(elmore) = parasitics
+                    .net_of(src_name)
+                    .and_then(|(net, _)| elmore_delay(net, src_name, dst_name))
+                else {
+                    continue;
+                };
+                let elmore = (elmore * 1e9) as f32;
+                checks.push(InterconnectCheck {
+                    src,
+                    dst: edge.dst,
+                    sdf: edge.delay,
+                    elmore,
+                });
+                if mode == InterconnectDelays::Elmore {
+                    edge.delay = elmore;
+                }
+            }
+        }
+
+        if mode == InterconnectDelays::Elmore {
+            for check in &checks {
+                for edge in self.reverse_edges_mut(&check.dst).filter(|edge| edge.dst == check.src) {
+                    edge.delay = check.elmore;
+                }
+            }
+        }
+
+        checks
+    }
+
+    /// Compare every interconnect arc of the SDF with the parasitics, to catch an SDF annotated from another
+    /// extraction than the SPEF: the nets missing from the parasitics and the Elmore delays far from the SDF ones.
+    pub fn check_annotation(&self, parasitics: &Parasitics) -> AnnotationCheck {
+        let symbols = &self.symbols;
+        let mut check = AnnotationCheck::default();
+        let mut missing = FxHashSet::default();
+
+        for (src, edges) in self.graph.iter() {
+            for edge in edges {
+                if !is_interconnect(symbols, src.0, edge.dst.0) {
+                    continue;
+                }
+                let (src_name, dst_name) = (symbols.pin_name(src.0), symbols.pin_name(edge.dst.0));
+                let Some((net, _)) = parasitics.net_of(src_name) else {
+                    if missing.insert((src.0, edge.dst.0)) {
+                        check.missing_nets.push((src.0, edge.dst.0));
+                    }
+                    continue;
+                };
+                let Some(elmore) = elmore_delay(net, src_name, dst_name) else {
+                    if missing.insert((src.0, edge.dst.0)) {
+                        check.disconnected.push((src.0, edge.dst.0));
+                    }
+                    continue;
+                };
+                check.checks.push(InterconnectCheck {
+                    src,
+                    dst: edge.dst,
+                    sdf: edge.delay,
+                    elmore: (elmore * 1e9) as f32,
+                });
+            }
+        }
+
+        check
+            .checks
+            .sort_by_key(|check| Reverse(OrderedFloat((check.elmore - check.sdf).abs())));
+        check
+    }
+}
+
+/// Interconnect arcs of the SDF checked against the parasitics, see [`SDFGraph::check_annotation`]
+#[derive(Debug, Clone, Default)]
+pub struct AnnotationCheck {
+    /// arcs with an Elmore delay, the largest difference with the SDF first
+    pub checks: Vec<InterconnectCheck>,
+    /// (driver, sink) of the arcs whose driver isn't on any net of the parasitics
+    pub missing_nets: Vec<(PinId, PinId)>,
+    /// (driver, sink) of the arcs whose sink isn't connected to the net of the driver
+    pub disconnected: Vec<(PinId, PinId)>,
+}
+
+impl AnnotationCheck {
+    pub fn mismatches(&self) -> impl Iterator<Item = &InterconnectCheck> {
+        self.checks.iter().filter(|check| check.is_mismatch())
+    }
+}
+
+/// Cell arcs stay inside an instance, interconnects go from one to another (or to/from a port)
+pub(crate) fn is_interconnect(symbols: &SymbolTable, src: PinId, dst: PinId) -> bool {
+    let src_instance = symbols.pin_instance(src);
+    src_instance.is_none() || symbols.pin_instance(dst) != src_instance
+}
+
+/// Drive strength of a cell from its name, e.g 4 for `sky130_fd_sc_hd__buf_4`
+pub(crate) fn drive_strength(celltype: &str) -> Option<f32> {
+    celltype.rsplit_once('_')?.1.parse().ok()
+}
+
+impl SDFGraph {
+    /// Replace the cell of `instance` by `celltype`, usually another drive strength of the same cell.
+    ///
+    /// The SDF delays of the arcs of the cell are scaled by the ratio of the Liberty delays of the two cells
+    /// when a delay calculator is given, by the ratio of the drive strengths otherwise.
+    /// With a delay calculator, the arcs of the cells driving the inputs are also updated for their new load.
+    /// The nodes at the end of the updated arcs are added to [`SDFGraph::dirty`].
+    pub fn swap_cell(&mut self, instance: InstanceId, celltype: &str, calculator: Option<&DelayCalculator>) {
+        let Some(old_celltype) = self.instance_celltype.get(&instance).cloned() else {
+            return;
+        };
+        let ins: Vec<_> = self
+            .instance_ins
+            .get(&instance)
+            .into_iter()
+            .flatten()
+            .copied()
+            .collect();
+        let outs: Vec<_> = self
+            .instance_outs
+            .get(&instance)
+            .into_iter()
+            .flatten()
+            .copied()
+            .collect();
+
+        // arcs of the cells driving the inputs, with their load before the swap
+        let mut upstream = Vec::new();
+        if let Some(calc) = calculator {
+            for &input in &ins {
+                for edge in self.fanin(&(input, Transition::Rise)).iter() {
+                    let driver = edge.dst.0;
+                    let Some(driver_instance) = self.symbols.pin_instance(driver) else {
+                        continue;
+                    };
+                    upstream.push((driver_instance, driver, calc.load(self, driver)));
+                }
+            }
+        }
+
+        self.instance_celltype.insert(instance, celltype.to_string());
+
+        let heuristic = match (drive_strength(&old_celltype), drive_strength(celltype)) {
+            (Some(old), Some(new)) => old / new,
+            _ => 1.0,
+        };
+
+        // (src, dst, factor)
+        let mut scaled = Vec::new();
+        for &output in &outs {
+            let load = calculator.map(|calc| calc.load(self, output));
+            for transition in [Transition::Rise, Transition::Fall] {
+                let dst = (output, transition);
+                for edge in self.fanin(&dst).iter() {
+                    if self.symbols.pin_instance(edge.dst.0) != Some(instance) {
+                        continue;
+                    }
+                    let factor = calculator
+                        .zip(load)
+                        .and_then(|(calc, load)| {
+                            let delay = |celltype: &str| {
+                                let input = self.symbols.pin_short_name(edge.dst.0);
+                                let output = (self.symbols.pin_short_name(output), transition);
+                                calc.arc_delay(celltype, input, output, calc.input_transition, load)
+                            };
+                            match (delay(&old_celltype)?, delay(celltype)?) {
+                                ((old, _), (new, _)) if old > 0.0 => Some(new / old),
+                                _ => None,
+                            }
+                        })
+                        .unwrap_or(heuristic);
+                    scaled.push((edge.dst, dst, factor));
+                }
+            }
+        }
+
+        if let Some(calc) = calculator {
+            for (driver_instance, driver, old_load) in upstream {
+                let new_load = calc.load(self, driver);
+                let Some(driver_celltype) = self.instance_celltype.get(&driver_instance) else {
+                    continue;
+                };
+                for transition in [Transition::Rise, Transition::Fall] {
+                    let dst = (driver, transition);
+                    for edge in self.fanin(&dst).iter() {
+                        if self.symbols.pin_instance(edge.dst.0) != Some(driver_instance) {
+                            continue;
+                        }
+                        let delay = |load: f32| {
+                            let input = self.symbols.pin_short_name(edge.dst.0);
+                            let output = (self.symbols.pin_short_name(driver), transition);
+                            calc.arc_delay(driver_celltype, input, output, calc.input_transition, load)
+                        };
+                        if let (Some((old, _)), Some((new, _))) = (delay(old_load), delay(new_load)) {
+                            if old > 0.0 {
+                                scaled.push((edge.dst, dst, new / old));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        for (src, dst, factor) in scaled {
+            for edge in self.graph[&src].iter_mut().filter(|edge| edge.dst == dst) {
+                edge.delay *= factor;
+            }
+            for edge in self.reverse_edges_mut(&dst).filter(|edge| edge.dst == src) {
+                edge.delay *= factor;
+            }
+            self.dirty.insert(dst);
+        }
+    }
+}
+
+/// Written before the graph by [`SDFGraph::save`], to reject caches of older versions of the graph
+#[cfg(feature = "serde")]
+const GRAPH_FILE_HEADER: &[u8; 8] = b"STARS\0\0\x06";
+
+#[cfg(feature = "serde")]
+impl SDFGraph {
+    /// Write the graph in a compact binary format, to skip the SDF parsing on the next runs with
+    /// [`SDFGraph::read_from`]. `source` identifies the inputs the graph was built from (e.g the SDF file and the
+    /// options), it is written after the header and has to match to read the graph back.
+    pub fn write_to(&self, mut w: impl std::io::Write, source: &str) -> Result<()> {
+        w.write_all(GRAPH_FILE_HEADER)?;
+        bincode::serialize_into(&mut w, source)?;
+        bincode::serialize_into(&mut w, self)?;
+        w.flush()?;
+        Ok(())
+    }
+
+    /// Read a graph written by [`SDFGraph::write_to`] from the same `source`
+    pub fn read_from(mut r: impl std::io::Read, source: &str) -> Result<Self> {
+        let invalid = |message: &str| StarsError::GraphFile(Box::new(bincode::ErrorKind::Custom(message.to_string())));
+        let mut header = [0; 8];
+        r.read_exact(&mut header)?;
+        if &header != GRAPH_FILE_HEADER {
+            return Err(invalid("not a graph saved by this version of stars"));
+        }
+        let saved_source: String = bincode::deserialize_from(&mut r)?;
+        if saved_source != source {
+            return Err(invalid("the graph was built from other inputs"));
+        }
+        Ok(bincode::deserialize_from(r)?)
+    }
+
+    /// Write the graph to the file `path`, see [`SDFGraph::write_to`]
+    #[cfg(feature = "fs")]
+    pub fn save(&self, path: impl AsRef<std::path::Path>, source: &str) -> Result<()> {
+        self.write_to(std::io::BufWriter::new(std::fs::File::create(path)?), source)
+    }
+
+    /// Read a graph written by [`SDFGraph::save`]
+    #[cfg(feature = "fs")]
+    pub fn load(path: impl AsRef<std::path::Path>, source: &str) -> Result<Self> {
+        Self::read_from(std::io::BufReader::new(std::fs::File::open(path)?), source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every instance is under the design-level one, and the buffer is inside the u_core block
+    const HIERARCHICAL: &str = r#"(DELAYFILE
+ (SDFVERSION "3.0")
+ (DESIGN "top")
+ (DIVIDER .)
+ (TIMESCALE 1ns)
+ (CELL (CELLTYPE "top") (INSTANCE top)
+  (DELAY (ABSOLUTE
+   (INTERCONNECT a u_core.a (0.010::0.010) (0.010::0.010))
+   (INTERCONNECT u_core.y y (0.020::0.020) (0.020::0.020)))))
+ (CELL (CELLTYPE "core") (INSTANCE top.u_core)
+  (DELAY (ABSOLUTE
+   (INTERCONNECT a u1.A (0.030::0.030) (0.030::0.030))
+   (INTERCONNECT u1.X y (0.040::0.040) (0.040::0.040)))))
+ (CELL (CELLTYPE "sky130_fd_sc_hd__buf_1") (INSTANCE top.u_core.u1)
+  (DELAY (ABSOLUTE (IOPATH A X (0.100::0.100) (0.100::0.100)))))
+)"#;
+
+    /// SDF of a design whose top-level cell has the `interconnects`, followed by the `cells`
+    fn design(interconnects: &str, cells: &str) -> String {
+        format!(
+            r#"(DELAYFILE
+ (SDFVERSION "3.0")
+ (DESIGN "top")
+ (DIVIDER .)
+ (TIMESCALE 1ns)
+ (CELL (CELLTYPE "top") (INSTANCE) (DELAY (ABSOLUTE {})))
+{}
+)"#,
+            interconnects, cells
+        )
+    }
+
+    fn graph_of(sdf: &str) -> SDFGraph {
+        SDFGraph::new(&sdfparse::SDF::parse_str(sdf).unwrap(), Corner::default()).unwrap()
+    }
+
+    fn graph_with(sdf: &str, options: &SDFGraphOptions) -> SDFGraph {
+        SDFGraph::with_options(&sdfparse::SDF::parse_str(sdf).unwrap(), options).unwrap()
+    }
+
+    fn node(graph: &SDFGraph, name: &str, transition: Transition) -> PinTrans {
+        (graph.symbols.pin_id(name).unwrap(), transition)
+    }
+
+    /// Edges of a node with the names of the pins they go to
+    fn edges<'a>(
+        graph: &'a SDFGraph,
+        name: &str,
+        transition: Transition,
+    ) -> Vec<(&'a str, Transition, f32, Option<u32>)> {
+        let edges = graph.graph.edges(&node(graph, name, transition));
+        (edges.iter())
+            .map(|edge| (graph.symbols.pin_name(edge.dst.0), edge.dst.1, edge.delay, edge.cond))
+            .collect()
+    }
+
+    /// Multiplexer with three conditional arcs from A0 to X
+    fn mux_sdf() -> String {
+        design(
+            "(INTERCONNECT a mux.A0 (0.010::0.010) (0.010::0.010))
+             (INTERCONNECT mux.X x (0.010::0.010) (0.010::0.010))",
+            r#"(CELL (CELLTYPE "sky130_fd_sc_hd__mux2_1") (INSTANCE mux)
+             (DELAY (ABSOLUTE
+              (COND S==1'b0 (IOPATH A0 X (0.100::0.100) (0.200::0.200)))
+              (COND S==1'b1 (IOPATH A0 X (0.300::0.300) (0.100::0.100)))
+              (CONDELSE (IOPATH A0 X (0.050::0.050) (0.050::0.050))))))"#,
+        )
+    }
+
+    #[test]
+    fn test_conditional_arcs() {
+        let sdf = mux_sdf();
+
+        // the worst delay of each transition by default
+        let graph = graph_of(&sdf);
+        assert_eq!(
+            edges(&graph, "mux/A0", Transition::Rise),
+            [("mux/X", Transition::Rise, 0.3, None)]
+        );
+        assert_eq!(
+            edges(&graph, "mux/A0", Transition::Fall),
+            [("mux/X", Transition::Fall, 0.2, None)]
+        );
+        assert!(graph.conditions.is_empty());
+
+        // or one edge per condition
+        let options = SDFGraphOptions {
+            conditional_arcs: CondArcPolicy::Parallel,
+            ..Default::default()
+        };
+        let graph = graph_with(&sdf, &options);
+        assert_eq!(
+            edges(&graph, "mux/A0", Transition::Rise),
+            [
+                ("mux/X", Transition::Rise, 0.1, Some(0)),
+                ("mux/X", Transition::Rise, 0.3, Some(1)),
+                ("mux/X", Transition::Rise, 0.05, Some(2)),
+            ]
+        );
+        assert_eq!(graph.conditions.len(), 3);
+        assert_eq!(graph.conditions[2], "CONDELSE");
+        // the fanin of X has the same parallel edges
+        assert_eq!(graph.fanin(&node(&graph, "mux/X", Transition::Fall)).len(), 3);
+    }
+
+    #[test]
+    fn test_edge_arcs() {
+        let graph = graph_of(&design(
+            "(INTERCONNECT clk ff.CLK (0.010::0.010) (0.010::0.010))
+             (INTERCONNECT d ff.D (0.010::0.010) (0.010::0.010))
+             (INTERCONNECT ff.Q q (0.010::0.010) (0.010::0.010))",
+            r#"(CELL (CELLTYPE "sky130_fd_sc_hd__dfxtp_1") (INSTANCE ff)
+             (DELAY (ABSOLUTE (IOPATH (posedge CLK) Q (0.300::0.300) (0.350::0.350)))))"#,
+        ));
+        // only the rising clock launches Q, in both directions
+        assert_eq!(
+            edges(&graph, "ff/CLK", Transition::Rise),
+            [
+                ("ff/Q", Transition::Rise, 0.3, None),
+                ("ff/Q", Transition::Fall, 0.35, None)
+            ]
+        );
+        assert!(edges(&graph, "ff/CLK", Transition::Fall).is_empty());
+
+        let ff = graph.symbols.instance_id("ff").unwrap();
+        assert_eq!(graph.registers[&ff].clock, node(&graph, "ff/CLK", Transition::Rise));
+        assert_eq!(
+            graph.launch_clock(&node(&graph, "ff/Q", Transition::Fall)),
+            Some(graph.registers[&ff].clock)
+        );
+    }
+
+    #[test]
+    fn test_latch_and_negedge_flop() {
+        let graph = graph_of(&design(
+            "(INTERCONNECT en latch.GATE (0.010::0.010) (0.010::0.010))
+             (INTERCONNECT d latch.D (0.010::0.010) (0.010::0.010))
+             (INTERCONNECT latch.Q ff.D (0.010::0.010) (0.010::0.010))
+             (INTERCONNECT clk ff.CLK_N (0.010::0.010) (0.010::0.010))
+             (INTERCONNECT ff.Q q (0.010::0.010) (0.010::0.010))",
+            r#"(CELL (CELLTYPE "sky130_fd_sc_hd__dlxtp_1") (INSTANCE latch)
+             (DELAY (ABSOLUTE
+              (IOPATH GATE Q (0.200::0.200) (0.250::0.250))
+              (IOPATH D Q (0.150::0.150) (0.150::0.150)))))
+             (CELL (CELLTYPE "sky130_fd_sc_hd__dfrtn_1") (INSTANCE ff)
+             (DELAY (ABSOLUTE (IOPATH (negedge CLK_N) Q (0.300::0.300) (0.350::0.350)))))"#,
+        ));
+        let register = |name: &str| graph.registers[&graph.symbols.instance_id(name).unwrap()];
+
+        // the latch is opened by the high gate, and its transparent D -> Q path is cut
+        let latch = register("latch");
+        assert!(latch.latch);
+        assert_eq!(latch.clock, node(&graph, "latch/GATE", Transition::Rise));
+        assert!(edges(&graph, "latch/D", Transition::Rise).is_empty());
+        assert_eq!(
+            edges(&graph, "latch/GATE", Transition::Rise),
+            [("latch/Q", Transition::Rise, 0.2, None)]
+        );
+
+        // the flip-flop is launched by the falling edge of CLK_N
+        let ff = register("ff");
+        assert!(!ff.latch);
+        assert_eq!(ff.clock, node(&graph, "ff/CLK_N", Transition::Fall));
+        assert_eq!(ff.data, graph.symbols.pin_id("ff/D").unwrap());
+        assert!(edges(&graph, "ff/CLK_N", Transition::Rise).is_empty());
+
+        // both split the paths: their D pins are endpoints and their Q pins startpoints
+        for (pin, transition) in [("latch/D", Transition::Rise), ("ff/D", Transition::Fall)] {
+            assert!(graph.outputs.contains(&node(&graph, pin, transition)));
+        }
+        for (pin, transition) in [("latch/Q", Transition::Rise), ("ff/Q", Transition::Fall)] {
+            assert!(graph.inputs.contains(&node(&graph, pin, transition)));
+        }
+    }
+
+    #[test]
+    fn test_hierarchical_cells() {
+        let graph = graph_of(HIERARCHICAL);
+
+        // the ports of the block are crossed, the interconnects on both sides adding up
+        assert_eq!(
+            edges(&graph, "a", Transition::Rise),
+            [("u_core/u1/A", Transition::Rise, 0.04, None)]
+        );
+        assert_eq!(
+            edges(&graph, "u_core/u1/X", Transition::Fall),
+            [("y", Transition::Fall, 0.06, None)]
+        );
+        assert!(!graph.graph.contains_key(&node(&graph, "u_core/a", Transition::Rise)));
+        assert_eq!(
+            graph.fanin(&node(&graph, "u_core/u1/A", Transition::Rise)).into_owned(),
+            [SDFEdge {
+                dst: node(&graph, "a", Transition::Rise),
+                delay: 0.04,
+                cond: None,
+            }]
+        );
+
+        // the block isn't a cell of the flat netlist, the design-level cell is the empty instance
+        let instances: Vec<&str> = (graph.instance_celltype.keys())
+            .map(|instance| graph.symbols.instance_name(instance))
+            .collect();
+        assert_eq!(instances, ["", "u_core/u1"]);
+        let inputs: Vec<&str> = graph
+            .inputs
+            .iter()
+            .map(|input| graph.symbols.pin_name(input.0))
+            .collect();
+        assert_eq!(inputs, ["a", "a"]);
+    }
+
+    #[test]
+    fn test_derive_fanin() {
+        let parallel = SDFGraphOptions {
+            conditional_arcs: CondArcPolicy::Parallel,
+            ..Default::default()
+        };
+        for (sdf, options) in [(mux_sdf(), parallel), (HIERARCHICAL.to_string(), Default::default())] {
+            let graph = graph_with(&sdf, &options);
+            let mut low = graph_with(&sdf, &options);
+            low.drop_reverse_graph();
+            assert!(low.low_memory);
+            // the parallel edges and the arcs across the block are derived like the other ones
+            assert!(low.reverse_graph.n_edges() < graph.reverse_graph.n_edges());
+            for node in graph.graph.keys() {
+                assert_eq!(
+                    graph.fanin(&node),
+                    low.fanin(&node),
+                    "{}",
+                    graph.symbols.pin_name(node.0)
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_save_load() {
+        let graph = graph_of(&design(
+            "(INTERCONNECT in buf.A (0.010:0.020:0.030) (0.010:0.020:0.030))
+             (INTERCONNECT buf.X out (0.010:0.020:0.030) (0.010:0.020:0.030))",
+            r#"(CELL (CELLTYPE "sky130_fd_sc_hd__buf_1") (INSTANCE buf)
+             (DELAY (ABSOLUTE (IOPATH A X (0.100:0.200:0.300) (0.100:0.200:0.300)))))"#,
+        ));
+        let mut file = Vec::new();
+        graph.write_to(&mut file, "buffer.sdf").unwrap();
+
+        let loaded = SDFGraph::read_from(&*file, "buffer.sdf").unwrap();
+        assert_eq!(loaded.corner, graph.corner);
+        let (a, x) = (
+            node(&graph, "buf/A", Transition::Rise),
+            node(&graph, "buf/X", Transition::Rise),
+        );
+        assert_eq!(node(&loaded, "buf/A", Transition::Rise), a);
+        let delays = |graph: &SDFGraph| {
+            graph.graph[&a]
+                .iter()
+                .map(|edge| (edge.dst, edge.delay))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(delays(&loaded), delays(&graph));
+        assert_eq!(delays(&loaded), vec![(x, 0.1)]);
+        assert_eq!(loaded.outputs, graph.outputs);
+
+        // a cache of other inputs is rejected
+        assert!(SDFGraph::read_from(&*file, "other.sdf").is_err());
+        assert!(SDFGraph::read_from(&file[1..], "buffer.sdf").is_err());
     }
 }
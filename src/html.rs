@@ -1,49 +1,17 @@
-use crate::analysis::SDFGraphAnalyzed;
+use crate::analysis::{PathReport, SDFGraphAnalyzed, SlackHistogram};
+use crate::bottleneck::Bottlenecks;
+use crate::comparison::AnalysisComparison;
+use crate::def::Placement;
+use crate::error::{Result, StarsError};
 use crate::graph::SDFGraph;
-use crate::types::{PinSet, PinTrans, SDFInstance, Transition};
-use crate::{instance_name, pin_name};
+use crate::instance_name;
+use crate::types::{InstanceId, PinId, PinSet, PinTrans, Transition};
+use minijinja::{context, AutoEscape, Environment, Value};
 use ordered_float::OrderedFloat;
+use rustc_hash::FxHashMap;
 use std::fmt::Write;
 
-pub fn extract_html_for_manual_analysis(
-    graph: &SDFGraph,
-    analysis: &SDFGraphAnalyzed,
-    output: &PinTrans,
-    max_delay: f32,
-    path: &[(PinTrans, f32)],
-) {
-    let mut instances: Vec<(SDFInstance, PinTrans, PinTrans)> = vec![];
-    let mut pins_in_path: PinSet = Default::default();
-
-    let mut last_pin: Option<&PinTrans> = None;
-    for (pin_t, _delay) in path {
-        let instance = instance_name(&pin_t.0);
-        let last_instance = instances.last().map(|v| &v.0);
-
-        pins_in_path.insert(pin_t.0.clone());
-        if last_instance == Some(&instance) {
-            last_pin = Some(pin_t);
-            instances.last_mut().unwrap().2 = pin_t.clone();
-            continue;
-        }
-
-        instances.push((instance.clone(), pin_t.clone(), pin_t.clone()));
-
-        last_pin = Some(pin_t);
-    }
-
-    let o_instance = output.0.rsplit_once('/').unwrap().0;
-
-    instances.push((o_instance.to_string(), output.clone(), output.clone()));
-    pins_in_path.insert(output.0.clone());
-    pins_in_path.insert(last_pin.unwrap().0.clone());
-
-    let mut html = String::new();
-    html.push_str(
-        r#"<html lang="en">
-<head>
-<meta charset="UTF-8">
-<style>
+const STYLE: &str = r#"<style>
     table, th, td { border: 1px solid #c1c1c1; border-collapse: collapse; }
     th, td { padding: 5px 10px; }
     td {
@@ -52,8 +20,10 @@ pub fn extract_html_for_manual_analysis(
     }
     .nogain { display: block; }
     .gain { display: none; }
-</style>
-<script>
+</style>"#;
+
+/// Toggles the `.gain` and `.nogain` columns of the path tables with the `assume-gain` checkbox
+const GAIN_SCRIPT: &str = r#"<script>
 document.addEventListener('DOMContentLoaded', function() {
     document.getElementById('assume-gain').addEventListener('change', function() {
         console.log(this.checked);
@@ -74,70 +44,853 @@ document.addEventListener('DOMContentLoaded', function() {
         }
     });
 });
-</script>
-<title>Path analysis</title>
-</head>
-<body>
-    <div style="padding: 10px;display:flex;align-items: center;">
+</script>"#;
+
+/// Sorts the `.sortable` tables by the column whose header is clicked (by the `data-sort` of the cells when they
+/// have one), adds a checkbox above them to hide each column, and filters their rows by the text of their first
+/// cell with the `search` box
+const TABLE_SCRIPT: &str = r#"<script>
+function sortValue(td) {
+    var value = td.dataset.sort !== undefined ? td.dataset.sort : td.textContent.trim();
+    var number = parseFloat(value);
+    return isNaN(number) ? value.toLowerCase() : number;
+}
+function sortTable(table, col, th) {
+    var ascending = th.dataset.order !== 'asc';
+    th.dataset.order = ascending ? 'asc' : 'desc';
+    var rows = Array.from(table.querySelectorAll('tr')).filter(function(tr) { return !tr.querySelector('th'); });
+    rows.sort(function(a, b) {
+        var x = sortValue(a.children[col]), y = sortValue(b.children[col]);
+        if (typeof x !== typeof y) return typeof x === 'number' ? -1 : 1;
+        return (x < y ? -1 : x > y ? 1 : 0) * (ascending ? 1 : -1);
+    });
+    rows.forEach(function(tr) { tr.parentNode.appendChild(tr); });
+}
+document.addEventListener('DOMContentLoaded', function() {
+    document.querySelectorAll('table.sortable').forEach(function(table) {
+        var toggles = document.createElement('div');
+        toggles.style.padding = '5px 0';
+        table.querySelectorAll('tr:first-child th').forEach(function(th, col) {
+            th.style.cursor = 'pointer';
+            th.title = 'Sort';
+            th.addEventListener('click', function() { sortTable(table, col, th); });
+            var box = document.createElement('input');
+            box.type = 'checkbox';
+            box.checked = true;
+            box.addEventListener('change', function() {
+                table.querySelectorAll('tr').forEach(function(tr) {
+                    if (tr.children[col]) tr.children[col].style.display = box.checked ? '' : 'none';
+                });
+            });
+            var label = document.createElement('label');
+            label.style.marginRight = '10px';
+            label.appendChild(box);
+            label.appendChild(document.createTextNode(th.textContent));
+            toggles.appendChild(label);
+        });
+        table.parentNode.insertBefore(toggles, table);
+    });
+    var search = document.getElementById('search');
+    if (!search) return;
+    search.addEventListener('input', function() {
+        var query = search.value.toLowerCase();
+        document.querySelectorAll('table.sortable tr').forEach(function(tr) {
+            if (tr.querySelector('th')) return;
+            tr.style.display = tr.children[0].textContent.toLowerCase().includes(query) ? '' : 'none';
+        });
+    });
+});
+</script>"#;
+
+const SEARCH_BOX: &str = r#"<input type="search" id="search" placeholder="Filter instances" />"#;
+
+const GAIN_CHECKBOX: &str = r#"    <div style="padding: 10px;display:flex;align-items: center;">
         <input type="checkbox" id="assume-gain" />
         <label for="assume-gain" style="user-select: none;">Assume 20% faster on non-critical paths</label>
-    </div>
-    <table>
+    </div>"#;
+
+const PATH_TABLE_HEADER: &str = r#"    <table class='sortable'>
     <tr>
         <th>Instance</th>
         <th>Setup</th>
         <th>Arr.</th>
+        <th>Net</th>
+        <th>Cell</th>
         <th>Input Pin: Setup, Arr, <b>Slack</b></th>
         <th>Output Cells Pin (fanout)</th>
-    </tr>"#,
-    );
+    </tr>"#;
+
+/// Default template of [`extract_html_for_manual_analysis`], see [`extract_html_with_template`] for its variables
+pub const PATH_TEMPLATE: &str = include_str!("templates/path.html");
+
+/// Generate a standalone HTML page describing the path of `report`, with setup/arrival/slack of every pin around it.
+pub fn extract_html_for_manual_analysis(
+    graph: &SDFGraph,
+    analysis: &SDFGraphAnalyzed,
+    report: &PathReport,
+) -> Result<String> {
+    extract_html_with_template(graph, analysis, report, PATH_TEMPLATE)
+}
+
+/// Same as [`extract_html_for_manual_analysis`] with a [minijinja](https://docs.rs/minijinja) `template` instead of
+/// [`PATH_TEMPLATE`], the variables being escaped as HTML. Given to the template:
+/// - `endpoint` and `startpoint` names with their transition, `total_delay` and `slack` (ns)
+/// - `stages`: the `instance`, `celltype`, `input` and `output` pins, `net_delay`, `cell_delay` and `arrival`
+///   of every stage, missing values being `none`
+/// - the HTML of the default page: `style`, `gain_script`, `table_script`, `gain_checkbox`, `search_box`,
+///   the schematic `svg`, the `table_header` and the `rows` of the path table
+pub fn extract_html_with_template(
+    graph: &SDFGraph,
+    analysis: &SDFGraphAnalyzed,
+    report: &PathReport,
+    template: &str,
+) -> Result<String> {
+    let symbols = &graph.symbols;
+    if report.stages.len() < 2 {
+        return Err(StarsError::EmptyPath(symbols.pin_name(report.endpoint.0).to_string()));
+    }
+
+    let mut rows = String::new();
+    path_rows(graph, analysis, report, &PathLinks::default(), &mut rows);
+
+    let pin_name = |pin: PinTrans| format!("{}{}", symbols.pin_name(pin.0), pin.1);
+    let stages: Vec<Value> = report
+        .stages
+        .iter()
+        .map(|stage| {
+            context! {
+                instance => stage.instance.map(|instance| symbols.instance_name(instance)),
+                celltype => stage.celltype,
+                input => stage.input.map(pin_name),
+                output => stage.output.map(pin_name),
+                net_delay => stage.net_delay,
+                cell_delay => stage.cell_delay,
+                arrival => stage.arrival,
+            }
+        })
+        .collect();
+
+    let mut env = Environment::new();
+    env.set_keep_trailing_newline(true);
+    env.set_auto_escape_callback(|_| AutoEscape::Html);
+    env.add_template("path", template)?;
+    let html = env.get_template("path")?.render(context! {
+        endpoint => pin_name(report.endpoint),
+        startpoint => pin_name(report.startpoint()),
+        total_delay => report.total_delay,
+        slack => analysis.slack(&report.endpoint),
+        stages,
+        style => Value::from_safe_string(STYLE.to_string()),
+        gain_script => Value::from_safe_string(GAIN_SCRIPT.to_string()),
+        table_script => Value::from_safe_string(TABLE_SCRIPT.to_string()),
+        gain_checkbox => Value::from_safe_string(GAIN_CHECKBOX.to_string()),
+        search_box => Value::from_safe_string(SEARCH_BOX.to_string()),
+        svg => Value::from_safe_string(path_svg(graph, report)),
+        table_header => Value::from_safe_string(PATH_TABLE_HEADER.to_string()),
+        rows => Value::from_safe_string(rows),
+    })?;
 
-    for (instance, pin_in, pin_out) in &instances {
-        let mut pin_out = pin_out;
-        let pin_out_holder = (String::new(), Transition::Rise);
-        if !pins_in_path.contains(&pin_out.0) {
-            pin_out = &pin_out_holder;
+    Ok(html)
+}
+
+/// Rows of the stages of a path in the page of `links`
+#[derive(Default)]
+struct PathLinks {
+    /// prefix of the ids of the rows, the row of the stage `i` is `{prefix}s{i}`. No ids when empty
+    prefix: String,
+    /// id of the row of the first stage of each instance over all the paths of the page
+    instances: FxHashMap<InstanceId, String>,
+}
+
+/// Generate a standalone HTML page reviewing several endpoints: an index sorted by slack, then the table of each path
+/// (as in [`extract_html_for_manual_analysis`]) that can be collapsed, with the fanout pins linking to the row of their
+/// instance when it is on one of the paths. Paths without a cell are left out.
+/// With a `placement`, the paths are also drawn over the floorplan (see [`floorplan_svg`]).
+pub fn timing_review_html(
+    graph: &SDFGraph,
+    analysis: &SDFGraphAnalyzed,
+    reports: &[PathReport],
+    placement: Option<&Placement>,
+) -> String {
+    let symbols = &graph.symbols;
+    let mut reports: Vec<(&PathReport, f32)> = reports
+        .iter()
+        .filter(|report| report.stages.len() >= 2)
+        .map(|report| (report, analysis.slack(&report.endpoint).unwrap_or(f32::INFINITY)))
+        .collect();
+    reports.sort_by_key(|(_, slack)| OrderedFloat(*slack));
+
+    let mut instances = FxHashMap::default();
+    for (i, (report, _)) in reports.iter().enumerate() {
+        for (j, stage) in report.stages.iter().enumerate() {
+            let Some(instance) = stage.first_pin().and_then(|pin| symbols.pin_instance(pin.0)) else {
+                continue;
+            };
+            instances.entry(instance).or_insert_with(|| format!("p{}s{}", i, j));
         }
+    }
+
+    let mut html = String::new();
+    writeln!(
+        &mut html,
+        "<html lang=\"en\">\n<head>\n<meta charset=\"UTF-8\">\n{}\n{}\n{}",
+        STYLE, GAIN_SCRIPT, TABLE_SCRIPT
+    )
+    .unwrap();
+    writeln!(
+        &mut html,
+        "<title>Timing review</title>\n</head>\n<body>\n{}",
+        GAIN_CHECKBOX
+    )
+    .unwrap();
+    writeln!(&mut html, "<div style='padding: 0 10px'>{}</div>", SEARCH_BOX).unwrap();
+
+    writeln!(
+        &mut html,
+        "<h2>Slack distribution</h2>\n<div style='padding: 0 10px'>\n{}</div>",
+        histogram_svg(&analysis.slack_histogram(graph, HISTOGRAM_BINS))
+    )
+    .unwrap();
+    if let Some(placement) = placement {
+        writeln!(
+            &mut html,
+            "<h2>Floorplan</h2>\n<div style='padding: 0 10px'>\n{}</div>",
+            floorplan_svg(graph, placement, &reports)
+        )
+        .unwrap();
+    }
 
-        let mut t_setup = analysis.max_delay.get(&pin_out).copied();
-        let mut t_arrival = analysis.max_delay_backwards.get(&pin_out).copied();
+    writeln!(&mut html, "<h2>Endpoints ({})</h2>", reports.len()).unwrap();
+    writeln!(
+        &mut html,
+        "<table class='sortable'>\n<tr><th>Endpoint</th><th>Startpoint</th><th>Arrival</th><th>Slack</th></tr>"
+    )
+    .unwrap();
+    for (i, (report, slack)) in reports.iter().enumerate() {
+        let startpoint = report.startpoint();
+        writeln!(
+            &mut html,
+            "<tr><td style='text-align: left'><a href='#p{}'>{}{}</a></td><td style='text-align: left'>{}{}</td>\
+             <td>{:.3}</td><td>{:.3}</td></tr>",
+            i,
+            symbols.pin_name(report.endpoint.0),
+            report.endpoint.1,
+            symbols.pin_name(startpoint.0),
+            startpoint.1,
+            report.total_delay,
+            slack
+        )
+        .unwrap();
+    }
+    writeln!(&mut html, "</table>").unwrap();
 
-        if instance == &instance_name(&output.0) {
-            t_setup = None;
-            t_arrival = None;
+    let mut links = PathLinks {
+        prefix: String::new(),
+        instances,
+    };
+    for (i, (report, slack)) in reports.iter().enumerate() {
+        writeln!(
+            &mut html,
+            "<details id='p{}' open>\n<summary><b>{}{}</b> slack {:.3}</summary>",
+            i,
+            symbols.pin_name(report.endpoint.0),
+            report.endpoint.1,
+            slack
+        )
+        .unwrap();
+        writeln!(
+            &mut html,
+            "<div style='overflow-x: auto'>\n{}</div>",
+            path_svg(graph, report)
+        )
+        .unwrap();
+        html.push_str(PATH_TABLE_HEADER);
+        links.prefix = format!("p{}", i);
+        path_rows(graph, analysis, report, &links, &mut html);
+        writeln!(&mut html, "</table>\n</details>").unwrap();
+    }
+
+    writeln!(&mut html, "</body>").unwrap();
+    writeln!(&mut html, "</html>").unwrap();
+
+    html
+}
+
+/// `value` with 3 decimals, or `-` when missing
+fn optional_ns(value: Option<f32>) -> String {
+    value.map_or("-".to_string(), |value| format!("{:.3}", value))
+}
+
+/// Cell of `delta` with its sign, green when it is an improvement and red otherwise
+fn delta_td(delta: Option<f32>, higher_is_better: bool) -> String {
+    match delta {
+        Some(delta) if delta.abs() >= 0.0005 => {
+            let color = if (delta > 0.0) == higher_is_better {
+                "#2a8a2a"
+            } else {
+                "#c02020"
+            };
+            format!("<td style='color: {}'>{:+.3}</td>", color, delta)
         }
+        Some(delta) => format!("<td>{:+.3}</td>", delta),
+        None => "<td>-</td>".to_string(),
+    }
+}
+
+/// Generate a standalone HTML page comparing two analyses (see [`AnalysisComparison`]): the slack and arrival of each
+/// endpoint before and after with the pin where the paths diverge, the two paths side by side, and the instances whose
+/// slack changed.
+pub fn comparison_html(comparison: &AnalysisComparison) -> String {
+    let mut html = String::new();
+    writeln!(
+        &mut html,
+        "<html lang=\"en\">\n<head>\n<meta charset=\"UTF-8\">\n{}\n{}",
+        STYLE, TABLE_SCRIPT
+    )
+    .unwrap();
+    writeln!(&mut html, "<title>Timing comparison</title>\n</head>\n<body>").unwrap();
+    writeln!(&mut html, "<div style='padding: 10px'>{}</div>", SEARCH_BOX).unwrap();
 
-        writeln!(&mut html, "<tr>").unwrap();
+    writeln!(&mut html, "<h2>Endpoints ({})</h2>", comparison.endpoints.len()).unwrap();
+    writeln!(
+        &mut html,
+        "<table class='sortable'>\n<tr><th>Endpoint</th><th>Slack before</th><th>Slack after</th><th>Δ slack</th>\
+         <th>Arrival before</th><th>Arrival after</th><th>Δ arrival</th><th>Diverges after</th></tr>"
+    )
+    .unwrap();
+    for (i, endpoint) in comparison.endpoints.iter().enumerate() {
+        let divergence = match endpoint.divergence() {
+            Some(pin) => pin,
+            None if endpoint.path_before == endpoint.path_after => "same path",
+            None => "startpoint",
+        };
+        let delay_delta = endpoint
+            .delay
+            .1
+            .zip(endpoint.delay.0)
+            .map(|(after, before)| after - before);
+        writeln!(
+            &mut html,
+            "<tr><td style='text-align: left'><a href='#e{}'>{}{}</a></td><td>{}</td><td>{}</td>{}\
+             <td>{}</td><td>{}</td>{}<td style='text-align: left'>{}</td></tr>",
+            i,
+            endpoint.endpoint,
+            endpoint.transition,
+            optional_ns(endpoint.slack.0),
+            optional_ns(endpoint.slack.1),
+            delta_td(endpoint.slack_delta(), true),
+            optional_ns(endpoint.delay.0),
+            optional_ns(endpoint.delay.1),
+            delta_td(delay_delta, false),
+            divergence
+        )
+        .unwrap();
+    }
+    writeln!(&mut html, "</table>").unwrap();
+
+    writeln!(&mut html, "<h2>Instances ({})</h2>", comparison.instances.len()).unwrap();
+    writeln!(
+        &mut html,
+        "<table class='sortable'>\n<tr><th>Instance</th><th>Cell before</th><th>Cell after</th><th>Slack before</th>\
+         <th>Slack after</th><th>Δ slack</th></tr>"
+    )
+    .unwrap();
+    for instance in &comparison.instances {
         writeln!(
             &mut html,
+            "<tr><td style='text-align: left'>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>{}</tr>",
+            instance.instance,
+            instance.celltype.0.as_deref().unwrap_or("-"),
+            instance.celltype.1.as_deref().unwrap_or("-"),
+            optional_ns(instance.slack.0),
+            optional_ns(instance.slack.1),
+            delta_td(instance.slack_delta(), true)
+        )
+        .unwrap();
+    }
+    writeln!(&mut html, "</table>").unwrap();
+
+    writeln!(&mut html, "<h2>Paths</h2>").unwrap();
+    for (i, endpoint) in comparison.endpoints.iter().enumerate() {
+        writeln!(
+            &mut html,
+            "<details id='e{}'>\n<summary><b>{}{}</b> {} → {}</summary>",
+            i,
+            endpoint.endpoint,
+            endpoint.transition,
+            optional_ns(endpoint.slack.0),
+            optional_ns(endpoint.slack.1)
+        )
+        .unwrap();
+        writeln!(&mut html, "<table>\n<tr><th>Before</th><th>After</th></tr>").unwrap();
+        let rows = endpoint.path_before.len().max(endpoint.path_after.len());
+        for row in 0..rows {
+            // the pins after the divergence are in bold
+            let pin = |path: &[String]| match path.get(row) {
+                Some(pin) if row >= endpoint.common => format!("<b>{}</b>", pin),
+                Some(pin) => pin.clone(),
+                None => String::new(),
+            };
+            writeln!(
+                &mut html,
+                "<tr><td style='text-align: left'>{}</td><td style='text-align: left'>{}</td></tr>",
+                pin(&endpoint.path_before),
+                pin(&endpoint.path_after)
+            )
+            .unwrap();
+        }
+        writeln!(&mut html, "</table>\n</details>").unwrap();
+    }
+
+    writeln!(&mut html, "</body>").unwrap();
+    writeln!(&mut html, "</html>").unwrap();
+
+    html
+}
+
+/// Horizontal distance between two stages of [`path_svg`] and size of their cells
+const SVG_STEP: f32 = 180.0;
+const SVG_CELL: (f32, f32) = (100.0, 56.0);
+/// Top of the cells and height of the wires between them
+const SVG_TOP: f32 = 50.0;
+const SVG_WIRE: f32 = SVG_TOP + SVG_CELL.1 / 2.0;
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Draw the path of `report` as a chain of cells from the startpoint to the endpoint, with the pins of each stage,
+/// the delays of the cells and of the wires, the arrival times and a stub for each fanout off the path
+/// (only the first three, the others are counted).
+pub fn path_svg(graph: &SDFGraph, report: &PathReport) -> String {
+    let symbols = &graph.symbols;
+    let pins_in_path = report.pin_set();
+    let (cell_w, cell_h) = SVG_CELL;
+    let width = 40.0 + report.stages.len().max(1) as f32 * SVG_STEP - (SVG_STEP - cell_w);
+
+    let mut svg = String::new();
+    writeln!(
+        &mut svg,
+        "<svg xmlns='http://www.w3.org/2000/svg' width='{}' height='190' font-family='monospace' font-size='11'>",
+        width
+    )
+    .unwrap();
+
+    for (i, stage) in report.stages.iter().enumerate() {
+        let x = 20.0 + i as f32 * SVG_STEP;
+        let center = x + cell_w / 2.0;
+
+        match (stage.instance, &stage.celltype) {
+            (Some(instance), Some(celltype)) => {
+                writeln!(
+                    &mut svg,
+                    "<rect x='{}' y='{}' width='{}' height='{}' rx='6' fill='#f3f6fb' stroke='#333'/>",
+                    x, SVG_TOP, cell_w, cell_h
+                )
+                .unwrap();
+                writeln!(
+                    &mut svg,
+                    "<text x='{}' y='{}' text-anchor='middle' font-weight='bold'>{}</text>",
+                    center,
+                    SVG_TOP + 24.0,
+                    xml_escape(crate::celltype_short_with_size(celltype))
+                )
+                .unwrap();
+                writeln!(
+                    &mut svg,
+                    "<text x='{}' y='{}' text-anchor='middle' fill='#555'>{}</text>",
+                    center,
+                    SVG_TOP + 42.0,
+                    xml_escape(symbols.instance_name(instance))
+                )
+                .unwrap();
+            }
+            // a port, drawn as an arrow with its name
+            _ => {
+                let pin = stage.first_pin().map_or("", |pin| symbols.pin_name(pin.0));
+                writeln!(
+                    &mut svg,
+                    "<polygon points='{},{} {},{} {},{} {},{} {},{}' fill='#fff8e6' stroke='#333'/>",
+                    x,
+                    SVG_TOP + 10.0,
+                    x + cell_w - 20.0,
+                    SVG_TOP + 10.0,
+                    x + cell_w,
+                    SVG_WIRE,
+                    x + cell_w - 20.0,
+                    SVG_TOP + cell_h - 10.0,
+                    x,
+                    SVG_TOP + cell_h - 10.0
+                )
+                .unwrap();
+                writeln!(
+                    &mut svg,
+                    "<text x='{}' y='{}' text-anchor='middle'>{}</text>",
+                    center - 8.0,
+                    SVG_WIRE + 4.0,
+                    xml_escape(pin)
+                )
+                .unwrap();
+            }
+        }
+
+        if stage.input.is_some() && stage.output.is_some() {
+            writeln!(
+                &mut svg,
+                "<text x='{}' y='{}' text-anchor='middle' fill='#a33'>cell {:.3}</text>",
+                center,
+                SVG_TOP - 8.0,
+                stage.cell_delay
+            )
+            .unwrap();
+        }
+        if let Some(input) = stage.input {
+            writeln!(
+                &mut svg,
+                "<text x='{}' y='{}'>{}{}</text>",
+                x,
+                SVG_TOP + cell_h + 14.0,
+                xml_escape(symbols.pin_short_name(input.0)),
+                input.1
+            )
+            .unwrap();
+        }
+        let Some(output) = stage.output else {
+            continue;
+        };
+        writeln!(
+            &mut svg,
+            "<text x='{}' y='{}' text-anchor='end'>{}{}</text>",
+            x + cell_w,
+            SVG_TOP + cell_h + 14.0,
+            xml_escape(symbols.pin_short_name(output.0)),
+            output.1
+        )
+        .unwrap();
+        writeln!(
+            &mut svg,
+            "<text x='{}' y='{}' text-anchor='end' fill='#555'>@{:.3}</text>",
+            x + cell_w,
+            SVG_TOP + cell_h + 28.0,
+            stage.arrival
+        )
+        .unwrap();
+
+        // wire to the next stage
+        if let Some(next) = report.stages.get(i + 1) {
+            writeln!(
+                &mut svg,
+                "<line x1='{}' y1='{}' x2='{}' y2='{}' stroke='#333' stroke-width='2'/>",
+                x + cell_w,
+                SVG_WIRE,
+                x + SVG_STEP,
+                SVG_WIRE
+            )
+            .unwrap();
+            writeln!(
+                &mut svg,
+                "<text x='{}' y='{}' text-anchor='middle' fill='#36a'>{:.3}</text>",
+                x + cell_w + (SVG_STEP - cell_w) / 2.0,
+                SVG_WIRE - 6.0,
+                next.net_delay
+            )
+            .unwrap();
+        }
+
+        let mut fanouts: Vec<PinId> = graph
+            .graph
+            .get(&output)
+            .into_iter()
+            .flatten()
+            .map(|edge| edge.dst.0)
+            .filter(|pin| !pins_in_path.contains(pin) && symbols.pin_instance(*pin) != stage.instance)
+            .collect();
+        fanouts.sort_unstable();
+        fanouts.dedup();
+        let stub_x = x + cell_w + 12.0;
+        for (k, &fanout) in fanouts.iter().take(3).enumerate() {
+            let end = (stub_x + 8.0 + k as f32 * 14.0, SVG_WIRE + 52.0 + k as f32 * 12.0);
+            writeln!(
+                &mut svg,
+                "<line x1='{}' y1='{}' x2='{}' y2='{}' stroke='#999' stroke-dasharray='3,2'><title>{}</title></line>",
+                stub_x,
+                SVG_WIRE,
+                end.0,
+                end.1,
+                xml_escape(symbols.pin_name(fanout))
+            )
+            .unwrap();
+        }
+        if !fanouts.is_empty() {
+            writeln!(
+                &mut svg,
+                "<text x='{}' y='{}' fill='#999'>{} fanout</text>",
+                stub_x + 50.0,
+                SVG_WIRE + 64.0,
+                fanouts.len()
+            )
+            .unwrap();
+        }
+    }
+
+    writeln!(&mut svg, "</svg>").unwrap();
+    svg
+}
+
+/// Width of [`floorplan_svg`], its height follows the aspect ratio of the die
+const FLOORPLAN_WIDTH: f32 = 600.0;
+
+/// Draw the placed instances as dots and the paths of `reports` as polylines through the instances they cross,
+/// colored from red for the worst `slack` to green for the best one. The startpoints are circles.
+pub fn floorplan_svg(graph: &SDFGraph, placement: &Placement, reports: &[(&PathReport, f32)]) -> String {
+    let Some(((x0, y0), (x1, y1))) = placement.bounds() else {
+        return String::new();
+    };
+    let scale = FLOORPLAN_WIDTH / (x1 - x0).max(f32::EPSILON);
+    let height = ((y1 - y0) * scale).max(1.0);
+    // the y axis of the DEF goes up
+    let point = |(x, y): (f32, f32)| ((x - x0) * scale + 10.0, height - (y - y0) * scale + 10.0);
+
+    let mut svg = String::new();
+    writeln!(
+        &mut svg,
+        "<svg xmlns='http://www.w3.org/2000/svg' width='{:.0}' height='{:.0}' font-family='monospace' font-size='11'>",
+        FLOORPLAN_WIDTH + 20.0,
+        height + 20.0
+    )
+    .unwrap();
+    writeln!(
+        &mut svg,
+        "<rect x='10' y='10' width='{:.1}' height='{:.1}' fill='#fafafa' stroke='#333'/>",
+        FLOORPLAN_WIDTH, height
+    )
+    .unwrap();
+
+    let mut dots = String::new();
+    for &location in placement.instances.values() {
+        let (x, y) = point(location);
+        write!(&mut dots, "M{:.1} {:.1}h1", x, y).unwrap();
+    }
+    writeln!(&mut svg, "<path d='{}' stroke='#bbb'/>", dots).unwrap();
+
+    let (worst, best) = reports
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(worst, best), (_, slack)| {
+            (worst.min(*slack), best.max(*slack))
+        });
+    // the worst path last, on top of the others
+    for (report, slack) in reports.iter().rev() {
+        let mut points: Vec<(f32, f32)> = Vec::new();
+        for (pin, _) in report.pins() {
+            let Some(location) = placement.pin_location(graph, pin.0) else {
+                continue;
+            };
+            let location = point(location);
+            if points.last() != Some(&location) {
+                points.push(location);
+            }
+        }
+        let Some(&(start_x, start_y)) = points.first() else {
+            continue;
+        };
+        let ratio = if best > worst {
+            (slack - worst) / (best - worst)
+        } else {
+            0.0
+        };
+        let color = format!("hsl({:.0}, 80%, 42%)", 120.0 * ratio);
+        let polyline: Vec<String> = points.iter().map(|(x, y)| format!("{:.1},{:.1}", x, y)).collect();
+        writeln!(
+            &mut svg,
+            "<g><title>{}{} slack {:.3}</title>\
+             <polyline points='{}' fill='none' stroke='{}' stroke-width='1.5' stroke-opacity='0.8'/>\
+             <circle cx='{:.1}' cy='{:.1}' r='3' fill='{}'/></g>",
+            xml_escape(graph.symbols.pin_name(report.endpoint.0)),
+            report.endpoint.1,
+            slack,
+            polyline.join(" "),
+            color,
+            start_x,
+            start_y,
+            color
+        )
+        .unwrap();
+    }
+
+    writeln!(&mut svg, "</svg>").unwrap();
+    svg
+}
+
+/// Bins of the histogram at the top of [`timing_review_html`]
+const HISTOGRAM_BINS: usize = 20;
+/// Size of the plot area of [`histogram_svg`]
+const HISTOGRAM_SIZE: (f32, f32) = (560.0, 100.0);
+
+/// Draw the slack histogram of the endpoints as bars, the bins of negative slack in red, with the slack range and the
+/// number of endpoints of each bin in its tooltip.
+pub fn histogram_svg(histogram: &SlackHistogram) -> String {
+    let (plot_w, plot_h) = HISTOGRAM_SIZE;
+    let (left, top) = (40.0, 10.0);
+    let bottom = top + plot_h;
+    let largest = histogram.counts.iter().copied().max().unwrap_or(0).max(1);
+    let bar_w = plot_w / histogram.counts.len() as f32;
+
+    let mut svg = String::new();
+    writeln!(
+        &mut svg,
+        "<svg xmlns='http://www.w3.org/2000/svg' width='{}' height='{}' font-family='monospace' font-size='11'>",
+        left + plot_w + 40.0,
+        bottom + 30.0
+    )
+    .unwrap();
+
+    for (i, &count) in histogram.counts.iter().enumerate() {
+        let (from, to) = histogram.bin_range(i);
+        // a single outlier endpoint stays visible next to a wall of them
+        let h = match count {
+            0 => 0.0,
+            _ => (count as f32 / largest as f32 * plot_h).max(2.0),
+        };
+        let fill = if from < 0.0 { "#d9534f" } else { "#5b9bd5" };
+        writeln!(
+            &mut svg,
+            "<rect x='{:.1}' y='{:.1}' width='{:.1}' height='{:.1}' fill='{}' stroke='#fff'>\
+             <title>{:.3} to {:.3}: {} endpoints</title></rect>",
+            left + i as f32 * bar_w,
+            bottom - h,
+            bar_w,
+            h,
+            fill,
+            from,
+            to,
+            count
+        )
+        .unwrap();
+    }
+
+    writeln!(
+        &mut svg,
+        "<line x1='{}' y1='{}' x2='{}' y2='{}' stroke='#333'/>",
+        left,
+        bottom,
+        left + plot_w,
+        bottom
+    )
+    .unwrap();
+    // where the violations stop, when some endpoints meet the timing and others don't
+    if histogram.min < 0.0 && histogram.max > 0.0 {
+        let x = left - histogram.min / (histogram.max - histogram.min) * plot_w;
+        writeln!(
+            &mut svg,
+            "<line x1='{:.1}' y1='{}' x2='{:.1}' y2='{}' stroke='#333' stroke-dasharray='3,3'/>\
+             <text x='{:.1}' y='{}' text-anchor='middle'>0</text>",
+            x,
+            top,
+            x,
+            bottom + 4.0,
+            x,
+            bottom + 16.0
+        )
+        .unwrap();
+    }
+    for (x, slack, anchor) in [(left, histogram.min, "start"), (left + plot_w, histogram.max, "end")] {
+        writeln!(
+            &mut svg,
+            "<text x='{}' y='{}' text-anchor='{}'>{:.3}</text>",
+            x,
+            bottom + 16.0,
+            anchor,
+            slack
+        )
+        .unwrap();
+    }
+    writeln!(
+        &mut svg,
+        "<text x='{}' y='{}' text-anchor='end'>{}</text>",
+        left - 6.0,
+        top + 10.0,
+        largest
+    )
+    .unwrap();
+    writeln!(
+        &mut svg,
+        "<text x='{}' y='{}' text-anchor='middle' fill='#555'>slack (ns), {} endpoints</text>",
+        left + plot_w / 2.0,
+        bottom + 28.0,
+        histogram.total()
+    )
+    .unwrap();
+
+    writeln!(&mut svg, "</svg>").unwrap();
+    svg
+}
+
+/// Rows of the table of a path, one per stage
+fn path_rows(graph: &SDFGraph, analysis: &SDFGraphAnalyzed, report: &PathReport, links: &PathLinks, html: &mut String) {
+    let symbols = &graph.symbols;
+    let max_delay = report.total_delay;
+    let pins_in_path = report.pin_set();
+    // fanout pin, linking to the row of its instance
+    let link = |pin: PinId, name: &str| match symbols
+        .pin_instance(pin)
+        .and_then(|instance| links.instances.get(&instance))
+    {
+        Some(id) => format!("<a href='#{}'>{}</a>", id, name),
+        None => name.to_string(),
+    };
+
+    for (stage_i, stage) in report.stages.iter().enumerate() {
+        let (Some(pin_in), pin_out) = (stage.first_pin(), stage.last_pin()) else {
+            continue;
+        };
+        let instance = instance_name(symbols.pin_name(pin_in.0));
+        let instance_id = symbols.instance_id(&instance);
+
+        // nothing after the endpoint
+        let (t_setup, t_arrival) = match stage.output {
+            Some(pin_out) => (
+                analysis.max_delay.get(&pin_out).copied(),
+                analysis.max_delay_backwards.get(&pin_out).copied(),
+            ),
+            None => (None, None),
+        };
+
+        if links.prefix.is_empty() {
+            writeln!(html, "<tr>").unwrap();
+        } else {
+            writeln!(html, "<tr id='{}s{}'>", links.prefix, stage_i).unwrap();
+        }
+        writeln!(
+            html,
             "<td><center>{}<br/>{}{} → {}{}</center></td>",
             instance,
-            pin_name(&pin_in.0),
+            symbols.pin_short_name(pin_in.0),
             pin_in.1,
-            pin_name(&pin_out.0),
-            pin_out.1
+            pin_out.map_or("", |pin_out| symbols.pin_short_name(pin_out.0)),
+            pin_out.map_or(Transition::Rise, |pin_out| pin_out.1)
         )
         .unwrap();
         let mut writecell = |v: Option<f32>| {
             if let Some(v) = v {
-                writeln!(&mut html, "<td>{:.3}</td>", v).unwrap();
+                writeln!(html, "<td>{:.3}</td>", v).unwrap();
             } else {
-                writeln!(&mut html, "<td></td>").unwrap();
+                writeln!(html, "<td></td>").unwrap();
             }
         };
         writecell(t_setup);
         writecell(t_arrival);
+        writecell(Some(stage.net_delay));
+        writecell(Some(stage.cell_delay));
 
-        let mut fanin_with_slack = graph
-            .instance_ins
-            .get(instance)
+        let mut fanin_with_slack = instance_id
+            .and_then(|id| graph.instance_ins.get(&id))
             .iter()
             .flat_map(|v| v.iter())
-            .filter(|fanin_pin| pin_name(&fanin_pin) != "CLK")
-            .flat_map(|fanin_pin| {
+            .filter(|&&fanin_pin| !graph.is_register_clock(fanin_pin))
+            .flat_map(|&fanin_pin| {
                 [Transition::Rise, Transition::Fall]
                     .iter()
-                    .map(move |transition| (fanin_pin.clone(), *transition))
+                    .map(move |transition| (fanin_pin, *transition))
             })
             .map(|pin| {
                 let t_setup = analysis.max_delay.get(&pin).copied();
@@ -153,6 +906,11 @@ document.addEventListener('DOMContentLoaded', function() {
             .collect::<Vec<_>>();
 
         fanin_with_slack.sort_unstable_by_key(|(_, _, _, slack)| OrderedFloat(slack.unwrap_or(f32::INFINITY)));
+        // the column of the inputs sorts by the slack of the input of the path
+        let critical_slack = fanin_with_slack
+            .iter()
+            .find(|(pin, _, _, _)| *pin == pin_in)
+            .and_then(|(_, _, _, slack)| *slack);
 
         let mut input_pin_html = String::new();
         let mut input_pin_20p = String::new();
@@ -165,23 +923,23 @@ document.addEventListener('DOMContentLoaded', function() {
                     write!(
                         html,
                         "{}{}{}: {:.3} {:.3} <b>{:.3}</b>{}<br>",
-                        is_critical.then(|| "<b>").unwrap_or(""),
-                        pin_name(&other_pin_in.0),
+                        if is_critical { "<b>" } else { "" },
+                        symbols.pin_short_name(other_pin_in.0),
                         other_pin_in.1,
                         t_setup,
                         t_arrival,
                         slack,
-                        is_critical.then(|| "</b>").unwrap_or("")
+                        if is_critical { "</b>" } else { "" }
                     )
                     .unwrap();
                 } else {
                     write!(
                         html,
                         "{}{}{}{}<br>",
-                        is_critical.then(|| "<b>").unwrap_or(""),
-                        pin_name(&other_pin_in.0),
+                        if is_critical { "<b>" } else { "" },
+                        symbols.pin_short_name(other_pin_in.0),
                         other_pin_in.1,
-                        is_critical.then(|| "</b>").unwrap_or("")
+                        if is_critical { "</b>" } else { "" }
                     )
                     .unwrap();
                 }
@@ -200,23 +958,19 @@ document.addEventListener('DOMContentLoaded', function() {
             write_times(&mut input_pin_20p, t_setup, t_arrival, slack);
         }
         writeln!(
-            &mut html,
-            "<td><div class='nogain'>{}</div><div class='gain'>{}</div></td>",
-            input_pin_html, input_pin_20p
+            html,
+            "<td data-sort='{}'><div class='nogain'>{}</div><div class='gain'>{}</div></td>",
+            critical_slack.map_or(String::new(), |slack| format!("{:.3}", slack)),
+            input_pin_html,
+            input_pin_20p
         )
         .unwrap();
 
-        let mut fanout_with_slack = graph
-            .instance_fanout
-            .get(instance)
+        let mut fanout_with_slack = instance_id
+            .and_then(|id| graph.instance_fanout.get(&id))
             .iter()
             .flat_map(|v| v.iter())
-            .flat_map(|fanout_pin_in| {
-                [
-                    (fanout_pin_in.clone(), Transition::Rise),
-                    (fanout_pin_in.clone(), Transition::Fall),
-                ]
-            })
+            .flat_map(|&fanout_pin_in| [(fanout_pin_in, Transition::Rise), (fanout_pin_in, Transition::Fall)])
             .map(|pin| {
                 let t_setup = analysis.max_delay.get(&pin).copied();
                 let t_arrival = analysis.max_delay_backwards.get(&pin).copied();
@@ -231,6 +985,7 @@ document.addEventListener('DOMContentLoaded', function() {
             .collect::<Vec<_>>();
 
         fanout_with_slack.sort_unstable_by_key(|(_, _, _, slack)| OrderedFloat(slack.unwrap_or(f32::INFINITY)));
+        let worst_fanout_slack = fanout_with_slack.first().and_then(|(_, _, _, slack)| *slack);
 
         let mut output_pin_html = String::new();
         let mut output_pin_20p = String::new();
@@ -243,17 +998,23 @@ document.addEventListener('DOMContentLoaded', function() {
                     write!(
                         html,
                         "{}{}{}: {:.3} {:.3} <b>{:.3}</b>{}<br>",
-                        is_critical.then(|| "<b>").unwrap_or(""),
-                        &fanout_pin_in.0,
+                        if is_critical { "<b>" } else { "" },
+                        link(fanout_pin_in.0, symbols.pin_name(fanout_pin_in.0)),
                         fanout_pin_in.1,
                         t_setup,
                         t_arrival,
                         slack,
-                        is_critical.then(|| "</b>").unwrap_or("")
+                        if is_critical { "</b>" } else { "" }
                     )
                     .unwrap();
                 } else {
-                    write!(html, "{}{}<br>", pin_name(&fanout_pin_in.0), fanout_pin_in.1).unwrap();
+                    write!(
+                        html,
+                        "{}{}<br>",
+                        link(fanout_pin_in.0, symbols.pin_short_name(fanout_pin_in.0)),
+                        fanout_pin_in.1
+                    )
+                    .unwrap();
                 }
             };
 
@@ -270,19 +1031,147 @@ document.addEventListener('DOMContentLoaded', function() {
             write_times(&mut output_pin_20p, t_setup, t_arrival, slack);
         }
         // no pain no gain
+        writeln!(
+            html,
+            "<td data-sort='{}'><div class='nogain'>{}</div><div class='gain'>{}</div></td>",
+            worst_fanout_slack.map_or(String::new(), |slack| format!("{:.3}", slack)),
+            output_pin_html,
+            output_pin_20p
+        )
+        .unwrap();
+
+        writeln!(html, "</tr>").unwrap();
+    }
+}
+
+/// Generate a standalone HTML page with the instances and segments shared by the worst paths, and the paths themselves
+/// with their shared instances in bold.
+pub fn bottleneck_html(graph: &SDFGraph, bottlenecks: &Bottlenecks) -> String {
+    let symbols = &graph.symbols;
+    let celltype = |instance| {
+        graph
+            .instance_celltype
+            .get(&instance)
+            .map_or("", |celltype| &**celltype)
+    };
+
+    let mut html = String::new();
+    html.push_str(
+        r#"<html lang="en">
+<head>
+<meta charset="UTF-8">
+<style>
+    table, th, td { border: 1px solid #c1c1c1; border-collapse: collapse; }
+    th, td { padding: 5px 10px; }
+    td {
+    font-family: monospace;
+    text-align: right;
+    }
+</style>
+<title>Bottlenecks</title>
+</head>
+<body>"#,
+    );
+    writeln!(
+        &mut html,
+        "{}\n<div style='padding: 10px'>{}</div>",
+        TABLE_SCRIPT, SEARCH_BOX
+    )
+    .unwrap();
+
+    writeln!(
+        &mut html,
+        "<h2>Shared instances ({} worst paths)</h2>",
+        bottlenecks.paths.len()
+    )
+    .unwrap();
+    writeln!(
+        &mut html,
+        "<table class='sortable'>\n<tr><th>Instance</th><th>Cell</th><th>Paths</th><th>Worst slack</th></tr>"
+    )
+    .unwrap();
+    for shared in &bottlenecks.instances {
         writeln!(
             &mut html,
-            "<td><div class='nogain'>{}</div><div class='gain'>{}</div></td>",
-            output_pin_html, output_pin_20p
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{:.3}</td></tr>",
+            symbols.instance_name(shared.instance),
+            celltype(shared.instance),
+            shared.paths,
+            shared.worst_slack
         )
         .unwrap();
+    }
+    writeln!(&mut html, "</table>").unwrap();
 
-        writeln!(&mut html, "</tr>").unwrap();
+    writeln!(&mut html, "<h2>Shared segments</h2>").unwrap();
+    writeln!(
+        &mut html,
+        "<table class='sortable'>\n<tr><th>Paths</th><th>Delay</th><th>Pins</th></tr>"
+    )
+    .unwrap();
+    for segment in &bottlenecks.segments {
+        let pins: Vec<String> = segment
+            .pins
+            .iter()
+            .map(|pin| format!("{}{}", symbols.pin_name(pin.0), pin.1))
+            .collect();
+        writeln!(
+            &mut html,
+            "<tr><td>{}</td><td>{:.3}</td><td>{}</td></tr>",
+            segment.paths,
+            segment.delay,
+            pins.join(" → ")
+        )
+        .unwrap();
     }
+    writeln!(&mut html, "</table>").unwrap();
+
+    let shared: PinSet = bottlenecks
+        .instances
+        .iter()
+        .flat_map(|shared| graph.instance_ins.get(&shared.instance).into_iter().flatten())
+        .chain(
+            bottlenecks
+                .instances
+                .iter()
+                .flat_map(|shared| graph.instance_outs.get(&shared.instance).into_iter().flatten()),
+        )
+        .copied()
+        .collect();
 
+    writeln!(&mut html, "<h2>Paths</h2>").unwrap();
+    writeln!(
+        &mut html,
+        "<table class='sortable'>\n<tr><th>Slack</th><th>Arrival</th><th>Path</th></tr>"
+    )
+    .unwrap();
+    for path in &bottlenecks.paths {
+        let pins: Vec<String> = path
+            .path
+            .iter()
+            .map(|(pin, _)| pin)
+            .chain([&path.endpoint])
+            .map(|pin| {
+                let name = format!("{}{}", symbols.pin_name(pin.0), pin.1);
+                if shared.contains(&pin.0) {
+                    format!("<b>{}</b>", name)
+                } else {
+                    name
+                }
+            })
+            .collect();
+        writeln!(
+            &mut html,
+            "<tr><td>{:.3}</td><td>{:.3}</td><td style='text-align: left'>{}</td></tr>",
+            path.slack,
+            path.arrival,
+            pins.join(" → ")
+        )
+        .unwrap();
+    }
     writeln!(&mut html, "</table>").unwrap();
     writeln!(&mut html, "</body>").unwrap();
     writeln!(&mut html, "</html>").unwrap();
 
-    std::fs::write("path.html", html).unwrap();
+    html
 }
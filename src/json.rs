@@ -0,0 +1,183 @@
+use crate::analysis::SDFGraphAnalyzed;
+use crate::graph::SDFGraph;
+use crate::types::{PinTrans, Transition};
+use miniserde::Serialize;
+
+#[derive(Serialize)]
+pub struct AnalysisJson {
+    pub corner: String,
+    /// arrival time of the slowest endpoint, used as required time of the unconstrained endpoints
+    pub worst_arrival: f32,
+    /// every reachable endpoint, smallest slack first
+    pub endpoints: Vec<EndpointJson>,
+    /// worst path of the endpoints with the smallest slack (one per pin)
+    pub paths: Vec<PathJson>,
+    /// worst slack through each instance relative to the clock period (or the worst arrival), smallest first
+    pub instances: Vec<InstanceSlackJson>,
+}
+
+#[derive(Serialize)]
+pub struct EndpointJson {
+    pub pin: String,
+    pub transition: Transition,
+    pub arrival: f32,
+    /// None when the endpoint is unconstrained
+    pub required: Option<f32>,
+    pub slack: f32,
+}
+
+#[derive(Serialize)]
+pub struct PathJson {
+    pub startpoint: String,
+    pub endpoint: String,
+    pub arrival: f32,
+    pub slack: f32,
+    pub stages: Vec<StageJson>,
+}
+
+#[derive(Serialize)]
+pub struct StageJson {
+    pub pin: String,
+    pub transition: Transition,
+    /// None for the ports of the design
+    pub celltype: Option<String>,
+    /// delay from the previous stage
+    pub delay: f32,
+    /// arrival time at this pin
+    pub time: f32,
+}
+
+#[derive(Serialize)]
+pub struct InstanceSlackJson {
+    pub instance: String,
+    pub celltype: String,
+    pub slack: f32,
+}
+
+impl AnalysisJson {
+    /// Gather the results of the analysis, with the worst path of the `top` endpoints with the smallest slack.
+    pub fn new(graph: &SDFGraph, analysis: &SDFGraphAnalyzed, top: usize) -> Self {
+        let symbols = &graph.symbols;
+        let endpoints = analysis.endpoint_pins_by_slack(graph);
+
+        let paths = endpoints
+            .iter()
+            .take(top)
+            .map(|(endpoint, slack)| PathJson::new(graph, analysis, endpoint, *slack))
+            .collect();
+
+        let mut instances: Vec<InstanceSlackJson> = graph
+            .instance_celltype
+            .iter()
+            .filter_map(|(instance, celltype)| {
+                // a path through the instance goes through one of its outputs
+                let worst = graph
+                    .instance_outs
+                    .get(&instance)?
+                    .iter()
+                    .flat_map(|&pin| [(pin, Transition::Rise), (pin, Transition::Fall)])
+                    .filter_map(|pin| Some(analysis.max_delay.get(&pin)? + analysis.max_delay_backwards.get(&pin)?))
+                    .fold(f32::NAN, f32::max);
+                if worst.is_nan() {
+                    return None;
+                }
+                Some(InstanceSlackJson {
+                    instance: symbols.instance_name(instance).to_string(),
+                    celltype: celltype.clone(),
+                    slack: analysis.period.unwrap_or(analysis.worst_arrival) - worst,
+                })
+            })
+            .collect();
+        instances.sort_by(|a, b| a.slack.total_cmp(&b.slack));
+
+        AnalysisJson {
+            corner: graph.corner.to_string(),
+            worst_arrival: analysis.worst_arrival,
+            endpoints: endpoints
+                .iter()
+                .map(|(pin, slack)| EndpointJson {
+                    pin: symbols.pin_name(pin.0).to_string(),
+                    transition: pin.1,
+                    arrival: analysis.arrival(pin).unwrap(),
+                    required: analysis.required.get(pin).copied(),
+                    slack: *slack,
+                })
+                .collect(),
+            paths,
+            instances,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        miniserde::json::to_string(self)
+    }
+}
+
+impl PathJson {
+    /// Worst path to `endpoint`, which must be reached
+    pub fn new(graph: &SDFGraph, analysis: &SDFGraphAnalyzed, endpoint: &PinTrans, slack: f32) -> Self {
+        let symbols = &graph.symbols;
+        let celltype_of = |pin: &PinTrans| {
+            symbols
+                .pin_instance(pin.0)
+                .and_then(|id| graph.instance_celltype.get(&id))
+                .cloned()
+        };
+
+        let arrival = analysis.arrival(endpoint).unwrap();
+        let mut path = analysis.extract_path(graph, endpoint);
+        path.push((*endpoint, arrival));
+
+        let mut previous = 0.0;
+        let stages = path
+            .iter()
+            .map(|(pin, time)| {
+                let delay = time - previous;
+                previous = *time;
+                StageJson {
+                    pin: symbols.pin_name(pin.0).to_string(),
+                    transition: pin.1,
+                    celltype: celltype_of(pin),
+                    delay,
+                    time: *time,
+                }
+            })
+            .collect();
+
+        PathJson {
+            startpoint: symbols.pin_name(path[0].0 .0).to_string(),
+            endpoint: symbols.pin_name(endpoint.0).to_string(),
+            arrival,
+            slack,
+            stages,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        miniserde::json::to_string(self)
+    }
+}
+
+/// Index of the SPICE decks written by `stars spice`
+#[derive(Serialize, Default)]
+pub struct SpiceManifestJson {
+    pub corner: String,
+    pub decks: Vec<SpiceDeckJson>,
+}
+
+#[derive(Serialize)]
+pub struct SpiceDeckJson {
+    pub endpoint: String,
+    pub transition: Transition,
+    /// arrival time of the endpoint in the SDF, the delay the simulation is compared to
+    pub delay: f32,
+    pub slack: f32,
+    /// file name of the deck, relative to the manifest
+    pub deck: String,
+}
+
+impl SpiceManifestJson {
+    pub fn to_json(&self) -> String {
+        miniserde::json::to_string(self)
+    }
+}
@@ -1,18 +1,52 @@
 #![allow(uncommon_codepoints)]
 
 pub mod analysis;
+pub mod bottleneck;
+pub mod comparison;
+pub mod csr;
+pub mod csv;
+pub mod def;
+pub mod delaycalc;
+pub mod dot;
+#[cfg(feature = "spice")]
+pub mod eco;
+pub mod error;
 pub mod graph;
+#[cfg(feature = "html")]
 pub mod html;
+pub mod json;
+pub mod liberty;
+pub mod logging;
+pub mod memory;
+pub mod net;
+#[cfg(feature = "ngspice")]
+pub mod ngspice;
+pub mod opensta;
+#[cfg(feature = "spef")]
 pub mod parasitics;
+#[cfg(feature = "spice")]
+pub mod pdk;
+pub mod power;
+pub mod progress;
+pub mod report;
+pub mod sdc;
+pub mod sdfdiff;
+#[cfg(feature = "spice")]
 pub mod spice;
+pub mod stats;
+#[cfg(feature = "spice")]
 pub mod subckt;
 pub mod types;
 
-use types::SDFPin;
+/// Read an input file (SDF, SPEF, subckt...), decompressing it when it is gzipped or zstd compressed.
+#[cfg(feature = "fs")]
+pub fn read_input(path: impl AsRef<std::path::Path>) -> std::io::Result<String> {
+    sdfparse::read_to_string_decompressed(std::fs::File::open(path)?)
+}
 
 /// Extract the name of the pin from the full path.
 /// For example, `and4/A` -> `A`
-pub fn pin_name_ref(pin: &SDFPin) -> &str {
+pub fn pin_name_ref(pin: &str) -> &str {
     let Some(v) = pin.rsplit_once('/') else {
         return pin;
     };
@@ -21,7 +55,7 @@ pub fn pin_name_ref(pin: &SDFPin) -> &str {
 
 /// Extract the name of the pin from the full path.
 /// For example, `and4/A` -> `A`
-pub fn pin_name(pin: &SDFPin) -> String {
+pub fn pin_name(pin: &str) -> String {
     let Some(v) = pin.rsplit_once('/') else {
         return pin.to_string();
     };
@@ -30,7 +64,7 @@ pub fn pin_name(pin: &SDFPin) -> String {
 
 /// Extract the name of the instance from the full path.
 /// For example, `and4/A` -> `and4`
-pub fn instance_name(pin: &SDFPin) -> String {
+pub fn instance_name(pin: &str) -> String {
     let Some(v) = pin.rsplit_once('/') else {
         return pin.to_string();
     };
@@ -39,13 +73,69 @@ pub fn instance_name(pin: &SDFPin) -> String {
 
 /// Turns sky130_fd_sc_hd__xor2_1 into xor2
 pub fn celltype_short(celltype: &str) -> &str {
-    celltype
-        .trim_start_matches("sky130_fd_sc_hd__")
-        .rsplit_once('_')
-        .unwrap()
-        .0
+    let celltype = celltype.trim_start_matches("sky130_fd_sc_hd__");
+    celltype.rsplit_once('_').map_or(celltype, |(short, _)| short)
 }
 
 pub fn celltype_short_with_size(celltype: &str) -> &str {
     celltype.trim_start_matches("sky130_fd_sc_hd__")
 }
+
+/// Match a name against a glob pattern where `*` is any sequence of characters and `?` any single character,
+/// as used by SDC patterns. For example, `_10*_/D` matches `_10618_/D`
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.as_bytes();
+    let name = name.as_bytes();
+
+    let (mut p, mut n) = (0, 0);
+    // position of the last `*` and where it started matching in name
+    let mut backtrack = None;
+
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == b'?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == b'*' {
+            backtrack = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = backtrack {
+            p = star_p + 1;
+            n = star_n + 1;
+            backtrack = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// Value of a SPICE number with its scale suffix (`0.15u`, `2.5k`, `1meg`, `3f`), and whether it had one
+#[cfg(feature = "spef")]
+pub(crate) fn spice_number(word: &str) -> Option<(f64, bool)> {
+    // the first letter that is not the exponent of the number
+    let split = word
+        .char_indices()
+        .find(|&(i, c)| {
+            c.is_ascii_alphabetic()
+                && !(matches!(c, 'e' | 'E')
+                    && word[i + 1..].starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+'))
+        })
+        .map_or(word.len(), |(i, _)| i);
+    let value: f64 = word[..split].parse().ok()?;
+    let suffix = word[split..].to_ascii_lowercase();
+    let scale = match suffix.chars().next() {
+        _ if suffix.starts_with("meg") => 1e6,
+        Some('t') => 1e12,
+        Some('g') => 1e9,
+        Some('k') => 1e3,
+        Some('m') => 1e-3,
+        Some('u') => 1e-6,
+        Some('n') => 1e-9,
+        Some('p') => 1e-12,
+        Some('f') => 1e-15,
+        // no suffix, or a unit like `ohm`
+        _ => return Some((value, false)),
+    };
+    Some((value * scale, true))
+}
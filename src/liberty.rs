@@ -0,0 +1,680 @@
+//! Reader for Liberty (`.lib`) cell libraries: pin capacitances, design rule limits, timing senses
+//! and NLDM delay/transition tables.
+//!
+//! The file is first read as a tree of generic groups (`cell (name) { ... }`) and attributes
+//! (`capacitance : 0.002;` or `index_1 ("0.01, 0.02");`), which is then turned into a [`Library`].
+
+use crate::types::TriUnate;
+use rustc_hash::FxHashMap;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LibertyError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for LibertyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Liberty line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for LibertyError {}
+
+/// Generic Liberty group, e.g `pin (A) { ... }` has kind `pin` and args `["A"]`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Group {
+    pub kind: String,
+    pub args: Vec<String>,
+    /// simple (`name : value;`) and complex (`name (a, b);`) attributes, in file order
+    pub attributes: Vec<(String, Vec<String>)>,
+    pub groups: Vec<Group>,
+}
+
+impl Group {
+    /// First value of an attribute
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attr, _)| attr == name)
+            .and_then(|(_, values)| values.first())
+            .map(|value| &**value)
+    }
+
+    pub fn number(&self, name: &str) -> Option<f32> {
+        self.attribute(name)?.parse().ok()
+    }
+
+    /// Numbers of a complex attribute, e.g `index_1 ("0.01, 0.02")` or `values ("1, 2", "3, 4")` (flattened)
+    pub fn numbers(&self, name: &str) -> Option<Vec<f32>> {
+        let (_, values) = self.attributes.iter().find(|(attr, _)| attr == name)?;
+        values
+            .iter()
+            .flat_map(|value| value.split(','))
+            .map(|number| number.trim())
+            .filter(|number| !number.is_empty())
+            .map(|number| number.parse().ok())
+            .collect()
+    }
+
+    pub fn groups<'a>(&'a self, kind: &'a str) -> impl Iterator<Item = &'a Group> + 'a {
+        self.groups.iter().filter(move |group| group.kind == kind)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum PinDirection {
+    #[default]
+    Input,
+    Output,
+    Inout,
+    Internal,
+}
+
+/// NLDM lookup table, `values[i1 * index_2.len() + i2]` is the value at `(index_1[i1], index_2[i2])`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NldmTable {
+    /// variables of the indices from the `lu_table_template`, e.g `input_net_transition`
+    pub variables: [Option<String>; 2],
+    pub index_1: Vec<f32>,
+    /// empty for one dimensional tables
+    pub index_2: Vec<f32>,
+    pub values: Vec<f32>,
+}
+
+impl NldmTable {
+    /// Bilinear interpolation at `(x1, x2)` (extrapolation outside of the table)
+    pub fn lookup(&self, x1: f32, x2: f32) -> f32 {
+        let (i1, t1) = interpolation_point(&self.index_1, x1);
+        if self.index_2.is_empty() {
+            return lerp(self.values[i1], self.values.get(i1 + 1).copied().unwrap_or(0.0), t1);
+        }
+        let (i2, t2) = interpolation_point(&self.index_2, x2);
+        let n2 = self.index_2.len();
+        let at = |i1: usize, i2: usize| self.values.get(i1 * n2 + i2).copied().unwrap_or(0.0);
+
+        lerp(
+            lerp(at(i1, i2), at(i1, i2 + 1), t2),
+            lerp(at(i1 + 1, i2), at(i1 + 1, i2 + 1), t2),
+            t1,
+        )
+    }
+
+    /// Value for an input transition and an output load, whatever the order of the variables in the table.
+    pub fn delay(&self, input_transition: f32, output_load: f32) -> f32 {
+        match self.variables[0].as_deref() {
+            Some("total_output_net_capacitance") => self.lookup(output_load, input_transition),
+            _ => self.lookup(input_transition, output_load),
+        }
+    }
+}
+
+/// Index of the segment of `index` containing `x` and the position of `x` in it (outside of \[0, 1\] when extrapolating)
+fn interpolation_point(index: &[f32], x: f32) -> (usize, f32) {
+    if index.len() < 2 {
+        return (0, 0.0);
+    }
+    let i = index.windows(2).position(|w| x <= w[1]).unwrap_or(index.len() - 2);
+    let (lo, hi) = (index[i], index[i + 1]);
+    if hi == lo {
+        return (i, 0.0);
+    }
+    (i, (x - lo) / (hi - lo))
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// `timing` group of an output pin: the arc from `related_pin` to the pin.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TimingArc {
+    pub related_pin: String,
+    pub timing_sense: Option<TriUnate>,
+    /// e.g `combinational`, `rising_edge`, `setup_rising`. None is combinational
+    pub timing_type: Option<String>,
+    /// condition of the arc, like the COND of the SDF
+    pub when: Option<String>,
+    pub cell_rise: Option<NldmTable>,
+    pub cell_fall: Option<NldmTable>,
+    pub rise_transition: Option<NldmTable>,
+    pub fall_transition: Option<NldmTable>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LibertyPin {
+    pub name: String,
+    pub direction: PinDirection,
+    /// in the capacitance unit of the library
+    pub capacitance: Option<f32>,
+    pub max_transition: Option<f32>,
+    pub max_capacitance: Option<f32>,
+    /// boolean function of an output, e.g `(A&B)`
+    pub function: Option<String>,
+    pub timing: Vec<TimingArc>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LibertyCell {
+    pub name: String,
+    pub area: Option<f32>,
+    /// in the leakage power unit of the library: `cell_leakage_power`, or the mean of the `leakage_power` groups
+    pub leakage_power: Option<f32>,
+    pub pins: Vec<LibertyPin>,
+}
+
+impl LibertyCell {
+    pub fn pin(&self, name: &str) -> Option<&LibertyPin> {
+        self.pins.iter().find(|pin| pin.name == name)
+    }
+
+    /// Timing sense from an input to the outputs of the cell, combining the arcs of all the outputs.
+    pub fn unateness(&self, input: &str) -> Option<TriUnate> {
+        let mut senses = self
+            .pins
+            .iter()
+            .flat_map(|pin| &pin.timing)
+            .filter(|arc| arc.related_pin == input)
+            .filter_map(|arc| arc.timing_sense);
+        let first = senses.next()?;
+        Some(if senses.all(|sense| sense == first) {
+            first
+        } else {
+            TriUnate::Non
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Library {
+    pub name: String,
+    /// in ns
+    pub time_unit: f32,
+    /// in pF
+    pub capacitive_load_unit: f32,
+    /// in W
+    pub leakage_power_unit: f32,
+    pub default_max_transition: Option<f32>,
+    pub cells: FxHashMap<String, LibertyCell>,
+}
+
+impl Library {
+    pub fn parse(src: &str) -> Result<Library, LibertyError> {
+        let mut parser = Parser {
+            src: src.as_bytes(),
+            pos: 0,
+            line: 1,
+        };
+        let group = parser.group_file()?;
+        if group.kind != "library" {
+            return Err(LibertyError {
+                line: 1,
+                message: format!("expected a library group, got {}", group.kind),
+            });
+        }
+        Ok(Library::from_group(&group))
+    }
+
+    pub fn from_group(library: &Group) -> Library {
+        let templates: FxHashMap<&str, &Group> = library
+            .groups("lu_table_template")
+            .filter_map(|template| Some((&**template.args.first()?, template)))
+            .collect();
+
+        let table = |arc: &Group, kind: &str| -> Option<NldmTable> {
+            let group = arc.groups(kind).next()?;
+            let template = group.args.first().and_then(|name| templates.get(&**name));
+            let variable = |name: &str| template.and_then(|t| t.attribute(name)).map(str::to_string);
+            // tables can override the indices of their template
+            let index = |name: &str| {
+                group
+                    .numbers(name)
+                    .or_else(|| template?.numbers(name))
+                    .unwrap_or_default()
+            };
+            Some(NldmTable {
+                variables: [variable("variable_1"), variable("variable_2")],
+                index_1: index("index_1"),
+                index_2: index("index_2"),
+                values: group.numbers("values").unwrap_or_default(),
+            })
+        };
+
+        let cells = library
+            .groups("cell")
+            .filter_map(|cell| {
+                let name = cell.args.first()?.clone();
+                let pins = cell
+                    .groups("pin")
+                    .filter_map(|pin| {
+                        Some(LibertyPin {
+                            name: pin.args.first()?.clone(),
+                            direction: match pin.attribute("direction") {
+                                Some("output") => PinDirection::Output,
+                                Some("inout") => PinDirection::Inout,
+                                Some("internal") => PinDirection::Internal,
+                                _ => PinDirection::Input,
+                            },
+                            capacitance: pin.number("capacitance"),
+                            max_transition: pin.number("max_transition"),
+                            max_capacitance: pin.number("max_capacitance"),
+                            function: pin.attribute("function").map(str::to_string),
+                            timing: pin
+                                .groups("timing")
+                                .map(|arc| TimingArc {
+                                    related_pin: arc.attribute("related_pin").unwrap_or_default().to_string(),
+                                    timing_sense: match arc.attribute("timing_sense") {
+                                        Some("positive_unate") => Some(TriUnate::Positive),
+                                        Some("negative_unate") => Some(TriUnate::Negative),
+                                        Some("non_unate") => Some(TriUnate::Non),
+                                        _ => None,
+                                    },
+                                    timing_type: arc.attribute("timing_type").map(str::to_string),
+                                    when: arc.attribute("when").map(str::to_string),
+                                    cell_rise: table(arc, "cell_rise"),
+                                    cell_fall: table(arc, "cell_fall"),
+                                    rise_transition: table(arc, "rise_transition"),
+                                    fall_transition: table(arc, "fall_transition"),
+                                })
+                                .collect(),
+                        })
+                    })
+                    .collect();
+                Some((
+                    name.clone(),
+                    LibertyCell {
+                        name,
+                        area: cell.number("area"),
+                        leakage_power: cell.number("cell_leakage_power").or_else(|| {
+                            let states: Vec<f32> = cell
+                                .groups("leakage_power")
+                                .filter_map(|state| state.number("value"))
+                                .collect();
+                            (!states.is_empty()).then(|| states.iter().sum::<f32>() / states.len() as f32)
+                        }),
+                        pins,
+                    },
+                ))
+            })
+            .collect();
+
+        let capacitive_load_unit = match library
+            .attributes
+            .iter()
+            .find(|(name, _)| name == "capacitive_load_unit")
+        {
+            Some((_, values)) => {
+                let value: f32 = values.first().and_then(|v| v.parse().ok()).unwrap_or(1.0);
+                match values.get(1).map(|unit| unit.to_ascii_lowercase()).as_deref() {
+                    Some("ff") => value * 1e-3,
+                    _ => value,
+                }
+            }
+            None => 1.0,
+        };
+
+        let time_unit = match library.attribute("time_unit") {
+            Some(unit) => {
+                let (value, unit) = unit.split_at(unit.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(unit.len()));
+                let value: f32 = value.parse().unwrap_or(1.0);
+                match unit {
+                    "ps" => value * 1e-3,
+                    "us" => value * 1e3,
+                    _ => value,
+                }
+            }
+            None => 1.0,
+        };
+
+        // no default in the standard, the libraries of the open PDKs use nW
+        let leakage_power_unit = match library.attribute("leakage_power_unit") {
+            Some(unit) => {
+                let (value, unit) = unit.split_at(unit.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(unit.len()));
+                let value: f32 = value.parse().unwrap_or(1.0);
+                match unit {
+                    "mW" => value * 1e-3,
+                    "uW" => value * 1e-6,
+                    "pW" => value * 1e-12,
+                    "fW" => value * 1e-15,
+                    _ => value * 1e-9,
+                }
+            }
+            None => 1e-9,
+        };
+
+        Library {
+            name: library.args.first().cloned().unwrap_or_default(),
+            time_unit,
+            capacitive_load_unit,
+            leakage_power_unit,
+            default_max_transition: library.number("default_max_transition"),
+            cells,
+        }
+    }
+
+    /// Capacitance of the input pins in pF, keyed by `celltype/pin` (like `pin_capa.json`)
+    pub fn pin_capacitances(&self) -> FxHashMap<String, f32> {
+        self.cells
+            .values()
+            .flat_map(|cell| {
+                cell.pins
+                    .iter()
+                    .filter(|pin| pin.direction == PinDirection::Input)
+                    .filter_map(move |pin| {
+                        Some((
+                            format!("{}/{}", cell.name, pin.name),
+                            pin.capacitance? * self.capacitive_load_unit,
+                        ))
+                    })
+            })
+            .collect()
+    }
+
+    /// Timing sense of the inputs of every cell (like `unateness.json`, but keyed by the full celltype)
+    pub fn unateness(&self) -> FxHashMap<String, FxHashMap<String, TriUnate>> {
+        self.cells
+            .values()
+            .map(|cell| {
+                let inputs = cell
+                    .pins
+                    .iter()
+                    .filter(|pin| pin.direction == PinDirection::Input)
+                    .filter_map(|pin| Some((pin.name.clone(), cell.unateness(&pin.name)?)))
+                    .collect();
+                (cell.name.clone(), inputs)
+            })
+            .collect()
+    }
+}
+
+struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+    line: usize,
+}
+
+impl Parser<'_> {
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, LibertyError> {
+        Err(LibertyError {
+            line: self.line,
+            message: message.into(),
+        })
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<u8> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == b'\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    /// Skip whitespace, comments and line continuations, newlines too if `newlines` is set
+    fn skip_blank(&mut self, newlines: bool) {
+        loop {
+            match self.peek() {
+                Some(b'\n') if !newlines => return,
+                Some(c) if c.is_ascii_whitespace() => {
+                    self.bump();
+                }
+                Some(b'\\') if matches!(self.src.get(self.pos + 1), Some(b'\n' | b'\r')) => {
+                    self.bump();
+                }
+                Some(b'/') if self.src.get(self.pos + 1) == Some(&b'*') => {
+                    self.pos += 2;
+                    while self.peek().is_some() && !self.src[self.pos..].starts_with(b"*/") {
+                        self.bump();
+                    }
+                    self.pos = (self.pos + 2).min(self.src.len());
+                }
+                Some(b'/') if self.src.get(self.pos + 1) == Some(&b'/') => {
+                    while !matches!(self.peek(), Some(b'\n') | None) {
+                        self.bump();
+                    }
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// Quoted string or bare word (identifiers, numbers, expressions without spaces)
+    fn word(&mut self) -> Result<String, LibertyError> {
+        if self.peek() == Some(b'"') {
+            self.bump();
+            let start = self.pos;
+            while !matches!(self.peek(), Some(b'"') | None) {
+                if self.peek() == Some(b'\\') {
+                    self.bump();
+                }
+                self.bump();
+            }
+            let word = String::from_utf8_lossy(&self.src[start..self.pos])
+                .replace("\\\n", "")
+                .replace("\\\r\n", "");
+            if self.bump().is_none() {
+                return self.error("unterminated string");
+            }
+            return Ok(word);
+        }
+
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_whitespace() || b"(){}:;,\"".contains(&c) {
+                break;
+            }
+            self.bump();
+        }
+        if start == self.pos {
+            return match self.peek() {
+                Some(c) => self.error(format!("unexpected '{}'", c as char)),
+                None => self.error("unexpected end of file"),
+            };
+        }
+        Ok(String::from_utf8_lossy(&self.src[start..self.pos]).into_owned())
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), LibertyError> {
+        self.skip_blank(true);
+        if self.bump() != Some(c) {
+            return self.error(format!("expected '{}'", c as char));
+        }
+        Ok(())
+    }
+
+    fn group_file(&mut self) -> Result<Group, LibertyError> {
+        self.skip_blank(true);
+        let kind = self.word()?;
+        let Statement::Group(group) = self.statement(kind)? else {
+            return self.error("expected a group");
+        };
+        Ok(group)
+    }
+
+    /// Comma separated arguments between parentheses
+    fn args(&mut self) -> Result<Vec<String>, LibertyError> {
+        self.expect(b'(')?;
+        let mut args = Vec::new();
+        loop {
+            self.skip_blank(true);
+            match self.peek() {
+                Some(b')') => {
+                    self.bump();
+                    return Ok(args);
+                }
+                Some(b',') => {
+                    self.bump();
+                }
+                _ => args.push(self.word()?),
+            }
+        }
+    }
+
+    fn statement(&mut self, name: String) -> Result<Statement, LibertyError> {
+        self.skip_blank(true);
+        match self.peek() {
+            Some(b':') => {
+                self.bump();
+                // value runs until the end of the statement, it can contain spaces when it's an expression
+                let mut words = Vec::new();
+                loop {
+                    self.skip_blank(false);
+                    match self.peek() {
+                        Some(b';') => {
+                            self.bump();
+                            break;
+                        }
+                        Some(b'\n' | b'}') | None => break,
+                        _ => words.push(self.word()?),
+                    }
+                }
+                Ok(Statement::Attribute(name, vec![words.join(" ")]))
+            }
+            Some(b'(') => {
+                let args = self.args()?;
+                self.skip_blank(false);
+                match self.peek() {
+                    Some(b'{') => {
+                        self.bump();
+                        let mut group = Group {
+                            kind: name,
+                            args,
+                            ..Default::default()
+                        };
+                        loop {
+                            self.skip_blank(true);
+                            match self.peek() {
+                                Some(b'}') => {
+                                    self.bump();
+                                    return Ok(Statement::Group(group));
+                                }
+                                Some(b';') => {
+                                    self.bump();
+                                }
+                                None => return self.error(format!("unterminated group {}", group.kind)),
+                                _ => {
+                                    let name = self.word()?;
+                                    match self.statement(name)? {
+                                        Statement::Attribute(name, values) => group.attributes.push((name, values)),
+                                        Statement::Group(child) => group.groups.push(child),
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    Some(b';') => {
+                        self.bump();
+                        Ok(Statement::Attribute(name, args))
+                    }
+                    _ => Ok(Statement::Attribute(name, args)),
+                }
+            }
+            _ => self.error(format!("expected ':' or '(' after {}", name)),
+        }
+    }
+}
+
+enum Statement {
+    Attribute(String, Vec<String>),
+    Group(Group),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIB: &str = r#"
+/* sky130-like excerpt */
+library ("sky130_test") {
+    time_unit : "1ns";
+    capacitive_load_unit (1.0000000000, "pf");
+    leakage_power_unit : "1pW";
+    default_max_transition : 1.5;
+    lu_table_template ("del_2x2") {
+        variable_1 : "input_net_transition";
+        variable_2 : "total_output_net_capacitance";
+        index_1 ("0.01, 0.5");
+        index_2 ("0.001, 0.1");
+    }
+    cell ("sky130_fd_sc_hd__nand2_1") {
+        area : 3.75;
+        leakage_power () {
+            value : 2.0;
+            when : "A&B";
+        }
+        leakage_power () {
+            value : 4.0;
+            when : "!A&!B";
+        }
+        pin ("A") {
+            capacitance : 0.0023;
+            direction : "input";
+        }
+        pin ("B") {
+            capacitance : 0.0024;
+            direction : "input";
+        }
+        pin ("Y") {
+            direction : "output";
+            function : "(!A) | (!B)";
+            max_capacitance : 0.07;
+            max_transition : 1.5;
+            timing () {
+                related_pin : "A";
+                timing_sense : "negative_unate";
+                timing_type : "combinational";
+                cell_fall ("del_2x2") {
+                    values ("0.02, 0.2", \
+                            "0.06, 0.3");
+                }
+            }
+            timing () {
+                related_pin : "B";
+                timing_sense : "negative_unate";
+                cell_fall ("del_2x2") {
+                    index_1 ("0.0, 1.0");
+                    values ("0.0, 0.1", "1.0, 1.1");
+                }
+            }
+        }
+    }
+}
+"#;
+
+    #[test]
+    fn test_parse_liberty() {
+        let lib = Library::parse(LIB).unwrap();
+        assert_eq!(lib.name, "sky130_test");
+        assert_eq!(lib.default_max_transition, Some(1.5));
+
+        let nand = &lib.cells["sky130_fd_sc_hd__nand2_1"];
+        assert_eq!(nand.area, Some(3.75));
+        assert_eq!(nand.leakage_power, Some(3.0));
+        assert!((lib.leakage_power_unit - 1e-12).abs() < 1e-18);
+        assert_eq!(nand.pin("A").unwrap().capacitance, Some(0.0023));
+        assert_eq!(nand.unateness("A"), Some(TriUnate::Negative));
+
+        let y = nand.pin("Y").unwrap();
+        assert_eq!(y.direction, PinDirection::Output);
+        assert_eq!(y.function.as_deref(), Some("(!A) | (!B)"));
+        assert_eq!(y.max_capacitance, Some(0.07));
+
+        let table = y.timing[0].cell_fall.as_ref().unwrap();
+        assert_eq!(table.index_1, vec![0.01, 0.5]);
+        assert_eq!(table.values, vec![0.02, 0.2, 0.06, 0.3]);
+        assert!((table.lookup(0.01, 0.001) - 0.02).abs() < 1e-6);
+        assert!((table.delay(0.5, 0.1) - 0.3).abs() < 1e-6);
+
+        // indices overridden by the table
+        let table = y.timing[1].cell_fall.as_ref().unwrap();
+        assert!((table.lookup(0.5, 0.0505) - 0.55).abs() < 1e-5);
+
+        assert_eq!(lib.pin_capacitances()["sky130_fd_sc_hd__nand2_1/B"], 0.0024);
+        assert!(Library::parse("library (x) { cell (a) {").is_err());
+    }
+}
@@ -0,0 +1,103 @@
+//! Logger of the command line: the messages of the `log` crate go to stderr, with the warnings deduplicated and
+//! limited per call site as some of them (missing parasitics, unknown pin combinations...) repeat for every wire
+//! or instance of the design.
+
+use std::hash::BuildHasher;
+use std::io::IsTerminal;
+use std::sync::Mutex;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use rustc_hash::{FxBuildHasher, FxHashMap, FxHashSet};
+
+/// Warnings printed by a call site before the next ones are suppressed
+pub const WARNINGS_PER_SITE: usize = 10;
+
+/// Logs to stderr, see [`init`]
+pub struct StderrLogger {
+    level: LevelFilter,
+    /// warnings printed by each call site (file and line), None when they are not limited
+    per_site: Option<Mutex<FxHashMap<(&'static str, u32), usize>>>,
+    /// hashes of the warnings already printed
+    printed: Mutex<FxHashSet<u64>>,
+    /// printed before the messages to erase the line of a [`crate::progress::Progress::stderr`] in a terminal
+    clear_line: &'static str,
+}
+
+/// Install the logger, printing the messages up to `level`. The warnings are deduplicated, and limited to
+/// [`WARNINGS_PER_SITE`] per call site below the [`LevelFilter::Debug`] verbosity.
+pub fn init(level: LevelFilter) {
+    let logger = StderrLogger {
+        level,
+        per_site: (level < LevelFilter::Debug).then(Default::default),
+        printed: Default::default(),
+        clear_line: if std::io::stderr().is_terminal() {
+            "\r\x1b[2K"
+        } else {
+            ""
+        },
+    };
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+impl StderrLogger {
+    /// Whether to print a warning, and whether it is the last one of its call site
+    fn admit(&self, record: &Record, message: &str) -> Option<bool> {
+        let mut printed = self.printed.lock().unwrap();
+        let hash = FxBuildHasher.hash_one(message);
+        if printed.contains(&hash) {
+            return None;
+        }
+        let last = match &self.per_site {
+            Some(per_site) => {
+                let site = (record.file_static().unwrap_or(""), record.line().unwrap_or(0));
+                let mut per_site = per_site.lock().unwrap();
+                let count = per_site.entry(site).or_default();
+                if *count == WARNINGS_PER_SITE {
+                    return None;
+                }
+                *count += 1;
+                *count == WARNINGS_PER_SITE
+            }
+            None => false,
+        };
+        printed.insert(hash);
+        Some(last)
+    }
+}
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        match record.level() {
+            // progress and results of the steps, printed as is
+            Level::Info => eprintln!("{}{}", self.clear_line, record.args()),
+            Level::Warn => {
+                let message = record.args().to_string();
+                match self.admit(record, &message) {
+                    Some(true) => eprintln!(
+                        "{}warning: {} (further similar warnings suppressed, -v shows them)",
+                        self.clear_line, message
+                    ),
+                    Some(false) => eprintln!("{}warning: {}", self.clear_line, message),
+                    None => {}
+                }
+            }
+            level => eprintln!(
+                "{}{}: {}",
+                self.clear_line,
+                level.as_str().to_lowercase(),
+                record.args()
+            ),
+        }
+    }
+
+    fn flush(&self) {}
+}
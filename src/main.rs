@@ -1,86 +1,1008 @@
-use std::cmp::Reverse;
-use std::fs::read_to_string;
-
-use ordered_float::OrderedFloat;
-use stars::analysis::SDFGraphAnalyzed;
-use stars::graph::SDFGraph;
-use stars::html::extract_html_for_manual_analysis;
-use stars::instance_name;
+use std::cell::OnceCell;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use log::LevelFilter;
+use stars::analysis::{AnalysisOptions, PathGroup, SDFGraphAnalyzed};
+use stars::bottleneck::Bottlenecks;
+use stars::comparison::AnalysisComparison;
+use stars::csv::{endpoints_csv, stages_csv};
+use stars::def::Placement;
+use stars::delaycalc::DelayCalculator;
+use stars::dot::DotOptions;
+use stars::eco;
+use stars::error::StarsError;
+use stars::graph::{InterconnectDelays, SDFGraph, SDFGraphOptions};
+use stars::html::{
+    bottleneck_html, comparison_html, extract_html_for_manual_analysis, extract_html_with_template, timing_review_html,
+};
+use stars::json::{AnalysisJson, SpiceDeckJson, SpiceManifestJson};
+use stars::liberty::Library;
+use stars::opensta;
 use stars::parasitics::Parasitics;
-use stars::spice::extract_spice_for_manual_analysis;
+use stars::pdk::PdkConfig;
+use stars::power::{Activity, Capacitances, LeakageReport, PowerReport};
+use stars::progress::{Phase, Progress};
+use stars::report::{
+    report_annotation, report_bottlenecks, report_checks, report_comparison, report_histogram, report_hold_checks,
+    report_leakage, report_path_groups, report_power, report_sdf_diff, report_sta_comparison, report_stats,
+};
+use stars::sdc::Sdc;
+use stars::sdfdiff::sdf_diff;
+use stars::spice::{
+    extract_hold_spice_for_manual_analysis, extract_spice_for_manual_analysis, SpiceDialect, SpiceOptions,
+};
 use stars::subckt::SubcktData;
+use stars::types::{Corner, PinTrans, Transition};
+use stars::{instance_name, read_input};
+
+/// File name (without extension) for the reports of an endpoint, e.g. `_271_-2fD_rise`. The other characters than
+/// `[A-Za-z0-9_]` are escaped as `-` and their hexadecimal bytes, so two endpoints never share a file.
+fn endpoint_file_stem(graph: &SDFGraph, output: &PinTrans) -> String {
+    let mut stem = String::new();
+    for c in graph.symbols.pin_name(output.0).chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            stem.push(c);
+            continue;
+        }
+        for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+            write!(stem, "-{:02x}", byte).unwrap();
+        }
+    }
+    stem.push_str(match output.1 {
+        Transition::Rise => "_rise",
+        Transition::Fall => "_fall",
+    });
+    stem
+}
+
+/// Graph of the SDF, read from `cache` when it was saved from the same SDF file and `options` and written to it
+/// otherwise. `build` (which parses the SDF) is only called when the graph isn't in the cache.
+fn build_graph(
+    sdf_path: &Path,
+    build: impl FnOnce() -> Result<SDFGraph, StarsError>,
+    options: &str,
+    cache: Option<&Path>,
+) -> Result<SDFGraph, StarsError> {
+    let Some(cache) = cache else {
+        return build();
+    };
+
+    #[cfg(feature = "serde")]
+    {
+        // the SDF is identified by its path, size and modification time, hashing it would cost as much as parsing it
+        let meta = std::fs::metadata(sdf_path).ok();
+        let modified = meta.as_ref().and_then(|meta| meta.modified().ok());
+        let source = format!(
+            "{}\n{:?}\n{:?}\n{}",
+            std::fs::canonicalize(sdf_path).as_deref().unwrap_or(sdf_path).display(),
+            meta.map(|meta| meta.len()),
+            modified,
+            options
+        );
+        if cache.exists() {
+            match SDFGraph::load(cache, &source) {
+                Ok(graph) => return Ok(graph),
+                Err(e) => log::warn!("Could not load the graph from {}: {}", cache.display(), e),
+            }
+        }
+        let graph = build()?;
+        if let Err(e) = graph.save(cache, &source) {
+            log::warn!("Could not save the graph to {}: {}", cache.display(), e);
+        }
+        Ok(graph)
+    }
+
+    #[cfg(not(feature = "serde"))]
+    {
+        let _ = (sdf_path, options);
+        log::warn!(
+            "--graph-cache {} ignored, stars was built without the serde feature",
+            cache.display()
+        );
+        build()
+    }
+}
+
+/// Simulate a deck written to `path` with ngspice and print its delays next to the ones of the SDF
+fn simulate_deck(path: &Path, deck: &str) {
+    #[cfg(feature = "ngspice")]
+    match stars::ngspice::simulate(path) {
+        Ok(measures) => print!(
+            "{}",
+            stars::ngspice::report_comparison(&stars::ngspice::compare(deck, &measures))
+        ),
+        Err(e) => log::error!("Could not simulate {}: {}", path.display(), e),
+    }
+
+    #[cfg(not(feature = "ngspice"))]
+    {
+        let _ = deck;
+        log::warn!(
+            "--simulate {} ignored, stars was built without the ngspice feature",
+            path.display()
+        );
+    }
+}
+
+/// What to do with the analyzed graph, given as the first argument (`stars report ...`)
+#[derive(PartialEq)]
+enum Command {
+    /// Write the HTML/SPICE of the paths to the --top endpoints with the largest delay (default)
+    Extract,
+    /// Print OpenSTA-like reports of the worst paths
+    Report,
+    /// Print resizing/buffering suggestions for the worst paths (needs --subckt)
+    Eco,
+    /// Report the worst path of the reg2reg, in2reg, reg2out and in2out path groups
+    PathGroups,
+    /// Plot the distribution of the endpoint slacks
+    Histogram,
+    /// Report the instances and segments shared by the --top worst paths, also written as bottlenecks.html
+    Bottleneck,
+    /// Compare the delays of the arcs of --sdf and --new-sdf
+    SdfDiff,
+    /// Write the SPICE decks of the --top endpoints with the smallest slack and their manifest.json (needs --subckt)
+    Spice,
+    /// Compare the interconnect delays of the SDF with the Elmore delays of the parasitics (needs --spef)
+    CheckAnnotation,
+    /// Compare the slacks and the worst paths of the analyses of --sdf and --new-sdf, also written as comparison.html
+    Compare,
+    /// Compare the arrival times along the paths of an OpenSTA `report_checks` output (needs --sta-report)
+    CompareSta,
+    /// Write the endpoints and the stages of the --top worst paths as endpoints.csv and stages.csv (needs --csv)
+    Export,
+    /// Estimate the dynamic power of the nets from the toggles of a VCD or SAIF file (needs --activity), and the
+    /// leakage of the cells with --leakage (needs --liberty)
+    Power,
+    /// Print the size of the graph and the pins and cells that look like mistakes of the SDF
+    Stats,
+}
+
+/// Messages printed by the logger from -qqq to -vv, the default being the warnings and the progress of the steps
+const VERBOSITY: [LevelFilter; 6] = [
+    LevelFilter::Off,
+    LevelFilter::Error,
+    LevelFilter::Warn,
+    LevelFilter::Info,
+    LevelFilter::Debug,
+    LevelFilter::Trace,
+];
 
 fn main() {
+    let mut command = Command::Extract;
+    let mut top = 1;
+    let mut bins = 20;
+    let mut period = None;
+    let mut group_periods = Vec::new();
+    let mut propagated_clock = false;
+    let mut short_names = false;
     let mut subckt_data_path = None;
+    let mut pdk_path = None;
     let mut sdf_data_path = None;
+    let mut new_sdf_path = None;
+    let mut sta_report_path = None;
+    let mut tolerance = 0.0;
     let mut spef_data_path = None;
+    let mut spef_divider = '/';
+    let mut sdc_data_path = None;
+    let mut liberty_path = None;
+    let mut def_path = None;
+    let mut activity_path = None;
+    let mut corners = vec![Corner::default()];
+    let mut interconnect = InterconnectDelays::Sdf;
+    let mut dot_path = None;
+    let mut region_from = Vec::new();
+    let mut region_to = Vec::new();
+    let mut cone_depth = None;
+    let mut spice_options = SpiceOptions::default();
+    let mut hold_spice = false;
+    let mut simulate = false;
+    let mut json_path: Option<PathBuf> = None;
+    let mut csv = false;
+    let mut leakage = false;
+    let mut review_path: Option<PathBuf> = None;
+    let mut template_path = None;
+    let mut graph_cache: Option<PathBuf> = None;
+    let mut low_memory = false;
+    let mut out_dir = PathBuf::from(".");
+    // index in VERBOSITY, raised by -v and lowered by -q
+    let mut verbosity = 3;
+    let mut unknown_args = Vec::new();
 
     let mut args_iter = std::env::args_os().skip(1);
     while let Some(arg) = args_iter.next() {
         let arg = arg.into_string().expect("Invalid argument");
-        if arg.starts_with("--subckt") {
+        if arg == "report" {
+            command = Command::Report;
+        } else if arg == "eco" {
+            command = Command::Eco;
+        } else if arg == "path-groups" {
+            command = Command::PathGroups;
+        } else if arg == "histogram" {
+            command = Command::Histogram;
+        } else if arg == "bottleneck" {
+            command = Command::Bottleneck;
+        } else if arg == "sdf-diff" {
+            command = Command::SdfDiff;
+        } else if arg == "spice" {
+            command = Command::Spice;
+        } else if arg == "check-annotation" {
+            command = Command::CheckAnnotation;
+        } else if arg == "compare-sta" {
+            command = Command::CompareSta;
+        } else if arg == "compare" {
+            command = Command::Compare;
+        } else if arg == "export" {
+            command = Command::Export;
+        } else if arg == "power" {
+            command = Command::Power;
+        } else if arg == "stats" {
+            command = Command::Stats;
+        } else if arg.starts_with("--sta-report") {
+            sta_report_path = Some(args_iter.next().expect("No argument given"));
+        } else if arg.starts_with("--new-sdf") {
+            new_sdf_path = Some(args_iter.next().expect("No argument given"));
+        } else if arg.starts_with("--tolerance") {
+            let t = args_iter.next().expect("No argument given");
+            tolerance = t
+                .to_str()
+                .and_then(|t| t.parse().ok())
+                .expect("--tolerance expects a number (ns)");
+        } else if arg.starts_with("--bins") {
+            let n = args_iter.next().expect("No argument given");
+            bins = n
+                .to_str()
+                .and_then(|n| n.parse().ok())
+                .expect("--bins expects a number");
+        } else if arg.starts_with("--top") {
+            let n = args_iter.next().expect("No argument given");
+            top = n.to_str().and_then(|n| n.parse().ok()).expect("--top expects a number");
+        } else if arg.starts_with("--period") {
+            let p = args_iter.next().expect("No argument given");
+            period = Some(
+                p.to_str()
+                    .and_then(|p| p.parse().ok())
+                    .expect("--period expects a number (ns)"),
+            );
+        } else if arg.starts_with("--group-period") {
+            let group_period = args_iter.next().expect("No argument given");
+            let (group, period) = group_period
+                .to_str()
+                .and_then(|group_period| group_period.split_once('='))
+                .expect("--group-period expects group=period, e.g in2out=5");
+            let group: PathGroup = group.parse().unwrap_or_else(|e| panic!("{}", e));
+            let period: f32 = period.parse().expect("--group-period expects a number (ns)");
+            group_periods.push((group, period));
+        } else if arg.starts_with("--propagated-clock") {
+            propagated_clock = true;
+        } else if arg.starts_with("--short-names") {
+            short_names = true;
+        } else if arg.starts_with("--subckt") {
             subckt_data_path = Some(args_iter.next().expect("No argument given"));
+        } else if arg.starts_with("--pdk") {
+            pdk_path = Some(args_iter.next().expect("No argument given"));
         } else if arg.starts_with("--sdf") {
             sdf_data_path = Some(args_iter.next().expect("No argument given"));
+        } else if arg.starts_with("--sdc") {
+            sdc_data_path = Some(args_iter.next().expect("No argument given"));
+        } else if arg.starts_with("--def") {
+            def_path = Some(args_iter.next().expect("No argument given"));
+        } else if arg.starts_with("--activity") {
+            activity_path = Some(args_iter.next().expect("No argument given"));
+        } else if arg.starts_with("--leakage") {
+            leakage = true;
+        } else if arg.starts_with("--liberty") {
+            liberty_path = Some(args_iter.next().expect("No argument given"));
+        } else if arg.starts_with("--spef-divider") {
+            let divider = args_iter.next().expect("No argument given");
+            let mut chars = divider.to_str().expect("Invalid argument").chars();
+            spef_divider = match (chars.next(), chars.next()) {
+                (Some(divider), None) => divider,
+                _ => panic!("--spef-divider expects a character"),
+            };
         } else if arg.starts_with("--spef") {
             spef_data_path = Some(args_iter.next().expect("No argument given"));
+        } else if arg.starts_with("--elmore") {
+            let mode = args_iter.next().expect("No argument given");
+            interconnect = match mode.to_str() {
+                Some("check") => InterconnectDelays::CrossCheck,
+                Some("replace") => InterconnectDelays::Elmore,
+                _ => panic!("--elmore expects check or replace"),
+            };
+        } else if arg.starts_with("--out-dir") {
+            out_dir = PathBuf::from(args_iter.next().expect("No argument given"));
+        } else if arg.starts_with("--review-html") {
+            review_path = Some(args_iter.next().expect("No argument given").into());
+        } else if arg.starts_with("--csv") {
+            csv = true;
+        } else if arg.starts_with("--json") {
+            json_path = Some(args_iter.next().expect("No argument given").into());
+        } else if arg.starts_with("--graph-cache") {
+            graph_cache = Some(args_iter.next().expect("No argument given").into());
+        } else if arg.starts_with("--low-memory") {
+            low_memory = true;
+        } else if arg.starts_with("--region-from") {
+            region_from.push(
+                args_iter
+                    .next()
+                    .expect("No argument given")
+                    .into_string()
+                    .expect("Invalid argument"),
+            );
+        } else if arg.starts_with("--region-to") {
+            region_to.push(
+                args_iter
+                    .next()
+                    .expect("No argument given")
+                    .into_string()
+                    .expect("Invalid argument"),
+            );
+        } else if arg.starts_with("--cone-depth") {
+            let n = args_iter.next().expect("No argument given");
+            cone_depth = Some(
+                n.to_str()
+                    .and_then(|n| n.parse().ok())
+                    .expect("--cone-depth expects a number of arcs"),
+            );
+        } else if arg.starts_with("--dot") {
+            dot_path = Some(args_iter.next().expect("No argument given"));
+        } else if arg.starts_with("--spice-dialect") {
+            let dialect = args_iter.next().expect("No argument given");
+            let dialect = dialect.to_str().expect("Invalid argument");
+            spice_options.dialect = dialect.parse().unwrap_or_else(|e| panic!("{}", e));
+        } else if arg.starts_with("--vdd") {
+            let v = args_iter.next().expect("No argument given");
+            spice_options.vdd = v
+                .to_str()
+                .and_then(|v| v.parse().ok())
+                .expect("--vdd expects a number (V)");
+        } else if arg.starts_with("--template") {
+            template_path = Some(args_iter.next().expect("No argument given"));
+        } else if arg.starts_with("--temp") {
+            let t = args_iter.next().expect("No argument given");
+            spice_options.temp = Some(
+                t.to_str()
+                    .and_then(|t| t.parse().ok())
+                    .expect("--temp expects a number (°C)"),
+            );
+        } else if arg.starts_with("--spice-corner") {
+            let corner = args_iter.next().expect("No argument given");
+            spice_options.corner_lib = Some(corner.into_string().expect("Invalid argument"));
+        } else if arg.starts_with("--spice-include") {
+            let path = args_iter.next().expect("No argument given");
+            spice_options.include_path = path.into_string().expect("Invalid argument");
+        } else if arg.starts_with("--hold-spice") {
+            hold_spice = true;
+        } else if arg.starts_with("--monte-carlo") {
+            let runs = args_iter.next().expect("No argument given");
+            spice_options.monte_carlo = Some(
+                runs.to_str()
+                    .and_then(|runs| runs.parse().ok())
+                    .expect("--monte-carlo expects a number of runs"),
+            );
+        } else if arg.starts_with("--simulate") {
+            simulate = true;
+        } else if arg.starts_with("--param-widths") {
+            spice_options.param_widths = true;
+        } else if arg.starts_with("--clocked-launch") {
+            spice_options.clocked_launch = true;
+        } else if arg.starts_with("--wire-segments") {
+            let n = args_iter.next().expect("No argument given");
+            spice_options.wire_segments = n
+                .to_str()
+                .and_then(|n| n.parse().ok())
+                .expect("--wire-segments expects a number of segments");
+        } else if arg.starts_with("--corner") {
+            let corner = args_iter.next().expect("No argument given");
+            let corner = corner.to_str().expect("Invalid argument");
+            corners = if corner == "all" {
+                Corner::ALL.to_vec()
+            } else {
+                vec![corner.parse().unwrap_or_else(|e| panic!("{}", e))]
+            };
+        } else if arg == "--verbose" {
+            verbosity += 1;
+        } else if arg == "--quiet" {
+            verbosity -= 1;
+        } else if let Some(flags) = arg
+            .strip_prefix('-')
+            .filter(|f| !f.is_empty() && f.chars().all(|c| c == 'v' || c == 'q'))
+        {
+            // -v, -vv, -q...
+            for flag in flags.chars() {
+                verbosity += if flag == 'v' { 1 } else { -1 };
+            }
         } else {
-            eprintln!("Unknown argument: {}", arg);
+            unknown_args.push(arg);
         }
     }
 
+    let level = VERBOSITY[verbosity.clamp(0, VERBOSITY.len() as i32 - 1) as usize];
+    stars::logging::init(level);
+    // progress of the long steps on stderr, hidden with the progress messages by -q
+    let progress = (level >= LevelFilter::Info).then(Progress::stderr);
+    for arg in unknown_args {
+        log::warn!("Unknown argument: {}", arg);
+    }
+
     let sdf_data_path = sdf_data_path.expect("No SDF file specified");
 
-    let sdf_content = read_to_string(sdf_data_path).expect("Could not read SDF file");
+    if command == Command::Export && !csv {
+        panic!("export needs a format, pass --csv");
+    }
 
-    let sdf = sdfparse::SDF::parse_str(&sdf_content).expect("Could not parse SDF");
+    if command == Command::SdfDiff {
+        let new_sdf_path = new_sdf_path.expect("sdf-diff needs a second SDF, pass it with --new-sdf {file}");
+        let parse = |path| {
+            let content = read_input(path).expect("Could not read SDF file");
+            sdfparse::SDF::parse_str(&content).expect("Could not parse SDF")
+        };
+        let (old_sdf, new_sdf) = (parse(&sdf_data_path), parse(&new_sdf_path));
+        for &corner in &corners {
+            let diff = sdf_diff(&old_sdf, &new_sdf, corner, tolerance)
+                .unwrap_or_else(|e| panic!("Could not compare the SDFs: {}", e));
+            print!("{}", report_sdf_diff(&diff, usize::MAX));
+        }
+        return;
+    }
+
+    // the SDF after the changes, analyzed like --sdf for each corner
+    let new_sdf = (command == Command::Compare).then(|| {
+        let new_sdf_path = new_sdf_path.expect("compare needs a second SDF, pass it with --new-sdf {file}");
+        let content = read_input(new_sdf_path).expect("Could not read SDF file");
+        sdfparse::SDF::parse_str(&content).expect("Could not parse SDF")
+    });
+
+    // paths of OpenSTA, compared with each corner
+    let sta_paths = (command == Command::CompareSta).then(|| {
+        let sta_report_path =
+            sta_report_path.expect("compare-sta needs the report of OpenSTA, pass it with --sta-report {file}");
+        let content = read_input(sta_report_path).expect("Could not read OpenSTA report");
+        let paths = opensta::parse_report(&content).unwrap_or_else(|e| panic!("Could not parse OpenSTA report: {}", e));
+        if paths.is_empty() {
+            log::warn!("No path in the OpenSTA report, it should be the output of report_checks");
+        }
+        paths
+    });
+
+    // toggles of the nets, the same for every corner
+    if command == Command::Power && activity_path.is_none() && !leakage {
+        panic!("power needs the activity, pass a VCD or SAIF file with --activity {{file}}, or --leakage");
+    }
+    if leakage && liberty_path.is_none() {
+        panic!("--leakage needs the cells, pass them with --liberty {{file}}");
+    }
+    let activity = activity_path
+        .filter(|_| command == Command::Power)
+        .map(|activity_path| {
+            let content = read_input(activity_path).expect("Could not read activity file");
+            Activity::parse_str(&content).unwrap_or_else(|e| panic!("Could not parse activity: {}", e))
+        });
+    if activity.is_some() && liberty_path.is_none() && subckt_data_path.is_none() && spef_data_path.is_none() {
+        log::warn!("power needs the capacitances, pass --liberty, --subckt or --spef, the nets have no load");
+    }
+
+    if simulate && spice_options.dialect != SpiceDialect::Ngspice {
+        panic!("--simulate runs ngspice, the decks have to be written for it");
+    }
+
+    std::fs::create_dir_all(&out_dir).expect("Could not create output directory");
 
-    let graph = SDFGraph::new(&sdf);
+    // parsed on first use, the graphs of every corner may come from --graph-cache
+    let sdf = OnceCell::new();
+    let parse_sdf = || {
+        let sdf_content = read_input(&sdf_data_path).expect("Could not read SDF file");
+        let options = sdfparse::SDFParseOptions {
+            // the other corners are dropped right away when a single one is analyzed
+            corner: (corners.len() == 1).then(|| corners[0].into()),
+            parallel: cfg!(feature = "rayon"),
+        };
+        let sdf = match &progress {
+            Some(progress) => sdfparse::SDF::parse_str_with_progress(&sdf_content, &options, |parsed, total| {
+                progress.report(Phase::SdfParse, parsed as u64, total as u64)
+            }),
+            None => sdfparse::SDF::parse_str_with(&sdf_content, &options),
+        };
+        sdf.expect("Could not parse SDF")
+    };
 
-    // print_graph(&graph, &mut keys);
+    let pdk = match pdk_path {
+        Some(path) => PdkConfig::from_json(&read_input(path).expect("Could not read PDK file"))
+            .unwrap_or_else(|e| panic!("Could not parse PDK file: {}", e)),
+        None => PdkConfig::sky130(),
+    };
 
     let subckt = match subckt_data_path {
-        Some(path) => Some(SubcktData::new(
-            &read_to_string(path).expect("Could not read SUBCKT_FILE"),
-        )),
+        Some(path) => Some(
+            SubcktData::with_pdk(&read_input(path).expect("Could not read SUBCKT_FILE"), pdk)
+                .unwrap_or_else(|e| panic!("Could not parse SUBCKT_FILE: {}", e)),
+        ),
+        None if matches!(
+            command,
+            Command::Report
+                | Command::PathGroups
+                | Command::Histogram
+                | Command::Bottleneck
+                | Command::Compare
+                | Command::CompareSta
+                | Command::Export
+                | Command::Power
+                | Command::Stats
+        ) =>
+        {
+            None
+        }
+        None if command == Command::CheckAnnotation => None,
+        None if command == Command::Eco => panic!("eco needs the cells, pass them with --subckt {{file}}"),
+        None if command == Command::Spice => panic!("spice needs the cells, pass them with --subckt {{file}}"),
         None => {
-            eprintln!("SUBCKT not passed with --subckt {{file}}, skipping spice extraction");
+            log::warn!("SUBCKT not passed with --subckt {{file}}, skipping spice extraction");
             None
         }
     };
 
-    let spef = match spef_data_path {
-        Some(path) => Some(Parasitics::new(&path)),
+    // parsed for each corner, SPEF values can be min:typ:max triples. DSPF files are also read with --spef
+    let spef_content = match spef_data_path {
+        Some(path) => match read_input(path) {
+            Ok(content) => Some(content),
+            Err(e) => {
+                log::warn!(
+                    "Could not read SPEF file: {}, using wire load model (inaccurate!) for parasitics",
+                    e
+                );
+                None
+            }
+        },
+        None if !matches!(command, Command::Extract | Command::Spice) => None,
         None => {
-            eprintln!("SPEF not passed with --spef {{file}}, using wire load model (inaccurate!) for parasitics");
+            log::warn!("SPEF not passed with --spef {{file}}, using wire load model (inaccurate!) for parasitics");
             None
         }
     };
 
-    let analysis = SDFGraphAnalyzed::analyze(&graph);
-    let mut outputs_with_delay = Vec::new();
-    for output in &graph.outputs {
-        let Some(delay) = analysis.max_delay.get(output) else {
-            continue;
+    // replaces the default page of the path (see stars::html::PATH_TEMPLATE)
+    let template = template_path.map(|path| read_input(path).expect("Could not read template file"));
+
+    let sdc = sdc_data_path.map(|path| {
+        let content = read_input(path).expect("Could not read SDC file");
+        Sdc::parse(&content).unwrap_or_else(|e| panic!("Could not parse SDC: {}", e))
+    });
+
+    let placement = def_path.map(|path| {
+        let content = read_input(path).expect("Could not read DEF file");
+        Placement::parse(&content).unwrap_or_else(|e| panic!("Could not parse DEF: {}", e))
+    });
+
+    let library = liberty_path.map(|path| {
+        let content = read_input(path).expect("Could not read Liberty file");
+        Library::parse(&content).unwrap_or_else(|e| panic!("Could not parse Liberty: {}", e))
+    });
+
+    let analysis_options = |graph: &SDFGraph| {
+        let mut options = match &sdc {
+            Some(sdc) => sdc.analysis_options(graph),
+            None => AnalysisOptions::default(),
         };
-        outputs_with_delay.push((output, *delay));
-    }
+        if period.is_some() {
+            options.period = period;
+        }
+        options.propagated_clock |= propagated_clock;
+        options.group_periods.extend(group_periods.iter().copied());
+        options.progress = progress.clone();
+        options
+    };
+
+    for &corner in &corners {
+        if corners.len() > 1 {
+            println!("=== corner {} ===", corner);
+        }
+
+        // one cache per corner, the graph only holds the delays of its corner
+        let graph_cache = graph_cache.as_ref().map(|path| {
+            let mut path = path.clone();
+            if corners.len() > 1 {
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                path.set_file_name(format!("{}_{}.bin", stem, corner));
+            }
+            path
+        });
+        let mut graph = build_graph(
+            Path::new(&sdf_data_path),
+            || {
+                let options = SDFGraphOptions {
+                    corner,
+                    progress: progress.clone(),
+                    low_memory,
+                    ..Default::default()
+                };
+                match corners.len() {
+                    // nothing else reads the SDF, it is freed while the graph is built
+                    1 => SDFGraph::from_sdf_with(parse_sdf(), &options),
+                    _ => SDFGraph::with_options(sdf.get_or_init(parse_sdf), &options),
+                }
+            },
+            &format!(
+                "corner {} low_memory {} short_names {} region {:?} -> {:?}",
+                corner, low_memory, short_names, region_from, region_to
+            ),
+            graph_cache.as_deref(),
+        )
+        .unwrap_or_else(|e| panic!("Could not build the timing graph: {}", e));
+        // the cache may hold the whole graph
+        if low_memory {
+            graph.drop_reverse_graph();
+        }
+        log::debug!("Graph memory: {}", graph.memory_stats());
+        if short_names {
+            graph.alias_by_celltype();
+        }
+
+        // decks through the missing cells fail, the other ones are still extracted
+        if let Some(Err(e)) = subckt.as_ref().map(|subckt| subckt.validate_against_graph(&graph)) {
+            log::warn!("{}", e);
+        }
+
+        let spef = spef_content.as_deref().and_then(|content| {
+            match Parasitics::parse_str_with_progress(content, corner, spef_divider, progress.as_ref()) {
+                Ok(spef) => Some(spef),
+                Err(e) => {
+                    log::warn!("{}, using wire load model (inaccurate!) for parasitics", e);
+                    None
+                }
+            }
+        });
+
+        if command == Command::CheckAnnotation {
+            let spef = spef
+                .as_ref()
+                .expect("check-annotation needs the parasitics, pass them with --spef {file}");
+            print!(
+                "{}",
+                report_annotation(&graph, &graph.check_annotation(spef), usize::MAX)
+            );
+            continue;
+        }
+
+        match &spef {
+            _ if interconnect == InterconnectDelays::Sdf => {}
+            None => log::warn!("--elmore needs the parasitics (--spef {{file}}), keeping the SDF interconnect delays"),
+            Some(spef) => {
+                let checks = graph.apply_parasitics(spef, interconnect);
+                for check in checks.iter().filter(|check| check.is_mismatch()) {
+                    log::warn!(
+                        "Interconnect {} -> {}{}: SDF delay {:.3} ns, Elmore delay {:.3} ns",
+                        graph.symbols.pin_name(check.src.0),
+                        graph.symbols.pin_name(check.dst.0),
+                        check.dst.1,
+                        check.sdf,
+                        check.elmore
+                    );
+                }
+                log::info!("{} interconnect delays estimated from the parasitics", checks.len());
+            }
+        }
+
+        if let Some(library) = &library {
+            let updated = DelayCalculator::new(library)
+                .with_parasitics(spef.as_ref())
+                .update_graph(&mut graph);
+            log::info!("{} cell delays recomputed from the Liberty tables", updated);
+        }
+
+        // only the paths between the pins matching --region-from and --region-to are analyzed
+        if !region_from.is_empty() || !region_to.is_empty() {
+            let pins = |patterns: &[String]| -> Vec<_> {
+                let pins: Vec<_> = (patterns.iter())
+                    .flat_map(|pattern| graph.symbols.pins_matching(pattern))
+                    .collect();
+                if pins.is_empty() && !patterns.is_empty() {
+                    panic!("No pin matches {}", patterns.join(" "));
+                }
+                pins
+            };
+            graph = graph.subgraph(&pins(&region_from), &pins(&region_to));
+        }
+
+        if command == Command::Stats {
+            print!("{}", report_stats(&graph, &graph.stats(), 20));
+            continue;
+        }
+
+        let analysis = SDFGraphAnalyzed::analyze_with(&graph, &analysis_options(&graph));
+
+        if let Some(json_path) = &json_path {
+            let mut json_path = json_path.clone();
+            if corners.len() > 1 {
+                let stem = json_path.file_stem().unwrap_or_default().to_string_lossy();
+                json_path.set_file_name(format!("{}_{}.json", stem, corner));
+            }
+            let json = AnalysisJson::new(&graph, &analysis, top).to_json();
+            std::fs::write(&json_path, json).expect("Could not write JSON file");
+        }
+
+        // one page for the --top endpoints with the smallest slack
+        if let Some(review_path) = &review_path {
+            let mut review_path = review_path.clone();
+            if corners.len() > 1 {
+                let stem = review_path.file_stem().unwrap_or_default().to_string_lossy();
+                review_path.set_file_name(format!("{}_{}.html", stem, corner));
+            }
+            let reports: Vec<_> = (analysis.endpoints_by_slack(&graph).into_iter())
+                .take(top)
+                .filter_map(|(endpoint, _)| analysis.path_report(&graph, &endpoint))
+                .collect();
+            let html = timing_review_html(&graph, &analysis, &reports, placement.as_ref());
+            std::fs::write(&review_path, html).expect("Could not write HTML file");
+        }
+
+        if let Some(new_sdf) = &new_sdf {
+            let mut new_graph = SDFGraph::new(new_sdf, corner)
+                .unwrap_or_else(|e| panic!("Could not build the timing graph of --new-sdf: {}", e));
+            // same delays as the first graph
+            if let Some(spef) = &spef {
+                new_graph.apply_parasitics(spef, interconnect);
+            }
+            if let Some(library) = &library {
+                DelayCalculator::new(library)
+                    .with_parasitics(spef.as_ref())
+                    .update_graph(&mut new_graph);
+            }
+            let new_analysis = SDFGraphAnalyzed::analyze_with(&new_graph, &analysis_options(&new_graph));
+
+            let comparison = AnalysisComparison::new((&graph, &analysis), (&new_graph, &new_analysis), top, tolerance);
+            print!("{}", report_comparison(&comparison, 20));
+            let stem = if corners.len() > 1 {
+                format!("comparison_{}", corner)
+            } else {
+                "comparison".to_string()
+            };
+            std::fs::write(out_dir.join(format!("{}.html", stem)), comparison_html(&comparison))
+                .expect("Could not write HTML file");
+            continue;
+        }
+
+        if command == Command::Export {
+            let suffix = if corners.len() > 1 {
+                format!("_{}", corner)
+            } else {
+                String::new()
+            };
+            std::fs::write(
+                out_dir.join(format!("endpoints{}.csv", suffix)),
+                endpoints_csv(&graph, &analysis),
+            )
+            .expect("Could not write CSV file");
+            std::fs::write(
+                out_dir.join(format!("stages{}.csv", suffix)),
+                stages_csv(&graph, &analysis, top),
+            )
+            .expect("Could not write CSV file");
+            println!("CSV tables written to {}", out_dir.display());
+            continue;
+        }
+
+        if let Some(sta_paths) = &sta_paths {
+            let paths = opensta::compare(&graph, &analysis, sta_paths);
+            print!("{}", report_sta_comparison(&paths, tolerance));
+            continue;
+        }
+
+        if command == Command::Power {
+            if let Some(activity) = &activity {
+                let capacitances = Capacitances {
+                    library: library.as_ref(),
+                    subckt: subckt.as_ref(),
+                    parasitics: spef.as_ref(),
+                };
+                let power = PowerReport::new(&graph, &analysis, activity, capacitances, spice_options.vdd);
+                print!("{}", report_power(&graph, &power, 20));
+            }
+            if let (true, Some(library)) = (leakage, &library) {
+                print!(
+                    "{}",
+                    report_leakage(&LeakageReport::new(&graph, &analysis, library), 20)
+                );
+            }
+            continue;
+        }
+
+        if command == Command::Report {
+            print!("{}", report_checks(&graph, &analysis, top, placement.as_ref()));
+            print!("{}", report_hold_checks(&graph, &analysis, top));
+            continue;
+        }
+
+        if command == Command::PathGroups {
+            let groups = analysis.path_groups(&graph);
+            print!("{}", report_path_groups(&graph, &groups, placement.as_ref()));
+            continue;
+        }
+
+        if command == Command::Histogram {
+            print!("{}", report_histogram(&graph, &analysis, bins));
+            continue;
+        }
+
+        if command == Command::Bottleneck {
+            let bottlenecks = Bottlenecks::new(&graph, &analysis, top);
+            print!("{}", report_bottlenecks(&graph, &bottlenecks, 20));
+            let stem = if corners.len() > 1 {
+                format!("bottlenecks_{}", corner)
+            } else {
+                "bottlenecks".to_string()
+            };
+            let html = bottleneck_html(&graph, &bottlenecks);
+            std::fs::write(out_dir.join(format!("{}.html", stem)), html).expect("Could not write HTML file");
+            continue;
+        }
+
+        if command == Command::Eco {
+            let subckt = subckt.as_ref().unwrap();
+            for suggestion in eco::suggest(&graph, &analysis, subckt, top) {
+                println!("{}", suggestion.describe(&graph));
+            }
+            continue;
+        }
+
+        if command == Command::Spice {
+            let subckt = subckt.as_ref().unwrap();
+            let mut manifest = SpiceManifestJson {
+                corner: corner.to_string(),
+                ..Default::default()
+            };
+            for (endpoint, slack) in analysis.endpoints_by_slack(&graph).into_iter().take(top) {
+                let Some(report) = analysis.path_report(&graph, &endpoint) else {
+                    continue;
+                };
+                let endpoint_name = graph.symbols.pin_name(endpoint.0);
+                let spice = match extract_spice_for_manual_analysis(
+                    &graph,
+                    &analysis,
+                    subckt,
+                    spef.as_ref(),
+                    &report,
+                    &spice_options,
+                ) {
+                    Ok(spice) => spice,
+                    Err(e) => {
+                        log::warn!("Could not extract the path to {}: {}", endpoint_name, e);
+                        continue;
+                    }
+                };
+                let mut stem = endpoint_file_stem(&graph, &endpoint);
+                if corners.len() > 1 {
+                    stem = format!("{}_{}", stem, corner);
+                }
+                let deck = format!("{}.spice", stem);
+                std::fs::write(out_dir.join(&deck), &spice).expect("Could not write SPICE file");
+                if simulate {
+                    simulate_deck(&out_dir.join(&deck), &spice);
+                }
+                manifest.decks.push(SpiceDeckJson {
+                    endpoint: endpoint_name.to_string(),
+                    transition: endpoint.1,
+                    delay: report.total_delay,
+                    slack,
+                    deck,
+                });
+            }
+            let manifest_name = if corners.len() > 1 {
+                format!("manifest_{}.json", corner)
+            } else {
+                "manifest.json".to_string()
+            };
+            std::fs::write(out_dir.join(manifest_name), manifest.to_json()).expect("Could not write manifest");
+            println!("{} SPICE decks written to {}", manifest.decks.len(), out_dir.display());
+            continue;
+        }
+
+        let outputs_with_delay = analysis.endpoints_by_delay(&graph);
+
+        for (i, (output, delay)) in outputs_with_delay.iter().take(top).enumerate() {
+            let (output, delay) = (output, *delay);
+            let output_name = graph.symbols.pin_name(output.0);
+            println!("{}  -- {}{}:\t{:.3}", i, output_name, output.1, delay);
+            let Some(report) = analysis.path_report(&graph, output) else {
+                continue;
+            };
+            for ((pin, transition), delay) in report.pins().filter(|(pin, _)| pin != output) {
+                println!("  {} {}{:.3}", graph.symbols.pin_name(pin), transition, delay);
+            }
+            let o_instance = instance_name(output_name);
+            let o_celltype = graph
+                .symbols
+                .pin_instance(output.0)
+                .and_then(|id| graph.instance_celltype.get(&id))
+                .map_or("", |celltype| &**celltype);
+            println!(
+                "  {}{} {:.3} {} {}",
+                output_name, output.1, delay, o_instance, o_celltype
+            );
+
+            // the cone of the worst endpoint only, the DOT file being a single file
+            if let (0, Some(dot_path)) = (i, &dot_path) {
+                let options = DotOptions {
+                    cone_of: Some(output_name.to_string()),
+                    cone_depth,
+                    highlight_path: report.pins().map(|(pin, _)| pin).collect(),
+                };
+                let file = std::fs::File::create(dot_path).expect("Could not create DOT file");
+                graph
+                    .to_dot(std::io::BufWriter::new(file), &options)
+                    .expect("Could not write DOT file");
+            }
 
-    outputs_with_delay.sort_by_key(|(_, delay)| Reverse(OrderedFloat(*delay)));
+            let mut stem = endpoint_file_stem(&graph, output);
+            if corners.len() > 1 {
+                stem = format!("{}_{}", stem, corner);
+            }
 
-    for (i, (output, delay)) in outputs_with_delay.into_iter().skip(44).take(1).enumerate() {
-        println!("{}  -- {}{}:\t{:.3}", i, output.0, output.1, delay);
-        let path = analysis.extract_path(&graph, output);
-        for ((pin, transition), delay) in &path {
-            println!("  {} {}{:.3}", pin, transition, *delay,);
+            let html = match &template {
+                Some(template) => extract_html_with_template(&graph, &analysis, &report, template),
+                None => extract_html_for_manual_analysis(&graph, &analysis, &report),
+            }
+            .unwrap_or_else(|e| panic!("Could not extract the path: {}", e));
+            std::fs::write(out_dir.join(format!("{}.html", stem)), html).expect("Could not write HTML file");
+            if let Some(subckt) = &subckt {
+                let spice = match extract_spice_for_manual_analysis(
+                    &graph,
+                    &analysis,
+                    subckt,
+                    spef.as_ref(),
+                    &report,
+                    &spice_options,
+                ) {
+                    Ok(spice) => spice,
+                    Err(e) => {
+                        log::warn!("Could not extract the path to {}: {}", output_name, e);
+                        continue;
+                    }
+                };
+                let deck = out_dir.join(format!("{}.spice", stem));
+                std::fs::write(&deck, &spice).expect("Could not write SPICE file");
+                if simulate {
+                    simulate_deck(&deck, &spice);
+                }
+            }
         }
-        let o_instance = instance_name(&output.0);
-        let o_celltype = &graph.instance_celltype[&o_instance];
-        println!("  {}{} {:.3} {} {}", output.0, output.1, delay, o_instance, o_celltype);
 
-        extract_html_for_manual_analysis(&graph, &analysis, output, delay, &path);
-        if let Some(subckt) = &subckt {
-            extract_spice_for_manual_analysis(&graph, &analysis, &subckt, spef.as_ref(), output, delay, &path);
+        // deck of the shortest path to the register with the worst hold slack, not counting the registers
+        // looping on themselves which can't be simulated
+        if let (true, Some(subckt)) = (hold_spice, &subckt) {
+            let symbols = &graph.symbols;
+            let Some((endpoint, slack)) = analysis
+                .endpoints_by_hold_slack(&graph)
+                .into_iter()
+                .find(|(endpoint, _)| {
+                    analysis.hold_path_report(&graph, endpoint).is_some_and(|report| {
+                        symbols.pin_instance(report.startpoint().0) != symbols.pin_instance(endpoint.0)
+                    })
+                })
+            else {
+                log::warn!("No hold path to simulate");
+                continue;
+            };
+            println!(
+                "hold -- {}{}:\t{:.3}",
+                graph.symbols.pin_name(endpoint.0),
+                endpoint.1,
+                slack
+            );
+            let mut stem = format!("{}_hold", endpoint_file_stem(&graph, &endpoint));
+            if corners.len() > 1 {
+                stem = format!("{}_{}", stem, corner);
+            }
+            let spice = extract_hold_spice_for_manual_analysis(
+                &graph,
+                &analysis,
+                subckt,
+                spef.as_ref(),
+                &endpoint,
+                &spice_options,
+            )
+            .unwrap_or_else(|e| panic!("Could not extract the hold path: {}", e));
+            std::fs::write(out_dir.join(format!("{}.spice", stem)), spice).expect("Could not write SPICE file");
         }
     }
 }
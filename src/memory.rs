@@ -0,0 +1,145 @@
+//! Memory held by a timing graph, to see where it goes on the large designs and whether
+//! [`SDFGraph::drop_reverse_graph`] is worth it.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{Display, Formatter};
+use std::mem::size_of;
+
+use crate::graph::{Register, SDFEdge, SDFGraph, TimingCheck};
+use crate::types::{InstanceId, PinId, Transition};
+
+/// Bytes allocated by a value on the heap, on top of its `size_of`. Estimated for the collections whose layout
+/// isn't public (B-trees, hash maps).
+pub(crate) trait HeapSize {
+    fn heap_size(&self) -> usize;
+}
+
+macro_rules! no_heap {
+    ($($ty:ty),*) => {
+        $(impl HeapSize for $ty {
+            fn heap_size(&self) -> usize {
+                0
+            }
+        })*
+    };
+}
+
+no_heap!(
+    u32,
+    f32,
+    bool,
+    PinId,
+    InstanceId,
+    Transition,
+    SDFEdge,
+    Register,
+    TimingCheck
+);
+
+impl HeapSize for String {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<A: HeapSize, B: HeapSize> HeapSize for (A, B) {
+    fn heap_size(&self) -> usize {
+        self.0.heap_size() + self.1.heap_size()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Option<T> {
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, T::heap_size)
+    }
+}
+
+impl<T: HeapSize> HeapSize for Vec<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>() + self.iter().map(T::heap_size).sum::<usize>()
+    }
+}
+
+/// Elements of a B-tree node, nodes being about two thirds full
+const BTREE_NODE_LEN: usize = 8;
+
+impl<T: HeapSize> HeapSize for BTreeSet<T> {
+    fn heap_size(&self) -> usize {
+        // keys of a whole node and its parent link
+        let node_size = 11 * size_of::<T>() + 16;
+        self.len().div_ceil(BTREE_NODE_LEN) * node_size + self.iter().map(T::heap_size).sum::<usize>()
+    }
+}
+
+impl<K: HeapSize, V: HeapSize, S> HeapSize for HashMap<K, V, S> {
+    fn heap_size(&self) -> usize {
+        // one control byte per bucket
+        let entries = self.capacity() * (size_of::<(K, V)>() + 1);
+        entries + self.iter().map(|(k, v)| k.heap_size() + v.heap_size()).sum::<usize>()
+    }
+}
+
+/// Bytes held by the structures of a [`SDFGraph`], see [`SDFGraph::memory_stats`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// forward edges, [`SDFGraph::graph`]
+    pub graph: usize,
+    /// backward edges, [`SDFGraph::reverse_graph`]
+    pub reverse_graph: usize,
+    /// names of the pins and instances
+    pub symbols: usize,
+    /// maps of the instances: celltypes, pins, fanin and fanout, registers and aliases
+    pub instances: usize,
+    /// timing checks, startpoints and endpoints, conditions and diagnostics
+    pub other: usize,
+}
+
+impl MemoryStats {
+    pub fn total(&self) -> usize {
+        self.graph + self.reverse_graph + self.symbols + self.instances + self.other
+    }
+}
+
+impl Display for MemoryStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mb = |bytes: usize| bytes as f64 / (1 << 20) as f64;
+        write!(
+            f,
+            "{:.1} MB (graph {:.1}, reverse graph {:.1}, names {:.1}, instances {:.1}, other {:.1})",
+            mb(self.total()),
+            mb(self.graph),
+            mb(self.reverse_graph),
+            mb(self.symbols),
+            mb(self.instances),
+            mb(self.other)
+        )
+    }
+}
+
+impl SDFGraph {
+    /// Bytes held by the graph, structure by structure. The sizes of the B-trees and hash maps are estimates.
+    pub fn memory_stats(&self) -> MemoryStats {
+        let diagnostics = &self.diagnostics;
+        MemoryStats {
+            graph: self.graph.heap_size(),
+            reverse_graph: self.reverse_graph.heap_size(),
+            symbols: self.symbols.heap_size(),
+            instances: self.instance_celltype.heap_size()
+                + self.instance_ins.heap_size()
+                + self.instance_outs.heap_size()
+                + self.instance_fanout.heap_size()
+                + self.instance_fanin.heap_size()
+                + self.registers.heap_size()
+                + self.aliases.heap_size(),
+            other: size_of::<SDFGraph>()
+                + self.timing_checks.heap_size()
+                + self.inputs.heap_size()
+                + self.outputs.heap_size()
+                + self.conditions.heap_size()
+                + self.dirty.heap_size()
+                + diagnostics.undriven.heap_size()
+                + diagnostics.unloaded.heap_size()
+                + diagnostics.dangling.heap_size(),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+//! Nets of the design: a driver and the pins it is connected to by the INTERCONNECT arcs of the SDF.
+//! The graph only has arcs between pins, this groups the arcs of a net together.
+
+use crate::graph::{is_interconnect, SDFGraph};
+#[cfg(feature = "spef")]
+use crate::parasitics::{NetParasitics, Parasitics};
+use crate::power::Capacitances;
+use crate::types::{PinId, PinSet, Transition};
+
+/// Output pin (or input port) of a net and the pins it drives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Net {
+    pub driver: PinId,
+    /// input pins and output ports driven by `driver`, sorted
+    pub sinks: Vec<PinId>,
+}
+
+impl Net {
+    pub fn fanout(&self) -> usize {
+        self.sinks.len()
+    }
+
+    /// Driver then sinks
+    pub fn pins(&self) -> impl Iterator<Item = PinId> + '_ {
+        std::iter::once(self.driver).chain(self.sinks.iter().copied())
+    }
+
+    /// Capacitance driven by the net in pF: its sinks and its wire, see [`Capacitances::load`]
+    pub fn capacitance(&self, graph: &SDFGraph, capacitances: &Capacitances) -> f32 {
+        capacitances.load(graph, self.driver)
+    }
+
+    /// Parasitics of the net, found by the name of its driver as the SPEF net names may differ from the SDF ones
+    #[cfg(feature = "spef")]
+    pub fn parasitics<'a>(&self, graph: &SDFGraph, parasitics: &'a Parasitics) -> Option<NetParasitics<'a>> {
+        parasitics.net(parasitics.net_name(graph.symbols.pin_name(self.driver))?)
+    }
+
+    /// Worst delay (ns) of the interconnect from the driver to `sink`, None if it isn't a sink of the net
+    pub fn delay_to(&self, graph: &SDFGraph, sink: PinId) -> Option<f32> {
+        [Transition::Rise, Transition::Fall]
+            .into_iter()
+            .flat_map(|transition| graph.graph.get(&(self.driver, transition)).into_iter().flatten())
+            .filter(|edge| edge.dst.0 == sink)
+            .map(|edge| edge.delay)
+            .reduce(f32::max)
+    }
+}
+
+impl SDFGraph {
+    /// Every net of the design, sorted by driver
+    pub fn nets(&self) -> Vec<Net> {
+        let drivers: PinSet = self.graph.keys().map(|node| node.0).collect();
+        drivers
+            .into_iter()
+            .filter_map(|driver| self.driven_net(driver))
+            .collect()
+    }
+
+    /// Net a pin is on, as its driver or one of its sinks. None for the pins without interconnect.
+    pub fn net_of(&self, pin: PinId) -> Option<Net> {
+        self.driven_net(pin).or_else(|| self.driven_net(self.driver(pin)?))
+    }
+
+    /// Net driven by `driver`, None if it drives nothing
+    fn driven_net(&self, driver: PinId) -> Option<Net> {
+        let edges = self.graph.get(&(driver, Transition::Rise)).into_iter().flatten();
+        let sinks: PinSet = edges
+            .map(|edge| edge.dst.0)
+            .filter(|&sink| is_interconnect(&self.symbols, driver, sink))
+            .collect();
+        if sinks.is_empty() {
+            return None;
+        }
+        Some(Net {
+            driver,
+            sinks: sinks.into_iter().collect(),
+        })
+    }
+}
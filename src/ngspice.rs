@@ -0,0 +1,133 @@
+//! Simulation of the SPICE decks with ngspice, to compare the delays of a path with the ones of the SDF.
+//!
+//! The deck of [`crate::spice::extract_spice_for_manual_analysis`] (ngspice dialect) measures the delay from the
+//! launch pin to the output of every stage, preceded by the delay of the SDF in a `* SDF: 1.234n` comment.
+
+use crate::error::{Result, StarsError};
+use rustc_hash::FxHashMap;
+use std::fmt::Write;
+use std::path::Path;
+use std::process::Command;
+
+/// Delay of one `.meas` of a deck in the SDF and in the simulation. ns
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeasureComparison {
+    pub name: String,
+    pub sdf: f32,
+    /// None when the measure failed, e.g the transition never reached the pin
+    pub spice: Option<f32>,
+}
+
+/// Run `ngspice -b` on a deck and return its measures in ns, keyed by their (lowercase) name.
+pub fn simulate(deck: &Path) -> Result<FxHashMap<String, f32>> {
+    let output = Command::new("ngspice")
+        .arg("-b")
+        .arg(deck)
+        .output()
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => StarsError::Simulation("ngspice not found in the PATH".to_string()),
+            _ => e.into(),
+        })?;
+    if !output.status.success() {
+        return Err(StarsError::Simulation(
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        ));
+    }
+    Ok(parse_measures(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Measures printed by ngspice, as `delay_i1 = 1.234e-09 targ= ... trig= ...` lines
+fn parse_measures(output: &str) -> FxHashMap<String, f32> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, value) = line.split_once('=')?;
+            let name = name.trim();
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                return None;
+            }
+            let value: f32 = value.split_whitespace().next()?.parse().ok()?;
+            Some((name.to_lowercase(), value * 1e9))
+        })
+        .collect()
+}
+
+/// Pair the measures of `deck` with the results of [`simulate`]
+pub fn compare(deck: &str, measures: &FxHashMap<String, f32>) -> Vec<MeasureComparison> {
+    let mut comparisons = Vec::new();
+    let mut sdf = None;
+    for line in deck.lines() {
+        if let Some(delay) = line.strip_prefix("* SDF: ").and_then(|delay| delay.strip_suffix('n')) {
+            sdf = delay.parse().ok();
+            continue;
+        }
+        let Some(name) = line
+            .strip_prefix(".meas tran ")
+            .and_then(|meas| meas.split_whitespace().next())
+        else {
+            continue;
+        };
+        if let Some(sdf) = sdf.take() {
+            comparisons.push(MeasureComparison {
+                name: name.to_string(),
+                sdf,
+                spice: measures.get(&name.to_lowercase()).copied(),
+            });
+        }
+    }
+    comparisons
+}
+
+/// Table of the comparisons, with the error of the SDF relative to the simulation
+pub fn report_comparison(comparisons: &[MeasureComparison]) -> String {
+    let mut report = String::new();
+    writeln!(report, "{:<16} {:>10} {:>10} {:>8}", "measure", "SDF", "SPICE", "error").unwrap();
+    for comparison in comparisons {
+        match comparison.spice {
+            Some(spice) => writeln!(
+                report,
+                "{:<16} {:>10.3} {:>10.3} {:>7.1}%",
+                comparison.name,
+                comparison.sdf,
+                spice,
+                (comparison.sdf - spice) / spice * 100.0
+            ),
+            None => writeln!(
+                report,
+                "{:<16} {:>10.3} {:>10} {:>8}",
+                comparison.name, comparison.sdf, "failed", ""
+            ),
+        }
+        .unwrap();
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare() {
+        let deck = "* SDF: 0.250n
+.meas tran delay_I1 TRIG V(I0/Q) VAL=0.9 RISE=1 TARG V(I1/X) VAL=0.9 RISE=1
+* SDF: 0.500n
+.meas tran delay_I2 TRIG V(I0/Q) VAL=0.9 RISE=1 TARG V(I2/Y) VAL=0.9 FALL=1
+";
+        let output = "
+Measurements for Transient Analysis
+
+delay_i1            =  2.000000e-10 targ=  2.300000e-10 trig=  3.000000e-11
+delay_i2            =  failed
+";
+        let measures = parse_measures(output);
+        assert_eq!(measures.len(), 1);
+
+        let comparisons = compare(deck, &measures);
+        assert_eq!(comparisons.len(), 2);
+        assert_eq!(comparisons[0].name, "delay_I1");
+        assert_eq!(comparisons[0].sdf, 0.25);
+        assert!((comparisons[0].spice.unwrap() - 0.2).abs() < 1e-6);
+        assert_eq!(comparisons[1].spice, None);
+    }
+}
@@ -0,0 +1,250 @@
+//! Comparison with the paths of OpenSTA's `report_checks`, to validate the timing graph built from the SDF (unateness
+//! of the arcs, corner of the delays) against a reference tool.
+//!
+//! Only the `Time` column and the pins of the `Description` column are read, so any `-fields` can be reported.
+//! OpenSTA starts the paths at the clock source while stars starts them at the output of the launching register:
+//! the clock pins of the registers are skipped and the arrival times are compared from the first pin both tools know.
+
+use crate::analysis::SDFGraphAnalyzed;
+use crate::graph::SDFGraph;
+use crate::types::{PinSet, Transition};
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug)]
+pub struct StaReportError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for StaReportError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "OpenSTA report line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for StaReportError {}
+
+/// Pin of a path of the report with its arrival time (ns)
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaPin {
+    pub pin: String,
+    pub transition: Transition,
+    pub arrival: f32,
+}
+
+/// Data arrival part of a path of the report
+#[derive(Debug, Clone, PartialEq)]
+pub struct StaPath {
+    /// `Path Type: min` (hold) instead of max (setup)
+    pub min: bool,
+    /// from the clock source to the endpoint
+    pub pins: Vec<StaPin>,
+    pub slack: Option<f32>,
+    /// smallest step of the times of the report, e.g 0.01 with the default `-digits 2`
+    pub resolution: f32,
+}
+
+impl StaPath {
+    pub fn endpoint(&self) -> Option<&StaPin> {
+        self.pins.last()
+    }
+}
+
+/// Paths of a `report_checks` output, in the order of the report
+pub fn parse_report(content: &str) -> Result<Vec<StaPath>, StaReportError> {
+    let mut paths: Vec<StaPath> = Vec::new();
+    // the pins are read until `data arrival time`, the required time part repeats the clock path
+    let mut in_arrival = false;
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.starts_with("Startpoint:") {
+            paths.push(StaPath {
+                min: false,
+                pins: Vec::new(),
+                slack: None,
+                resolution: 0.0,
+            });
+            in_arrival = true;
+            continue;
+        }
+        let Some(path) = paths.last_mut() else {
+            continue;
+        };
+        if let Some(path_type) = line.strip_prefix("Path Type:") {
+            path.min = path_type.trim() == "min";
+        } else if line.ends_with("data arrival time") {
+            in_arrival = false;
+        } else if line.contains("slack (") {
+            path.slack = line.split_whitespace().next().and_then(|slack| slack.parse().ok());
+        } else if in_arrival {
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let Some(marker) = tokens.iter().position(|token| matches!(*token, "^" | "v")) else {
+                continue;
+            };
+            let (Some(time), Some(pin)) = (marker.checked_sub(1).map(|i| tokens[i]), tokens.get(marker + 1)) else {
+                continue;
+            };
+            let arrival = time.parse().map_err(|_| StaReportError {
+                line: i + 1,
+                message: format!("invalid time {} for {}", time, pin),
+            })?;
+            let decimals = time.split_once('.').map_or(0, |(_, decimals)| decimals.len());
+            path.resolution = path.resolution.max(10f32.powi(-(decimals as i32)));
+            path.pins.push(StaPin {
+                pin: pin.to_string(),
+                transition: if tokens[marker] == "^" {
+                    Transition::Rise
+                } else {
+                    Transition::Fall
+                },
+                arrival,
+            });
+        }
+    }
+    Ok(paths)
+}
+
+/// Arrival time at a pin of the path in both tools
+#[derive(Debug, Clone)]
+pub struct StageDelta {
+    pub pin: String,
+    pub transition: Transition,
+    pub sta: f32,
+    /// arrival of stars shifted to the time of OpenSTA at the first common pin. None when stars doesn't reach the
+    /// pin with this transition, e.g a wrong unateness
+    pub stars: Option<f32>,
+}
+
+impl StageDelta {
+    pub fn error(&self) -> Option<f32> {
+        Some(self.stars? - self.sta)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PathDelta {
+    pub min: bool,
+    pub stages: Vec<StageDelta>,
+    /// (OpenSTA, stars) slacks
+    pub slack: (Option<f32>, Option<f32>),
+    /// errors up to this are the rounding of the report
+    pub resolution: f32,
+}
+
+impl PathDelta {
+    /// The arrival of a stage differs by more than `tolerance` (ns) and the rounding of the report, or stars
+    /// doesn't reach its pin
+    pub fn diverges(&self, stage: &StageDelta, tolerance: f32) -> bool {
+        stage
+            .error()
+            .is_none_or(|error| error.abs() > tolerance + self.resolution)
+    }
+
+    /// First stage that [diverges](Self::diverges)
+    pub fn divergence(&self, tolerance: f32) -> Option<&StageDelta> {
+        self.stages.iter().find(|stage| self.diverges(stage, tolerance))
+    }
+}
+
+/// Arrival times of stars along the paths of OpenSTA. The pins are matched by name.
+pub fn compare(graph: &SDFGraph, analysis: &SDFGraphAnalyzed, paths: &[StaPath]) -> Vec<PathDelta> {
+    let symbols = &graph.symbols;
+    let clocks: PinSet = graph.registers.values().map(|register| register.clock.0).collect();
+
+    paths
+        .iter()
+        .map(|path| {
+            let delays = if path.min {
+                &analysis.min_delay
+            } else {
+                &analysis.max_delay
+            };
+            let nodes: Vec<_> = path
+                .pins
+                .iter()
+                .map(|pin| (pin, symbols.pin_id(&pin.pin)))
+                .filter(|(_, id)| id.is_none_or(|id| !clocks.contains(&id)))
+                .map(|(pin, id)| (pin, id.and_then(|id| delays.get(&(id, pin.transition)).copied())))
+                .collect();
+            let offset = nodes
+                .iter()
+                .find_map(|(pin, arrival)| Some(pin.arrival - (*arrival)?))
+                .unwrap_or(0.0);
+            let stages = nodes
+                .into_iter()
+                .map(|(pin, arrival)| StageDelta {
+                    pin: pin.pin.clone(),
+                    transition: pin.transition,
+                    sta: pin.arrival,
+                    stars: arrival.map(|arrival| arrival + offset),
+                })
+                .collect();
+
+            let endpoint = path
+                .endpoint()
+                .and_then(|pin| Some((symbols.pin_id(&pin.pin)?, pin.transition)));
+            let slack = endpoint.and_then(|endpoint| match path.min {
+                true => analysis.hold_slack(graph, &endpoint),
+                false => analysis.slack(&endpoint),
+            });
+            PathDelta {
+                min: path.min,
+                stages,
+                slack: (path.slack, slack),
+                resolution: path.resolution,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_report() {
+        let report = "
+Startpoint: _1_ (rising edge-triggered flip-flop clocked by clk)
+Endpoint: _3_ (rising edge-triggered flip-flop clocked by clk)
+Path Group: clk
+Path Type: max
+
+Fanout     Cap    Slew   Delay    Time   Description
+-----------------------------------------------------------------------------
+                  0.00    0.00    0.00   clock clk (rise edge)
+                          0.00    0.00   clock network delay (ideal)
+                  0.00    0.00    0.00 ^ _1_/CLK (sky130_fd_sc_hd__dfxtp_1)
+     2    0.01    0.05    0.31    0.31 v _1_/Q (sky130_fd_sc_hd__dfxtp_1)
+                                         net1 (net)
+                  0.05    0.00    0.31 v _2_/A (sky130_fd_sc_hd__inv_1)
+     1    0.00    0.04    0.06    0.37 ^ _2_/Y (sky130_fd_sc_hd__inv_1)
+                  0.04    0.00    0.37 ^ _3_/D (sky130_fd_sc_hd__dfxtp_1)
+                                  0.37   data arrival time
+
+                  0.00   10.00   10.00   clock clk (rise edge)
+                          0.00   10.00   clock network delay (ideal)
+                                 10.00 ^ _3_/CLK (sky130_fd_sc_hd__dfxtp_1)
+                         -0.12    9.88   library setup time
+                                  9.88   data required time
+-----------------------------------------------------------------------------
+                                  9.88   data required time
+                                 -0.37   data arrival time
+-----------------------------------------------------------------------------
+                                  9.51   slack (MET)
+";
+        let paths = parse_report(report).unwrap();
+        assert_eq!(paths.len(), 1);
+        let path = &paths[0];
+        assert!(!path.min);
+        assert_eq!(path.pins.len(), 5);
+        assert_eq!(path.pins[1].pin, "_1_/Q");
+        assert_eq!(path.pins[1].transition, Transition::Fall);
+        assert_eq!(path.pins[1].arrival, 0.31);
+        assert_eq!(path.endpoint().unwrap().pin, "_3_/D");
+        assert_eq!(path.slack, Some(9.51));
+        assert!((path.resolution - 0.01).abs() < 1e-6);
+
+        let err = parse_report("Startpoint: a\n 0.1 x.y ^ _1_/Q (buf)\n").unwrap_err();
+        assert_eq!(err.line, 2);
+    }
+}
@@ -1,76 +1,643 @@
-use crate::types::SDFPin;
-use rustc_hash::FxHashMap;
-use spefparse::{ParValue, SPEFHierPortPinRef};
-use std::ffi::OsString;
-
-#[derive(Default, Copy, Clone, Debug)]
-pub struct ParasitWire {
-    /// Ohm
-    pub res: f64,
-    /// Farad
-    pub cap: f64,
+use crate::progress::{Phase, PhaseProgress, Progress};
+use crate::spice_number;
+use crate::types::{Corner, SDFPin};
+use itertools::Itertools;
+use rustc_hash::{FxHashMap, FxHashSet};
+use spefparse::{Direction, ParValue, SPEFHierPortPinRef};
+use std::fmt::{Display, Formatter};
+
+/// RC network of a net as described in the SPEF: pins and internal nodes connected by resistors,
+/// with a capacitance to ground on each node.
+#[derive(Default, Clone, Debug)]
+pub struct RcNet {
+    pub name: SDFPin,
+    /// pins of the net and internal nodes (named `net/1` for `net:1`)
+    pub nodes: Vec<SDFPin>,
+    /// the pins of the `*CONN` section are the first `pin_count` nodes
+    pub pin_count: usize,
+    /// capacitance to ground of each node, coupling capacitances are counted as grounded. Farad
+    pub caps: Vec<f64>,
+    /// resistors between two nodes. Ohm
+    pub ress: Vec<(usize, usize, f64)>,
+    /// node driving the net: output pin of a cell or input port
+    pub driver: Option<usize>,
+}
+
+impl RcNet {
+    pub fn node(&self, name: &str) -> Option<usize> {
+        self.nodes.iter().position(|node| node == name)
+    }
+
+    fn node_or_insert(&mut self, name: SDFPin) -> usize {
+        if let Some(i) = self.node(&name) {
+            return i;
+        }
+        self.nodes.push(name);
+        self.caps.push(0.0);
+        self.nodes.len() - 1
+    }
+
+    /// Add a capacitance between `a` and the ground, or the node `b` of another net. The node of the other net is
+    /// returned for the coupling capacitances, which can list it first
+    fn add_cap(&mut self, mut a: SDFPin, mut b: Option<SDFPin>, val: f64) -> Option<SDFPin> {
+        if let Some(other) = b.as_mut().filter(|b| self.node(&a).is_none() && self.node(b).is_some()) {
+            std::mem::swap(&mut a, other);
+        }
+        let a = self.node_or_insert(a);
+        self.caps[a] += val;
+        b
+    }
+
+    /// Sum of the capacitances of the net. Farad
+    pub fn total_cap(&self) -> f64 {
+        self.caps.iter().sum()
+    }
 }
 
 pub struct Parasitics {
-    pub wires: FxHashMap<(SDFPin, SDFPin), ParasitWire>,
-    pub caps: FxHashMap<SDFPin, f64>,
+    pub nets: Vec<RcNet>,
+    /// net and node index of every node name
+    pub nodes: FxHashMap<SDFPin, (usize, usize)>,
+    /// index in `nets` of every net name
+    pub net_names: FxHashMap<SDFPin, usize>,
+}
+
+/// Summary of the parasitics of a net, see [`Parasitics::net`]
+#[derive(Debug, Clone)]
+pub struct NetParasitics<'a> {
+    pub net: &'a RcNet,
+    /// sum of the capacitances of the net. Farad
+    pub total_cap: f64,
+    /// pins of cells and ports connected to the net, without its internal nodes
+    pub pins: Vec<&'a str>,
+    /// resistance from the driver to each pin of `pins`, None when the net has no driver or the pin isn't
+    /// connected to it. Ohm
+    pub path_resistances: Vec<Option<f64>>,
+}
+
+#[derive(Debug)]
+pub enum ParasiticsError {
+    Io(std::io::Error),
+    Parse(String),
+}
+
+impl Display for ParasiticsError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParasiticsError::Io(e) => write!(f, "Could not read parasitics file: {}", e),
+            ParasiticsError::Parse(e) => write!(f, "Could not parse parasitics file: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ParasiticsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParasiticsError::Io(e) => Some(e),
+            ParasiticsError::Parse(_) => None,
+        }
+    }
+}
+
+/// Value of a `min:typ:max` triple at the corner
+fn par_value(value: ParValue, corner: Corner) -> f64 {
+    match value {
+        ParValue::Single(v) => v as f64,
+        ParValue::Three(min, typ, max) => corner.select(Some(min), Some(typ), Some(max)).unwrap() as f64,
+    }
+}
+
+/// Turns the names of the SPEF (or DSPF) into the ones of the graph: `/` between the levels of hierarchy whatever
+/// the divider of the file, and `[]` around the bits of a bus.
+///
+/// The escapes are already removed by the SPEF parser, so `u1\[3\]` is `u1[3]` like in the SDF.
+struct SpefNames {
+    /// character of the flattened names standing for a level of hierarchy, replaced by `/`
+    divider: char,
+    bus_delimiters: (char, char),
+}
+
+impl SpefNames {
+    fn char(&self, c: char) -> char {
+        match c {
+            c if c == self.divider => '/',
+            c if c == self.bus_delimiters.0 => '[',
+            c if c == self.bus_delimiters.1 => ']',
+            c => c,
+        }
+    }
+
+    fn part(&self, part: &str) -> String {
+        part.chars().map(|c| self.char(c)).collect()
+    }
+
+    /// Name of a DSPF node, e.g `top/u1:A`, the `separators` (divider and pin delimiter of the file) becoming `/`.
+    /// A backslash escapes the next character
+    fn flat_name(&self, name: &str, separators: [char; 2]) -> SDFPin {
+        let mut flat = String::with_capacity(name.len());
+        let mut chars = name.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => flat.extend(chars.next()),
+                c if separators.contains(&c) => flat.push('/'),
+                c => flat.push(self.char(c)),
+            }
+        }
+        flat
+    }
+
+    fn name(&self, pin: SPEFHierPortPinRef) -> SDFPin {
+        let mut name = pin.0 .0.iter().map(|part| self.part(part)).join("/");
+        if let Some(pin) = pin.1 {
+            name.push('/');
+            name.push_str(&self.part(&pin));
+        }
+        if let Some(bit) = pin.2 {
+            name.push_str(&format!("[{}]", bit));
+        }
+        name
+    }
 }
 
-fn extract_name(pin: SPEFHierPortPinRef) -> SDFPin {
-    format!(
-        "{}{}{}",
-        &*pin.0 .0.first().unwrap(),
-        pin.1.map(|x| format!("/{}", x)).unwrap_or_default(),
-        pin.2.map(|x| format!("[{}]", x)).unwrap_or_default()
-    )
+/// Check that every `*<index>` reference of the SPEF is in its `*NAME_MAP`, the parser panics on the unknown ones
+fn check_name_map(content: &str) -> Result<(), ParasiticsError> {
+    let mut indices = FxHashSet::default();
+    let mut in_name_map = false;
+    for line in content.lines() {
+        let mut words = line.split_whitespace();
+        let Some(first) = words.next() else {
+            continue;
+        };
+        let index = |word: &str| {
+            let word = word.strip_prefix('*')?;
+            let end = word.find(|c: char| !c.is_ascii_digit()).unwrap_or(word.len());
+            (end > 0).then(|| word[..end].to_string())
+        };
+        if first == "*NAME_MAP" {
+            in_name_map = true;
+            continue;
+        }
+        match index(first) {
+            Some(i) if in_name_map => {
+                indices.insert(i);
+                continue;
+            }
+            _ => in_name_map = false,
+        }
+        for word in std::iter::once(first).chain(words) {
+            match index(word) {
+                Some(i) if !indices.contains(&i) => {
+                    return Err(ParasiticsError::Parse(format!("*{} is not in the *NAME_MAP", i)));
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
 }
 
 impl Parasitics {
-    pub fn new(path: &OsString) -> Self {
-        let content = std::fs::read_to_string(path).expect("Could not read SPEF file");
+    /// Read the parasitics of a SPEF file, taking the values at `corner` when they are given as triples.
+    #[cfg(feature = "fs")]
+    pub fn new(path: &std::ffi::OsString, corner: Corner) -> Result<Self, ParasiticsError> {
+        let content = crate::read_input(path).map_err(ParasiticsError::Io)?;
+        Self::parse_str(&content, corner)
+    }
 
-        let spef = spefparse::SPEF::parse_str(&content).expect("Could not parse SPEF file");
+    pub fn parse_str(content: &str, corner: Corner) -> Result<Self, ParasiticsError> {
+        Self::parse_str_with(content, corner, '/')
+    }
 
-        let mut me = Self {
-            wires: FxHashMap::default(),
-            caps: FxHashMap::default(),
+    /// Same as [`Self::parse_str`] for a design flattened with `divider` in its instance names
+    /// (e.g `.` for `core.alu.u1`), which becomes a level of hierarchy like in the SDF
+    pub fn parse_str_with(content: &str, corner: Corner, divider: char) -> Result<Self, ParasiticsError> {
+        Self::parse_str_with_progress(content, corner, divider, None)
+    }
+
+    /// Same as [`Self::parse_str_with`], reporting the nets read to `progress` as [`Phase::SpefParse`] once the
+    /// SPEF is parsed
+    pub fn parse_str_with_progress(
+        content: &str,
+        corner: Corner,
+        divider: char,
+        progress: Option<&Progress>,
+    ) -> Result<Self, ParasiticsError> {
+        if content.trim_start().starts_with("*|DSPF") {
+            return Self::parse_dspf(content, divider);
+        }
+        check_name_map(content)?;
+        let spef = spefparse::SPEF::parse_str(content).map_err(ParasiticsError::Parse)?;
+        let names = SpefNames {
+            divider,
+            bus_delimiters: (spef.header.bus_delimiter_left, spef.header.bus_delimiter_right),
         };
 
         let res_unit = spef.header.res_unit as f64;
         let cap_unit = spef.header.cap_unit as f64;
 
-        for net in spef.nets {
-            for wire in net.caps {
-                let from = extract_name(wire.a);
-                let to = wire.b.map(|b| extract_name(b));
-                let ParValue::Single(val) = wire.val else {
-                    panic!("Expected single value")
+        let mut nets = Vec::with_capacity(spef.nets.len());
+        // coupling capacitances to a node of another net, added once every net is known
+        let mut couplings = Vec::new();
+
+        let progress = PhaseProgress::start(progress, Phase::SpefParse, spef.nets.len(), 1024);
+        for spef_net in spef.nets {
+            let net_i = nets.len();
+            progress.update(net_i);
+            let mut net = RcNet {
+                name: names.name(spef_net.name),
+                ..Default::default()
+            };
+
+            for conn in spef_net.conns {
+                // a cell pin drives the net when it's an output, a port when it's an input
+                let is_port = conn.name.1.is_none();
+                let is_driver = match conn.direction {
+                    Direction::O => !is_port,
+                    Direction::I => is_port,
+                    Direction::B => false,
                 };
-                let val = val as f64 * cap_unit;
+                let node = net.node_or_insert(names.name(conn.name));
+                if is_driver {
+                    net.driver = Some(node);
+                }
+            }
+            net.pin_count = net.nodes.len();
+
+            for wire in spef_net.ress {
+                let val = par_value(wire.val, corner) * res_unit;
+                let a = net.node_or_insert(names.name(wire.a));
+                let b = net.node_or_insert(names.name(wire.b));
+                net.ress.push((a, b, val));
+            }
+
+            for wire in spef_net.caps {
+                let val = par_value(wire.val, corner) * cap_unit;
 
                 if val == 0.0 {
                     continue;
                 }
-                match to {
-                    Some(to) => {
-                        me.wires.entry((from.clone(), to.clone())).or_default().cap = val;
-                        me.wires.entry((to, from)).or_default().cap = val;
+                if let Some(coupling) = net.add_cap(names.name(wire.a), wire.b.map(|b| names.name(b)), val) {
+                    couplings.push((net_i, coupling, val));
+                }
+            }
+
+            nets.push(net);
+        }
+        progress.finish();
+
+        Ok(Self::from_nets(nets, couplings))
+    }
+
+    /// Read the parasitics of a DSPF file (`*|NET` sections of R and C elements). The values have no corner.
+    ///
+    /// `divider` is the same as in [`Self::parse_str_with`].
+    pub fn parse_dspf(content: &str, divider: char) -> Result<Self, ParasiticsError> {
+        let mut names = SpefNames {
+            divider,
+            bus_delimiters: ('[', ']'),
+        };
+        let mut separators = ['/', ':'];
+        let mut grounds: FxHashSet<&str> = ["0"].into_iter().collect();
+
+        let mut nets: Vec<RcNet> = Vec::new();
+        let mut couplings = Vec::new();
+
+        for (line_i, line) in content.lines().enumerate() {
+            let error = |what: &str| ParasiticsError::Parse(format!("DSPF line {}: {}", line_i + 1, what));
+            let line = line.trim();
+            let mut words = line.split_whitespace();
+            let Some(first) = words.next() else {
+                continue;
+            };
+            let header_char = |word: Option<&str>| {
+                let mut chars = word.ok_or_else(|| error("missing character"))?.chars();
+                chars.next().ok_or_else(|| error("missing character"))
+            };
+
+            match first.to_ascii_uppercase().as_str() {
+                "*|DIVIDER" => separators[0] = header_char(words.next())?,
+                "*|DELIMITER" => separators[1] = header_char(words.next())?,
+                "*|BUSBIT" => {
+                    let mut chars = words.next().ok_or_else(|| error("missing bus delimiters"))?.chars();
+                    names.bus_delimiters = (chars.next().unwrap_or('['), chars.next().unwrap_or(']'));
+                }
+                "*|GROUND_NET" => grounds.extend(words),
+                "*|NET" => {
+                    let name = words.next().ok_or_else(|| error("missing net name"))?;
+                    nets.push(RcNet {
+                        name: names.flat_name(name, separators),
+                        ..Default::default()
+                    });
+                }
+                // `*|P (port direction cap x y)` and `*|I (inst:pin inst pin direction cap x y)`
+                "*|P" | "*|I" => {
+                    let net = nets.last_mut().ok_or_else(|| error("pin outside of a *|NET"))?;
+                    let fields: Vec<&str> = line[first.len()..]
+                        .trim()
+                        .trim_start_matches('(')
+                        .trim_end_matches(')')
+                        .split_whitespace()
+                        .collect();
+                    let (is_port, direction) = match (first.eq_ignore_ascii_case("*|P"), fields.as_slice()) {
+                        (true, [_, direction, ..]) => (true, *direction),
+                        (false, [_, _, _, direction, ..]) => (false, *direction),
+                        _ => return Err(error("malformed pin")),
+                    };
+                    // a cell pin drives the net when it's an output, a port when it's an input
+                    let is_driver = match direction {
+                        "O" => !is_port,
+                        "I" => is_port,
+                        _ => false,
+                    };
+                    let node = net.node_or_insert(names.flat_name(fields[0], separators));
+                    net.pin_count = net.nodes.len();
+                    if is_driver {
+                        net.driver = Some(node);
+                    }
+                }
+                _ if line.starts_with('*') || line.starts_with('.') => {}
+                element => {
+                    let kind = element.chars().next();
+                    if !matches!(kind, Some('R' | 'C')) {
+                        continue;
+                    }
+                    let net_i = nets
+                        .len()
+                        .checked_sub(1)
+                        .ok_or_else(|| error("element outside of a *|NET"))?;
+                    let (Some(a), Some(b), Some(value)) = (words.next(), words.next(), words.next()) else {
+                        return Err(error("expected two nodes and a value"));
+                    };
+                    let (value, _) = spice_number(value).ok_or_else(|| error("invalid value"))?;
+                    let net = &mut nets[net_i];
+                    if kind == Some('R') {
+                        let a = net.node_or_insert(names.flat_name(a, separators));
+                        let b = net.node_or_insert(names.flat_name(b, separators));
+                        net.ress.push((a, b, value));
+                        continue;
                     }
-                    None => {
-                        me.caps.insert(from.clone(), val);
+                    let (a, b) = match (grounds.contains(a), grounds.contains(b)) {
+                        (_, true) => (a, None),
+                        (true, false) => (b, None),
+                        (false, false) => (a, Some(names.flat_name(b, separators))),
+                    };
+                    if let Some(coupling) = net.add_cap(names.flat_name(a, separators), b, value) {
+                        couplings.push((net_i, coupling, value));
                     }
                 }
             }
-            for wire in net.ress {
-                let from = extract_name(wire.a);
-                let to = extract_name(wire.b);
-                let ParValue::Single(val) = wire.val else {
-                    panic!("Expected single value")
-                };
-                me.wires.entry((from, to)).or_default().res = val as f64 * res_unit;
+        }
+
+        Ok(Self::from_nets(nets, couplings))
+    }
+
+    /// Index the nodes of the nets, and add the coupling capacitances `(net, node of another net, value)` to the
+    /// node of the other net once every net is known
+    fn from_nets(nets: Vec<RcNet>, couplings: Vec<(usize, SDFPin, f64)>) -> Self {
+        let mut me = Self {
+            nodes: FxHashMap::default(),
+            net_names: FxHashMap::default(),
+            nets,
+        };
+
+        for (net_i, net) in me.nets.iter().enumerate() {
+            for (node_i, node) in net.nodes.iter().enumerate() {
+                me.nodes.insert(node.clone(), (net_i, node_i));
+            }
+            me.net_names.insert(net.name.clone(), net_i);
+        }
+
+        for (from_net, node, val) in couplings {
+            match me.nodes.get(&node) {
+                Some(&(net, node)) if net != from_net => me.nets[net].caps[node] += val,
+                _ => {}
             }
         }
 
         me
     }
+
+    /// Net connected to a pin, along with the index of the pin in the net.
+    pub fn net_of(&self, pin: &str) -> Option<(&RcNet, usize)> {
+        let &(net, node) = self.nodes.get(pin)?;
+        Some((&self.nets[net], node))
+    }
+
+    /// Parasitics of the net named `name`
+    pub fn net(&self, name: &str) -> Option<NetParasitics<'_>> {
+        let net = &self.nets[*self.net_names.get(name)?];
+        let pins = &net.nodes[..net.pin_count];
+        let path_resistances = match net.driver {
+            Some(driver) => {
+                let (_, parents) = tree_order(net, driver);
+                (0..net.pin_count)
+                    .map(|pin| {
+                        if pin != driver && parents[pin].is_none() {
+                            return None;
+                        }
+                        let (mut res, mut node) = (0.0, pin);
+                        while let Some((up, r)) = parents[node] {
+                            res += r;
+                            node = up;
+                        }
+                        Some(res)
+                    })
+                    .collect()
+            }
+            None => vec![None; net.pin_count],
+        };
+        Some(NetParasitics {
+            net,
+            total_cap: net.total_cap(),
+            pins: pins.iter().map(String::as_str).collect(),
+            path_resistances,
+        })
+    }
+
+    /// Name of the net connected to a pin
+    pub fn net_name(&self, pin: &str) -> Option<&str> {
+        self.net_of(pin).map(|(net, _)| &*net.name)
+    }
+}
+
+/// Breadth first order of the nodes reached from `root` and the parent of each one with the resistance to it.
+/// Resistors forming loops are ignored so that the network is a tree.
+fn tree_order(net: &RcNet, root: usize) -> (Vec<usize>, Vec<Option<(usize, f64)>>) {
+    let mut adjacency = vec![Vec::new(); net.nodes.len()];
+    for &(a, b, res) in &net.ress {
+        adjacency[a].push((b, res));
+        adjacency[b].push((a, res));
+    }
+
+    let mut parent: Vec<Option<(usize, f64)>> = vec![None; net.nodes.len()];
+    let mut visited = vec![false; net.nodes.len()];
+    let mut order = vec![root];
+    visited[root] = true;
+    let mut i = 0;
+    while let Some(&node) = order.get(i) {
+        i += 1;
+        for &(next, res) in &adjacency[node] {
+            if !visited[next] {
+                visited[next] = true;
+                parent[next] = Some((node, res));
+                order.push(next);
+            }
+        }
+    }
+    (order, parent)
+}
+
+/// Elmore delay (in seconds) from `driver_pin` to `sink_pin` through the RC network of `net`,
+/// None if one of the pins is not connected to the network.
+///
+/// Resistors forming loops are ignored so that the network is a tree rooted at the driver.
+pub fn elmore_delay(net: &RcNet, driver_pin: &str, sink_pin: &str) -> Option<f64> {
+    let driver = net.node(driver_pin)?;
+    let sink = net.node(sink_pin)?;
+
+    let (order, parent) = tree_order(net, driver);
+    if sink != driver && parent[sink].is_none() {
+        return None;
+    }
+
+    // capacitance seen downstream of each node
+    let mut downstream = net.caps.clone();
+    for &node in order.iter().rev() {
+        if let Some((up, _)) = parent[node] {
+            downstream[up] += downstream[node];
+        }
+    }
+
+    let mut delay = 0.0;
+    let mut node = sink;
+    while let Some((up, res)) = parent[node] {
+        delay += res * downstream[node];
+        node = up;
+    }
+    Some(delay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEF: &str = r#"*SPEF "ieee 1481-1999"
+*DESIGN "test"
+*DATE "Sunday October 13, 2024"
+*VENDOR "stars"
+*PROGRAM "stars"
+*VERSION "1"
+*DESIGN_FLOW "NAME_SCOPE LOCAL" "PIN_CAP NONE"
+*DIVIDER /
+*DELIMITER :
+*BUS_DELIMITER []
+*T_UNIT 1 NS
+*C_UNIT 1 PF
+*R_UNIT 1 OHM
+*L_UNIT 1 HENRY
+
+*NAME_MAP
+*1 n1
+*2 u1
+*3 u2
+*4 u3
+
+*D_NET *1 0.003
+*CONN
+*I *2:X O *D sky130_fd_sc_hd__buf_1
+*I *3:A I *D sky130_fd_sc_hd__buf_1
+*I *4:A I *D sky130_fd_sc_hd__buf_1
+*CAP
+1 *1:1 0.001:0.002:0.003
+2 *3:A 0.001
+3 *4:A 0.001
+*RES
+1 *2:X *1:1 10:20:30
+2 *1:1 *3:A 100
+3 *1:1 *4:A 200
+*END
+"#;
+
+    #[test]
+    fn test_elmore_delay() {
+        let parasitics = Parasitics::parse_str(SPEF, Corner::Max).unwrap();
+        let (net, driver) = parasitics.net_of("u1/X").unwrap();
+        assert_eq!(net.driver, Some(driver));
+        assert_eq!(net.nodes.len(), 4);
+
+        // 30 Ohm to the branch point loaded by 5 fF, then 100 Ohm to the 1 fF sink
+        let delay = elmore_delay(net, "u1/X", "u2/A").unwrap();
+        assert!((delay - (30.0 * 5e-15 + 100.0 * 1e-15)).abs() < 1e-18);
+
+        assert_eq!(parasitics.net_name("u3/A"), Some("n1"));
+        let net = parasitics.net("n1").unwrap();
+        assert_eq!(net.pins, ["u1/X", "u2/A", "u3/A"]);
+        assert!((net.total_cap - 5e-15).abs() < 1e-20);
+        assert_eq!(net.path_resistances, [Some(0.0), Some(130.0), Some(230.0)]);
+        assert!(parasitics.net("u1/X").is_none());
+
+        let parasitics = Parasitics::parse_str(SPEF, Corner::Min).unwrap();
+        let (net, _) = parasitics.net_of("u1/X").unwrap();
+        let delay = elmore_delay(net, "u1/X", "u3/A").unwrap();
+        assert!((delay - (10.0 * 3e-15 + 200.0 * 1e-15)).abs() < 1e-18);
+
+        assert!(Parasitics::parse_str("*SPEF", Corner::Min).is_err());
+
+        // hierarchical names with another divider
+        let spef = SPEF.replace("*DIVIDER /", "*DIVIDER .").replace("*4 u3", "*4 top.u3");
+        let parasitics = Parasitics::parse_str(&spef, Corner::Min).unwrap();
+        let (net, _) = parasitics.net_of("u1/X").unwrap();
+        assert!(elmore_delay(net, "u1/X", "top/u3/A").is_some());
+    }
+
+    #[test]
+    fn test_dspf() {
+        let dspf = "*|DSPF 1.0
+*|DESIGN \"test\"
+*|DIVIDER /
+*|DELIMITER :
+*|BUSBIT []
+.SUBCKT test
+*|GROUND_NET VSS
+*|NET n1 0.005PF
+*|I (u1:X u1 X O 0.0 0 0)
+*|I (u2:A u2 A I 0.001PF 0 0)
+*|I (u3:A u3 A I 0.001PF 0 0)
+*|S (n1:1 0 0)
+R1 u1:X n1:1 30
+R2 n1:1 u2:A 100
+R3 n1:1 u3:A 0.2K
+C1 n1:1 VSS 0.003PF
+C2 u2:A 0 1f
+C3 VSS u3:A 0.001PF
+.ENDS
+";
+        let parasitics = Parasitics::parse_str(dspf, Corner::Max).unwrap();
+        let net = parasitics.net("n1").unwrap();
+        assert_eq!(net.pins, ["u1/X", "u2/A", "u3/A"]);
+        assert_eq!(net.net.driver, Some(0));
+        assert!((net.total_cap - 5e-15).abs() < 1e-20);
+
+        let delay = elmore_delay(net.net, "u1/X", "u2/A").unwrap();
+        assert!((delay - (30.0 * 5e-15 + 100.0 * 1e-15)).abs() < 1e-18);
+
+        assert!(Parasitics::parse_dspf("*|DSPF 1.0\nR1 a b 10", '/').is_err());
+    }
+
+    #[test]
+    fn test_spef_names() {
+        // escaped bus and flattened hierarchy in the NAME_MAP, <> bus delimiters
+        let spef = SPEF
+            .replace("*BUS_DELIMITER []", "*BUS_DELIMITER <>")
+            .replace("*1 n1", "*1 data<3>")
+            .replace("*2 u1", "*2 core\\.u1")
+            .replace("*3 u2", "*3 u2\\[0\\]");
+        let parasitics = Parasitics::parse_str_with(&spef, Corner::Max, '.').unwrap();
+        let (net, _) = parasitics.net_of("core/u1/X").unwrap();
+        assert_eq!(net.name, "data[3]");
+        assert!(elmore_delay(net, "core/u1/X", "u2[0]/A").is_some());
+
+        let spef = SPEF.replace("*D_NET *1", "*D_NET *5");
+        assert!(Parasitics::parse_str(&spef, Corner::Max).is_err());
+    }
 }
@@ -0,0 +1,174 @@
+//! Description of the process design kit used by the SPICE extraction and the ECO estimations:
+//! transistor models, their sizes and electrical constants, and the naming of the standard cells.
+//!
+//! Defaults to sky130 (`sky130_fd_sc_hd`), other PDKs are loaded from a JSON file with the same fields
+//! (see [`PdkConfig::from_json`]).
+
+use crate::error::{Result, StarsError};
+use ordered_float::OrderedFloat;
+use rustc_hash::FxHashMap;
+
+/// Transistor model of one polarity
+#[derive(Debug, Clone, miniserde::Deserialize)]
+pub struct FetModel {
+    /// model name written in the decks, e.g `sky130_fd_pr__nfet_01v8`
+    pub model: String,
+    /// prefix of the models of this polarity in the SUBCKTs (e.g `sky130_fd_pr__nfet` also matches the lvt flavor)
+    pub family: String,
+    /// gate length in µm
+    pub length: f32,
+    /// length of the source and drain diffusions in µm, for their area and perimeter
+    pub diffusion_length: f32,
+    /// widths in µm the model is binned at, sorted
+    pub bins: Vec<f32>,
+    /// equivalent resistance in Ohms, premultiplied by the W / L of the transistor it was measured on so that
+    /// a transistor has a resistance of `eq_resistance` x L / W
+    pub eq_resistance: f32,
+    /// gate capacitance in pF / µm²
+    pub capa_per_area: f32,
+}
+
+/// Process design kit, see the module documentation
+#[derive(Debug, Clone, miniserde::Deserialize)]
+pub struct PdkConfig {
+    pub pfet: FetModel,
+    pub nfet: FetModel,
+    /// prefix of the standard cells, e.g `sky130_fd_sc_hd__` for `sky130_fd_sc_hd__xor2_1`
+    pub cell_prefix: String,
+    /// models of the SUBCKTs replaced by another one in the decks
+    pub model_aliases: FxHashMap<String, String>,
+}
+
+impl Default for PdkConfig {
+    fn default() -> Self {
+        Self::sky130()
+    }
+}
+
+impl PdkConfig {
+    pub fn sky130() -> Self {
+        PdkConfig {
+            // .lib says 5614.3 (calculated from inv1 by calculating delta time over delta capacitance)
+            // spice sim says 6572.7
+            pfet: FetModel {
+                model: "sky130_fd_pr__pfet_01v8_hvt".to_string(),
+                family: "sky130_fd_pr__pfet".to_string(),
+                length: 0.15,
+                diffusion_length: 0.15,
+                bins: vec![
+                    0.36, 0.42, 0.54, 0.55, 0.63, 0.64, 0.70, 0.75, 0.79, 0.82, 0.84, 0.86, 0.94, 1.00, 1.12, 1.26,
+                    1.65, 1.68, 2.00, 3.00, 5.00, 7.00,
+                ],
+                eq_resistance: 6591.7 * 1.0 / 0.15 / std::f32::consts::LN_2,
+                capa_per_area: 0.00990114,
+            },
+            // .lib says 3326.1 (calculated from inv1 by calculating delta time over delta capacitance)
+            // spice sim says 2841.4
+            nfet: FetModel {
+                model: "sky130_fd_pr__nfet_01v8".to_string(),
+                family: "sky130_fd_pr__nfet".to_string(),
+                length: 0.15,
+                diffusion_length: 0.15,
+                bins: vec![
+                    0.36, 0.39, 0.42, 0.52, 0.54, 0.55, 0.58, 0.6, 0.61, 0.64, 0.65, 0.74, 0.84, 1.0, 1.26, 1.68, 2.0,
+                    3.0, 5.0, 7.0,
+                ],
+                eq_resistance: 2832.4 * 0.65 / 0.15 / std::f32::consts::LN_2,
+                capa_per_area: 0.01010523,
+            },
+            cell_prefix: "sky130_fd_sc_hd__".to_string(),
+            model_aliases: [("sky130_fd_pr__special_nfet_01v8", "sky130_fd_pr__nfet_01v8")]
+                .into_iter()
+                .map(|(from, to)| (from.to_string(), to.to_string()))
+                .collect(),
+        }
+    }
+
+    /// Parse a PDK from JSON, every field of [`PdkConfig`] and [`FetModel`] is required
+    pub fn from_json(contents: &str) -> Result<Self> {
+        miniserde::json::from_str(contents)
+            .map_err(|_| StarsError::InvalidPdk("malformed JSON or missing field".into()))
+    }
+
+    /// Model of a SUBCKT transistor once its alias is applied
+    pub fn model<'a>(&'a self, model: &'a str) -> &'a str {
+        self.model_aliases.get(model).map_or(model, String::as_str)
+    }
+
+    pub fn is_nfet(&self, model: &str) -> bool {
+        self.model(model).starts_with(&self.nfet.family)
+    }
+
+    /// Turns sky130_fd_sc_hd__xor2_1 into xor2
+    pub fn celltype_short<'a>(&self, celltype: &'a str) -> &'a str {
+        let celltype = celltype.strip_prefix(&*self.cell_prefix).unwrap_or(celltype);
+        celltype.rsplit_once('_').map_or(celltype, |(short, _)| short)
+    }
+}
+
+impl FetModel {
+    /// Closest bin above `w` (or the biggest one) and the multiplier reaching `w` with it
+    pub fn size(&self, w: f32) -> (f32, f32) {
+        let pos = self
+            .bins
+            .binary_search_by(|val| OrderedFloat(*val).cmp(&OrderedFloat(w)))
+            .unwrap_or_else(|x| x);
+        let closest_bin = self.bins[usize::min(pos, self.bins.len() - 1)];
+        let mult = w / closest_bin;
+        (closest_bin, mult)
+    }
+
+    /// Instance line of a transistor of width `w` (µm) between the drain, gate, source and bulk `nodes`,
+    /// `prefix` being `X` when the model is a subcircuit
+    pub fn card(&self, prefix: char, name: &str, nodes: [&str; 4], w: f32) -> String {
+        let (_, mult) = self.size(w);
+        self.card_with_mult(prefix, name, nodes, w, &format!("{:.2}", mult))
+    }
+
+    /// Same as [`Self::card`] with the multiplier written as `mult`, e.g an expression of a `.param`.
+    /// The diffusions are still computed for a width of `w`
+    pub fn card_with_mult(&self, prefix: char, name: &str, nodes: [&str; 4], w: f32, mult: &str) -> String {
+        let [d, g, s, b] = nodes;
+        let (closest_bin, w_mult) = self.size(w);
+        let ar = self.diffusion_length * closest_bin / w_mult;
+        let pe = (closest_bin + 2.0 * self.diffusion_length) / w_mult;
+
+        format!(
+            "{prefix}{name} {d} {g} {s} {b} {} w={:.2} l={} ad={:.2} as={:.2} pd={:.2} ps={:.2} m={}",
+            self.model, closest_bin, self.length, ar, ar, pe, pe, mult
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_json() {
+        let fet = |model: &str| {
+            format!(
+                r#"{{"model": "{model}", "family": "{model}", "length": 0.18, "diffusion_length": 0.24,
+                    "bins": [0.22, 0.5, 1.0], "eq_resistance": 10000.0, "capa_per_area": 0.008}}"#
+            )
+        };
+        let json = format!(
+            r#"{{"pfet": {}, "nfet": {}, "cell_prefix": "gf180mcu_fd_sc_mcu7t5v0__", "model_aliases": {{}}}}"#,
+            fet("pfet_03v3"),
+            fet("nfet_03v3")
+        );
+        let pdk = PdkConfig::from_json(&json).unwrap();
+
+        assert!(pdk.is_nfet("nfet_03v3"));
+        assert!(!pdk.is_nfet("pfet_03v3"));
+        assert_eq!(pdk.celltype_short("gf180mcu_fd_sc_mcu7t5v0__nand2_2"), "nand2");
+        assert_eq!(pdk.nfet.size(0.4), (0.5, 0.8));
+        assert_eq!(pdk.nfet.size(3.0), (1.0, 3.0));
+        assert_eq!(
+            pdk.pfet.card('X', "M1", ["y", "a", "vdd", "vdd"], 1.0),
+            "XM1 y a vdd vdd pfet_03v3 w=1.00 l=0.18 ad=0.24 as=0.24 pd=1.48 ps=1.48 m=1.00"
+        );
+
+        assert!(PdkConfig::from_json(r#"{"cell_prefix": ""}"#).is_err());
+    }
+}
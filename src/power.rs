@@ -0,0 +1,691 @@
+//! Dynamic power estimation from the switching activity of a simulation (VCD or SAIF file).
+//!
+//! Every net switching dissipates ½·C·V² per toggle, C being the capacitance it drives: the input pins of its fanout
+//! and its wire. The power of a net is counted in the instance driving it.
+//!
+//! Signals are matched to the design by their name, once the scopes of the testbench are removed: the output pins of
+//! the instances (when the cells are dumped with their ports), the input ports, and the nets of the parasitics.
+//!
+//! The static (leakage) power comes from the Liberty cells alone, see [`LeakageReport`].
+
+use crate::analysis::SDFGraphAnalyzed;
+use crate::graph::SDFGraph;
+use crate::liberty::Library;
+#[cfg(feature = "spef")]
+use crate::parasitics::Parasitics;
+#[cfg(feature = "spice")]
+use crate::subckt::SubcktData;
+use crate::types::{InstanceId, PinId, Transition};
+use crate::{celltype_short, graph::drive_strength};
+use itertools::Itertools;
+use ordered_float::OrderedFloat;
+use rustc_hash::FxHashMap;
+use std::cmp::Reverse;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug)]
+pub struct ActivityError {
+    /// `VCD` or `SAIF`
+    pub format: &'static str,
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for ActivityError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} line {}: {}", self.format, self.line, self.message)
+    }
+}
+
+impl std::error::Error for ActivityError {}
+
+/// Number of 0 ↔ 1 transitions of each signal during a simulation
+#[derive(Debug, Clone, Default)]
+pub struct Activity {
+    /// length of the simulation in s
+    pub duration: f64,
+    /// by the full name of the signal, `/` between the scopes, e.g `tb/dut/_123_/X`
+    pub toggles: FxHashMap<String, u64>,
+}
+
+/// Name of a bit of a VCD variable with its last value, None while it is x or z
+type VcdBit = (String, Option<bool>);
+
+/// Seconds in one unit of a timescale, e.g `ns`
+fn time_unit(unit: &str) -> Option<f64> {
+    Some(match unit {
+        "s" => 1.0,
+        "ms" => 1e-3,
+        "us" => 1e-6,
+        "ns" => 1e-9,
+        "ps" => 1e-12,
+        "fs" => 1e-15,
+        _ => return None,
+    })
+}
+
+/// Remove the backslash of the escaped identifiers
+fn unescape(name: &str) -> String {
+    let mut plain = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => plain.extend(chars.next()),
+            c => plain.push(c),
+        }
+    }
+    plain
+}
+
+impl Activity {
+    /// Parse a VCD or a SAIF file, recognized by its first token
+    pub fn parse_str(content: &str) -> Result<Self, ActivityError> {
+        if content.trim_start().starts_with('(') {
+            Self::parse_saif(content)
+        } else {
+            Self::parse_vcd(content)
+        }
+    }
+
+    /// Count the toggles of the value changes of a VCD. The bits of a vector are counted as `name[i]`
+    /// and transitions from or to `x` and `z` are not toggles.
+    pub fn parse_vcd(content: &str) -> Result<Self, ActivityError> {
+        let mut tokens = content
+            .lines()
+            .enumerate()
+            .flat_map(|(i, line)| line.split_whitespace().map(move |token| (i + 1, token)));
+        let error = |line, message: String| ActivityError {
+            format: "VCD",
+            line,
+            message,
+        };
+
+        let mut timescale = 1e-9;
+        let mut scopes: Vec<String> = Vec::new();
+        // variables of each identifier code: the names of their bits, msb first, with their last value
+        let mut vars: FxHashMap<&str, Vec<Vec<VcdBit>>> = FxHashMap::default();
+        let mut toggles: FxHashMap<String, u64> = FxHashMap::default();
+        let (mut first_time, mut last_time) = (None, 0u64);
+
+        let mut toggle = |bit: &mut (String, Option<bool>), value: char| {
+            let value = match value {
+                '0' => Some(false),
+                '1' => Some(true),
+                _ => None,
+            };
+            if let (Some(old), Some(new)) = (bit.1, value) {
+                if old != new {
+                    *toggles.entry(bit.0.clone()).or_insert(0) += 1;
+                }
+            }
+            bit.1 = value;
+        };
+
+        while let Some((line, token)) = tokens.next() {
+            match token {
+                "$timescale" => {
+                    let words: String = tokens
+                        .by_ref()
+                        .map(|(_, token)| token)
+                        .take_while(|token| *token != "$end")
+                        .collect();
+                    let split = words.find(|c: char| !c.is_ascii_digit()).unwrap_or(words.len());
+                    let (number, unit) = words.split_at(split);
+                    timescale = match (number.parse::<f64>(), time_unit(unit)) {
+                        (Ok(number), Some(unit)) => number * unit,
+                        _ => return Err(error(line, format!("invalid timescale {}", words))),
+                    };
+                }
+                "$scope" => {
+                    let name = tokens
+                        .nth(1)
+                        .ok_or_else(|| error(line, "scope without a name".into()))?;
+                    scopes.push(unescape(name.1));
+                    tokens.next();
+                }
+                "$upscope" => {
+                    scopes.pop();
+                    tokens.next();
+                }
+                "$var" => {
+                    let words: Vec<&str> = tokens
+                        .by_ref()
+                        .map(|(_, token)| token)
+                        .take_while(|token| *token != "$end")
+                        .collect();
+                    let [_, width, code, name, range @ ..] = &words[..] else {
+                        return Err(error(line, "invalid $var".into()));
+                    };
+                    let width: usize = width
+                        .parse()
+                        .map_err(|_| error(line, format!("invalid width of {}", name)))?;
+                    let mut full_name = scopes.join("/");
+                    if !full_name.is_empty() {
+                        full_name.push('/');
+                    }
+                    full_name.push_str(&unescape(name));
+                    // `name [7:0]`, the msb first
+                    let msb = range
+                        .first()
+                        .and_then(|range| range.trim_matches(['[', ']']).split(':').next()?.parse::<i64>().ok());
+                    let bits: Vec<(String, Option<bool>)> = match (width, msb) {
+                        (1, None) => vec![(full_name, None)],
+                        (_, msb) => {
+                            let msb = msb.unwrap_or(width as i64 - 1);
+                            let step = match range.first().and_then(|range| range.split(':').nth(1)) {
+                                Some(lsb) if lsb.trim_matches(']').parse::<i64>().is_ok_and(|lsb| lsb > msb) => 1,
+                                _ => -1,
+                            };
+                            (0..width as i64)
+                                .map(|i| (format!("{}[{}]", full_name, msb + step * i), None))
+                                .collect()
+                        }
+                    };
+                    vars.entry(code).or_default().push(bits);
+                }
+                "$enddefinitions" | "$dumpvars" | "$dumpall" | "$dumpon" | "$dumpoff" | "$end" => {}
+                "$comment" | "$date" | "$version" => {
+                    tokens.by_ref().find(|(_, token)| *token == "$end");
+                }
+                _ if token.starts_with('#') => {
+                    let time: u64 = token[1..]
+                        .parse()
+                        .map_err(|_| error(line, format!("invalid time {}", token)))?;
+                    first_time.get_or_insert(time);
+                    last_time = time;
+                }
+                _ if token.starts_with(['b', 'B']) => {
+                    let (_, code) = tokens
+                        .next()
+                        .ok_or_else(|| error(line, "vector value without a signal".into()))?;
+                    let value: Vec<char> = token[1..].chars().collect();
+                    // shorter values are extended with 0, or with their x or z
+                    let padding = match value.first() {
+                        Some('x' | 'X') => 'x',
+                        Some('z' | 'Z') => 'z',
+                        _ => '0',
+                    };
+                    for bits in vars.get_mut(code).into_iter().flatten() {
+                        let extension = bits.len().saturating_sub(value.len());
+                        for (i, bit) in bits.iter_mut().enumerate() {
+                            let c = i.checked_sub(extension).map_or(padding, |j| value[j]);
+                            toggle(bit, c);
+                        }
+                    }
+                }
+                _ if token.starts_with(['r', 'R']) => {
+                    tokens.next();
+                }
+                _ => {
+                    let mut chars = token.chars();
+                    let value = chars.next().unwrap_or('x');
+                    for bit in vars.get_mut(chars.as_str()).into_iter().flatten().flatten() {
+                        toggle(bit, value);
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            duration: (last_time - first_time.unwrap_or(0)) as f64 * timescale,
+            toggles,
+        })
+    }
+
+    /// Read the toggle counts (`TC`) of the NET and PORT entries of a SAIF, over its DURATION
+    pub fn parse_saif(content: &str) -> Result<Self, ActivityError> {
+        let mut tokens = Vec::new();
+        for (i, line) in content.lines().enumerate() {
+            let mut chars = line.char_indices().peekable();
+            while let Some((start, c)) = chars.next() {
+                match c {
+                    '(' | ')' => tokens.push((i + 1, &line[start..start + 1])),
+                    c if c.is_whitespace() => {}
+                    '"' => {
+                        // kept quoted, to tell the design name of an INSTANCE from its path
+                        let end = chars
+                            .by_ref()
+                            .find(|(_, c)| *c == '"')
+                            .map_or(line.len(), |(end, _)| end + 1);
+                        tokens.push((i + 1, &line[start..end]));
+                    }
+                    _ => {
+                        let mut end = line.len();
+                        let mut escaped = c == '\\';
+                        while let Some(&(i, c)) = chars.peek() {
+                            if !escaped && (c.is_whitespace() || c == '(' || c == ')') {
+                                end = i;
+                                break;
+                            }
+                            escaped = c == '\\' && !escaped;
+                            chars.next();
+                        }
+                        tokens.push((i + 1, &line[start..end]));
+                    }
+                }
+            }
+        }
+
+        let error = |line, message: String| ActivityError {
+            format: "SAIF",
+            line,
+            message,
+        };
+        let mut activity = Activity::default();
+        let (mut timescale, mut duration) = (1e-9, 0.0);
+        // kind of each open group, and the instances they are in
+        let mut groups: Vec<&str> = Vec::new();
+        let mut scopes: Vec<String> = Vec::new();
+        let mut i = 0;
+        while i < tokens.len() {
+            let (line, token) = tokens[i];
+            match token {
+                "(" => {
+                    let (_, kind) = *tokens.get(i + 1).ok_or_else(|| error(line, "unclosed group".into()))?;
+                    let parent = groups.last().copied();
+                    match kind {
+                        "INSTANCE" => {
+                            // the optional name of the design comes first
+                            let name = match tokens.get(i + 2) {
+                                Some((_, design)) if design.starts_with('"') => tokens.get(i + 3),
+                                name => name,
+                            };
+                            scopes.push(unescape(name.map_or("", |(_, name)| name)));
+                        }
+                        "TIMESCALE" => {
+                            let words: String = tokens[i + 2..]
+                                .iter()
+                                .map(|(_, token)| *token)
+                                .take_while(|token| *token != ")")
+                                .collect();
+                            let split = words.find(|c: char| !c.is_ascii_digit()).unwrap_or(words.len());
+                            let (number, unit) = words.split_at(split);
+                            timescale = match (number.parse::<f64>(), time_unit(unit)) {
+                                (Ok(number), Some(unit)) => number * unit,
+                                _ => return Err(error(line, format!("invalid timescale {}", words))),
+                            };
+                        }
+                        "DURATION" => {
+                            let value = tokens.get(i + 2).map_or("", |(_, value)| *value);
+                            duration = value
+                                .parse()
+                                .map_err(|_| error(line, format!("invalid duration {}", value)))?;
+                        }
+                        "TC" if matches!(groups.get(groups.len().saturating_sub(2)), Some(&"NET" | &"PORT")) => {
+                            let value = tokens.get(i + 2).map_or("", |(_, value)| *value);
+                            let count: u64 = value
+                                .parse()
+                                .map_err(|_| error(line, format!("invalid toggle count {}", value)))?;
+                            let signal = parent.map(unescape);
+                            let name = scopes.iter().chain(&signal).join("/");
+                            *activity.toggles.entry(name).or_insert(0) += count;
+                        }
+                        _ => {}
+                    }
+                    groups.push(kind);
+                    i += 2;
+                }
+                ")" => {
+                    if groups.pop() == Some("INSTANCE") {
+                        scopes.pop();
+                    }
+                    i += 1;
+                }
+                _ => i += 1,
+            }
+        }
+
+        activity.duration = duration * timescale;
+        Ok(activity)
+    }
+}
+
+/// Where the capacitances driven by the nets come from. The Liberty pin capacitances are preferred to the ones of
+/// the SUBCKTs, and the wires only count with the parasitics.
+#[derive(Clone, Copy, Default)]
+pub struct Capacitances<'a> {
+    pub library: Option<&'a Library>,
+    #[cfg(feature = "spice")]
+    pub subckt: Option<&'a SubcktData>,
+    #[cfg(feature = "spef")]
+    pub parasitics: Option<&'a Parasitics>,
+}
+
+impl Capacitances<'_> {
+    /// Capacitance driven by `driver` in pF: the input pins of its fanout and its wire
+    pub fn load(&self, graph: &SDFGraph, driver: PinId) -> f32 {
+        let symbols = &graph.symbols;
+        let driver_instance = symbols.pin_instance(driver);
+        let pins: f32 = (graph.graph.get(&(driver, Transition::Rise)).into_iter().flatten())
+            .filter(|edge| driver_instance.is_none() || symbols.pin_instance(edge.dst.0) != driver_instance)
+            .filter_map(|edge| {
+                let instance = symbols.pin_instance(edge.dst.0)?;
+                let celltype = graph.instance_celltype.get(&instance)?;
+                let pin = symbols.pin_short_name(edge.dst.0);
+                let library = self.library.and_then(|library| {
+                    let capacitance = library.cells.get(celltype)?.pin(pin)?.capacitance?;
+                    Some(capacitance * library.capacitive_load_unit)
+                });
+                #[cfg(feature = "spice")]
+                let library = library.or_else(|| self.subckt?.pin_capacitance(celltype, pin));
+                library
+            })
+            .sum();
+        #[cfg(not(feature = "spef"))]
+        let wire = 0.0;
+        #[cfg(feature = "spef")]
+        let wire = self
+            .parasitics
+            .and_then(|parasitics| parasitics.net_of(symbols.pin_name(driver)))
+            .map_or(0.0, |(net, _)| net.total_cap() as f32 * 1e12);
+        pins + wire
+    }
+}
+
+/// Switching power of a net
+#[derive(Debug, Clone)]
+pub struct NetPower {
+    /// output pin of a cell or input port driving the net
+    pub driver: PinId,
+    /// toggles per ns
+    pub toggle_rate: f64,
+    /// pF
+    pub capacitance: f32,
+    /// W
+    pub power: f64,
+}
+
+/// Switching power of the nets driven by an instance
+#[derive(Debug, Clone)]
+pub struct InstancePower {
+    pub instance: InstanceId,
+    /// W
+    pub power: f64,
+    /// worst slack of the paths through the instance, None when no path goes through it
+    pub slack: Option<f32>,
+}
+
+pub struct PowerReport {
+    /// supply voltage in V
+    pub vdd: f32,
+    /// length of the simulation in s
+    pub duration: f64,
+    /// nets with activity, the most consuming first
+    pub nets: Vec<NetPower>,
+    /// instances driving nets with activity, the most consuming first
+    pub instances: Vec<InstancePower>,
+    /// W
+    pub total: f64,
+    /// signals of the activity file that aren't a net of the design
+    pub unmatched: usize,
+}
+
+impl PowerReport {
+    /// Estimate the switching power of the nets of the design with the toggles of `activity`
+    pub fn new(
+        graph: &SDFGraph,
+        analysis: &SDFGraphAnalyzed,
+        activity: &Activity,
+        capacitances: Capacitances,
+        vdd: f32,
+    ) -> Self {
+        let symbols = &graph.symbols;
+        let drives_net = |pin: PinId| match symbols.pin_instance(pin) {
+            None => graph
+                .graph
+                .get(&(pin, Transition::Rise))
+                .is_some_and(|edges| !edges.is_empty()),
+            Some(instance) => graph
+                .instance_outs
+                .get(&instance)
+                .is_some_and(|outs| outs.contains(&pin)),
+        };
+        let driver_of = |name: &str| {
+            let pin = symbols.pin_id(name).filter(|&pin| drives_net(pin));
+            #[cfg(feature = "spef")]
+            let pin = pin.or_else(|| {
+                let parasitics = capacitances.parasitics?;
+                let net = &parasitics.nets[*parasitics.net_names.get(name)?];
+                let driver = symbols.pin_id(&net.nodes[net.driver?])?;
+                drives_net(driver).then_some(driver)
+            });
+            pin
+        };
+
+        // the testbench scopes are removed until the name is found, the same net can be dumped at several levels
+        let mut toggles: FxHashMap<PinId, u64> = FxHashMap::default();
+        let mut unmatched = 0;
+        for (signal, &count) in &activity.toggles {
+            let mut name = signal.as_str();
+            let driver = loop {
+                if let Some(driver) = driver_of(name) {
+                    break Some(driver);
+                }
+                match name.split_once('/') {
+                    Some((_, rest)) => name = rest,
+                    None => break None,
+                }
+            };
+            match driver {
+                Some(driver) => {
+                    let total = toggles.entry(driver).or_insert(0);
+                    *total = (*total).max(count);
+                }
+                None => unmatched += 1,
+            }
+        }
+
+        let duration_ns = activity.duration * 1e9;
+        let mut nets: Vec<NetPower> = toggles
+            .into_iter()
+            .filter(|_| duration_ns > 0.0)
+            .map(|(driver, count)| {
+                let toggle_rate = count as f64 / duration_ns;
+                let capacitance = capacitances.load(graph, driver);
+                NetPower {
+                    driver,
+                    toggle_rate,
+                    capacitance,
+                    // ½CV² per toggle
+                    power: 0.5 * capacitance as f64 * 1e-12 * (vdd as f64).powi(2) * toggle_rate * 1e9,
+                }
+            })
+            .collect();
+        nets.sort_by_key(|net| (Reverse(OrderedFloat(net.power)), net.driver));
+
+        let mut instances: FxHashMap<InstanceId, f64> = FxHashMap::default();
+        for net in &nets {
+            if let Some(instance) = symbols.pin_instance(net.driver) {
+                *instances.entry(instance).or_insert(0.0) += net.power;
+            }
+        }
+        let mut instances: Vec<InstancePower> = instances
+            .into_iter()
+            .map(|(instance, power)| InstancePower {
+                instance,
+                power,
+                slack: instance_slack(graph, analysis, instance),
+            })
+            .collect();
+        instances.sort_by_key(|instance| (Reverse(OrderedFloat(instance.power)), instance.instance));
+
+        Self {
+            vdd,
+            duration: activity.duration,
+            total: nets.iter().map(|net| net.power).sum(),
+            nets,
+            instances,
+            unmatched,
+        }
+    }
+}
+
+/// Worst slack of the paths through the pins of an instance
+fn instance_slack(graph: &SDFGraph, analysis: &SDFGraphAnalyzed, instance: InstanceId) -> Option<f32> {
+    let pins = graph.instance_ins.get(&instance).into_iter().flatten();
+    let pins = pins.chain(graph.instance_outs.get(&instance).into_iter().flatten());
+    pins.flat_map(|&pin| [(pin, Transition::Rise), (pin, Transition::Fall)])
+        .filter_map(|node| analysis.slack_through(graph, &node))
+        .reduce(f32::min)
+}
+
+/// Leakage of the instances sharing a celltype, a cell family or a drive strength
+#[derive(Debug, Clone, Default)]
+pub struct LeakageGroup {
+    /// e.g `sky130_fd_sc_hd__nand2_4`, `nand2` or `4`
+    pub name: String,
+    pub instances: usize,
+    /// W
+    pub power: f64,
+    /// leakage of the instances with a positive slack (or on no path), which could be swapped for slower cells. W
+    pub non_critical: f64,
+}
+
+pub struct LeakageReport {
+    /// W
+    pub total: f64,
+    /// most leaking first
+    pub celltypes: Vec<LeakageGroup>,
+    /// cells of all the drive strengths, e.g `nand2`, most leaking first
+    pub families: Vec<LeakageGroup>,
+    /// the cells without a drive strength in their name are under `-`, most leaking first
+    pub drive_strengths: Vec<LeakageGroup>,
+    /// celltypes of the design without leakage in the library
+    pub unknown: Vec<String>,
+}
+
+impl LeakageReport {
+    /// Sum the leakage of the Liberty cells over the instances of the design
+    pub fn new(graph: &SDFGraph, analysis: &SDFGraphAnalyzed, library: &Library) -> Self {
+        let mut celltypes: FxHashMap<&str, LeakageGroup> = FxHashMap::default();
+        let mut unknown: Vec<String> = Vec::new();
+        for (instance, celltype) in graph.instance_celltype.iter() {
+            // the design itself
+            if graph.symbols.instance_name(instance).is_empty() {
+                continue;
+            }
+            let Some(leakage) = library.cells.get(celltype).and_then(|cell| cell.leakage_power) else {
+                if !unknown.contains(celltype) {
+                    unknown.push(celltype.clone());
+                }
+                continue;
+            };
+            let power = leakage as f64 * library.leakage_power_unit as f64;
+            let group = celltypes.entry(celltype).or_insert_with(|| LeakageGroup {
+                name: celltype.clone(),
+                ..LeakageGroup::default()
+            });
+            group.instances += 1;
+            group.power += power;
+            if instance_slack(graph, analysis, instance).is_none_or(|slack| slack > 0.0) {
+                group.non_critical += power;
+            }
+        }
+        unknown.sort();
+
+        let merge = |key: &dyn Fn(&str) -> String| {
+            let mut groups: FxHashMap<String, LeakageGroup> = FxHashMap::default();
+            for celltype in celltypes.values() {
+                let name = key(&celltype.name);
+                let group = groups.entry(name.clone()).or_insert_with(|| LeakageGroup {
+                    name,
+                    ..LeakageGroup::default()
+                });
+                group.instances += celltype.instances;
+                group.power += celltype.power;
+                group.non_critical += celltype.non_critical;
+            }
+            sorted(groups.into_values().collect())
+        };
+        let families = merge(&|celltype| celltype_short(celltype).to_string());
+        let drive_strengths = merge(&|celltype| drive_strength(celltype).map_or("-".to_string(), |s| s.to_string()));
+        let celltypes = sorted(celltypes.into_values().collect());
+
+        Self {
+            total: celltypes.iter().map(|group| group.power).sum(),
+            celltypes,
+            families,
+            drive_strengths,
+            unknown,
+        }
+    }
+}
+
+fn sorted(mut groups: Vec<LeakageGroup>) -> Vec<LeakageGroup> {
+    groups.sort_by(|a, b| (Reverse(OrderedFloat(a.power)), &a.name).cmp(&(Reverse(OrderedFloat(b.power)), &b.name)));
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vcd() {
+        let vcd = "$date today $end
+$timescale 1 ps $end
+$scope module tb $end
+$var wire 1 ! clk $end
+$scope module dut $end
+$var wire 1 \" \\_12_/X $end
+$var wire 4 # data [3:0] $end
+$var wire 1 ! clk $end
+$upscope $end
+$upscope $end
+$enddefinitions $end
+#0
+$dumpvars
+0!
+x\"
+b0 #
+$end
+#500
+1!
+0\"
+b101 #
+#1000
+0!
+1\"
+b11 #
+";
+        let activity = Activity::parse_vcd(vcd).unwrap();
+        assert!((activity.duration - 1e-9).abs() < 1e-15);
+        assert_eq!(activity.toggles["tb/clk"], 2);
+        assert_eq!(activity.toggles["tb/dut/clk"], 2);
+        assert_eq!(activity.toggles["tb/dut/_12_/X"], 1);
+        assert_eq!(activity.toggles["tb/dut/data[0]"], 1);
+        assert_eq!(activity.toggles["tb/dut/data[1]"], 1);
+        assert_eq!(activity.toggles["tb/dut/data[2]"], 2);
+        assert!(!activity.toggles.contains_key("tb/dut/data[3]"));
+
+        assert!(Activity::parse_vcd("$timescale 1 parsec $end").is_err());
+    }
+
+    #[test]
+    fn test_saif() {
+        let saif = r#"(SAIFILE
+(SAIFVERSION "2.0")
+(DIRECTION "backward")
+(DIVIDER / )
+(TIMESCALE 1 ns)
+(DURATION 1000)
+(INSTANCE tb
+  (INSTANCE "top" dut
+    (NET
+      (net\[1\] (T0 600) (T1 400) (TX 0) (TC 20) (IG 0))
+    )
+    (INSTANCE _12_
+      (PORT
+        (X (T0 500) (T1 500) (TC 7))
+      )
+    )
+  )
+)
+)"#;
+        let activity = Activity::parse_str(saif).unwrap();
+        assert!((activity.duration - 1e-6).abs() < 1e-12);
+        assert_eq!(activity.toggles.len(), 2);
+        assert_eq!(activity.toggles["tb/dut/net[1]"], 20);
+        assert_eq!(activity.toggles["tb/dut/_12_/X"], 7);
+    }
+}
@@ -0,0 +1,132 @@
+//! Progress of the long steps (parsing the SDF and SPEF, building the graph, analyzing it), which take minutes
+//! on the SDF of a large design. They report to a [`Progress`] given in their options.
+
+use std::fmt::{Display, Formatter};
+use std::io::IsTerminal;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Step reporting its progress, with the items it counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// bytes of the SDF source
+    SdfParse,
+    /// nets of the SPEF
+    SpefParse,
+    /// cells of the SDF
+    GraphBuild,
+    /// levels of the graph, once per propagation
+    Analysis,
+}
+
+impl Display for Phase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Phase::SdfParse => "Parsing the SDF",
+            Phase::SpefParse => "Reading the SPEF",
+            Phase::GraphBuild => "Building the graph",
+            Phase::Analysis => "Analyzing",
+        })
+    }
+}
+
+/// Callback receiving `(phase, done, total)` as the steps go: `done` is 0 when the phase starts and `total` when
+/// it ends, the calls in between are frequent enough to update a progress bar.
+#[derive(Clone)]
+pub struct Progress(Arc<dyn Fn(Phase, u64, u64) + Send + Sync>);
+
+impl Progress {
+    pub fn new(report: impl Fn(Phase, u64, u64) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(report))
+    }
+
+    pub fn report(&self, phase: Phase, done: u64, total: u64) {
+        (self.0)(phase, done, total)
+    }
+
+    /// Progress line on stderr with the count and the estimated time left, overwritten as the phase goes. Nothing
+    /// is printed when stderr isn't a terminal.
+    pub fn stderr() -> Self {
+        if !std::io::stderr().is_terminal() {
+            return Self::new(|_, _, _| {});
+        }
+        let state: Mutex<Option<(Phase, Instant, Instant)>> = Mutex::new(None);
+        Self::new(move |phase, done, total| {
+            let now = Instant::now();
+            let mut state = state.lock().unwrap();
+            let (start, last) = match &mut *state {
+                Some((current, start, last)) if *current == phase => (*start, last),
+                _ => {
+                    let (_, _, last) = state.insert((phase, now, now));
+                    (now, last)
+                }
+            };
+            let end = done >= total;
+            if done > 0 && !end && now - *last < Duration::from_millis(100) {
+                return;
+            }
+            *last = now;
+
+            let count = match phase {
+                Phase::SdfParse => format!("{}/{} MB", done >> 20, total >> 20),
+                _ => format!("{}/{}", done, total),
+            };
+            let elapsed = (now - start).as_secs_f64();
+            let eta = match done {
+                _ if end => format!("done in {:.1}s", elapsed),
+                0 => String::new(),
+                _ => format!("{:.0}s left", elapsed * (total - done) as f64 / done as f64),
+            };
+            let percent = (done * 100).checked_div(total).unwrap_or(100);
+            eprint!("\r\x1b[2K{}: {} ({}%) {}", phase, count, percent, eta);
+            if end {
+                eprintln!();
+                *state = None;
+            }
+        })
+    }
+}
+
+impl std::fmt::Debug for Progress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Progress(..)")
+    }
+}
+
+/// Report of a phase going through `total` items, sent every `step` items to keep the callback out of the loops
+pub(crate) struct PhaseProgress<'a> {
+    progress: Option<&'a Progress>,
+    phase: Phase,
+    total: u64,
+    step: u64,
+}
+
+impl<'a> PhaseProgress<'a> {
+    pub(crate) fn start(progress: Option<&'a Progress>, phase: Phase, total: usize, step: usize) -> Self {
+        if let Some(progress) = progress {
+            progress.report(phase, 0, total as u64);
+        }
+        Self {
+            progress,
+            phase,
+            total: total as u64,
+            step: step.max(1) as u64,
+        }
+    }
+
+    /// `done` items went through
+    pub(crate) fn update(&self, done: usize) {
+        if let Some(progress) = self.progress {
+            let done = done as u64;
+            if done > 0 && done.is_multiple_of(self.step) && done < self.total {
+                progress.report(self.phase, done, self.total);
+            }
+        }
+    }
+
+    pub(crate) fn finish(self) {
+        if let Some(progress) = self.progress {
+            progress.report(self.phase, self.total, self.total);
+        }
+    }
+}
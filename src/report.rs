@@ -0,0 +1,775 @@
+use crate::analysis::{PathGroupSummary, PathReport, SDFGraphAnalyzed};
+use crate::bottleneck::Bottlenecks;
+use crate::comparison::AnalysisComparison;
+use crate::def::Placement;
+use crate::graph::{AnnotationCheck, SDFGraph};
+use crate::opensta::PathDelta;
+use crate::power::{LeakageGroup, LeakageReport, PowerReport};
+use crate::sdfdiff::SdfDiff;
+use crate::stats::GraphStats;
+use crate::types::{PinTrans, Transition};
+use std::fmt::Write;
+
+const SEPARATOR: &str = "---------------------------------------------------------------------------";
+
+/// Describe the start or end of a path the way OpenSTA does, e.g. `(rising edge-triggered flip-flop)`
+fn point_description(graph: &SDFGraph, pin: &PinTrans) -> String {
+    let instance = graph.symbols.pin_instance(pin.0);
+    match instance.and_then(|id| graph.registers.get(&id)) {
+        Some(register) => match (register.latch, register.clock.1) {
+            (false, Transition::Rise) => "(rising edge-triggered flip-flop)".to_string(),
+            (false, Transition::Fall) => "(falling edge-triggered flip-flop)".to_string(),
+            (true, Transition::Rise) => "(positive level-sensitive latch)".to_string(),
+            (true, Transition::Fall) => "(negative level-sensitive latch)".to_string(),
+        },
+        None if instance.is_none() => "(port)".to_string(),
+        None => format!("({})", celltype_of(graph, pin)),
+    }
+}
+
+fn celltype_of<'a>(graph: &'a SDFGraph, pin: &PinTrans) -> &'a str {
+    graph
+        .symbols
+        .pin_instance(pin.0)
+        .and_then(|id| graph.instance_celltype.get(&id))
+        .map_or("port", |celltype| &**celltype)
+}
+
+/// One line per pin of a path with its delay and arrival time, the delay of the first one counted from `previous`
+fn write_path_pins(
+    w: &mut String,
+    graph: &SDFGraph,
+    report: &PathReport,
+    mut previous: f32,
+    placement: Option<&Placement>,
+) -> std::fmt::Result {
+    for (pin, time) in report.pins() {
+        // fanout is only meaningful on the pins driving a net: ports and cell outputs
+        let drives_net = match graph.symbols.pin_instance(pin.0) {
+            None => true,
+            Some(id) => graph.instance_outs.get(&id).is_some_and(|outs| outs.contains(&pin.0)),
+        };
+        let fanout = match graph.graph.edges(&pin).len() {
+            n if drives_net && n > 0 => n.to_string(),
+            _ => String::new(),
+        };
+        write_pin(w, graph, &pin, &fanout, time - previous, time, placement)?;
+        previous = time;
+    }
+    Ok(())
+}
+
+fn write_pin(
+    w: &mut String,
+    graph: &SDFGraph,
+    pin: &PinTrans,
+    fanout: &str,
+    delay: f32,
+    time: f32,
+    placement: Option<&Placement>,
+) -> std::fmt::Result {
+    write!(
+        w,
+        "{:>6} {:>9.3} {:>9.3} {} {} ({})",
+        fanout,
+        delay,
+        time,
+        pin.1,
+        graph.display_pin_name(pin.0),
+        celltype_of(graph, pin),
+    )?;
+    match placement.and_then(|placement| placement.pin_location(graph, pin.0)) {
+        Some((x, y)) => writeln!(w, " ({:.1}, {:.1})", x, y),
+        None => writeln!(w),
+    }
+}
+
+/// Lines of the clock edge launching a path from a register: the clock network delay and the clock pin of the
+/// register, so that the startpoint shows the delay of the CLK -> Q arc. Returns the arrival of the clock edge.
+fn write_launch(
+    w: &mut String,
+    graph: &SDFGraph,
+    analysis: &SDFGraphAnalyzed,
+    startpoint: &PinTrans,
+    placement: Option<&Placement>,
+) -> Result<f32, std::fmt::Error> {
+    let Some(clock) = graph.launch_clock(startpoint) else {
+        return Ok(0.0);
+    };
+    let launch = analysis.clock_arrival(&clock);
+    if launch != 0.0 {
+        writeln!(
+            w,
+            "{:>6} {:>9.3} {:>9.3}   clock network delay (launch)",
+            "", launch, launch
+        )?;
+    }
+    write_pin(w, graph, &clock, "", 0.0, launch, placement)?;
+    Ok(launch)
+}
+
+/// Write the report of the worst path to `endpoint`, in the style of OpenSTA's `report_checks`.
+/// With a `placement`, the location of every pin and the length of the wires of the path are added.
+pub fn report_path(
+    w: &mut String,
+    graph: &SDFGraph,
+    analysis: &SDFGraphAnalyzed,
+    endpoint: &PinTrans,
+    placement: Option<&Placement>,
+) -> std::fmt::Result {
+    let Some(report) = analysis.path_report(graph, endpoint) else {
+        return writeln!(w, "No path to {}{}", graph.display_pin_name(endpoint.0), endpoint.1);
+    };
+    let arrival = report.total_delay;
+    let startpoint = &report.startpoint();
+
+    writeln!(
+        w,
+        "Startpoint: {} {}",
+        graph.display_pin_name(startpoint.0),
+        point_description(graph, startpoint)
+    )?;
+    writeln!(
+        w,
+        "Endpoint: {} {}",
+        graph.display_pin_name(endpoint.0),
+        point_description(graph, endpoint)
+    )?;
+    writeln!(w, "Corner: {}", graph.corner)?;
+    writeln!(w, "Path Type: max")?;
+    writeln!(w)?;
+    writeln!(w, "Fanout     Delay      Time   Description")?;
+    writeln!(w, "{}", SEPARATOR)?;
+
+    let launch = write_launch(w, graph, analysis, startpoint, placement)?;
+    write_path_pins(w, graph, &report, launch, placement)?;
+
+    writeln!(w, "{:>26}   data arrival time", format!("{:.3}", arrival))?;
+    // the stages start at the register outputs, after their CLK -> Q arc
+    let clock_to_q = graph
+        .launch_clock(startpoint)
+        .map_or(0.0, |clock| graph.clock_to_q(startpoint, &clock));
+    let (cell_delay, net_delay) = (report.cell_delay() + clock_to_q, report.net_delay());
+    let total = cell_delay + net_delay;
+    for (delay, kind) in [(cell_delay, "cell"), (net_delay, "net")] {
+        let share = if total > 0.0 { 100.0 * delay / total } else { 0.0 };
+        writeln!(w, "{:>26}   {} delay ({:.0}%)", format!("{:.3}", delay), kind, share)?;
+    }
+    if let Some(placement) = placement {
+        let (length, direct) = placement.path_length(graph, &report);
+        write!(w, "{:>26}   wire length (µm)", format!("{:.1}", length))?;
+        match direct {
+            Some(direct) => writeln!(w, ", {:.1} from start to end", direct)?,
+            None => writeln!(w)?,
+        }
+    }
+    writeln!(w)?;
+    let required = analysis.required_time(endpoint);
+    if analysis.required.contains_key(endpoint) {
+        let capture = graph
+            .capture_clock(endpoint)
+            .map_or(0.0, |clock| analysis.early_clock_arrival(&clock));
+        if capture != 0.0 {
+            writeln!(w, "{:>26}   clock network delay (capture)", format!("{:.3}", capture))?;
+        }
+        if let Some(setup) = graph.timing_checks.get(endpoint).and_then(|check| check.setup) {
+            writeln!(w, "{:>26}   library setup time", format!("{:.3}", -setup))?;
+        }
+    }
+    if analysis.required.contains_key(endpoint) {
+        writeln!(w, "{:>26}   data required time", format!("{:.3}", required))?;
+    } else {
+        writeln!(
+            w,
+            "{:>26}   data required time (unconstrained, worst arrival)",
+            format!("{:.3}", required)
+        )?;
+    }
+    writeln!(w, "{}", SEPARATOR)?;
+
+    let slack = required - arrival;
+    writeln!(
+        w,
+        "{:>26}   slack ({})",
+        format!("{:.3}", slack),
+        if slack < 0.0 { "VIOLATED" } else { "MET" }
+    )?;
+
+    Ok(())
+}
+
+/// Report the `top` endpoints with the smallest slack (one per pin, worst transition), worst first.
+pub fn report_checks(
+    graph: &SDFGraph,
+    analysis: &SDFGraphAnalyzed,
+    top: usize,
+    placement: Option<&Placement>,
+) -> String {
+    let endpoints = analysis.endpoint_pins_by_slack(graph);
+
+    let mut report = String::new();
+
+    for (endpoint, _) in endpoints.iter().take(top) {
+        report_path(&mut report, graph, analysis, endpoint, placement).unwrap();
+        writeln!(&mut report).unwrap();
+        writeln!(&mut report).unwrap();
+    }
+
+    report
+}
+
+/// Report the `top` register inputs with the smallest hold slack, worst first.
+pub fn report_hold_checks(graph: &SDFGraph, analysis: &SDFGraphAnalyzed, top: usize) -> String {
+    let mut report = String::new();
+    let endpoints = analysis.endpoints_by_hold_slack(graph);
+    let violations = endpoints.iter().filter(|(_, slack)| *slack < 0.0).count();
+
+    writeln!(
+        &mut report,
+        "Hold checks ({} violated of {})",
+        violations,
+        endpoints.len()
+    )
+    .unwrap();
+    writeln!(&mut report, "  Arrival      Hold     Slack   Endpoint").unwrap();
+    writeln!(&mut report, "{}", SEPARATOR).unwrap();
+    for (endpoint, slack) in endpoints.iter().take(top) {
+        let check = &graph.timing_checks[endpoint];
+        writeln!(
+            &mut report,
+            "{:>9.3} {:>9.3} {:>9.3}   {}{} ({}){}",
+            analysis.min_delay[endpoint],
+            check.hold.unwrap_or(0.0),
+            slack,
+            graph.display_pin_name(endpoint.0),
+            endpoint.1,
+            celltype_of(graph, endpoint),
+            if *slack < 0.0 { " VIOLATED" } else { "" },
+        )
+        .unwrap();
+    }
+
+    report
+}
+
+/// Plot the slack histogram of the endpoints, with the number of endpoints within 5% and 10%
+/// of the critical path (relative to the clock period, or to the worst arrival when unconstrained).
+pub fn report_histogram(graph: &SDFGraph, analysis: &SDFGraphAnalyzed, bins: usize) -> String {
+    const BAR_WIDTH: usize = 50;
+
+    let histogram = analysis.slack_histogram(graph, bins);
+    let largest = histogram.counts.iter().copied().max().unwrap_or(0).max(1);
+
+    let mut report = String::new();
+    writeln!(&mut report, "Corner: {}", graph.corner).unwrap();
+    writeln!(&mut report, "          Slack      Count  Cumulative").unwrap();
+    writeln!(&mut report, "{}", SEPARATOR).unwrap();
+    let mut cumulative = 0;
+    for (i, &count) in histogram.counts.iter().enumerate() {
+        let (from, to) = histogram.bin_range(i);
+        cumulative += count;
+        writeln!(
+            &mut report,
+            "{:>7.3} {:>7.3} {:>10} {:>11}  {}",
+            from,
+            to,
+            count,
+            cumulative,
+            "#".repeat((count * BAR_WIDTH).div_ceil(largest))
+        )
+        .unwrap();
+    }
+    writeln!(&mut report, "{}", SEPARATOR).unwrap();
+
+    let reference = analysis.period.unwrap_or(analysis.worst_arrival);
+    for percent in [5, 10] {
+        let limit = histogram.min + reference * percent as f32 / 100.0;
+        let within = analysis
+            .endpoint_pins_by_slack(graph)
+            .iter()
+            .take_while(|(_, slack)| *slack <= limit)
+            .count();
+        writeln!(
+            &mut report,
+            "{} of {} endpoints within {}% of the critical path (slack <= {:.3})",
+            within,
+            histogram.total(),
+            percent,
+            limit
+        )
+        .unwrap();
+    }
+
+    report
+}
+
+/// Report the instances and segments shared by the worst paths, the `max_rows` most shared of each.
+pub fn report_bottlenecks(graph: &SDFGraph, bottlenecks: &Bottlenecks, max_rows: usize) -> String {
+    let mut report = String::new();
+
+    let (Some(worst), Some(best)) = (bottlenecks.paths.first(), bottlenecks.paths.last()) else {
+        return "No path found\n".to_string();
+    };
+    writeln!(
+        &mut report,
+        "Corner: {}\n{} worst paths, slack {:.3} to {:.3}\n",
+        graph.corner,
+        bottlenecks.paths.len(),
+        worst.slack,
+        best.slack
+    )
+    .unwrap();
+
+    writeln!(&mut report, " Paths  Worst slack   Instance").unwrap();
+    writeln!(&mut report, "{}", SEPARATOR).unwrap();
+    for shared in bottlenecks.instances.iter().take(max_rows) {
+        writeln!(
+            &mut report,
+            "{:>6} {:>12.3}   {} ({})",
+            shared.paths,
+            shared.worst_slack,
+            graph.display_instance_name(shared.instance),
+            graph
+                .instance_celltype
+                .get(&shared.instance)
+                .map_or("", |celltype| &**celltype)
+        )
+        .unwrap();
+    }
+    writeln!(&mut report).unwrap();
+
+    writeln!(&mut report, " Paths        Delay   Segment").unwrap();
+    writeln!(&mut report, "{}", SEPARATOR).unwrap();
+    for segment in bottlenecks.segments.iter().take(max_rows) {
+        let pins: Vec<String> = segment
+            .pins
+            .iter()
+            .map(|pin| format!("{}{}", graph.display_pin_name(pin.0), pin.1))
+            .collect();
+        writeln!(
+            &mut report,
+            "{:>6} {:>12.3}   {}",
+            segment.paths,
+            segment.delay,
+            pins.join(" -> ")
+        )
+        .unwrap();
+    }
+
+    report
+}
+
+/// Report of the arcs added, removed and changed between two SDFs, at most `max_rows` of each.
+pub fn report_sdf_diff(diff: &SdfDiff, max_rows: usize) -> String {
+    let mut report = String::new();
+    writeln!(
+        &mut report,
+        "Corner: {}\n{} arcs added, {} removed, {} changed\n",
+        diff.corner,
+        diff.added.len(),
+        diff.removed.len(),
+        diff.changed.len()
+    )
+    .unwrap();
+
+    if !diff.changed.is_empty() {
+        writeln!(&mut report, "    Delta   Before (rise/fall)    After (rise/fall)   Arc").unwrap();
+        writeln!(&mut report, "{}", SEPARATOR).unwrap();
+        for change in diff.changed.iter().take(max_rows) {
+            writeln!(
+                &mut report,
+                "{:>+9.3} {:>9.3}/{:<9.3} {:>9.3}/{:<9.3}   {}",
+                change.delta(),
+                change.before.0,
+                change.before.1,
+                change.after.0,
+                change.after.1,
+                change.arc
+            )
+            .unwrap();
+        }
+        writeln!(&mut report).unwrap();
+    }
+
+    for (sign, arcs) in [("+", &diff.added), ("-", &diff.removed)] {
+        for (arc, (rise, fall)) in arcs.iter().take(max_rows) {
+            writeln!(&mut report, "{} {:>9.3}/{:<9.3} {}", sign, rise, fall, arc).unwrap();
+        }
+    }
+
+    report
+}
+
+/// Report of the interconnect arcs of the SDF disagreeing with the parasitics, at most `max_rows` of each kind.
+pub fn report_annotation(graph: &SDFGraph, check: &AnnotationCheck, max_rows: usize) -> String {
+    let mut report = String::new();
+    let mismatches: Vec<_> = check.mismatches().collect();
+    writeln!(
+        &mut report,
+        "Corner: {}\n{} interconnect arcs checked, {} mismatches, {} without a net, {} not connected to their net\n",
+        graph.corner,
+        check.checks.len(),
+        mismatches.len(),
+        check.missing_nets.len(),
+        check.disconnected.len()
+    )
+    .unwrap();
+
+    if !mismatches.is_empty() {
+        writeln!(&mut report, "      SDF    Elmore     Delta   Arc").unwrap();
+        writeln!(&mut report, "{}", SEPARATOR).unwrap();
+        for mismatch in mismatches.iter().take(max_rows) {
+            writeln!(
+                &mut report,
+                "{:>9.3} {:>9.3} {:>+9.3}   {} -> {}{}",
+                mismatch.sdf,
+                mismatch.elmore,
+                mismatch.elmore - mismatch.sdf,
+                graph.display_pin_name(mismatch.src.0),
+                graph.display_pin_name(mismatch.dst.0),
+                mismatch.dst.1
+            )
+            .unwrap();
+        }
+        writeln!(&mut report).unwrap();
+    }
+
+    for (what, arcs) in [("no net", &check.missing_nets), ("not connected", &check.disconnected)] {
+        for &(src, dst) in arcs.iter().take(max_rows) {
+            writeln!(
+                &mut report,
+                "{}: {} -> {}",
+                what,
+                graph.display_pin_name(src),
+                graph.display_pin_name(dst)
+            )
+            .unwrap();
+        }
+    }
+
+    report
+}
+
+/// Report of the slack changes between two analyses, the endpoints then at most `max_rows` instances.
+pub fn report_comparison(comparison: &AnalysisComparison, max_rows: usize) -> String {
+    let optional = |value: Option<f32>| value.map_or("-".to_string(), |value| format!("{:.3}", value));
+    let delta = |value: Option<f32>| value.map_or("-".to_string(), |value| format!("{:+.3}", value));
+    let mut report = String::new();
+    writeln!(
+        &mut report,
+        "{} endpoints compared, {} instances changed\n",
+        comparison.endpoints.len(),
+        comparison.instances.len()
+    )
+    .unwrap();
+
+    writeln!(&mut report, "   Before     After     Delta   Endpoint (diverges after)").unwrap();
+    writeln!(&mut report, "{}", SEPARATOR).unwrap();
+    for endpoint in &comparison.endpoints {
+        write!(
+            &mut report,
+            "{:>9} {:>9} {:>9}   {}{}",
+            optional(endpoint.slack.0),
+            optional(endpoint.slack.1),
+            delta(endpoint.slack_delta()),
+            endpoint.endpoint,
+            endpoint.transition
+        )
+        .unwrap();
+        match endpoint.divergence() {
+            Some(pin) => writeln!(&mut report, " ({})", pin),
+            None if endpoint.path_before != endpoint.path_after => writeln!(&mut report, " (startpoint)"),
+            None => writeln!(&mut report),
+        }
+        .unwrap();
+    }
+    writeln!(&mut report).unwrap();
+
+    writeln!(&mut report, "   Before     After     Delta   Instance").unwrap();
+    writeln!(&mut report, "{}", SEPARATOR).unwrap();
+    for instance in comparison.instances.iter().take(max_rows) {
+        write!(
+            &mut report,
+            "{:>9} {:>9} {:>9}   {}",
+            optional(instance.slack.0),
+            optional(instance.slack.1),
+            delta(instance.slack_delta()),
+            instance.instance
+        )
+        .unwrap();
+        match &instance.celltype {
+            (Some(before), Some(after)) if before != after => writeln!(&mut report, " ({} -> {})", before, after),
+            (_, Some(celltype)) | (Some(celltype), None) => writeln!(&mut report, " ({})", celltype),
+            (None, None) => writeln!(&mut report),
+        }
+        .unwrap();
+    }
+
+    report
+}
+
+/// Dynamic power of the design, then the `max_rows` most consuming instances and nets. The worst slack of the
+/// instances tells the power spent on the critical paths.
+pub fn report_power(graph: &SDFGraph, power: &PowerReport, max_rows: usize) -> String {
+    let mut report = String::new();
+    writeln!(
+        &mut report,
+        "Dynamic power {:.3} µW at {:.2} V over {:.3} ns, {} nets with activity",
+        power.total * 1e6,
+        power.vdd,
+        power.duration * 1e9,
+        power.nets.len()
+    )
+    .unwrap();
+    if power.unmatched > 0 {
+        writeln!(
+            &mut report,
+            "{} signals of the activity file not found in the design",
+            power.unmatched
+        )
+        .unwrap();
+    }
+    writeln!(&mut report).unwrap();
+
+    writeln!(&mut report, "  Power (µW)     Slack   Instance").unwrap();
+    writeln!(&mut report, "{}", SEPARATOR).unwrap();
+    for instance in power.instances.iter().take(max_rows) {
+        let slack = instance.slack.map_or("-".to_string(), |slack| format!("{:.3}", slack));
+        let celltype = graph
+            .instance_celltype
+            .get(&instance.instance)
+            .map_or("", String::as_str);
+        writeln!(
+            &mut report,
+            "{:>12.3} {:>9}   {} ({})",
+            instance.power * 1e6,
+            slack,
+            graph.display_instance_name(instance.instance),
+            celltype
+        )
+        .unwrap();
+    }
+    writeln!(&mut report).unwrap();
+
+    writeln!(&mut report, "  Power (µW)  Toggles/ns   Load (pF)   Net driver").unwrap();
+    writeln!(&mut report, "{}", SEPARATOR).unwrap();
+    for net in power.nets.iter().take(max_rows) {
+        writeln!(
+            &mut report,
+            "{:>12.3} {:>11.4} {:>11.4}   {}",
+            net.power * 1e6,
+            net.toggle_rate,
+            net.capacitance,
+            graph.display_pin_name(net.driver)
+        )
+        .unwrap();
+    }
+    report
+}
+
+/// Leakage of the design by cell family, by drive strength, then the `max_rows` most leaking celltypes. The
+/// non-critical part is what swapping the cells with a positive slack for high-Vt ones could save.
+pub fn report_leakage(leakage: &LeakageReport, max_rows: usize) -> String {
+    let mut report = String::new();
+    writeln!(&mut report, "Leakage power {:.3} µW", leakage.total * 1e6).unwrap();
+    if let Some(first) = leakage.unknown.first() {
+        writeln!(
+            &mut report,
+            "{} celltypes without leakage in the library (e.g. {}) are not counted",
+            leakage.unknown.len(),
+            first
+        )
+        .unwrap();
+    }
+
+    let percent = |power: f64| {
+        if leakage.total > 0.0 {
+            power / leakage.total * 100.0
+        } else {
+            0.0
+        }
+    };
+    let mut table = |title: &str, groups: &[LeakageGroup]| {
+        writeln!(&mut report).unwrap();
+        writeln!(
+            &mut report,
+            "Instances  Leakage (nW)  Share  Non-critical (nW)   {}",
+            title
+        )
+        .unwrap();
+        writeln!(&mut report, "{}", SEPARATOR).unwrap();
+        for group in groups.iter().take(max_rows) {
+            writeln!(
+                &mut report,
+                "{:>9} {:>13.3} {:>5.1}% {:>18.3}   {}",
+                group.instances,
+                group.power * 1e9,
+                percent(group.power),
+                group.non_critical * 1e9,
+                group.name
+            )
+            .unwrap();
+        }
+    };
+    table("Cell family", &leakage.families);
+    table("Drive strength", &leakage.drive_strengths);
+    table("Celltype", &leakage.celltypes);
+    report
+}
+
+/// Arrival times of OpenSTA and stars along the paths of a `report_checks`. The pins whose arrival differs by more
+/// than `tolerance` (ns) plus the rounding of the report are marked with a `*`.
+pub fn report_sta_comparison(paths: &[PathDelta], tolerance: f32) -> String {
+    let optional = |value: Option<f32>| value.map_or("-".to_string(), |value| format!("{:.3}", value));
+    let mut report = String::new();
+    let diverging = paths.iter().filter(|path| path.divergence(tolerance).is_some()).count();
+    writeln!(
+        &mut report,
+        "{} of {} paths diverge from OpenSTA by more than {:.3} ns",
+        diverging,
+        paths.len(),
+        tolerance
+    )
+    .unwrap();
+
+    for path in paths {
+        let Some(endpoint) = path.stages.last() else {
+            continue;
+        };
+        writeln!(&mut report).unwrap();
+        writeln!(
+            &mut report,
+            "Endpoint: {}{} ({}), slack OpenSTA {} stars {}",
+            endpoint.pin,
+            endpoint.transition,
+            if path.min { "min" } else { "max" },
+            optional(path.slack.0),
+            optional(path.slack.1)
+        )
+        .unwrap();
+        writeln!(&mut report, "  OpenSTA     stars     Error   Pin").unwrap();
+        writeln!(&mut report, "{}", SEPARATOR).unwrap();
+        for stage in &path.stages {
+            writeln!(
+                &mut report,
+                "{:>9.3} {:>9} {:>9} {} {}{}",
+                stage.sta,
+                optional(stage.stars),
+                stage.error().map_or("-".to_string(), |error| format!("{:+.3}", error)),
+                if path.diverges(stage, tolerance) { '*' } else { ' ' },
+                stage.pin,
+                stage.transition
+            )
+            .unwrap();
+        }
+    }
+    report
+}
+
+/// Endpoints and worst slack of each path group, then the worst path of the groups that have one
+pub fn report_path_groups(graph: &SDFGraph, groups: &[PathGroupSummary], placement: Option<&Placement>) -> String {
+    let mut report = String::new();
+    writeln!(&mut report, "Group     Endpoints  Violated  Worst slack").unwrap();
+    writeln!(&mut report, "{}", SEPARATOR).unwrap();
+    for group in groups {
+        let slack = group
+            .worst
+            .as_ref()
+            .map_or("-".to_string(), |(slack, _)| format!("{:.3}", slack));
+        writeln!(
+            &mut report,
+            "{:<9} {:>9} {:>9} {:>12}",
+            group.group.to_string(),
+            group.endpoints,
+            group.violations,
+            slack
+        )
+        .unwrap();
+    }
+
+    for group in groups {
+        let Some((slack, path)) = &group.worst else {
+            continue;
+        };
+        writeln!(&mut report).unwrap();
+        writeln!(&mut report, "Path group: {}", group.group).unwrap();
+        writeln!(
+            &mut report,
+            "Startpoint: {}",
+            graph.display_pin_name(path.startpoint().0)
+        )
+        .unwrap();
+        writeln!(&mut report, "Endpoint: {}", graph.display_pin_name(path.endpoint.0)).unwrap();
+        writeln!(&mut report).unwrap();
+        writeln!(&mut report, "Fanout     Delay      Time   Description").unwrap();
+        writeln!(&mut report, "{}", SEPARATOR).unwrap();
+        write_path_pins(&mut report, graph, path, 0.0, placement).unwrap();
+        writeln!(
+            &mut report,
+            "{:>26}   data arrival time",
+            format!("{:.3}", path.total_delay)
+        )
+        .unwrap();
+        writeln!(
+            &mut report,
+            "{:>26}   slack ({})",
+            format!("{:.3}", slack),
+            if *slack < 0.0 { "VIOLATED" } else { "MET" }
+        )
+        .unwrap();
+    }
+    report
+}
+
+/// Size of the graph, then at most `max_rows` of the unconnected pins and of the cells with zero-delay arcs.
+pub fn report_stats(graph: &SDFGraph, stats: &GraphStats, max_rows: usize) -> String {
+    let mut report = String::new();
+    let mut line = |what: &str, value: String| writeln!(&mut report, "{:<20} {}", what, value).unwrap();
+    line("Corner", graph.corner.to_string());
+    line(
+        "Instances",
+        format!("{} ({} registers)", stats.instances, stats.registers),
+    );
+    line("Pins", stats.pins.to_string());
+    line("Cell arcs", stats.cell_arcs.to_string());
+    line("Net arcs", stats.net_arcs.to_string());
+    line("Startpoints", stats.startpoints.to_string());
+    line("Endpoints", stats.endpoints.to_string());
+    line("Levels", stats.levels.to_string());
+    line("Nodes in loops", stats.loop_nodes.to_string());
+    line(
+        "Fanout",
+        match stats.max_fanout {
+            Some((pin, fanout)) => format!(
+                "max {} ({}), avg {:.2}",
+                fanout,
+                graph.display_pin_name(pin),
+                stats.avg_fanout
+            ),
+            None => "-".to_string(),
+        },
+    );
+    line("Unconnected pins", stats.unconnected_pins.len().to_string());
+    line("Zero-delay cells", stats.zero_delay_cells.len().to_string());
+    line("Memory", stats.memory.to_string());
+
+    if !stats.unconnected_pins.is_empty() {
+        writeln!(&mut report, "\nUnconnected pins").unwrap();
+        writeln!(&mut report, "{}", SEPARATOR).unwrap();
+        for &pin in stats.unconnected_pins.iter().take(max_rows) {
+            writeln!(&mut report, "{}", graph.display_pin_name(pin)).unwrap();
+        }
+    }
+    if !stats.zero_delay_cells.is_empty() {
+        writeln!(&mut report, "\nCells with zero-delay arcs").unwrap();
+        writeln!(&mut report, "{}", SEPARATOR).unwrap();
+        for &instance in stats.zero_delay_cells.iter().take(max_rows) {
+            let celltype = graph
+                .instance_celltype
+                .get(&instance)
+                .map_or("", |celltype| &**celltype);
+            writeln!(&mut report, "{} ({})", graph.display_instance_name(instance), celltype).unwrap();
+        }
+    }
+    report
+}
@@ -0,0 +1,897 @@
+//! Reader for the subset of SDC (Synopsys Design Constraints) that matters for setup analysis:
+//! clocks, input/output delays, false paths and multicycle paths.
+//!
+//! SDC is Tcl, this implements just enough of it to read the files produced by the usual flows:
+//! `set` variables, `$var`/`$::env(VAR)` substitution, `[...]` commands, `{...}` and `"..."` quoting and `expr`.
+
+use crate::analysis::{AnalysisOptions, PathException};
+use crate::glob_match;
+use crate::graph::SDFGraph;
+use crate::types::{PinId, PinSet, Transition};
+use rustc_hash::FxHashMap;
+use std::fmt::{Display, Formatter};
+
+/// Design objects as returned by `get_ports`, `all_inputs`, etc. Names can contain `*` and `?` wildcards.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SdcObject {
+    Port(String),
+    Pin(String),
+    /// all the pins of the matching instances
+    Cell(String),
+    Clock(String),
+    AllInputs,
+    AllOutputs,
+    /// clock pins of all the registers
+    AllRegisters,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdcClock {
+    pub name: String,
+    /// in ns
+    pub period: f32,
+    /// ports/pins the clock is defined on, empty for virtual clocks
+    pub sources: Vec<SdcObject>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdcIoDelay {
+    /// in ns
+    pub delay: f32,
+    pub clock: Option<String>,
+    pub objects: Vec<SdcObject>,
+}
+
+/// `-from`/`-through`/`-to` of a timing exception, an empty list matches everything
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SdcPathSpec {
+    pub from: Vec<SdcObject>,
+    pub through: Vec<SdcObject>,
+    pub to: Vec<SdcObject>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Sdc {
+    pub clocks: Vec<SdcClock>,
+    pub input_delays: Vec<SdcIoDelay>,
+    pub output_delays: Vec<SdcIoDelay>,
+    pub false_paths: Vec<SdcPathSpec>,
+    /// setup multiplier of the paths
+    pub multicycle_paths: Vec<(SdcPathSpec, u32)>,
+    /// `set_propagated_clock` was used: the clocks go through the clock tree instead of being ideal
+    pub propagated_clock: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SdcError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for SdcError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SDC line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for SdcError {}
+
+/// Value of a Tcl word: plain text or a collection of design objects.
+#[derive(Debug, Clone)]
+enum Value {
+    Text(String),
+    Objects(Vec<SdcObject>),
+}
+
+impl Value {
+    fn into_text(self) -> String {
+        match self {
+            Value::Text(text) => text,
+            Value::Objects(objects) => objects
+                .iter()
+                .map(|object| match object {
+                    SdcObject::Port(name) | SdcObject::Pin(name) | SdcObject::Cell(name) | SdcObject::Clock(name) => {
+                        name.clone()
+                    }
+                    SdcObject::AllInputs => "[all_inputs]".to_string(),
+                    SdcObject::AllOutputs => "[all_outputs]".to_string(),
+                    SdcObject::AllRegisters => "[all_registers]".to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" "),
+        }
+    }
+
+    /// Objects of an argument like `-from`, bare names are taken as pins or ports.
+    fn into_objects(self) -> Vec<SdcObject> {
+        match self {
+            Value::Objects(objects) => objects,
+            Value::Text(text) => text
+                .split_whitespace()
+                .map(|name| SdcObject::Pin(name.to_string()))
+                .collect(),
+        }
+    }
+}
+
+struct Interpreter<'a> {
+    src: &'a [u8],
+    pos: usize,
+    line: usize,
+    vars: FxHashMap<String, Value>,
+    /// divider of the hierarchical names, see `set_hierarchy_separator`
+    separator: char,
+    sdc: Sdc,
+}
+
+impl Sdc {
+    pub fn parse(src: &str) -> Result<Sdc, SdcError> {
+        let mut interp = Interpreter {
+            src: src.as_bytes(),
+            pos: 0,
+            line: 1,
+            vars: FxHashMap::default(),
+            separator: '/',
+            sdc: Sdc::default(),
+        };
+        interp.eval_script(false)?;
+
+        // names of the graph have `/` between the levels of hierarchy
+        let mut sdc = interp.sdc;
+        if interp.separator != '/' {
+            let objects = (sdc.clocks.iter_mut().flat_map(|clock| &mut clock.sources))
+                .chain(sdc.input_delays.iter_mut().flat_map(|delay| &mut delay.objects))
+                .chain(sdc.output_delays.iter_mut().flat_map(|delay| &mut delay.objects))
+                .chain(
+                    (sdc.false_paths.iter_mut())
+                        .chain(sdc.multicycle_paths.iter_mut().map(|(spec, _)| spec))
+                        .flat_map(|spec| spec.from.iter_mut().chain(&mut spec.through).chain(&mut spec.to)),
+                );
+            for object in objects {
+                if let SdcObject::Pin(name) | SdcObject::Cell(name) = object {
+                    *name = name.replace(interp.separator, "/");
+                }
+            }
+        }
+        Ok(sdc)
+    }
+
+    /// Resolve the constraints on the pins of `graph`.
+    /// The first clock is the main clock, registers reached by the other clocks get their own period.
+    ///
+    /// Exceptions using `-through` are not supported and ignored with a warning.
+    pub fn analysis_options(&self, graph: &SDFGraph) -> AnalysisOptions {
+        let mut options = AnalysisOptions {
+            period: self.clocks.first().map(|clock| clock.period),
+            propagated_clock: self.propagated_clock,
+            clock_sources: (self.clocks.iter())
+                .flat_map(|clock| resolve(graph, &clock.sources))
+                .collect(),
+            ..Default::default()
+        };
+
+        for clock in self.clocks.iter().skip(1) {
+            for register_clk in clocked_pins(graph, &resolve(graph, &clock.sources)) {
+                let Some(register) = graph
+                    .symbols
+                    .pin_instance(register_clk)
+                    .and_then(|id| graph.registers.get(&id))
+                else {
+                    continue;
+                };
+                options.endpoint_periods.insert(register.data, clock.period);
+            }
+        }
+
+        let period_of = |clock: &Option<String>| {
+            let clock = clock.as_ref()?;
+            self.clocks.iter().find(|c| &c.name == clock).map(|c| c.period)
+        };
+
+        for io_delay in &self.input_delays {
+            for pin in resolve(graph, &io_delay.objects) {
+                options.input_delays.insert(pin, io_delay.delay);
+            }
+        }
+
+        for io_delay in &self.output_delays {
+            for pin in resolve(graph, &io_delay.objects) {
+                options.output_delays.insert(pin, io_delay.delay);
+                if let Some(period) = period_of(&io_delay.clock) {
+                    if Some(period) != options.period {
+                        options.endpoint_periods.insert(pin, period);
+                    }
+                }
+            }
+        }
+
+        let exceptions = self
+            .false_paths
+            .iter()
+            .map(|spec| (spec, None))
+            .chain(self.multicycle_paths.iter().map(|(spec, mult)| (spec, Some(*mult))));
+        for (spec, multicycle) in exceptions {
+            if !spec.through.is_empty() {
+                log::warn!("SDC: -through is not supported, ignoring exception {:?}", spec);
+                continue;
+            }
+            let (Some(from), Some(to)) = (
+                self.exception_pins(graph, &spec.from, true),
+                self.exception_pins(graph, &spec.to, false),
+            ) else {
+                log::warn!("SDC: exception {:?} matches no pins, ignoring it", spec);
+                continue;
+            };
+            options.exceptions.push(PathException { from, to, multicycle });
+        }
+
+        options
+    }
+
+    /// Names of the startpoint (or endpoint) pins of an exception, None if the objects match nothing.
+    /// Registers and clocks are turned into the outputs (or D pins) of the registers.
+    fn exception_pins(&self, graph: &SDFGraph, objects: &[SdcObject], startpoints: bool) -> Option<Vec<String>> {
+        if objects.is_empty() {
+            return Some(Vec::new());
+        }
+        let symbols = &graph.symbols;
+
+        let mut clk_pins = PinSet::new();
+        let mut pins = PinSet::new();
+        for pin in resolve(graph, objects) {
+            if graph.is_register_clock(pin) {
+                clk_pins.insert(pin);
+            } else {
+                pins.insert(pin);
+            }
+        }
+        for object in objects {
+            if let SdcObject::Clock(pattern) = object {
+                for clock in self.clocks.iter().filter(|clock| glob_match(pattern, &clock.name)) {
+                    clk_pins.extend(clocked_pins(graph, &resolve(graph, &clock.sources)));
+                }
+            }
+        }
+
+        for clk in clk_pins {
+            let Some(instance) = symbols.pin_instance(clk) else {
+                continue;
+            };
+            if startpoints {
+                pins.extend(graph.instance_outs.get(&instance).into_iter().flatten().copied());
+            } else {
+                pins.extend(graph.registers.get(&instance).map(|register| register.data));
+            }
+        }
+
+        if pins.is_empty() {
+            return None;
+        }
+        Some(pins.into_iter().map(|pin| symbols.pin_name(pin).to_string()).collect())
+    }
+}
+
+/// Pins of the graph matching the objects.
+pub fn resolve(graph: &SDFGraph, objects: &[SdcObject]) -> PinSet {
+    let symbols = &graph.symbols;
+    let is_port = |pin: PinId| symbols.pin_instance(pin).is_none();
+    let mut pins = PinSet::new();
+
+    for object in objects {
+        match object {
+            SdcObject::Port(pattern) => pins.extend(
+                symbols
+                    .pins()
+                    .iter()
+                    .filter(|&(pin, name)| is_port(pin) && glob_match(pattern, name))
+                    .map(|(pin, _)| pin),
+            ),
+            SdcObject::Pin(pattern) => pins.extend(
+                symbols
+                    .pins()
+                    .iter()
+                    .filter(|(_, name)| glob_match(pattern, name))
+                    .map(|(pin, _)| pin),
+            ),
+            SdcObject::Cell(pattern) => pins.extend(symbols.pins().iter().map(|(pin, _)| pin).filter(|&pin| {
+                symbols
+                    .pin_instance(pin)
+                    .is_some_and(|instance| glob_match(pattern, symbols.instance_name(instance)))
+            })),
+            SdcObject::AllInputs => pins.extend(graph.inputs.iter().map(|pin| pin.0).filter(|&pin| is_port(pin))),
+            SdcObject::AllOutputs => pins.extend(graph.outputs.iter().map(|pin| pin.0).filter(|&pin| is_port(pin))),
+            SdcObject::AllRegisters => {
+                pins.extend(graph.registers.values().map(|register| register.clock.0));
+            }
+            SdcObject::Clock(_) => {}
+        }
+    }
+
+    pins
+}
+
+/// Register clock pins reached from the clock sources through the clock tree.
+fn clocked_pins(graph: &SDFGraph, sources: &PinSet) -> PinSet {
+    let mut visited = PinSet::new();
+    let mut stack: Vec<PinId> = sources.iter().copied().collect();
+    let mut clocked = PinSet::new();
+
+    while let Some(pin) = stack.pop() {
+        if !visited.insert(pin) {
+            continue;
+        }
+        if graph.is_register_clock(pin) {
+            clocked.insert(pin);
+            continue;
+        }
+        for edge in graph.graph.get(&(pin, Transition::Rise)).into_iter().flatten() {
+            stack.push(edge.dst.0);
+        }
+    }
+
+    clocked
+}
+
+impl Interpreter<'_> {
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, SdcError> {
+        Err(SdcError {
+            line: self.line,
+            message: message.into(),
+        })
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<u8> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == b'\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    /// Run commands until the end of the file, or the closing `]` when `nested`. Returns the value of the last one.
+    fn eval_script(&mut self, nested: bool) -> Result<Value, SdcError> {
+        let mut last = Value::Text(String::new());
+        loop {
+            // skip blank space between commands, and comments
+            while let Some(c) = self.peek() {
+                match c {
+                    b' ' | b'\t' | b'\r' | b'\n' | b';' => {
+                        self.bump();
+                    }
+                    b'\\' if self.src.get(self.pos + 1) == Some(&b'\n') => {
+                        self.bump();
+                        self.bump();
+                    }
+                    b'#' => {
+                        while !matches!(self.peek(), None | Some(b'\n')) {
+                            self.bump();
+                        }
+                    }
+                    _ => break,
+                }
+            }
+
+            match self.peek() {
+                None if nested => return self.error("missing `]`"),
+                None => return Ok(last),
+                Some(b']') if nested => {
+                    self.bump();
+                    return Ok(last);
+                }
+                _ => {}
+            }
+
+            let line = self.line;
+            let mut words = Vec::new();
+            loop {
+                while matches!(self.peek(), Some(b' ' | b'\t' | b'\r')) {
+                    self.bump();
+                }
+                if self.peek() == Some(b'\\') && self.src.get(self.pos + 1) == Some(&b'\n') {
+                    self.bump();
+                    self.bump();
+                    continue;
+                }
+                match self.peek() {
+                    None | Some(b'\n' | b';') => break,
+                    Some(b']') if nested => break,
+                    _ => words.push(self.parse_word(nested)?),
+                }
+            }
+
+            let command_line = std::mem::replace(&mut self.line, line);
+            last = self.run(words)?;
+            self.line = command_line;
+        }
+    }
+
+    fn parse_word(&mut self, nested: bool) -> Result<Value, SdcError> {
+        match self.peek() {
+            Some(b'{') => {
+                self.bump();
+                let start = self.pos;
+                let mut depth = 1;
+                while depth > 0 {
+                    match self.bump() {
+                        None => return self.error("missing `}`"),
+                        Some(b'{') => depth += 1,
+                        Some(b'}') => depth -= 1,
+                        Some(b'\\') => {
+                            self.bump();
+                        }
+                        _ => {}
+                    }
+                }
+                Ok(Value::Text(
+                    String::from_utf8_lossy(&self.src[start..self.pos - 1]).into_owned(),
+                ))
+            }
+            Some(b'"') => {
+                self.bump();
+                let mut text = String::new();
+                loop {
+                    match self.peek() {
+                        None => return self.error("missing `\"`"),
+                        Some(b'"') => {
+                            self.bump();
+                            return Ok(Value::Text(text));
+                        }
+                        _ => self.parse_substitution(&mut text)?,
+                    }
+                }
+            }
+            _ => {
+                let mut parts = Vec::new();
+                let mut text = String::new();
+                loop {
+                    match self.peek() {
+                        None | Some(b' ' | b'\t' | b'\r' | b'\n' | b';') => break,
+                        Some(b']') if nested => break,
+                        Some(b'[') => {
+                            self.bump();
+                            if !text.is_empty() {
+                                parts.push(Value::Text(std::mem::take(&mut text)));
+                            }
+                            parts.push(self.eval_script(true)?);
+                        }
+                        _ => self.parse_substitution(&mut text)?,
+                    }
+                }
+                if !text.is_empty() {
+                    parts.push(Value::Text(text));
+                }
+                // keep objects intact when the word is only a command substitution, e.g [get_ports clk]
+                if parts.len() == 1 {
+                    return Ok(parts.pop().unwrap());
+                }
+                Ok(Value::Text(parts.into_iter().map(Value::into_text).collect()))
+            }
+        }
+    }
+
+    /// Append the next character to `text`, substituting `$var`, `[cmd]` and backslash escapes.
+    fn parse_substitution(&mut self, text: &mut String) -> Result<(), SdcError> {
+        match self.bump() {
+            Some(b'\\') => match self.bump() {
+                Some(b'n') => text.push('\n'),
+                Some(b't') => text.push('\t'),
+                Some(c) => text.push(c as char),
+                None => {}
+            },
+            Some(b'$') => {
+                let value = self.parse_variable()?;
+                text.push_str(&value.into_text());
+            }
+            Some(b'[') => {
+                let value = self.eval_script(true)?;
+                text.push_str(&value.into_text());
+            }
+            Some(c) if c.is_ascii() => text.push(c as char),
+            Some(c) => {
+                // multi-byte utf-8 character, copy it whole
+                let start = self.pos - 1;
+                let len = match c {
+                    0xF0.. => 4,
+                    0xE0.. => 3,
+                    _ => 2,
+                };
+                let end = (start + len).min(self.src.len());
+                text.push_str(&String::from_utf8_lossy(&self.src[start..end]));
+                self.pos = end;
+            }
+            None => {}
+        }
+        Ok(())
+    }
+
+    /// After the `$`: `name`, `{name}`, `::env(NAME)` or `name(index)`
+    fn parse_variable(&mut self) -> Result<Value, SdcError> {
+        let mut name = String::new();
+        if self.peek() == Some(b'{') {
+            self.bump();
+            while let Some(c) = self.bump() {
+                if c == b'}' {
+                    break;
+                }
+                name.push(c as char);
+            }
+        } else {
+            while let Some(c) = self.peek() {
+                if c.is_ascii_alphanumeric() || c == b'_' || c == b':' {
+                    name.push(c as char);
+                    self.bump();
+                } else {
+                    break;
+                }
+            }
+            if self.peek() == Some(b'(') {
+                self.bump();
+                name.push('(');
+                loop {
+                    match self.peek() {
+                        None => return self.error("missing `)`"),
+                        Some(b')') => {
+                            self.bump();
+                            break;
+                        }
+                        _ => self.parse_substitution(&mut name)?,
+                    }
+                }
+                name.push(')');
+            }
+        }
+
+        let name = name.trim_start_matches("::");
+        if let Some(value) = self.vars.get(name) {
+            return Ok(value.clone());
+        }
+        if let Some(env_var) = name.strip_prefix("env(").and_then(|v| v.strip_suffix(')')) {
+            if let Ok(value) = std::env::var(env_var) {
+                return Ok(Value::Text(value));
+            }
+        }
+        self.error(format!("can't read \"{}\": no such variable", name))
+    }
+
+    fn run(&mut self, words: Vec<Value>) -> Result<Value, SdcError> {
+        let mut words = words.into_iter();
+        let Some(command) = words.next() else {
+            return Ok(Value::Text(String::new()));
+        };
+        let command = command.into_text();
+        let args = Args::new(words);
+
+        match &*command {
+            "set" => {
+                let mut positional = args.positional.into_iter();
+                let (Some(name), Some(value)) = (positional.next(), positional.next()) else {
+                    return self.error("set expects a name and a value");
+                };
+                let name = name.into_text().trim_start_matches("::").to_string();
+                self.vars.insert(name, value.clone());
+                Ok(value)
+            }
+            "expr" => {
+                let expr: Vec<String> = args.into_words().into_iter().map(Value::into_text).collect();
+                match eval_expr(&expr.join(" ")) {
+                    Some(v) => Ok(Value::Text(v.to_string())),
+                    None => self.error(format!("can't evaluate expression \"{}\"", expr.join(" "))),
+                }
+            }
+            "get_ports" | "get_port" => Ok(args.objects(SdcObject::Port)),
+            "get_pins" | "get_pin" => Ok(args.objects(SdcObject::Pin)),
+            "get_cells" | "get_cell" => Ok(args.objects(SdcObject::Cell)),
+            "get_clocks" | "get_clock" => Ok(args.objects(SdcObject::Clock)),
+            "get_nets" | "get_net" => Ok(args.objects(SdcObject::Pin)),
+            "all_inputs" => Ok(Value::Objects(vec![SdcObject::AllInputs])),
+            "all_outputs" => Ok(Value::Objects(vec![SdcObject::AllOutputs])),
+            "all_registers" => Ok(Value::Objects(vec![SdcObject::AllRegisters])),
+            "create_clock" => {
+                let period = self.number(args.value("-period"), "create_clock -period")?;
+                let sources: Vec<SdcObject> = args.positional.iter().cloned().flat_map(Value::into_objects).collect();
+                let name = match args.value("-name") {
+                    Some(name) => name.clone().into_text(),
+                    None => match sources.first() {
+                        Some(SdcObject::Port(name) | SdcObject::Pin(name)) => name.clone(),
+                        _ => return self.error("create_clock needs -name or a source"),
+                    },
+                };
+                self.sdc.clocks.push(SdcClock { name, period, sources });
+                Ok(Value::Text(String::new()))
+            }
+            "set_input_delay" | "set_output_delay" => {
+                if args.has_flag("-min") && !args.has_flag("-max") {
+                    return Ok(Value::Text(String::new()));
+                }
+                let mut positional = args.positional.iter().cloned();
+                let delay = self.number(positional.next().as_ref(), &command)?;
+                let io_delay = SdcIoDelay {
+                    delay,
+                    clock: args.value("-clock").map(|clock| clock.clone().into_text()),
+                    objects: positional.flat_map(Value::into_objects).collect(),
+                };
+                if command == "set_input_delay" {
+                    self.sdc.input_delays.push(io_delay);
+                } else {
+                    self.sdc.output_delays.push(io_delay);
+                }
+                Ok(Value::Text(String::new()))
+            }
+            "set_false_path" => {
+                if !args.has_flag("-hold") || args.has_flag("-setup") {
+                    self.sdc.false_paths.push(args.path_spec());
+                }
+                Ok(Value::Text(String::new()))
+            }
+            "set_multicycle_path" => {
+                if !args.has_flag("-hold") || args.has_flag("-setup") {
+                    let multiplier = self.number(args.positional.first(), "set_multicycle_path")?;
+                    self.sdc.multicycle_paths.push((args.path_spec(), multiplier as u32));
+                }
+                Ok(Value::Text(String::new()))
+            }
+            "set_hierarchy_separator" => {
+                match args.positional.first().map(|v| v.clone().into_text()) {
+                    Some(separator) if separator.chars().count() == 1 => {
+                        self.separator = separator.chars().next().unwrap();
+                    }
+                    _ => return self.error("set_hierarchy_separator expects a character"),
+                }
+                Ok(Value::Text(String::new()))
+            }
+            "set_propagated_clock" => {
+                self.sdc.propagated_clock = true;
+                Ok(Value::Text(String::new()))
+            }
+            _ => {
+                log::warn!("SDC line {}: ignoring unsupported command `{}`", self.line, command);
+                Ok(Value::Text(String::new()))
+            }
+        }
+    }
+
+    fn number(&self, value: Option<&Value>, what: &str) -> Result<f32, SdcError> {
+        let Some(value) = value else {
+            return self.error(format!("{} expects a number", what));
+        };
+        let text = value.clone().into_text();
+        match text.trim().parse() {
+            Ok(v) => Ok(v),
+            Err(_) => self.error(format!("{} expects a number, got \"{}\"", what, text)),
+        }
+    }
+}
+
+/// Arguments of a command, split into `-option value` pairs, `-flag`s and positional values.
+struct Args {
+    options: Vec<(String, Option<Value>)>,
+    positional: Vec<Value>,
+}
+
+/// Options followed by a value, other `-words` are flags.
+const VALUE_OPTIONS: &[&str] = &[
+    "-name",
+    "-period",
+    "-waveform",
+    "-clock",
+    "-from",
+    "-to",
+    "-through",
+    "-rise_from",
+    "-fall_from",
+    "-rise_to",
+    "-fall_to",
+    "-reference_pin",
+    "-of_objects",
+    "-filter",
+    "-comment",
+];
+
+impl Args {
+    fn new(words: impl Iterator<Item = Value>) -> Self {
+        let mut options = Vec::new();
+        let mut positional = Vec::new();
+        let mut words = words.peekable();
+
+        while let Some(word) = words.next() {
+            let option = match &word {
+                Value::Text(text) if text.starts_with('-') && text.parse::<f32>().is_err() => text.clone(),
+                _ => {
+                    positional.push(word);
+                    continue;
+                }
+            };
+            let value = VALUE_OPTIONS.contains(&&*option).then(|| words.next()).flatten();
+            options.push((option, value));
+        }
+
+        Args { options, positional }
+    }
+
+    fn value(&self, option: &str) -> Option<&Value> {
+        self.options
+            .iter()
+            .find(|(o, _)| o == option)
+            .and_then(|(_, v)| v.as_ref())
+    }
+
+    fn has_flag(&self, flag: &str) -> bool {
+        self.options.iter().any(|(o, _)| o == flag)
+    }
+
+    fn all_objects(&self, options: &[&str]) -> Vec<SdcObject> {
+        self.options
+            .iter()
+            .filter(|(o, _)| options.contains(&&**o))
+            .filter_map(|(_, v)| v.clone())
+            .flat_map(Value::into_objects)
+            .collect()
+    }
+
+    fn path_spec(&self) -> SdcPathSpec {
+        SdcPathSpec {
+            from: self.all_objects(&["-from", "-rise_from", "-fall_from"]),
+            through: self.all_objects(&["-through"]),
+            to: self.all_objects(&["-to", "-rise_to", "-fall_to"]),
+        }
+    }
+
+    /// `get_ports {a b}` and friends: each positional word is a list of patterns
+    fn objects(self, kind: fn(String) -> SdcObject) -> Value {
+        Value::Objects(
+            self.positional
+                .into_iter()
+                .flat_map(|v| match v {
+                    Value::Objects(objects) => objects,
+                    Value::Text(text) => text.split_whitespace().map(|name| kind(name.to_string())).collect(),
+                })
+                .collect(),
+        )
+    }
+
+    fn into_words(self) -> Vec<Value> {
+        let mut words = Vec::new();
+        for (option, value) in self.options {
+            words.push(Value::Text(option));
+            words.extend(value);
+        }
+        words.extend(self.positional);
+        words
+    }
+}
+
+/// Evaluate the arithmetic of an `expr`: numbers, `+ - * /` and parentheses.
+fn eval_expr(expr: &str) -> Option<f64> {
+    fn tokens(expr: &str) -> Option<Vec<String>> {
+        let mut tokens = Vec::new();
+        let mut chars = expr.chars().peekable();
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+            } else if "+-*/()".contains(c) {
+                tokens.push(c.to_string());
+                chars.next();
+            } else if c.is_ascii_digit() || c == '.' {
+                let mut number = String::new();
+                while let Some(&c) = chars.peek() {
+                    let exponent_sign = (c == '-' || c == '+') && number.ends_with(['e', 'E']);
+                    if c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || exponent_sign {
+                        number.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(number);
+            } else {
+                return None;
+            }
+        }
+        Some(tokens)
+    }
+
+    fn sum(tokens: &[String], pos: &mut usize) -> Option<f64> {
+        let mut value = product(tokens, pos)?;
+        while let Some(op) = tokens.get(*pos).filter(|t| *t == "+" || *t == "-") {
+            *pos += 1;
+            let rhs = product(tokens, pos)?;
+            value = if op == "+" { value + rhs } else { value - rhs };
+        }
+        Some(value)
+    }
+
+    fn product(tokens: &[String], pos: &mut usize) -> Option<f64> {
+        let mut value = unary(tokens, pos)?;
+        while let Some(op) = tokens.get(*pos).filter(|t| *t == "*" || *t == "/") {
+            *pos += 1;
+            let rhs = unary(tokens, pos)?;
+            value = if op == "*" { value * rhs } else { value / rhs };
+        }
+        Some(value)
+    }
+
+    fn unary(tokens: &[String], pos: &mut usize) -> Option<f64> {
+        let token = tokens.get(*pos)?;
+        *pos += 1;
+        match &**token {
+            "-" => Some(-unary(tokens, pos)?),
+            "+" => unary(tokens, pos),
+            "(" => {
+                let value = sum(tokens, pos)?;
+                (tokens.get(*pos)? == ")").then_some(())?;
+                *pos += 1;
+                Some(value)
+            }
+            number => number.parse().ok(),
+        }
+    }
+
+    let tokens = tokens(expr)?;
+    let mut pos = 0;
+    let value = sum(&tokens, &mut pos)?;
+    (pos == tokens.len()).then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sdc() {
+        std::env::set_var("STARS_TEST_CLOCK_PERIOD", "10");
+        let sdc = Sdc::parse(
+            r#"
+# comment
+set clk_port clk
+create_clock [get_ports $clk_port] -name core_clock -period $::env(STARS_TEST_CLOCK_PERIOD)
+set input_delay_value [expr $::env(STARS_TEST_CLOCK_PERIOD) * 0.2]
+set_input_delay $input_delay_value -clock [get_clocks core_clock] \
+    [get_ports {a b[0]}]
+set_output_delay 1.5 -clock core_clock [all_outputs]
+set_input_delay -min 0.1 -clock core_clock [all_inputs]
+set_false_path -from [get_cells sync_*] -to [get_pins out_reg/D]
+set_multicycle_path 2 -setup -to [get_pins slow_*/D]
+set_multicycle_path 1 -hold -to [get_pins slow_*/D]
+set_load 0.05 [all_outputs]
+set_propagated_clock [get_clocks core_clock]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            sdc.clocks,
+            vec![SdcClock {
+                name: "core_clock".to_string(),
+                period: 10.0,
+                sources: vec![SdcObject::Port("clk".to_string())],
+            }]
+        );
+        assert_eq!(
+            sdc.input_delays,
+            vec![SdcIoDelay {
+                delay: 2.0,
+                clock: Some("core_clock".to_string()),
+                objects: vec![SdcObject::Port("a".to_string()), SdcObject::Port("b[0]".to_string())],
+            }]
+        );
+        assert_eq!(sdc.output_delays[0].objects, vec![SdcObject::AllOutputs]);
+        assert_eq!(
+            sdc.false_paths,
+            vec![SdcPathSpec {
+                from: vec![SdcObject::Cell("sync_*".to_string())],
+                through: vec![],
+                to: vec![SdcObject::Pin("out_reg/D".to_string())],
+            }]
+        );
+        assert_eq!(sdc.multicycle_paths.len(), 1);
+        assert_eq!(sdc.multicycle_paths[0].1, 2);
+        assert!(sdc.propagated_clock);
+
+        assert_eq!(Sdc::parse("create_clock -period").unwrap_err().line, 1);
+    }
+}
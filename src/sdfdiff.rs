@@ -0,0 +1,160 @@
+//! Differences between the delays of two SDF files, e.g. before and after an ECO.
+
+use crate::error::Result;
+use crate::graph::{cell_path, edge_transition, parse_delays, unique_name, unique_name_port};
+use crate::types::{Corner, Transition};
+use ordered_float::OrderedFloat;
+use rustc_hash::FxHashMap;
+use sdfparse::{SDFBus, SDFDelay, SDFIOPathCond, SDFPath, SDF};
+use std::cmp::Reverse;
+use std::fmt::{Display, Formatter};
+
+/// Arc of a SDF, an INTERCONNECT between two pins or an IOPATH of an instance
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SdfArc {
+    pub from: String,
+    pub to: String,
+    /// edge of `from` launching an IOPATH, e.g `(posedge CLK)`
+    pub edge: Option<Transition>,
+    /// condition of an IOPATH, `CONDELSE` for the default one
+    pub cond: Option<String>,
+}
+
+impl Display for SdfArc {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.from)?;
+        if let Some(edge) = self.edge {
+            write!(f, "{}", edge)?;
+        }
+        write!(f, " -> {}", self.to)?;
+        if let Some(cond) = &self.cond {
+            write!(f, " if {}", cond)?;
+        }
+        Ok(())
+    }
+}
+
+/// Arc whose delay changed by more than the tolerance
+#[derive(Debug, Clone)]
+pub struct DelayChange {
+    pub arc: SdfArc,
+    /// (rise, fall) delays of the first SDF
+    pub before: (f32, f32),
+    /// (rise, fall) delays of the second SDF
+    pub after: (f32, f32),
+}
+
+impl DelayChange {
+    /// Largest change of the rise and fall delays, positive when the arc got slower
+    pub fn delta(&self) -> f32 {
+        let rise = self.after.0 - self.before.0;
+        let fall = self.after.1 - self.before.1;
+        if rise.abs() >= fall.abs() {
+            rise
+        } else {
+            fall
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SdfDiff {
+    pub corner: Corner,
+    /// arcs only in the second SDF, with their (rise, fall) delays
+    pub added: Vec<(SdfArc, (f32, f32))>,
+    /// arcs only in the first SDF, with their (rise, fall) delays
+    pub removed: Vec<(SdfArc, (f32, f32))>,
+    /// arcs of both SDFs with different delays, the largest change first
+    pub changed: Vec<DelayChange>,
+}
+
+/// Compare the delays of the arcs of `a` and `b` at the corner, arcs being matched by their pins.
+/// Delay changes up to `tolerance` (in the unit of the SDF) are ignored.
+/// PORT, NETDELAY and DEVICE entries aren't compared.
+pub fn sdf_diff(a: &SDF, b: &SDF, corner: Corner, tolerance: f32) -> Result<SdfDiff> {
+    let before = sdf_arcs(a, corner)?;
+    let after = sdf_arcs(b, corner)?;
+
+    let mut diff = SdfDiff {
+        corner,
+        ..Default::default()
+    };
+    for (arc, &delays) in &after {
+        match before.get(arc) {
+            None => diff.added.push((arc.clone(), delays)),
+            Some(&old) if (delays.0 - old.0).abs() > tolerance || (delays.1 - old.1).abs() > tolerance => {
+                diff.changed.push(DelayChange {
+                    arc: arc.clone(),
+                    before: old,
+                    after: delays,
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    for (arc, &delays) in &before {
+        if !after.contains_key(arc) {
+            diff.removed.push((arc.clone(), delays));
+        }
+    }
+
+    diff.added.sort_by(|x, y| x.0.cmp(&y.0));
+    diff.removed.sort_by(|x, y| x.0.cmp(&y.0));
+    diff.changed
+        .sort_by_key(|change| (Reverse(OrderedFloat(change.delta().abs())), change.arc.clone()));
+    Ok(diff)
+}
+
+/// (rise, fall) delays of the arcs of the SDF, INCREMENT entries being added to the ABSOLUTE ones.
+/// The worst delay is kept for the arcs given several times.
+fn sdf_arcs(sdf: &SDF, corner: Corner) -> Result<FxHashMap<SdfArc, (f32, f32)>> {
+    let mut arcs: FxHashMap<SdfArc, (f32, f32)> = FxHashMap::default();
+    let no_instance = SDFPath {
+        path: vec![],
+        bus: SDFBus::None,
+    };
+
+    for cell in &sdf.cells {
+        let cell_name = unique_name(cell.instance.as_ref().unwrap_or(&no_instance))?;
+        let delays =
+            (cell.delays.iter().map(|delay| (delay, false))).chain(cell.increments.iter().map(|delay| (delay, true)));
+
+        for (delay, increment) in delays {
+            let (arc, (rise, fall)) = match delay {
+                SDFDelay::Interconnect(inter) => {
+                    let arc = SdfArc {
+                        from: cell_path(&cell_name, &inter.a)?,
+                        to: cell_path(&cell_name, &inter.b)?,
+                        edge: None,
+                        cond: None,
+                    };
+                    (arc, parse_delays(&inter.delay, corner)?)
+                }
+                SDFDelay::IOPath(cond, io) => {
+                    let arc = SdfArc {
+                        from: unique_name_port(&cell_name, &io.a.port)?,
+                        to: unique_name_port(&cell_name, &io.b)?,
+                        edge: edge_transition(&io.a.edge_type),
+                        cond: match cond {
+                            SDFIOPathCond::None => None,
+                            SDFIOPathCond::Cond(_, expr) => Some(expr.to_string()),
+                            SDFIOPathCond::CondElse => Some("CONDELSE".to_string()),
+                        },
+                    };
+                    (arc, parse_delays(&io.delay, corner)?)
+                }
+                _ => continue,
+            };
+
+            match arcs.get_mut(&arc) {
+                Some(old) if increment => *old = (old.0 + rise, old.1 + fall),
+                Some(old) => *old = (old.0.max(rise), old.1.max(fall)),
+                None => {
+                    arcs.insert(arc, (rise, fall));
+                }
+            }
+        }
+    }
+
+    Ok(arcs)
+}
@@ -1,13 +1,16 @@
-use crate::analysis::SDFGraphAnalyzed;
+use crate::analysis::{PathReport, SDFGraphAnalyzed};
+use crate::error::{Result, StarsError};
 use crate::graph::SDFGraph;
 use crate::parasitics::Parasitics;
+use crate::pdk::PdkConfig;
 use crate::subckt::SubcktData;
-use crate::types::{BiUnate, PinTrans, SDFCellType, SDFInstance, SDFPin, Transition};
-use crate::{instance_name, pin_name, pin_name_ref};
-use ordered_float::OrderedFloat;
+use crate::types::{BiUnate, InstanceMap, PinId, PinSet, PinTrans, SDFCellType, SDFInstance, SDFPin, Transition};
+use crate::{instance_name, pin_name_ref};
 use rustc_hash::{FxHashMap, FxHashSet};
 use std::borrow::Cow;
+use std::f32::consts::LN_2;
 use std::fmt::Write;
+use std::str::FromStr;
 
 static PIN_CAPA_JSON: &str = include_str!("pin_capa.json");
 
@@ -21,151 +24,241 @@ impl PinCapas {
             data: miniserde::json::from_str(PIN_CAPA_JSON).unwrap(),
         }
     }
-}
-
-static CELL_TRANSITION_COMBINATIONS_JSON: &str = include_str!("cells_transition_combinations.json");
-
-// .lib says 5614.3 (calculated from inv1 by calculating delta time over delta capacitance)
-// spice sim says 6572.7
-/// Equivalent resistance for a    1um/0.15um PFET (in Ohms). We premultiply by W / L so we can get actual resistance with R x L / W
-pub const EQ_RESISTANCE_PFET_HVT: f32 = 6591.7 * 1.0 / 0.15 / std::f32::consts::LN_2;
-
-// .lib says 3326.1 (calculated from inv1 by calculating delta time over delta capacitance)
-// spice sim says 2841.4
-/// Equivalent resistance for a 0.65um/0.15um NFET (in Ohms). We premultiply by W / L so we can get actual resistance with R x L / W
-pub const EQ_RESISTANCE_NFET: f32 = 2832.4 * 0.65 / 0.15 / std::f32::consts::LN_2;
 
-/// Equivalent capacitance for pfet hvt (in picoFarads / um²)
-pub const CAPA_PER_AREA_PFET_HVT: f32 = 0.00990114;
-
-/// Equivalent capacitance for nfet (in picoFarads / um²)
-pub const CAPA_PER_AREA_NFET: f32 = 0.01010523;
-
-#[derive(Debug, miniserde::Deserialize)]
-struct CellTransitionCombination {
-    pins: FxHashMap<SDFPin, bool>,
-    unate: BiUnate,
+    /// Capacitance in pF of an input pin, from the SUBCKT when the cell isn't characterized
+    fn capacitance(&self, subckt: &SubcktData, celltype: &str, pin: &str) -> Option<f32> {
+        self.data
+            .get(&format!("{}/{}", celltype, pin))
+            .copied()
+            .or_else(|| subckt.pin_capacitance(celltype, pin))
+    }
 }
 
-#[derive(Debug)]
-pub struct CellTransitionData {
-    data: FxHashMap<SDFCellType, FxHashMap<SDFPin, Vec<CellTransitionCombination>>>,
+/// How the SPICE decks are generated (see [`extract_spice_for_manual_analysis`])
+#[derive(Debug, Clone)]
+pub struct SpiceOptions {
+    pub dialect: SpiceDialect,
+    /// supply voltage in V
+    pub vdd: f32,
+    /// temperature in °C, left to the simulator's default when None
+    pub temp: Option<f32>,
+    /// section of the library at `include_path` to load (e.g `ss` or `ff` for the sky130 corners),
+    /// `include_path` is included as a whole when None
+    pub corner_lib: Option<String>,
+    /// models and cells included by the deck
+    pub include_path: String,
+    /// number of RC segments of the wires estimated without parasitics, each one ending with its share of the
+    /// capacitance. The wires of the SPEF are written with their whole RC network
+    pub wire_segments: usize,
+    /// launch the path with a clock edge on the first flip-flop after a cycle loading the opposite value,
+    /// instead of forcing its output with an initial condition
+    pub clocked_launch: bool,
+    /// write the widths (µm) of the transistors driving the side inputs as `.param wp_<pin>` and `wn_<pin>`,
+    /// which set their multiplier, so that they can be swept
+    pub param_widths: bool,
+    /// number of Monte Carlo runs, each one with new process and mismatch variations (the `mc` section of the
+    /// sky130 library when `corner_lib` is None), reporting the delays of every run
+    pub monte_carlo: Option<usize>,
 }
 
-impl CellTransitionData {
-    pub fn new() -> Self {
+impl Default for SpiceOptions {
+    fn default() -> Self {
         Self {
-            data: miniserde::json::from_str(CELL_TRANSITION_COMBINATIONS_JSON).unwrap(),
+            dialect: SpiceDialect::default(),
+            vdd: 1.8,
+            temp: None,
+            corner_lib: None,
+            include_path: "./prelude.spice".to_string(),
+            wire_segments: 1,
+            clocked_launch: false,
+            param_widths: false,
+            monte_carlo: None,
         }
     }
 }
 
-fn area(w: f32) -> f32 {
-    0.15 * w
+/// Simulator the generated decks are written for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SpiceDialect {
+    #[default]
+    Ngspice,
+    Xyce,
+    Hspice,
 }
 
-fn perim(w: f32) -> f32 {
-    w + 2.0 * 0.15
+impl FromStr for SpiceDialect {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "ngspice" => Ok(SpiceDialect::Ngspice),
+            "xyce" => Ok(SpiceDialect::Xyce),
+            "hspice" => Ok(SpiceDialect::Hspice),
+            _ => Err(format!(
+                "Unknown SPICE dialect {} (expected ngspice, xyce or hspice)",
+                s
+            )),
+        }
+    }
 }
 
-pub fn pfet_size(w: f32) -> (f32, f32) {
-    static BINS_PFET: &[f32] = &[
-        0.36, 0.42, 0.54, 0.55, 0.63, 0.64, 0.70, 0.75, 0.79, 0.82, 0.84, 0.86, 0.94, 1.00, 1.12, 1.26, 1.65, 1.68,
-        2.00, 3.00, 5.00, 7.00,
-    ];
-    let pos = BINS_PFET
-        .binary_search_by(|val| OrderedFloat(*val).cmp(&OrderedFloat(w)))
-        .unwrap_or_else(|x| x);
-    let closest_bin = BINS_PFET[usize::min(pos, BINS_PFET.len() - 1)];
-    let mult = w / closest_bin;
-    (closest_bin, mult)
-}
+impl SpiceDialect {
+    /// The sky130 transistors are subcircuits in the ngspice and Xyce models, MOSFET models in the HSPICE ones
+    fn device_prefix(self) -> char {
+        match self {
+            SpiceDialect::Hspice => 'M',
+            SpiceDialect::Ngspice | SpiceDialect::Xyce => 'X',
+        }
+    }
 
-fn pfet(name: &str, d: &str, g: &str, s: &str, w: f32) -> String {
-    let (closest_bin, mult) = pfet_size(w);
-    let ar = area(closest_bin) / mult;
-    let pe = perim(closest_bin) / mult;
+    /// Value of a `.param` in an instance or initial condition
+    fn param(self, name: &str) -> String {
+        match self {
+            SpiceDialect::Hspice => format!("'{}'", name),
+            SpiceDialect::Ngspice | SpiceDialect::Xyce => format!("{{{}}}", name),
+        }
+    }
 
-    format!(
-        "X{name} {d} {g} {s} Vdd sky130_fd_pr__pfet_01v8_hvt w={:.2} l=0.15 ad={:.2} as={:.2} pd={:.2} ps={:.2} m={:.2}",
-        closest_bin, ar, ar, pe, pe, mult
-    )
-}
+    fn temperature(self, temp: f32) -> String {
+        match self {
+            SpiceDialect::Xyce => format!(".options device temp={}", temp),
+            SpiceDialect::Ngspice | SpiceDialect::Hspice => format!(".temp {}", temp),
+        }
+    }
+
+    /// Transient analysis and waveforms of `nodes` (e.g `V(I0/Q) V(I1/X)`), ending the deck
+    /// `runs` transient analyses of `stop` ns with new random variations, printing the `measures` of each run
+    fn monte_carlo(self, nodes: &str, stop: f32, runs: usize, measures: &[String]) -> String {
+        match self {
+            SpiceDialect::Ngspice => {
+                let names = measures.join(" ").to_lowercase();
+                let values: Vec<String> = measures.iter().map(|m| format!("$&{}", m.to_lowercase())).collect();
+                format!(
+                    r#".control
+echo "run {names}"
+let mc_run = 1
+dowhile mc_run <= {runs}
+  setseed $&mc_run
+  reset
+  tran 0.01n {stop}n
+  echo "$&mc_run {}"
+  let mc_run = mc_run + 1
+end
+.endc
+.end"#,
+                    values.join(" ")
+                )
+            }
+            SpiceDialect::Xyce => format!(
+                ".tran 0.01n {}n\n.sampling useexpr=true\n.options samples numsamples={}\n.print tran format=csv {}\n.end",
+                stop, runs, nodes
+            ),
+            SpiceDialect::Hspice => {
+                format!(".tran 0.01n {}n sweep monte={}\n.probe tran {}\n.end", stop, runs, nodes)
+            }
+        }
+    }
 
-pub fn nfet_size(w: f32) -> (f32, f32) {
-    static BINS_NFET: &[f32] = &[
-        0.36, 0.39, 0.42, 0.52, 0.54, 0.55, 0.58, 0.6, 0.61, 0.64, 0.65, 0.74, 0.84, 1.0, 1.26, 1.68, 2.0, 3.0, 5.0,
-        7.0,
-    ];
-    let pos = BINS_NFET
-        .binary_search_by(|val| OrderedFloat(*val).cmp(&OrderedFloat(w)))
-        .unwrap_or_else(|x| x);
-    let closest_bin = BINS_NFET[usize::min(pos, BINS_NFET.len() - 1)];
-    let mult = w / closest_bin;
-    (closest_bin, mult)
+    /// Transient analysis of `stop` ns plotting `nodes`
+    fn analysis(self, nodes: &str, stop: f32) -> String {
+        match self {
+            SpiceDialect::Ngspice => format!(".tran 0.01n {}n\n.control\nrun\nplot {}\n.endc\n.end", stop, nodes),
+            SpiceDialect::Xyce => format!(".tran 0.01n {}n\n.print tran format=csv {}\n.end", stop, nodes),
+            SpiceDialect::Hspice => format!(".tran 0.01n {}n\n.probe tran {}\n.end", stop, nodes),
+        }
+    }
 }
 
-fn nfet(name: &str, d: &str, g: &str, s: &str, w: f32) -> String {
-    let (closest_bin, mult) = nfet_size(w);
-    let ar = area(w) / mult;
-    let pe = perim(w) / mult;
+/// Generate a deck simulating the path of `report` with the actual cells from `subckt`.
+pub fn extract_spice_for_manual_analysis(
+    graph: &SDFGraph,
+    analysis: &SDFGraphAnalyzed,
+    subckt: &SubcktData,
+    parasitics: Option<&Parasitics>,
+    report: &PathReport,
+    options: &SpiceOptions,
+) -> Result<String> {
+    path_deck(graph, analysis, subckt, parasitics, report, options, false)
+}
 
-    format!(
-        "X{name} {d} {g} {s} Vgnd sky130_fd_pr__nfet_01v8 w={:.2} l=0.15 ad={:.2} as={:.2} pd={:.2} ps={:.2} m={:.2}",
-        closest_bin, ar, ar, pe, pe, mult
-    )
+/// Generate a deck simulating the shortest path to the register input `endpoint` and the flip-flop capturing it,
+/// both clocked by the edge launching the path, with measures of the arrival after the capture edge and of
+/// the captured value.
+pub fn extract_hold_spice_for_manual_analysis(
+    graph: &SDFGraph,
+    analysis: &SDFGraphAnalyzed,
+    subckt: &SubcktData,
+    parasitics: Option<&Parasitics>,
+    endpoint: &PinTrans,
+    options: &SpiceOptions,
+) -> Result<String> {
+    let report = analysis
+        .hold_path_report(graph, endpoint)
+        .ok_or_else(|| StarsError::EmptyPath(graph.symbols.pin_name(endpoint.0).to_string()))?;
+    // the launching and capturing flip-flops are two instances of the deck
+    let symbols = &graph.symbols;
+    if symbols.pin_instance(report.startpoint().0) == symbols.pin_instance(endpoint.0) {
+        return Err(StarsError::LoopPath(symbols.pin_name(endpoint.0).to_string()));
+    }
+    let options = SpiceOptions {
+        clocked_launch: true,
+        ..options.clone()
+    };
+    path_deck(graph, analysis, subckt, parasitics, &report, &options, true)
 }
 
-pub fn extract_spice_for_manual_analysis(
+/// Deck of [`extract_spice_for_manual_analysis`], with the measures of the hold check when `hold`
+fn path_deck(
     graph: &SDFGraph,
     analysis: &SDFGraphAnalyzed,
     subckt: &SubcktData,
     parasitics: Option<&Parasitics>,
-    output: &PinTrans,
-    max_delay: f32,
-    path: &[(PinTrans, f32)],
-) {
-    let transdata = CellTransitionData::new();
+    report: &PathReport,
+    options: &SpiceOptions,
+    hold: bool,
+) -> Result<String> {
     let pincapas = PinCapas::new();
 
-    let mut instances: Vec<(SDFInstance, SDFCellType, PinTrans, PinTrans)> = vec![];
-    let mut wires: Vec<(SDFPin, SDFPin)> = Default::default();
-    let mut all_pins_in_path = FxHashSet::default();
-
-    let mut last_pin: Option<&PinTrans> = None;
+    let symbols = &graph.symbols;
+    let output = &report.endpoint;
+    let max_delay = report.total_delay;
+    let all_pins_in_path = report.pin_set();
 
-    for (pin, _delay) in path {
-        let instance = instance_name(&pin.0);
-        let celltype = &graph.instance_celltype[&instance];
+    let celltype_of = |instance: &str| -> Result<&SDFCellType> {
+        symbols
+            .instance_id(instance)
+            .and_then(|id| graph.instance_celltype.get(&id))
+            .ok_or_else(|| StarsError::UnknownInstance(instance.to_string()))
+    };
+    let pins_of = |map: &InstanceMap<PinSet>, instance: &str| -> Vec<PinId> {
+        let pins = symbols.instance_id(instance).and_then(|id| map.get(&id));
+        pins.into_iter().flatten().copied().collect()
+    };
 
-        let last_instance = instances.last().map(|v| &v.0);
+    if report.stages.len() < 2 {
+        return Err(StarsError::EmptyPath(symbols.pin_name(output.0).to_string()));
+    }
 
-        all_pins_in_path.insert(pin.0.clone());
+    // a path starting at a primary input is launched by a source on the port instead of a flip-flop
+    let port_launch = report.stages[0].instance.is_none().then(|| report.startpoint());
 
-        if last_instance == Some(&instance) {
-            instances.last_mut().unwrap().3 = pin.clone();
-            last_pin = Some(pin);
+    let mut instances: Vec<(SDFInstance, SDFCellType, PinTrans, PinTrans)> = vec![];
+    for stage in &report.stages {
+        let (Some(_), Some(pin_in), Some(pin_out)) = (stage.instance, stage.first_pin(), stage.last_pin()) else {
             continue;
-        }
-
-        if let Some(last_pin) = last_pin {
-            wires.push((last_pin.0.clone(), pin.0.clone()));
-        }
-        instances.push((instance.clone(), celltype.clone(), pin.clone(), pin.clone()));
-
-        last_pin = Some(pin);
+        };
+        let instance = instance_name(symbols.pin_name(pin_in.0));
+        let celltype = celltype_of(&instance)?;
+        instances.push((instance, celltype.clone(), pin_in, pin_out));
     }
+    let Some(o_instance) = instances.last().map(|instance| instance.0.clone()) else {
+        return Err(StarsError::EmptyPath(symbols.pin_name(output.0).to_string()));
+    };
 
-    let o_instance = output.0.rsplit_once('/').unwrap().0;
-    let o_celltype = &graph.instance_celltype[o_instance];
-
-    instances.push((
-        o_instance.to_string(),
-        o_celltype.clone(),
-        output.clone(),
-        output.clone(),
-    ));
-    wires.push((last_pin.unwrap().0.clone(), output.0.clone()));
+    let wires: Vec<(SDFPin, SDFPin)> = report
+        .wires()
+        .map(|(from, to)| (symbols.pin_name(from.0).to_string(), symbols.pin_name(to.0).to_string()))
+        .collect();
 
     let mut shortname_map = FxHashMap::default();
 
@@ -175,7 +268,7 @@ pub fn extract_spice_for_manual_analysis(
 
     let shortify = |pin: &str| {
         if let Some((instance, pin)) = pin.rsplit_once('/') {
-            if let Some(i) = shortname_map.get(&*instance) {
+            if let Some(i) = shortname_map.get(instance) {
                 return format!("I{}/{}", i, pin);
             }
             return pin.to_string();
@@ -188,32 +281,89 @@ pub fn extract_spice_for_manual_analysis(
 
     let mut spice = String::new();
 
-    const VDD: &str = "1.8";
+    let dialect = options.dialect;
+    let pdk = &subckt.pdk;
+    let vdd = options.vdd.to_string();
+    let vdd = vdd.as_str();
 
-    writeln!(
-        &mut spice,
-        r#"
-* Generated by SDF using stars
-* Delay: {:.3}
+    // the first line of the deck is its title, ngspice takes it from .title instead
+    let title = format!("sdf_based_path_extraction_of_{}", o_instance);
+    let (first_line, title) = match dialect {
+        SpiceDialect::Ngspice => (String::new(), format!("\n.title {}\n", title)),
+        SpiceDialect::Xyce | SpiceDialect::Hspice => (title, String::new()),
+    };
+    // the models of the HSPICE PDK are sized in meters
+    let mut setup = match dialect {
+        SpiceDialect::Hspice => ".option post scale=1u\n".to_string(),
+        SpiceDialect::Ngspice | SpiceDialect::Xyce => String::new(),
+    };
+    if let Some(temp) = options.temp {
+        writeln!(setup, "{}", dialect.temperature(temp)).unwrap();
+    }
+    let include = match (&options.corner_lib, options.monte_carlo) {
+        (Some(corner), _) => format!(".lib \"{}\" {}", options.include_path, corner),
+        // the switch of the sky130 models enabling the mismatch of the transistors
+        (None, Some(_)) => format!(".lib \"{}\" mc\n.param mc_mm_switch=1", options.include_path),
+        (None, None) => format!(".include \"{}\"", options.include_path),
+    };
+    // time in ns of the clock edge launching the path
+    let launch_time = if options.clocked_launch { 2.5 } else { 0.0 };
+    let launch = if let Some(port) = port_launch {
+        let (from, to) = match port.1 {
+            Transition::Rise => ("0", vdd),
+            Transition::Fall => (vdd, "0"),
+        };
+        let port = shortify(symbols.pin_name(port.0));
+        let start = if options.clocked_launch {
+            format!("{launch_time}n {from} ")
+        } else {
+            String::new()
+        };
+        format!(
+            r#"Vclk clk Vgnd PULSE(0 {vdd} 0.5n 0.05n 0.05n 0.95n 2n)
 
-.title sdf_based_path_extraction_of_{}
+V{port} {port} Vgnd PWL(0 {from} {start}{}n {to})"#,
+            launch_time + 0.05
+        )
+    } else if options.clocked_launch {
+        let (from, to) = match instances[0].3 .1 {
+            Transition::Rise => ("0", vdd),
+            Transition::Fall => (vdd, "0"),
+        };
+        format!(
+            r#"Vclk clk Vgnd PULSE(0 {vdd} 0.5n 0.05n 0.05n 0.95n 2n)
 
-.include "./prelude.spice"
-Vgnd Vgnd 0 0
-Vdd Vdd Vgnd {VDD}
-Vclk clk Vgnd PULSE(0 {VDD} 0n 0.2n 0 0 0)
+* the first clock edge loads the opposite value, the second one launches the path
+VI0/D I0/D Vgnd PWL(0 {from} 1.5n {from} 1.55n {to})"#
+        )
+    } else {
+        format!(
+            r#"Vclk clk Vgnd PULSE(0 {vdd} 0n 0.2n 0 0 0)
 
 .param v_q_ic = 0
-.param v_start = 1.8
+.param v_start = {vdd}
 
-.ic V({}) = {{v_q_ic}}
+.ic V({}) = {}
 
-VI0/D I0/D Vgnd {{v_start}}
+VI0/D I0/D Vgnd {}"#,
+            shortify(symbols.pin_name(instances[0].2 .0)),
+            dialect.param("v_q_ic"),
+            dialect.param("v_start"),
+        )
+    };
+    writeln!(
+        &mut spice,
+        r#"{first_line}
+* Generated by SDF using stars
+* Delay: {:.3}
+{title}
+{include}
+{setup}Vgnd Vgnd 0 0
+Vdd Vdd Vgnd {vdd}
+{launch}
 
 "#,
-        analysis.max_delay[output],
-        o_instance,
-        shortify(&*instances[0].2 .0)
+        max_delay,
     )
     .unwrap();
 
@@ -244,11 +394,7 @@ VI0/D I0/D Vgnd {{v_start}}
     }*/
 
     for (i, (instance, celltype, pin_i, pin_o)) in instances.iter().enumerate() {
-        let celltype_short = celltype
-            .trim_start_matches("sky130_fd_sc_hd__")
-            .rsplit_once('_')
-            .unwrap()
-            .0;
+        let celltype_short = pdk.celltype_short(celltype);
         values.clear();
 
         values.insert("VGND", "Vgnd".into());
@@ -258,33 +404,38 @@ VI0/D I0/D Vgnd {{v_start}}
         values.insert("CLK", "clk".into());
         values.insert("RESET_B", "Vdd".into()); // reset really is nreset (damnit)
 
-        let transition_pin = pin_name_ref(&pin_i.0); // instance/A -> A
-        values.insert(transition_pin, shortify(&pin_i.0).into());
+        let transition_pin = symbols.pin_short_name(pin_i.0); // instance/A -> A
+        values.insert(transition_pin, shortify(symbols.pin_name(pin_i.0)).into());
 
         let mut total_out_capa = 0.0;
 
-        for fanout_pin in &graph.instance_fanout[instance] {
-            if all_pins_in_path.contains(fanout_pin) {
+        for fanout_pin in pins_of(&graph.instance_fanout, instance) {
+            if all_pins_in_path.contains(&fanout_pin) {
+                continue;
+            }
+
+            // the output ports of the design have no input capacitance
+            if symbols.pin_instance(fanout_pin).is_none() {
                 continue;
             }
 
+            let fanout_pin = symbols.pin_name(fanout_pin);
             let fanout_instance = instance_name(fanout_pin);
-            let fanout_celltype = &graph.instance_celltype[&fanout_instance];
+            let fanout_celltype = celltype_of(&fanout_instance)?;
 
             let pin = pin_name_ref(fanout_pin);
 
-            let full = format!("{}/{}", fanout_celltype, pin);
-            let Some(capa_v) = pincapas.data.get(&full).copied() else {
+            let Some(capa_v) = pincapas.capacitance(subckt, fanout_celltype, pin) else {
                 continue;
             };
 
             total_out_capa += capa_v;
         }
 
-        for out in &graph.instance_outs[instance] {
-            values.insert(pin_name_ref(out), shortify(&*out).into());
+        for out in pins_of(&graph.instance_outs, instance) {
+            values.insert(symbols.pin_short_name(out), shortify(symbols.pin_name(out)).into());
         }
-        pins_to_plot.insert(shortify(&*pin_o.0));
+        pins_to_plot.insert(shortify(symbols.pin_name(pin_o.0)));
 
         let unate = if pin_i.1 == pin_o.1 {
             BiUnate::Positive
@@ -292,14 +443,14 @@ VI0/D I0/D Vgnd {{v_start}}
             BiUnate::Negative
         };
 
-        let pin_vals = transdata
-            .data
-            .get(celltype)
-            .and_then(|v| v.get(transition_pin))
-            .map(|v| v.iter().find(|v| v.unate == unate).expect("No transition found"));
+        let pin_vals = subckt
+            .subckt(celltype)?
+            .transition_combinations(transition_pin, symbols.pin_short_name(pin_o.0))
+            .into_iter()
+            .find(|v| v.unate == unate);
 
         if pin_vals.is_none() && celltype_short != "dfxtp" {
-            eprintln!("no pin combination found for {}", celltype);
+            log::warn!("No pin combination found for {}", celltype);
         }
 
         let mut celltype_with_combinations = celltype_short.to_string();
@@ -311,7 +462,10 @@ VI0/D I0/D Vgnd {{v_start}}
         writeln!(
             &mut spice,
             "\n* transition {} -> {} ({})\n* celltype {} ",
-            pin_i.0, pin_o.0, pin_i.1, celltype_with_combinations
+            symbols.pin_name(pin_i.0),
+            symbols.pin_name(pin_o.0),
+            pin_i.1,
+            celltype_with_combinations
         )
         .unwrap();
 
@@ -327,13 +481,19 @@ VI0/D I0/D Vgnd {{v_start}}
 
         writeln!(&mut spice, "* pins ").unwrap();
 
-        for pin in &subckt.data[celltype].pins {
+        for pin in &subckt.subckt(celltype)?.pins {
             let full_pin = format!("{}/{}", instance, pin);
             if values.contains_key(&**pin) {
                 continue;
             }
 
-            let connected_to = &graph.reverse_graph[&(full_pin.clone(), Transition::Rise)][0].dst.0;
+            let full_pin_id = symbols
+                .pin_id(&full_pin)
+                .ok_or_else(|| StarsError::UnknownPin(full_pin.clone()))?;
+            let connected_to_id = graph
+                .driver(full_pin_id)
+                .ok_or_else(|| StarsError::UnknownPin(full_pin.clone()))?;
+            let connected_to = symbols.pin_name(connected_to_id);
 
             let instance_name_ = instance_name(connected_to);
 
@@ -343,15 +503,22 @@ VI0/D I0/D Vgnd {{v_start}}
                     "V{} {} Vgnd {}",
                     shortify(&*full_pin),
                     shortify(&*full_pin),
-                    VDD
+                    vdd
                 )
                 .unwrap();*/
-                values.insert(pin, shortify(&*full_pin).into());
+                values.insert(pin, shortify(&full_pin).into());
                 continue;
             }
-            if let Some(pin_vals) = pin_vals {
-                if let Some(celltype_name) = graph.instance_celltype.get(&instance_name_) {
-                    let drive = subckt.data[celltype_name].output_pin_drive[pin_name_ref(connected_to)];
+            if let Some(pin_vals) = &pin_vals {
+                if let Some(celltype_name) = symbols
+                    .instance_id(&instance_name_)
+                    .and_then(|id| graph.instance_celltype.get(&id))
+                {
+                    let drive = *subckt
+                        .subckt(celltype_name)?
+                        .output_pin_drive
+                        .get(pin_name_ref(connected_to))
+                        .ok_or_else(|| StarsError::UnknownPin(connected_to.to_string()))?;
 
                     let inv_in_node = format!("inv_in_{}/{}", shortify(instance), shortify(pin));
                     let pin_val = pin_vals.pins[pin];
@@ -362,7 +529,7 @@ VI0/D I0/D Vgnd {{v_start}}
                     const RISE_DELAY: f32 = 0.1;
 
                     let connected_to_trans = (
-                        connected_to.clone(),
+                        connected_to_id,
                         if pin_val { Transition::Rise } else { Transition::Fall },
                     );
 
@@ -382,39 +549,48 @@ VI0/D I0/D Vgnd {{v_start}}
                         t_setup
                     );*/
 
-                    let slack_p = get_slack(&(connected_to.clone(), Transition::Rise), 1.2).unwrap_or(0.0); // in ns
-                    let slack_n = get_slack(&(connected_to.clone(), Transition::Fall), 1.2).unwrap_or(0.0); // in ns
+                    let slack_p = get_slack(&(connected_to_id, Transition::Rise), 1.2).unwrap_or(0.0); // in ns
+                    let slack_n = get_slack(&(connected_to_id, Transition::Fall), 1.2).unwrap_or(0.0); // in ns
 
                     let slack = if inv_in_val { slack_p } else { slack_n } * 1e-9; // in seconds
 
                     let rd = if pin_val {
-                        EQ_RESISTANCE_PFET_HVT * drive.rise_lw
+                        pdk.pfet.eq_resistance * drive.rise_lw
                     } else {
-                        EQ_RESISTANCE_NFET * drive.fall_lw
+                        pdk.nfet.eq_resistance * drive.fall_lw
                     };
 
-                    let maxw_p = slack / (rd * 0.15e-6 * CAPA_PER_AREA_PFET_HVT * std::f32::consts::LN_2);
-                    let maxw_n = slack / (rd * 0.15e-6 * CAPA_PER_AREA_NFET * std::f32::consts::LN_2);
-
-                    let c_e = graph.instance_fanout[&instance_name_].iter().fold(0.0, |acc, fanout| {
-                        if fanout == &full_pin {
-                            return acc;
-                        }
-
-                        let fanout_instance = instance_name(fanout);
-                        let fanout_celltype = &graph.instance_celltype[&fanout_instance];
-                        let pin = pin_name_ref(fanout);
-                        let full = format!("{}/{}", fanout_celltype, pin);
-                        let Some(capa_v) = pincapas.data.get(&full).copied() else {
-                            return acc;
-                        };
-                        acc + capa_v
-                    });
+                    let maxw_p = slack / (rd * pdk.pfet.length * 1e-6 * pdk.pfet.capa_per_area * LN_2);
+                    let maxw_n = slack / (rd * pdk.nfet.length * 1e-6 * pdk.nfet.capa_per_area * LN_2);
+
+                    let c_e = pins_of(&graph.instance_fanout, &instance_name_)
+                        .into_iter()
+                        .fold(0.0, |acc, fanout| {
+                            if fanout == full_pin_id {
+                                return acc;
+                            }
+
+                            let fanout = symbols.pin_name(fanout);
+                            let fanout_instance = instance_name(fanout);
+                            let Ok(fanout_celltype) = celltype_of(&fanout_instance) else {
+                                return acc;
+                            };
+                            let pin = pin_name_ref(fanout);
+                            let Some(capa_v) = pincapas.capacitance(subckt, fanout_celltype, pin) else {
+                                return acc;
+                            };
+                            acc + capa_v
+                        });
+                    // the wire of the side input isn't in the deck, its capacitance loads the driver
+                    let c_e = c_e
+                        + parasitics
+                            .and_then(|para| para.net(para.net_name(connected_to)?))
+                            .map_or(0.0, |net| net.total_cap as f32 * 1e12);
 
                     writeln!(
                         &mut spice,
                         "* maxw_p {}/{} {:.3} {:.0} slack {} ns",
-                        shortify(&*instance),
+                        shortify(instance),
                         pin,
                         maxw_p * 1e6,
                         maxw_p * 1e6 / 7.0,
@@ -425,7 +601,7 @@ VI0/D I0/D Vgnd {{v_start}}
                     writeln!(
                         &mut spice,
                         "* maxw_n {}/{} {:.3} {:.0} slack {} ns",
-                        shortify(&*instance),
+                        shortify(instance),
                         pin,
                         maxw_n * 1e6,
                         maxw_n * 1e6 / 7.0,
@@ -437,8 +613,8 @@ VI0/D I0/D Vgnd {{v_start}}
                         writeln!(
                             &mut spice,
                             "C{}_fanout {} Vgnd {}p",
-                            shortify(&*full_pin),
-                            shortify(&*full_pin),
+                            shortify(&full_pin),
+                            shortify(&full_pin),
                             c_e
                         )
                         .unwrap();
@@ -454,7 +630,7 @@ VI0/D I0/D Vgnd {{v_start}}
                             "V{} {} Vgnd {}",
                             &inv_in_node,
                             &inv_in_node,
-                            if inv_in_val { VDD } else { "0" },
+                            if inv_in_val { vdd } else { "0" },
                         )
                         .unwrap();
                     } else {
@@ -463,74 +639,74 @@ VI0/D I0/D Vgnd {{v_start}}
                             "V{} {} Vgnd PULSE({} {} {}n {}n 0 1 2)",
                             &inv_in_node,
                             &inv_in_node,
-                            if inv_in_val { "0" } else { VDD },
-                            if inv_in_val { VDD } else { "0" },
+                            if inv_in_val { "0" } else { vdd },
+                            if inv_in_val { vdd } else { "0" },
                             _t_setup,
                             RISE_DELAY * 2.0,
                         )
                         .unwrap();
                     }
 
-                    writeln!(
-                        &mut spice,
-                        "{}\n{}",
-                        pfet(
-                            &shortify(&*full_pin),
-                            &shortify(&*full_pin),
-                            &inv_in_node,
-                            "Vdd",
-                            0.15 / drive.rise_lw
-                        ),
-                        nfet(
-                            &shortify(&*full_pin),
-                            &shortify(&*full_pin),
-                            &inv_in_node,
-                            "Vgnd",
-                            0.15 / drive.fall_lw
+                    let driver = shortify(&full_pin);
+                    let (w_p, w_n) = (pdk.pfet.length / drive.rise_lw, pdk.nfet.length / drive.fall_lw);
+                    let p_nodes = [&*driver, &inv_in_node, "Vdd", "Vdd"];
+                    let n_nodes = [&*driver, &inv_in_node, "Vgnd", "Vgnd"];
+                    let prefix = dialect.device_prefix();
+                    if options.param_widths {
+                        let param = driver.replace('/', "_");
+                        writeln!(
+                            &mut spice,
+                            ".param wp_{} = {:.3}\n.param wn_{} = {:.3}",
+                            param, w_p, param, w_n
                         )
-                    )
-                    .unwrap();
-                } else {
-                    if pin_vals.pins[pin] {
+                        .unwrap();
+                        let p_mult = dialect.param(&format!("wp_{}/{}", param, pdk.pfet.size(w_p).0));
+                        let n_mult = dialect.param(&format!("wn_{}/{}", param, pdk.nfet.size(w_n).0));
                         writeln!(
                             &mut spice,
-                            "V{} {} Vgnd {}",
-                            &shortify(&*full_pin),
-                            &shortify(&*full_pin),
-                            VDD
+                            "{}\n{}",
+                            pdk.pfet.card_with_mult(prefix, &driver, p_nodes, w_p, &p_mult),
+                            pdk.nfet.card_with_mult(prefix, &driver, n_nodes, w_n, &n_mult)
                         )
                         .unwrap();
                     } else {
                         writeln!(
                             &mut spice,
-                            "V{} {} Vgnd 0",
-                            &shortify(&*full_pin),
-                            &shortify(&*full_pin)
+                            "{}\n{}",
+                            pdk.pfet.card(prefix, &driver, p_nodes, w_p),
+                            pdk.nfet.card(prefix, &driver, n_nodes, w_n)
+                        )
+                        .unwrap();
+                    }
+                } else {
+                    if pin_vals.pins[pin] {
+                        writeln!(
+                            &mut spice,
+                            "V{} {} Vgnd {}",
+                            &shortify(&full_pin),
+                            &shortify(&full_pin),
+                            vdd
                         )
                         .unwrap();
+                    } else {
+                        writeln!(&mut spice, "V{} {} Vgnd 0", &shortify(&full_pin), &shortify(&full_pin)).unwrap();
                     };
                 }
             }
 
-            values.insert(pin, shortify(&*full_pin).into());
+            values.insert(pin, shortify(&full_pin).into());
         }
 
         writeln!(&mut spice, "\n* cell ").unwrap();
 
-        subckt.instanciate(
-            &shortify(&*instance),
-            celltype,
-            &values,
-            &mut spice,
-            &Default::default(),
-        );
+        subckt.instanciate(&shortify(instance), celltype, &values, &mut spice, &Default::default())?;
 
         if total_out_capa != 0.0 {
             writeln!(
                 &mut spice,
                 "C{}_fanout {} Vgnd {}p",
-                shortify(&pin_o.0),
-                shortify(&pin_o.0),
+                shortify(symbols.pin_name(pin_o.0)),
+                shortify(symbols.pin_name(pin_o.0)),
                 total_out_capa
             )
             .unwrap();
@@ -538,8 +714,8 @@ VI0/D I0/D Vgnd {{v_start}}
     }
 
     // remove output of last instance
-    for out in &graph.instance_outs[o_instance] {
-        pins_to_plot.remove(&shortify(&*out));
+    for out in pins_of(&graph.instance_outs, &o_instance) {
+        pins_to_plot.remove(&shortify(symbols.pin_name(out)));
     }
 
     writeln!(&mut spice).unwrap();
@@ -553,34 +729,60 @@ VI0/D I0/D Vgnd {{v_start}}
     let mut resistances = String::new();
     let mut capacitances = String::new();
 
+    // pins of the nets that are not on the path (other sinks and internal nodes) get their own node
+    let in_path = |pin: &str| {
+        symbols
+            .pin_id(pin)
+            .is_some_and(|pin| all_pins_in_path.contains(&pin) || pin == output.0)
+    };
+    let mut written_nets = FxHashSet::default();
+
     for (i, (pin_in, pin_out)) in wires.iter().enumerate() {
         if let Some(para) = parasitics {
-            if let Some(wire) = para.wires.get(&(pin_in.clone(), pin_out.clone())) {
-                writeln!(
-                    &mut resistances,
-                    "RW{} {} {} {}",
-                    i,
-                    shortify(pin_in),
-                    shortify(pin_out),
-                    wire.res
-                )
-                .unwrap();
-                writeln!(
-                    &mut capacitances,
-                    "CW{} {} Vgnd {}p",
-                    i,
-                    shortify(pin_out),
-                    wire.cap * 1e12
-                )
-                .unwrap();
-                continue;
-            } else {
-                eprintln!("No parasitics for wire {} -> {}", pin_in, pin_out);
+            match para.net_of(pin_in) {
+                Some((net, _)) if net.node(pin_out).is_some() && !net.ress.is_empty() => {
+                    if !written_nets.insert(&net.name) {
+                        continue;
+                    }
+                    let node_name = |node: usize| match &net.nodes[node] {
+                        pin if in_path(pin) => shortify(pin),
+                        _ => format!("N{}_{}", i, node),
+                    };
+                    for (j, (a, b, res)) in net.ress.iter().enumerate() {
+                        writeln!(
+                            &mut resistances,
+                            "RW{}_{} {} {} {}",
+                            i,
+                            j,
+                            node_name(*a),
+                            node_name(*b),
+                            res
+                        )
+                        .unwrap();
+                    }
+                    for (node, cap) in net.caps.iter().enumerate() {
+                        if *cap == 0.0 {
+                            continue;
+                        }
+                        writeln!(
+                            &mut capacitances,
+                            "CW{}_{} {} Vgnd {}p",
+                            i,
+                            node,
+                            node_name(node),
+                            cap * 1e12
+                        )
+                        .unwrap();
+                    }
+                    continue;
+                }
+                _ => log::warn!("No parasitics for wire {} -> {}", pin_in, pin_out),
             }
         }
 
         let instance_in = instance_name(pin_in);
-        let fanout = graph.instance_fanout[&instance_in].len();
+        // a primary input is modeled like a cell driving a single pin
+        let fanout = pins_of(&graph.instance_fanout, &instance_in).len().max(1);
 
         let mult = if fanout <= load_model.len() {
             load_model[fanout - 1]
@@ -591,26 +793,36 @@ VI0/D I0/D Vgnd {{v_start}}
         let res = res_base * mult;
         let capa = capa_base * mult + 0.002 * fanout as f32;
 
-        writeln!(
-            &mut resistances,
-            "RW{} {} {} {}",
-            i,
-            shortify(pin_in),
-            shortify(pin_out),
-            res
-        )
-        .unwrap();
-        writeln!(&mut capacitances, "CW{} {} Vgnd {}p", i, shortify(pin_out), capa).unwrap();
-    }
+        let segments = options.wire_segments.max(1);
+        if segments == 1 {
+            writeln!(
+                &mut resistances,
+                "RW{} {} {} {}",
+                i,
+                shortify(pin_in),
+                shortify(pin_out),
+                res
+            )
+            .unwrap();
+            writeln!(&mut capacitances, "CW{} {} Vgnd {}p", i, shortify(pin_out), capa).unwrap();
+            continue;
+        }
 
-    if let Some(para) = parasitics {
-        for (pin, value) in &para.caps {
+        let node_name = |k: usize| match k {
+            0 => shortify(pin_in),
+            k if k == segments => shortify(pin_out),
+            k => format!("NW{}_{}", i, k),
+        };
+        for k in 1..=segments {
+            let (a, b) = (node_name(k - 1), node_name(k));
+            writeln!(&mut resistances, "RW{}_{} {} {} {}", i, k, a, b, res / segments as f32).unwrap();
             writeln!(
                 &mut capacitances,
-                "CW{}_solo {} Vgnd {}p",
-                shortify(pin),
-                shortify(pin),
-                value * 1e12
+                "CW{}_{} {} Vgnd {}p",
+                i,
+                k,
+                b,
+                capa / segments as f32
             )
             .unwrap();
         }
@@ -624,105 +836,275 @@ VI0/D I0/D Vgnd {{v_start}}
         write!(to_plot_str, "V({}) ", pin).unwrap();
     }
 
-    writeln!(
-        &mut spice,
-        r#"
-.tran 0.01n 8n
-.control
-run
-plot {}
-.endc
-.end"#,
-        to_plot_str
-    )
-    .unwrap();
-
-    std::fs::write("out.spice", spice).unwrap();
-}
-
-#[allow(dead_code)]
-mod cell_logic {
-    fn xnor2(a: bool, b: bool) -> bool {
-        !(a ^ b)
-    }
-
-    fn dfxtp(d: bool) -> bool {
-        d
+    // 50% crossing delays from the launch pin, comparable to the arrival times of the SDF
+    let v_half = options.vdd / 2.0;
+    let edge = |transition| match transition {
+        Transition::Rise => "RISE",
+        Transition::Fall => "FALL",
+    };
+    // the edges of the cycle loading the flip-flop are not measured
+    let skip_load = if options.clocked_launch {
+        format!(" TD={}n", launch_time - 1.0)
+    } else {
+        String::new()
+    };
+    let launch = port_launch.unwrap_or(instances[0].3);
+    let arrivals: FxHashMap<PinTrans, f32> = report.pins().collect();
+    let mut measures = Vec::new();
+    writeln!(&mut spice).unwrap();
+    let launching_instances = if port_launch.is_some() { 0 } else { 1 };
+    for (i, (_, _, _, pin_o)) in instances.iter().enumerate().skip(launching_instances) {
+        measures.push(format!("delay_I{}", i));
+        if let (Some(launch), Some(arrival)) = (arrivals.get(&launch), arrivals.get(pin_o)) {
+            writeln!(&mut spice, "* SDF: {:.3}n", arrival - launch).unwrap();
+        }
+        writeln!(
+            &mut spice,
+            ".meas tran delay_I{} TRIG V({}) VAL={v_half}{} {}=1 TARG V({}) VAL={v_half}{} {}=1",
+            i,
+            shortify(symbols.pin_name(launch.0)),
+            skip_load,
+            edge(launch.1),
+            shortify(symbols.pin_name(pin_o.0)),
+            skip_load,
+            edge(pin_o.1),
+        )
+        .unwrap();
     }
 
-    fn dfrtp(d: bool) -> bool {
-        d
+    if hold {
+        let capture = shortify(symbols.pin_name(output.0));
+        if let Some(slack) = analysis.hold_slack(graph, output) {
+            writeln!(
+                &mut spice,
+                "* SDF: earliest arrival {:.3}n, hold slack {:.3}n",
+                max_delay, slack
+            )
+            .unwrap();
+        }
+        writeln!(
+            &mut spice,
+            ".meas tran hold_arrival TRIG V(clk) VAL={v_half} RISE=2 TARG V({}) VAL={v_half}{} {}=1",
+            capture,
+            skip_load,
+            edge(output.1),
+        )
+        .unwrap();
+        // the new value is captured one cycle early when the hold time is violated
+        if let Some(&q) = pins_of(&graph.instance_outs, &o_instance).first() {
+            writeln!(
+                &mut spice,
+                ".meas tran captured FIND V({}) AT={}n",
+                shortify(symbols.pin_name(q)),
+                launch_time + 1.9
+            )
+            .unwrap();
+        }
     }
 
-    fn a21o(a1: bool, a2: bool, b1: bool) -> bool {
-        (a1 && a2) || b1
-    }
+    let analysis = match options.monte_carlo {
+        Some(runs) => dialect.monte_carlo(&to_plot_str, 8.0 + launch_time, runs, &measures),
+        None => dialect.analysis(&to_plot_str, 8.0 + launch_time),
+    };
+    writeln!(&mut spice, "\n{}", analysis).unwrap();
 
-    fn a41o(a1: bool, a2: bool, a3: bool, a4: bool, b1: bool) -> bool {
-        (a1 && a2 && a3 && a4) || b1
-    }
+    validate_deck(&spice, pdk)?;
+    Ok(spice)
+}
 
-    fn xor2(a: bool, b: bool) -> bool {
-        a ^ b
-    }
+/// Nodes of a deck grouped by the DC paths between them
+#[derive(Default)]
+struct DcPaths<'a> {
+    ids: FxHashMap<&'a str, usize>,
+    parent: Vec<usize>,
+    /// first element connected to each node, to report it
+    elements: Vec<&'a str>,
+}
 
-    fn nor2(a: bool, b: bool) -> bool {
-        !(a || b)
+impl<'a> DcPaths<'a> {
+    fn id(&mut self, node: &'a str, element: &'a str) -> usize {
+        *self.ids.entry(node).or_insert_with(|| {
+            self.parent.push(self.parent.len());
+            self.elements.push(element);
+            self.parent.len() - 1
+        })
     }
 
-    fn mux2(a0: bool, a1: bool, s: bool) -> bool {
-        if s {
-            a1
-        } else {
-            a0
+    fn root(&mut self, mut id: usize) -> usize {
+        while self.parent[id] != id {
+            self.parent[id] = self.parent[self.parent[id]];
+            id = self.parent[id];
         }
+        id
     }
 
-    fn a211o(a1: bool, a2: bool, b1: bool, c1: bool) -> bool {
-        (a1 && a2) || b1 || c1
-    }
-
-    fn a22o(a1: bool, a2: bool, b1: bool, b2: bool) -> bool {
-        (a1 && a2) || (b1 && b2)
-    }
-
-    fn o211a(a1: bool, a2: bool, b1: bool, c1: bool) -> bool {
-        (a1 || a2) && b1 && c1
+    fn connect(&mut self, a: &'a str, b: &'a str, element: &'a str) {
+        let (a, b) = (self.id(a, element), self.id(b, element));
+        let (a, b) = (self.root(a), self.root(b));
+        self.parent[a] = b;
     }
+}
 
-    fn a21oi(a1: bool, a2: bool, b1: bool) -> bool {
-        !((a1 && a2) || b1)
-    }
+/// Check that every node of a deck has a DC path to the ground (through the sources, resistors and transistor
+/// channels) or an initial condition, so that no transistor gate is left floating.
+pub fn validate_deck(deck: &str, pdk: &PdkConfig) -> Result<()> {
+    let mut paths = DcPaths::default();
+    paths.id("0", "");
+    let mut initialized = Vec::new();
+    let mut in_control = false;
+
+    for line in deck.lines().map(str::trim) {
+        if line.starts_with(".control") {
+            in_control = true;
+        } else if line.starts_with(".endc") {
+            in_control = false;
+        } else if let Some(ic) = line.strip_prefix(".ic").filter(|_| !in_control) {
+            initialized.extend(
+                ic.split_whitespace()
+                    .filter_map(|v| v.strip_prefix("V(")?.strip_suffix(')')),
+            );
+        }
+        if in_control || line.starts_with(['*', '.']) {
+            continue;
+        }
 
-    fn a311o(a1: bool, a2: bool, a3: bool, b1: bool, c1: bool) -> bool {
-        (a1 && a2 && a3) || b1 || c1
+        let words: Vec<&str> = line.split_whitespace().filter(|word| !word.contains('=')).collect();
+        let Some((&element, nodes)) = words.split_first() else {
+            continue;
+        };
+        match element.chars().next().map(|c| c.to_ascii_uppercase()) {
+            Some('R' | 'V') if nodes.len() >= 2 => paths.connect(nodes[0], nodes[1], element),
+            Some('C') => {
+                for &node in nodes.iter().take(2) {
+                    paths.id(node, element);
+                }
+            }
+            Some('M' | 'X') => {
+                let Some((&model, nodes)) = nodes.split_last() else {
+                    continue;
+                };
+                let model = pdk.model(model);
+                let is_fet = model.starts_with(&pdk.nfet.family) || model.starts_with(&pdk.pfet.family);
+                if let (true, &[drain, gate, source, bulk]) = (is_fet, nodes) {
+                    paths.connect(drain, source, element);
+                    paths.id(gate, element);
+                    paths.id(bulk, element);
+                } else {
+                    // the nodes of a subcircuit are assumed to be connected inside it
+                    for window in nodes.windows(2) {
+                        paths.connect(window[0], window[1], element);
+                    }
+                }
+            }
+            _ => {}
+        }
     }
 
-    fn nand2b(a_n: bool, b: bool) -> bool {
-        !(!a_n && b)
+    let ground = paths.id("0", "");
+    let mut driven = vec![paths.root(ground)];
+    for node in initialized {
+        if let Some(&id) = paths.ids.get(node) {
+            driven.push(paths.root(id));
+        }
     }
-
-    fn o21a(a1: bool, a2: bool, b1: bool) -> bool {
-        (a1 || a2) && b1
+    let mut nodes: Vec<(&str, usize)> = paths.ids.iter().map(|(&node, &id)| (node, id)).collect();
+    nodes.sort_by_key(|&(_, id)| id);
+    for (node, id) in nodes {
+        if !driven.contains(&paths.root(id)) {
+            return Err(StarsError::FloatingNode {
+                node: node.to_string(),
+                element: paths.elements[id].to_string(),
+            });
+        }
     }
+    Ok(())
+}
 
-    fn clkbuf(a: bool) -> bool {
-        a
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    fn and2(a: bool, b: bool) -> bool {
-        a && b
-    }
+    #[test]
+    fn test_validate_deck() {
+        let deck = r#"
+Vgnd Vgnd 0 0
+Vdd Vdd Vgnd 1.8
+VA a Vgnd 1.8
+X0 y a Vdd Vdd sky130_fd_pr__pfet_01v8_hvt w=1.00 l=0.15 m=1.00
+X1 y b Vgnd Vgnd sky130_fd_pr__nfet_01v8 w=0.65 l=0.15 m=1.00
+CW0 y Vgnd 0.001p
+.tran 0.01n 8n
+"#;
+        let pdk = PdkConfig::sky130();
+        match validate_deck(deck, &pdk) {
+            Err(StarsError::FloatingNode { node, element }) => assert_eq!((&*node, &*element), ("b", "X1")),
+            other => panic!("expected a floating node, got {:?}", other),
+        }
 
-    fn buf(a: bool) -> bool {
-        a
+        let deck = deck.replace("CW0", "VB b Vgnd 0\nCW0");
+        assert!(validate_deck(&deck, &pdk).is_ok());
     }
 
-    fn a221oi(a1: bool, a2: bool, b1: bool, b2: bool, c1: bool) -> bool {
-        !((a1 && a2) || (b1 && b2) || c1)
-    }
+    #[test]
+    fn test_port_launch() {
+        // two buffers between a primary input and a primary output
+        let sdf = r#"(DELAYFILE
+ (SDFVERSION "3.0")
+ (DESIGN "buffers")
+ (DIVIDER .)
+ (TIMESCALE 1ns)
+ (CELL
+  (CELLTYPE "buffers")
+  (INSTANCE)
+  (DELAY
+   (ABSOLUTE
+    (INTERCONNECT in b1.A (0.010:0.010:0.010) (0.010:0.010:0.010))
+    (INTERCONNECT b1.X b2.A (0.010:0.010:0.010) (0.010:0.010:0.010))
+    (INTERCONNECT b2.X out (0.010:0.010:0.010) (0.010:0.010:0.010))
+   )
+  )
+ )
+ (CELL
+  (CELLTYPE "sky130_fd_sc_hd__buf_1")
+  (INSTANCE b1)
+  (DELAY
+   (ABSOLUTE
+    (IOPATH A X (0.100:0.100:0.100) (0.100:0.100:0.100))
+   )
+  )
+ )
+ (CELL
+  (CELLTYPE "sky130_fd_sc_hd__buf_1")
+  (INSTANCE b2)
+  (DELAY
+   (ABSOLUTE
+    (IOPATH A X (0.100:0.100:0.100) (0.100:0.100:0.100))
+   )
+  )
+ )
+)"#;
+        let subckt = SubcktData::new(
+            r#"
+.subckt sky130_fd_sc_hd__buf_1 VGND VPWR X A VPB VNB
+X0 VPWR A a_27_47# VPB sky130_fd_pr__pfet_01v8_hvt w=0.79 l=0.15
+X1 X a_27_47# VGND VNB sky130_fd_pr__nfet_01v8 w=0.52 l=0.15
+X2 X a_27_47# VPWR VPB sky130_fd_pr__pfet_01v8_hvt w=0.79 l=0.15
+X3 VGND A a_27_47# VNB sky130_fd_pr__nfet_01v8 w=0.52 l=0.15
+.ends"#,
+        )
+        .unwrap();
+        let graph = SDFGraph::new(&sdfparse::SDF::parse_str(sdf).unwrap(), Default::default()).unwrap();
+        let analysis = SDFGraphAnalyzed::analyze(&graph);
+        let out = (graph.symbols.pin_id("out").unwrap(), Transition::Rise);
+        let report = analysis.path_report(&graph, &out).unwrap();
 
-    fn or4(a: bool, b: bool, c: bool, d: bool) -> bool {
-        a || b || c || d
+        let deck =
+            extract_spice_for_manual_analysis(&graph, &analysis, &subckt, None, &report, &SpiceOptions::default())
+                .unwrap();
+        // the port is driven by a source instead of a flip-flop, and both buffers are measured from it
+        assert!(deck.contains("Vin in Vgnd PWL(0 0 0.05n 1.8)"), "{}", deck);
+        assert!(!deck.contains(".ic"));
+        assert!(deck.contains(".meas tran delay_I0 TRIG V(in)"));
+        assert!(deck.contains(".meas tran delay_I1 TRIG V(in)"));
     }
 }
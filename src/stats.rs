@@ -0,0 +1,96 @@
+//! Size and sanity figures of a timing graph, to check that an SDF was read as expected before analyzing it.
+
+use crate::graph::{is_interconnect, SDFGraph};
+use crate::memory::MemoryStats;
+use crate::types::{InstanceId, PinId, PinSet, Transition};
+use std::cmp::Reverse;
+use std::collections::BTreeSet;
+
+/// See [`SDFGraph::stats`]
+#[derive(Debug, Clone, Default)]
+pub struct GraphStats {
+    pub instances: usize,
+    pub registers: usize,
+    pub pins: usize,
+    /// arcs inside the cells (IOPATH), one per pair of transitions
+    pub cell_arcs: usize,
+    /// arcs between the cells and the ports (INTERCONNECT), one per pair of transitions
+    pub net_arcs: usize,
+    /// pins where the paths start (input ports and register outputs)
+    pub startpoints: usize,
+    /// pins where the paths end (output ports and register inputs)
+    pub endpoints: usize,
+    /// number of levels of [`SDFGraph::levels`], the depth of the deepest path in arcs
+    pub levels: usize,
+    /// nodes left out of the levels because they are part of a combinational loop
+    pub loop_nodes: usize,
+    /// pin driving the most pins, with their number
+    pub max_fanout: Option<(PinId, usize)>,
+    /// average number of pins driven by the pins driving a net
+    pub avg_fanout: f32,
+    /// pins of the cells connected on one side only, see [`crate::graph::GraphDiagnostics`]
+    pub unconnected_pins: Vec<PinId>,
+    /// instances with an arc of zero delay, often a cell the SDF writer had no timing for
+    pub zero_delay_cells: Vec<InstanceId>,
+    /// bytes held by the graph, see [`SDFGraph::memory_stats`]
+    pub memory: MemoryStats,
+}
+
+impl SDFGraph {
+    /// Count the instances, arcs and path ends of the graph and look for the pins and cells that are likely
+    /// mistakes of the SDF (unconnected pins, zero-delay arcs).
+    pub fn stats(&self) -> GraphStats {
+        let symbols = &self.symbols;
+        let mut stats = GraphStats {
+            instances: self.instance_celltype.len(),
+            registers: self.registers.len(),
+            pins: symbols.pins().len(),
+            startpoints: self.inputs.iter().map(|node| node.0).collect::<PinSet>().len(),
+            endpoints: self.outputs.iter().map(|node| node.0).collect::<PinSet>().len(),
+            memory: self.memory_stats(),
+            ..Default::default()
+        };
+
+        let mut zero_delay = PinSet::new();
+        let mut fanouts = Vec::new();
+        for (src, edges) in &self.graph {
+            let mut sinks = PinSet::new();
+            for edge in edges {
+                if is_interconnect(symbols, src.0, edge.dst.0) {
+                    stats.net_arcs += 1;
+                    sinks.insert(edge.dst.0);
+                } else {
+                    stats.cell_arcs += 1;
+                    if edge.delay == 0.0 {
+                        zero_delay.insert(src.0);
+                    }
+                }
+            }
+            // both transitions of a driver reach the same sinks
+            if src.1 == Transition::Rise && !sinks.is_empty() {
+                fanouts.push((src.0, sinks.len()));
+            }
+        }
+        stats.max_fanout = fanouts
+            .iter()
+            .copied()
+            .max_by_key(|&(pin, fanout)| (fanout, Reverse(pin)));
+        if !fanouts.is_empty() {
+            stats.avg_fanout = fanouts.iter().map(|(_, fanout)| *fanout).sum::<usize>() as f32 / fanouts.len() as f32;
+        }
+
+        let zero_delay_cells: BTreeSet<InstanceId> = zero_delay
+            .into_iter()
+            .filter_map(|pin| symbols.pin_instance(pin))
+            .collect();
+        stats.zero_delay_cells = zero_delay_cells.into_iter().collect();
+
+        stats.unconnected_pins = self.diagnostics.pins().into_iter().collect();
+
+        let levels = self.levels();
+        stats.levels = levels.len();
+        stats.loop_nodes = self.graph.len() - levels.iter().map(Vec::len).sum::<usize>();
+
+        stats
+    }
+}
@@ -1,4 +1,8 @@
-use crate::types::{SDFCellType, SDFInstance, SDFPin};
+use crate::error::{Result, StarsError};
+use crate::graph::SDFGraph;
+use crate::pdk::PdkConfig;
+use crate::spice_number;
+use crate::types::{BiUnate, SDFCellType, SDFInstance, SDFPin};
 use rustc_hash::{FxHashMap, FxHashSet};
 use std::borrow::Cow;
 use std::fmt::Write;
@@ -6,6 +10,8 @@ use std::fmt::Write;
 #[derive(Debug)]
 pub struct SubcktData {
     pub data: FxHashMap<SDFCellType, Subckt>,
+    /// transistor models of the cells
+    pub pdk: PdkConfig,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -20,6 +26,59 @@ pub struct Load {
     /// in µm², proportional to load capacitance
     pub pfet_area: f32,
     pub nfet_area: f32,
+    /// capacitors of the SUBCKT connected to the pin, in pF
+    pub wire_cap: f32,
+}
+
+impl Load {
+    /// Gate capacitance in pF
+    pub fn capacitance(&self, pdk: &PdkConfig) -> f32 {
+        self.pfet_area * pdk.pfet.capa_per_area + self.nfet_area * pdk.nfet.capa_per_area + self.wire_cap
+    }
+}
+
+/// Values of the other inputs of a cell for which a transition on an input reaches an output
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransitionCombination {
+    pub pins: FxHashMap<SDFPin, bool>,
+    pub unate: BiUnate,
+}
+
+/// Boolean function of an output of a cell, derived from its transistors
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogicFunction {
+    /// inputs of the cell in the order of the .subckt line, the first one being the least significant bit
+    /// of the rows of `table`
+    pub inputs: Vec<SDFPin>,
+    /// value of the output for each assignment of the inputs, None when it doesn't settle
+    /// (e.g. the outputs of the sequential cells, which also depend on their state)
+    pub table: Vec<Option<bool>>,
+}
+
+impl LogicFunction {
+    /// Value of the output for the given inputs, None when one is missing or the output doesn't settle
+    pub fn eval(&self, values: &FxHashMap<&str, bool>) -> Option<bool> {
+        let mut row = 0;
+        for (i, pin) in self.inputs.iter().enumerate() {
+            row |= usize::from(*values.get(&**pin)?) << i;
+        }
+        self.table[row]
+    }
+
+    /// Whether the output only depends on the inputs
+    pub fn is_combinational(&self) -> bool {
+        self.table.iter().all(Option::is_some)
+    }
+}
+
+/// Transistor seen as a switch, closed when its gate is high (nfet) or low (pfet)
+#[derive(Debug)]
+struct Switch {
+    nfet: bool,
+    drain: String,
+    /// None for the resistors, which are always closed
+    gate: Option<String>,
+    source: String,
 }
 
 #[derive(Debug)]
@@ -30,13 +89,18 @@ pub struct Subckt {
     body: String,
     pub input_pin_load: FxHashMap<String, Load>,
     pub output_pin_drive: FxHashMap<String, Drive>,
+    /// pins driving transistor gates, in the order of the .subckt line
+    inputs: Vec<SDFPin>,
+    switches: Vec<Switch>,
 }
 
 impl Subckt {
-    pub fn new<'a>(subckt_line: &'a str, lines: &mut impl Iterator<Item = &'a str>) -> Self {
+    pub fn new<'a>(subckt_line: &'a str, lines: &mut impl Iterator<Item = &'a str>, pdk: &PdkConfig) -> Result<Self> {
+        let invalid = |line: &str| StarsError::InvalidSubckt(line.to_string());
+
         let mut parts = subckt_line.split_whitespace();
         let _ = parts.next(); // .subckt
-        let name = parts.next().unwrap();
+        let name = parts.next().ok_or_else(|| invalid(subckt_line))?;
         let io_pins: Vec<_> = parts.map(String::from).collect();
 
         let mut body = String::with_capacity(256);
@@ -56,44 +120,65 @@ impl Subckt {
         }
 
         let mut transistors = Vec::new();
+        // (node, node, Ohm)
+        let mut resistors: Vec<(&str, &str, f32)> = Vec::new();
+        // (node, node, pF)
+        let mut capacitors: Vec<(&str, &str, f32)> = Vec::new();
 
-        while let Some(line) = lines.next() {
+        for line in lines.by_ref() {
             if line.starts_with(".ends") {
                 break;
             }
 
-            if line.starts_with('X') {
-                let mut words = line.split_whitespace();
-                let _ = words.next(); // Xtruc
-                let drain = words.next().unwrap();
-                let gate = words.next().unwrap();
-                let source = words.next().unwrap();
-                let _ = words.next(); // vpb or vnb
-                let kind = if words.next().unwrap().starts_with("sky130_fd_pr__nfet") {
-                    TransistorKind::Nfet
-                } else {
-                    TransistorKind::Pfet
-                };
-
-                let mut l_µm = 1.0; // in um
-                let mut w_µm = 1.0; // in um
-
-                for word in words {
-                    if word.starts_with("w=") {
-                        w_µm = word[2..].parse().unwrap();
-                    } else if word.starts_with("l=") {
-                        l_µm = word[2..].parse().unwrap();
+            let words: Vec<&str> = line.split_whitespace().skip(1).collect();
+            match line.chars().next().map(|c| c.to_ascii_uppercase()) {
+                // transistors are `Xtruc drain gate source bulk model w=.. l=..`, with `M` in CDL,
+                // the other subcircuits (diodes, resistors, cells) are left out
+                Some('X' | 'M') if words.iter().position(|word| word.contains('=')).unwrap_or(words.len()) == 5 => {
+                    let (drain, gate, source, model) = (words[0], words[1], words[2], words[4]);
+                    let kind = if pdk.is_nfet(model) {
+                        TransistorKind::Nfet
+                    } else {
+                        TransistorKind::Pfet
+                    };
+
+                    let mut l_µm = 1.0; // in um
+                    let mut w_µm = 1.0; // in um
+
+                    for word in &words[5..] {
+                        let Some((param, value)) = word.split_once('=') else {
+                            continue;
+                        };
+                        match param {
+                            "w" | "W" => w_µm = parse_µm(value).ok_or_else(|| invalid(line))?,
+                            "l" | "L" => l_µm = parse_µm(value).ok_or_else(|| invalid(line))?,
+                            _ => {}
+                        }
                     }
-                }
 
-                transistors.push(Transistor {
-                    kind,
-                    drain,
-                    gate,
-                    source,
-                    w_µm,
-                    l_µm,
-                })
+                    transistors.push(Transistor {
+                        kind,
+                        drain,
+                        gate,
+                        source,
+                        w_µm,
+                        l_µm,
+                    })
+                }
+                // `Rtruc a b 100` or `Rtruc a b model w=.. l=..`, the resistors given by their model
+                // (e.g. metal jumpers) are taken as shorts
+                Some('R') if words.len() >= 2 => {
+                    let ohms = words
+                        .get(2)
+                        .and_then(|value| spice_number(value))
+                        .map_or(0.0, |(v, _)| v);
+                    resistors.push((words[0], words[1], ohms as f32));
+                }
+                Some('C') if words.len() >= 3 => {
+                    let (farads, _) = spice_number(words[2]).ok_or_else(|| invalid(line))?;
+                    capacitors.push((words[0], words[1], (farads * 1e12) as f32));
+                }
+                _ => {}
             }
 
             body.push_str(line);
@@ -131,6 +216,7 @@ impl Subckt {
 
         let mut input_pin_load = FxHashMap::default();
         let mut output_pin_drive = FxHashMap::default();
+        let inputs: Vec<SDFPin> = input_pins.iter().map(|pin| pin.to_string()).collect();
 
         for pin in input_pins {
             let mut in_pfet_area = 0.0;
@@ -149,11 +235,18 @@ impl Subckt {
                 }
             }
 
+            let wire_cap = capacitors
+                .iter()
+                .filter(|(a, b, _)| a == pin || b == pin)
+                .map(|(_, _, cap)| cap)
+                .sum();
+
             input_pin_load.insert(
                 pin.to_string(),
                 Load {
                     pfet_area: in_pfet_area,
                     nfet_area: in_nfet_area,
+                    wire_cap,
                 },
             );
         }
@@ -161,12 +254,16 @@ impl Subckt {
         let mut pin_lw = FxHashMap::default();
         let mut visited = FxHashSet::default();
 
+        /// Worst L/W of the series transistors from `pin` to a supply, a resistor counting for its resistance
+        /// over the equivalent resistance `eq_resistance` of the transistors
         fn calc_lw<'a>(
             pin_wl: &mut FxHashMap<&'a str, f32>,
             visited: &mut FxHashSet<&'a str>,
             transistors: &[Transistor<'a>],
+            resistors: &[(&'a str, &'a str, f32)],
             pin: &'a str,
             kind: TransistorKind,
+            eq_resistance: f32,
         ) -> f32 {
             match pin {
                 "VGND" | "VPWR" | "VNB" | "VPB" => return 0.0,
@@ -181,24 +278,35 @@ impl Subckt {
                 if transistor.kind != kind {
                     continue;
                 }
+                let lw = transistor.l_µm / transistor.w_µm;
                 if transistor.drain == pin {
                     if visited.contains(&transistor.source) {
                         continue;
                     }
-                    max_lw = max_lw.max(
-                        calc_lw(pin_wl, visited, transistors, &*transistor.source, kind)
-                            + transistor.l_µm / transistor.w_µm,
-                    );
+                    let next = transistor.source;
+                    max_lw =
+                        max_lw.max(calc_lw(pin_wl, visited, transistors, resistors, next, kind, eq_resistance) + lw);
                 }
                 if transistor.source == pin {
                     if visited.contains(&transistor.drain) {
                         continue;
                     }
-                    max_lw = max_lw.max(
-                        calc_lw(pin_wl, visited, transistors, &*transistor.drain, kind)
-                            + transistor.l_µm / transistor.w_µm,
-                    );
+                    let next = transistor.drain;
+                    max_lw =
+                        max_lw.max(calc_lw(pin_wl, visited, transistors, resistors, next, kind, eq_resistance) + lw);
+                }
+            }
+            for &(a, b, ohms) in resistors {
+                let next = match pin {
+                    _ if a == pin => b,
+                    _ if b == pin => a,
+                    _ => continue,
+                };
+                if visited.contains(next) {
+                    continue;
                 }
+                let lw = ohms / eq_resistance;
+                max_lw = max_lw.max(calc_lw(pin_wl, visited, transistors, resistors, next, kind, eq_resistance) + lw);
             }
             pin_wl.insert(pin, max_lw);
             max_lw
@@ -207,11 +315,28 @@ impl Subckt {
         for pin in output_pins {
             pin_lw.clear();
             visited.clear();
-            let rise_lw = calc_lw(&mut pin_lw, &mut visited, &transistors, &**pin, TransistorKind::Pfet);
+            let (pfet, nfet) = (TransistorKind::Pfet, TransistorKind::Nfet);
+            let rise_lw = calc_lw(
+                &mut pin_lw,
+                &mut visited,
+                &transistors,
+                &resistors,
+                pin,
+                pfet,
+                pdk.pfet.eq_resistance,
+            );
 
             pin_lw.clear();
             visited.clear();
-            let fall_lw = calc_lw(&mut pin_lw, &mut visited, &transistors, &**pin, TransistorKind::Nfet);
+            let fall_lw = calc_lw(
+                &mut pin_lw,
+                &mut visited,
+                &transistors,
+                &resistors,
+                pin,
+                nfet,
+                pdk.nfet.eq_resistance,
+            );
 
             output_pin_drive.insert(pin.to_string(), Drive { rise_lw, fall_lw });
         }
@@ -223,37 +348,325 @@ impl Subckt {
             temp_variables_set.insert(&transistor.source);
         }
 
+        for (a, b, _) in resistors.iter().chain(&capacitors) {
+            temp_variables_set.insert(a);
+            temp_variables_set.insert(b);
+        }
+
         for pin in io_pins.iter() {
             temp_variables_set.remove(&&**pin);
         }
 
-        Subckt {
+        let switches = transistors
+            .iter()
+            .map(|transistor| Switch {
+                nfet: transistor.kind == TransistorKind::Nfet,
+                drain: transistor.drain.to_string(),
+                gate: Some(transistor.gate.to_string()),
+                source: transistor.source.to_string(),
+            })
+            .chain(resistors.iter().map(|&(a, b, _)| Switch {
+                nfet: false,
+                drain: a.to_string(),
+                gate: None,
+                source: b.to_string(),
+            }))
+            .collect();
+
+        Ok(Subckt {
             name: name.to_string(),
             temp_variables: temp_variables_set.into_iter().map(ToString::to_string).collect(),
             pins: io_pins,
             body,
             input_pin_load,
             output_pin_drive,
+            inputs,
+            switches,
+        })
+    }
+
+    /// Value of every node of the transistor network for the given input values, with a switch-level simulation.
+    /// None when a node is floating or driven both ways, e.g. in the feedback loops of the flip-flops.
+    fn evaluate<'a>(&'a self, inputs: &FxHashMap<&'a str, bool>) -> FxHashMap<&'a str, Option<bool>> {
+        let driven = |node: &str| match node {
+            "VPWR" => Some(true),
+            "VGND" => Some(false),
+            _ => inputs.get(node).copied(),
+        };
+
+        let mut values: FxHashMap<&str, Option<bool>> = FxHashMap::default();
+        // each round settles the nodes whose gates were settled by the previous one
+        for _ in 0..=self.switches.len() {
+            let value = |node: &str| driven(node).or_else(|| values.get(node).copied().flatten());
+            let closed: Vec<&Switch> = self
+                .switches
+                .iter()
+                .filter(|switch| {
+                    switch
+                        .gate
+                        .as_deref()
+                        .is_none_or(|gate| value(gate) == Some(switch.nfet))
+                })
+                .collect();
+
+            // (reached from a high node, reached from a low node)
+            let mut reached: FxHashMap<&str, (bool, bool)> = FxHashMap::default();
+            let sources = self
+                .switches
+                .iter()
+                .flat_map(|switch| [&*switch.drain, &*switch.source]);
+            for source in sources.filter_map(|node| Some((node, driven(node)?))) {
+                let (start, high) = source;
+                let mut stack = vec![start];
+                let mut visited = FxHashSet::default();
+                while let Some(node) = stack.pop() {
+                    for switch in &closed {
+                        let other = match node {
+                            _ if switch.drain == node => &*switch.source,
+                            _ if switch.source == node => &*switch.drain,
+                            _ => continue,
+                        };
+                        if driven(other).is_some() || !visited.insert(other) {
+                            continue;
+                        }
+                        let entry = reached.entry(other).or_default();
+                        if high {
+                            entry.0 = true;
+                        } else {
+                            entry.1 = true;
+                        }
+                        stack.push(other);
+                    }
+                }
+            }
+
+            let next: FxHashMap<&str, Option<bool>> = self
+                .switches
+                .iter()
+                .flat_map(|switch| [Some(&*switch.drain), switch.gate.as_deref(), Some(&*switch.source)])
+                .flatten()
+                .filter(|node| driven(node).is_none())
+                .map(|node| {
+                    let value = match reached.get(node) {
+                        Some((true, false)) => Some(true),
+                        Some((false, true)) => Some(false),
+                        _ => None,
+                    };
+                    (node, value)
+                })
+                .collect();
+            if next == values {
+                break;
+            }
+            values = next;
+        }
+
+        for node in ["VPWR", "VGND"].into_iter().chain(inputs.keys().copied()) {
+            values.insert(node, driven(node));
+        }
+        values
+    }
+
+    /// Boolean function of every output of the cell, see [`LogicFunction`]
+    pub fn extract_function(&self) -> FxHashMap<SDFPin, LogicFunction> {
+        self.output_pin_drive
+            .keys()
+            .filter(|output| !self.inputs.contains(output))
+            .map(|output| (output.clone(), self.function(output)))
+            .collect()
+    }
+
+    fn function(&self, output: &str) -> LogicFunction {
+        let table = (0..1u32 << self.inputs.len())
+            .map(|row| {
+                let values: FxHashMap<&str, bool> = self
+                    .inputs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, pin)| (&**pin, row >> i & 1 == 1))
+                    .collect();
+                self.evaluate(&values).get(output).copied().flatten()
+            })
+            .collect();
+        LogicFunction {
+            inputs: self.inputs.clone(),
+            table,
+        }
+    }
+
+    /// Values of the other inputs propagating a transition of `input` to `output`, from all high to all low
+    /// (the first pin of the .subckt line being the least significant).
+    /// Empty for the sequential cells, whose outputs don't settle from the inputs alone, and when `output` is an input.
+    pub fn transition_combinations(&self, input: &str, output: &str) -> Vec<TransitionCombination> {
+        if input == output || self.inputs.iter().any(|pin| pin == output) {
+            return Vec::new();
+        }
+        let Some(position) = self.inputs.iter().position(|pin| pin == input) else {
+            return Vec::new();
+        };
+        let function = self.function(output);
+        let others: Vec<usize> = (0..self.inputs.len()).filter(|&i| i != position).collect();
+
+        let mut combinations = Vec::new();
+        for assignment in (0..1usize << others.len()).rev() {
+            let row = others
+                .iter()
+                .enumerate()
+                .fold(0, |row, (bit, &i)| row | (assignment >> bit & 1) << i);
+            let (low, high) = (function.table[row], function.table[row | 1 << position]);
+
+            if let (Some(high), Some(low)) = (high, low) {
+                if high != low {
+                    combinations.push(TransitionCombination {
+                        pins: others
+                            .iter()
+                            .map(|&i| (self.inputs[i].clone(), row >> i & 1 == 1))
+                            .collect(),
+                        unate: if high { BiUnate::Positive } else { BiUnate::Negative },
+                    });
+                }
+            }
+        }
+        combinations
+    }
+}
+
+/// SUBCKT as written in the file, before its instances of other SUBCKTs are flattened
+struct RawSubckt<'a> {
+    line: &'a str,
+    pins: Vec<&'a str>,
+    body: Vec<&'a str>,
+}
+
+/// Body of the SUBCKT `name` with the instances of the other SUBCKTs replaced by their (flattened) body.
+/// The elements and internal nodes of an instance `Xinst` are renamed `elem_inst` and `inst/node`,
+/// `stack` holds the SUBCKTs being flattened to leave the recursive instances as they are.
+fn flatten<'a>(raw: &FxHashMap<&'a str, RawSubckt<'a>>, name: &'a str, stack: &mut Vec<&'a str>) -> Vec<String> {
+    let mut body = Vec::new();
+    for &line in &raw[name].body {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let positional: Vec<&str> = words.iter().copied().filter(|word| !word.contains('=')).collect();
+        let instance = match positional.split_last() {
+            Some((model, [element, nodes @ ..])) if element.starts_with(['X', 'x']) => raw
+                .get(model)
+                .filter(|sub| sub.pins.len() == nodes.len() && !stack.contains(model))
+                .map(|sub| (&element[1..], model, sub, nodes)),
+            _ => None,
+        };
+        let Some((instance, model, sub, nodes)) = instance else {
+            body.push(line.to_string());
+            continue;
+        };
+
+        let connections: FxHashMap<&str, &str> = sub.pins.iter().copied().zip(nodes.iter().copied()).collect();
+        stack.push(model);
+        for sub_line in flatten(raw, model, stack) {
+            let sub_words: Vec<&str> = sub_line.split_whitespace().collect();
+            let Some((element, rest)) = sub_words.split_first() else {
+                continue;
+            };
+            let n_nodes = element_nodes(&sub_words);
+            let mut new_line = format!("{}_{}", element, instance);
+            for (i, word) in rest.iter().enumerate() {
+                new_line.push(' ');
+                match connections.get(word) {
+                    Some(node) if i < n_nodes => new_line.push_str(node),
+                    None if i < n_nodes => new_line.push_str(&format!("{}/{}", instance, word)),
+                    _ => new_line.push_str(word),
+                }
+            }
+            body.push(new_line);
         }
+        stack.pop();
     }
+    body
+}
+
+/// Number of nodes after the name of an element line
+fn element_nodes(words: &[&str]) -> usize {
+    match words
+        .first()
+        .and_then(|element| element.chars().next())
+        .map(|c| c.to_ascii_uppercase())
+    {
+        // the last positional word is the model
+        Some('X' | 'M') => words
+            .iter()
+            .filter(|word| !word.contains('='))
+            .count()
+            .saturating_sub(2),
+        Some('R' | 'C' | 'L' | 'D' | 'V' | 'I') => 2,
+        _ => 0,
+    }
+}
+
+/// Length of a transistor in µm: bare numbers are µm (`.option scale=1u` of the extracted SUBCKTs) unless they are
+/// too small to be, values with a suffix are meters like in CDL (`W=0.65u`)
+fn parse_µm(word: &str) -> Option<f32> {
+    let (value, scaled) = spice_number(word)?;
+    Some(if scaled || value < 1e-3 { value * 1e6 } else { value } as f32)
 }
 
 impl SubcktData {
-    pub fn new(contents: &str) -> Self {
+    /// SUBCKTs of sky130 cells
+    pub fn new(contents: &str) -> Result<Self> {
+        Self::with_pdk(contents, PdkConfig::sky130())
+    }
+
+    pub fn with_pdk(contents: &str, pdk: PdkConfig) -> Result<Self> {
         let mut subckt_data = Self {
             data: Default::default(),
+            pdk,
         };
 
-        let mut lines = contents.lines();
+        // `+` lines continue the previous one
+        let mut lines: Vec<String> = Vec::new();
+        for line in contents.lines() {
+            match (line.strip_prefix('+'), lines.last_mut()) {
+                (Some(continuation), Some(last)) => {
+                    last.push(' ');
+                    last.push_str(continuation.trim_start());
+                }
+                _ => lines.push(line.to_string()),
+            }
+        }
 
-        while let Some(line) = lines.next() {
-            if line.starts_with(".subckt") {
-                let subckt = Subckt::new(line, &mut lines);
-                subckt_data.data.insert(subckt.name.clone(), subckt);
+        // .subckt line and body of each SUBCKT, the last one wins when a name is defined twice
+        let mut raw: FxHashMap<&str, RawSubckt> = FxHashMap::default();
+        let mut order = Vec::new();
+        let mut lines_iter = lines.iter().map(String::as_str);
+        while let Some(line) = lines_iter.next() {
+            if !line.starts_with(".subckt") {
+                continue;
+            }
+            let mut words = line.split_whitespace().skip(1);
+            let Some(name) = words.next() else {
+                return Err(StarsError::InvalidSubckt(line.to_string()));
+            };
+            let body = lines_iter
+                .by_ref()
+                .take_while(|line| !line.starts_with(".ends"))
+                .collect();
+            let subckt = RawSubckt {
+                line,
+                pins: words.collect(),
+                body,
+            };
+            if raw.insert(name, subckt).is_none() {
+                order.push(name);
             }
         }
 
-        subckt_data
+        for name in order {
+            let Some(subckt) = raw.get(name) else {
+                continue;
+            };
+            let body = flatten(&raw, name, &mut vec![name]);
+            let subckt = Subckt::new(subckt.line, &mut body.iter().map(String::as_str), &subckt_data.pdk)?;
+            subckt_data.data.insert(subckt.name.clone(), subckt);
+        }
+
+        Ok(subckt_data)
     }
 
     pub fn call(
@@ -262,17 +675,18 @@ impl SubcktData {
         celltype: &SDFCellType,
         values: &FxHashMap<&str, Cow<str>>,
         spice_append: &mut String,
-    ) {
-        let subckt = self.data.get(celltype).unwrap();
+    ) -> Result<()> {
+        let subckt = self.subckt(celltype)?;
 
         write!(spice_append, "X{} ", instance).unwrap();
         for pin in &subckt.pins {
-            let Some(val) = values.get(&**pin) else {
-                panic!("Missing value for pin {} for instance {}({})", pin, instance, celltype);
-            };
+            let val = values
+                .get(&**pin)
+                .ok_or_else(|| missing_value(pin, instance, celltype, values))?;
             write!(spice_append, "{} ", val).unwrap();
         }
         writeln!(spice_append, "{}", celltype).unwrap();
+        Ok(())
     }
 
     pub fn instanciate(
@@ -282,20 +696,20 @@ impl SubcktData {
         values: &FxHashMap<&str, Cow<str>>,
         spice_append: &mut String,
         nfet_override: &FxHashMap<&str, f32>,
-    ) {
-        let subckt = self.data.get(celltype).unwrap();
+    ) -> Result<()> {
+        let subckt = self.subckt(celltype)?;
 
         let mut substitutions =
             FxHashMap::with_capacity_and_hasher(subckt.temp_variables.len() + subckt.pins.len(), Default::default());
 
-        for (i, temp_variable) in subckt.temp_variables.iter().enumerate() {
-            substitutions.insert(&**temp_variable, format!("{}/temp{}", instance, i));
+        for temp_variable in &subckt.temp_variables {
+            substitutions.insert(&**temp_variable, format!("{}_{}", instance, temp_variable));
         }
 
         for pin in &subckt.pins {
-            let Some(val) = values.get(&**pin) else {
-                panic!("Missing value for pin {} for instance {}({})", pin, instance, celltype);
-            };
+            let val = values
+                .get(&**pin)
+                .ok_or_else(|| missing_value(pin, instance, celltype, values))?;
             substitutions.insert(pin, val.to_string());
         }
 
@@ -311,48 +725,96 @@ impl SubcktData {
                 }
                 if let Some(substitution) = substitutions.get(word) {
                     write!(&mut newline, "{} ", substitution).unwrap();
-                } else if word == "sky130_fd_pr__special_nfet_01v8" {
-                    write!(&mut newline, "sky130_fd_pr__nfet_01v8 ").unwrap();
+                } else if let Some(model) = self.pdk.model_aliases.get(word) {
+                    write!(&mut newline, "{} ", model).unwrap();
+                } else if word.ends_with('#') {
+                    // extracted internal node not seen on any transistor, still local to the instance
+                    write!(&mut newline, "{}_{} ", instance, word).unwrap();
                 } else {
                     write!(&mut newline, "{} ", word).unwrap();
                 }
             }
 
-            if nfet_override.len() > 0 {
-                if newline.contains("sky130_fd_pr__nfet_01v8") {
-                    let mut parts = newline.split_whitespace();
-                    let _ = parts.next();
-                    let _drain = parts.next();
-                    let gate = parts.next().unwrap();
-
-                    if let Some(v) = nfet_override.get(&*gate) {
-                        let mut new_line = String::with_capacity(newline.len());
-
-                        let mut mult_ = None;
+            if !nfet_override.is_empty() && newline.contains(&*self.pdk.nfet.model) {
+                let mut parts = newline.split_whitespace();
+                let _ = parts.next();
+                let _drain = parts.next();
+                let gate = parts
+                    .next()
+                    .ok_or_else(|| StarsError::InvalidSubckt(line.to_string()))?;
 
-                        for word in newline.split_whitespace() {
-                            if !word.starts_with("w=") {
-                                write!(&mut new_line, "{} ", word).unwrap();
-                                continue;
-                            }
+                if let Some(v) = nfet_override.get(gate) {
+                    let mut new_line = String::with_capacity(newline.len());
 
-                            let (closest_bin, mult) = crate::spice::nfet_size(*v);
-                            mult_ = Some(mult);
+                    let mut mult_ = None;
 
-                            write!(&mut new_line, "w={} ", closest_bin).unwrap();
+                    for word in newline.split_whitespace() {
+                        if !word.starts_with("w=") {
+                            write!(&mut new_line, "{} ", word).unwrap();
+                            continue;
                         }
 
-                        if let Some(mult) = mult_ {
-                            write!(&mut new_line, "m={} ", mult).unwrap();
-                        }
+                        let (closest_bin, mult) = self.pdk.nfet.size(*v);
+                        mult_ = Some(mult);
+
+                        write!(&mut new_line, "w={} ", closest_bin).unwrap();
+                    }
 
-                        newline = new_line;
+                    if let Some(mult) = mult_ {
+                        write!(&mut new_line, "m={} ", mult).unwrap();
                     }
+
+                    newline = new_line;
                 }
             }
 
             writeln!(spice_append, "{}", newline).unwrap();
         }
+        Ok(())
+    }
+
+    /// Subckt of a celltype
+    pub fn subckt(&self, celltype: &str) -> Result<&Subckt> {
+        self.data
+            .get(celltype)
+            .ok_or_else(|| StarsError::UnknownSubckt(celltype.to_string()))
+    }
+
+    /// Check that every celltype instanciated in the design has a SUBCKT, before extracting any deck.
+    /// The error lists all the missing celltypes, sorted
+    pub fn validate_against_graph(&self, graph: &SDFGraph) -> Result<()> {
+        let mut missing: Vec<String> = graph
+            .instance_celltype
+            .iter()
+            // the design itself is the cell without an instance
+            .filter(|&(instance, celltype)| {
+                !graph.symbols.instance_name(instance).is_empty() && !self.data.contains_key(celltype)
+            })
+            .map(|(_, celltype)| celltype.clone())
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        missing.sort_unstable();
+        missing.dedup();
+        Err(StarsError::MissingSubckts(missing))
+    }
+
+    /// Capacitance in pF of an input pin computed from the gate area of its transistors
+    pub fn pin_capacitance(&self, celltype: &str, pin: &str) -> Option<f32> {
+        let load = self.data.get(celltype)?.input_pin_load.get(pin)?;
+        Some(load.capacitance(&self.pdk))
+    }
+}
+
+fn missing_value(pin: &str, instance: &str, celltype: &str, values: &FxHashMap<&str, Cow<str>>) -> StarsError {
+    let mut available: Vec<_> = values.keys().copied().collect();
+    available.sort_unstable();
+    StarsError::MissingPinValue {
+        pin: pin.to_string(),
+        instance: instance.to_string(),
+        celltype: celltype.to_string(),
+        available: available.join(", "),
     }
 }
 
@@ -370,7 +832,7 @@ M3 y a c vdd sky130_fd_sc_hd__pmos
 M4 a_test# a vdd vdd sky130_fd_sc_hd__nmos
 .ends"#;
 
-        let subckt_data = SubcktData::new(contents);
+        let subckt_data = SubcktData::new(contents).unwrap();
 
         eprintln!("{:#?}", &subckt_data);
 
@@ -381,30 +843,125 @@ M4 a_test# a vdd vdd sky130_fd_sc_hd__nmos
         values.insert("y", "oy".into());
 
         let mut spice = String::new();
-        subckt_data.call(
-            &"and4_0".to_string(),
-            &"sky130_fd_sc_hd__and4".to_string(),
-            &values,
-            &mut spice,
-        );
+        subckt_data
+            .call(
+                &"and4_0".to_string(),
+                &"sky130_fd_sc_hd__and4".to_string(),
+                &values,
+                &mut spice,
+            )
+            .unwrap();
 
         let expected = "Xand4_0 oa ob oc oy sky130_fd_sc_hd__and4\n";
         assert_eq!(spice, expected);
 
         let mut spice = String::new();
-        subckt_data.instanciate(
-            &"and4_0".to_string(),
-            &"sky130_fd_sc_hd__and4".to_string(),
-            &values,
-            &mut spice,
-            &FxHashMap::from_iter([("oc", 1.0)]),
-        );
-
-        let expected = r#"M1_and4_0 oy oa ob vdd sky130_fd_pr__nfet_01v8 
-M2_and4_0 oy oc ob vdd sky130_fd_pr__nfet_01v8 w=1 l=0.15 m=1 
-M3_and4_0 oy oa oc vdd sky130_fd_sc_hd__pmos 
-M4_and4_0 and4_0_a_test# oa vdd vdd sky130_fd_sc_hd__nmos 
+        subckt_data
+            .instanciate(
+                &"and4_0".to_string(),
+                &"sky130_fd_sc_hd__and4".to_string(),
+                &values,
+                &mut spice,
+                &FxHashMap::from_iter([("oc", 1.0)]),
+            )
+            .unwrap();
+
+        // M cards are transistors too, vdd isn't a pin so it's local to the instance
+        let expected = r#"M1_and4_0 oy oa ob and4_0_vdd sky130_fd_pr__nfet_01v8 
+M2_and4_0 oy oc ob and4_0_vdd sky130_fd_pr__nfet_01v8 w=1 l=0.15 m=1 
+M3_and4_0 oy oa oc and4_0_vdd sky130_fd_sc_hd__pmos 
+M4_and4_0 and4_0_a_test# oa and4_0_vdd and4_0_vdd sky130_fd_sc_hd__nmos 
 "#;
         assert_eq!(spice, expected);
     }
+
+    #[test]
+    fn test_transition_combinations() {
+        let contents = r#"
+.subckt sky130_fd_sc_hd__nand2_1 VPB VNB VGND VPWR A Y B
+X0 VPWR A Y VPB sky130_fd_pr__pfet_01v8_hvt ad=0.26 pd=2.52 as=0.135 ps=1.27 w=1 l=0.15
+X1 Y A a_113_47# VNB sky130_fd_pr__nfet_01v8 ad=0.169 pd=1.82 as=0.08775 ps=0.92 w=0.65 l=0.15
+X2 a_113_47# B VGND VNB sky130_fd_pr__nfet_01v8 ad=0.08775 pd=0.92 as=0.169 ps=1.82 w=0.65 l=0.15
+X3 Y B VPWR VPB sky130_fd_pr__pfet_01v8_hvt ad=0.135 pd=1.27 as=0.26 ps=2.52 w=1 l=0.15
+.ends"#;
+
+        let subckt_data = SubcktData::new(contents).unwrap();
+        let nand2 = subckt_data.subckt("sky130_fd_sc_hd__nand2_1").unwrap();
+
+        let combinations = nand2.transition_combinations("A", "Y");
+        assert_eq!(combinations.len(), 1);
+        assert_eq!(combinations[0].pins, FxHashMap::from_iter([("B".to_string(), true)]));
+        assert_eq!(combinations[0].unate, BiUnate::Negative);
+
+        assert!(nand2.transition_combinations("A", "B").is_empty());
+
+        let function = &nand2.extract_function()["Y"];
+        assert_eq!(function.inputs, ["A", "B"]);
+        assert_eq!(function.table, [Some(true), Some(true), Some(true), Some(false)]);
+        assert_eq!(
+            function.eval(&FxHashMap::from_iter([("A", true), ("B", false)])),
+            Some(true)
+        );
+        assert!(function.is_combinational());
+    }
+
+    #[test]
+    fn test_cdl_subckt() {
+        let contents = r#"
+.subckt inv_1 A Y VGND VPWR VNB VPB
+M0 Y A VGND VNB sky130_fd_pr__nfet_01v8 W=0.65u L=0.15u
+M1 Y A n1 VPB sky130_fd_pr__pfet_01v8_hvt W=1e-06 L=1.5e-07
+R0 n1 VPWR 1k
+C0 A VGND 2f
+.ends"#;
+
+        let subckt_data = SubcktData::new(contents).unwrap();
+        let inv = subckt_data.subckt("inv_1").unwrap();
+
+        let load = &inv.input_pin_load["A"];
+        assert!((load.nfet_area - 0.65 * 0.15).abs() < 1e-6);
+        assert!((load.pfet_area - 0.15).abs() < 1e-6);
+        assert!((load.wire_cap - 0.002).abs() < 1e-9);
+
+        // the resistor is in series with the pfet
+        let drive = &inv.output_pin_drive["Y"];
+        let pfet_res = PdkConfig::sky130().pfet.eq_resistance;
+        assert!((drive.rise_lw - (0.15 + 1000.0 / pfet_res)).abs() < 1e-4);
+        assert!((drive.fall_lw - 0.15 / 0.65).abs() < 1e-4);
+        assert!(inv.temp_variables.contains(&"n1".to_string()));
+
+        assert_eq!(inv.transition_combinations("A", "Y")[0].unate, BiUnate::Negative);
+    }
+
+    #[test]
+    fn test_flatten() {
+        let contents = r#"
+.subckt buf A X VGND VPWR VNB VPB
+Xinv_0 VPB VNB VPWR VGND inv_0/Y A
++ inv
+Xinv_1 VPB VNB VPWR VGND X inv_0/Y inv
+.ends
+.subckt inv VPB VNB VPWR VGND Y A
+X0 Y A VGND VNB sky130_fd_pr__nfet_01v8 w=0.65 l=0.15
+X1 Y A n1 VPB sky130_fd_pr__pfet_01v8_hvt w=1 l=0.15
+R0 n1 VPWR 0
+.ends"#;
+
+        let subckt_data = SubcktData::new(contents).unwrap();
+        let buf = subckt_data.subckt("buf").unwrap();
+
+        assert_eq!(
+            buf.body,
+            "X0_inv_0 inv_0/Y A VGND VNB sky130_fd_pr__nfet_01v8 w=0.65 l=0.15
+X1_inv_0 inv_0/Y A inv_0/n1 VPB sky130_fd_pr__pfet_01v8_hvt w=1 l=0.15
+R0_inv_0 inv_0/n1 VPWR 0
+X0_inv_1 X inv_0/Y VGND VNB sky130_fd_pr__nfet_01v8 w=0.65 l=0.15
+X1_inv_1 X inv_0/Y inv_1/n1 VPB sky130_fd_pr__pfet_01v8_hvt w=1 l=0.15
+R0_inv_1 inv_1/n1 VPWR 0
+"
+        );
+        assert!(buf.input_pin_load["A"].nfet_area > 0.0);
+        assert!((buf.output_pin_drive["X"].fall_lw - 0.15 / 0.65).abs() < 1e-4);
+        assert_eq!(buf.transition_combinations("A", "X")[0].unate, BiUnate::Positive);
+    }
 }
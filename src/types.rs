@@ -1,7 +1,11 @@
-use miniserde::Deserialize;
-use std::collections::{BTreeMap, BTreeSet};
+use crate::memory::HeapSize;
+use miniserde::{Deserialize, Serialize};
+use rustc_hash::FxHashMap;
+use std::collections::BTreeSet;
 use std::fmt::{Display, Formatter};
-use std::ops::Neg;
+use std::marker::PhantomData;
+use std::ops::{Index, IndexMut, Neg};
+use std::str::FromStr;
 
 /// Example: instance3/A
 pub type SDFPin = String;
@@ -9,16 +13,388 @@ pub type SDFPin = String;
 pub type SDFInstance = String;
 /// Example: sky130_fd_sc_hd__xor2_1
 pub type SDFCellType = String;
-/// Example: (instance1/A, rise)
-pub type PinTrans = (SDFPin, Transition);
+/// Example: (instance1/A, rise), with the pin interned in the graph's `SymbolTable`
+pub type PinTrans = (PinId, Transition);
 
-pub type PinMap<V> = BTreeMap<SDFPin, V>;
-pub type PinTransMap<V> = BTreeMap<PinTrans, V>;
-pub type PinSet = BTreeSet<SDFPin>;
+pub type PinMap<V> = IdMap<PinId, V>;
+pub type PinTransMap<V> = IdMap<PinTrans, V>;
+pub type PinSet = BTreeSet<PinId>;
 pub type PinTransSet = BTreeSet<PinTrans>;
-pub type InstanceMap<V> = BTreeMap<SDFInstance, V>;
+pub type InstanceMap<V> = IdMap<InstanceId, V>;
 
-#[derive(Debug, Deserialize)]
+/// Interned [`SDFPin`], see [`SymbolTable`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PinId(pub u32);
+
+/// Interned [`SDFInstance`], see [`SymbolTable`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InstanceId(pub u32);
+
+/// Integer id handed out by an [`Interner`], ids are dense and start at 0.
+pub trait Id: Copy {
+    fn from_index(index: usize) -> Self;
+    fn index(self) -> usize;
+}
+
+impl Id for PinId {
+    fn from_index(index: usize) -> Self {
+        PinId(index as u32)
+    }
+
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl Id for InstanceId {
+    fn from_index(index: usize) -> Self {
+        InstanceId(index as u32)
+    }
+
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Both transitions of a pin are next to each other: `2 * pin + (0 for rise, 1 for fall)`.
+impl Id for PinTrans {
+    fn from_index(index: usize) -> Self {
+        let transition = match index % 2 {
+            0 => Transition::Rise,
+            _ => Transition::Fall,
+        };
+        (PinId((index / 2) as u32), transition)
+    }
+
+    fn index(self) -> usize {
+        self.0.index() * 2 + self.1 as usize
+    }
+}
+
+/// Map keyed by an [`Id`], stored as a `Vec` indexed by the id.
+/// Lookups are a bounds check away, iteration goes in increasing id order (same order as a `BTreeMap`).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IdMap<K, V> {
+    values: Vec<Option<V>>,
+    len: usize,
+    _key: PhantomData<K>,
+}
+
+impl<K, V> Default for IdMap<K, V> {
+    fn default() -> Self {
+        Self {
+            values: Vec::new(),
+            len: 0,
+            _key: PhantomData,
+        }
+    }
+}
+
+impl<K: Id, V> IdMap<K, V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.values.get(key.index()).and_then(Option::as_ref)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.values.get_mut(key.index()).and_then(Option::as_mut)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let old = self.slot(key).replace(value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let old = self.values.get_mut(key.index()).and_then(Option::take);
+        if old.is_some() {
+            self.len -= 1;
+        }
+        old
+    }
+
+    pub fn entry(&mut self, key: K) -> IdMapEntry<'_, V> {
+        let index = key.index();
+        if index >= self.values.len() {
+            self.values.resize_with(index + 1, || None);
+        }
+        IdMapEntry {
+            value: &mut self.values[index],
+            len: &mut self.len,
+        }
+    }
+
+    pub fn retain(&mut self, mut f: impl FnMut(K, &mut V) -> bool) {
+        for (i, slot) in self.values.iter_mut().enumerate() {
+            if let Some(value) = slot {
+                if !f(K::from_index(i), value) {
+                    *slot = None;
+                    self.len -= 1;
+                }
+            }
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (K, &V)> {
+        self.values
+            .iter()
+            .enumerate()
+            .filter_map(|(i, v)| v.as_ref().map(|v| (K::from_index(i), v)))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (K, &mut V)> {
+        self.values
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, v)| v.as_mut().map(|v| (K::from_index(i), v)))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = K> + '_ {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.values.iter().flatten()
+    }
+
+    fn slot(&mut self, key: K) -> &mut Option<V> {
+        let index = key.index();
+        if index >= self.values.len() {
+            self.values.resize_with(index + 1, || None);
+        }
+        &mut self.values[index]
+    }
+}
+
+/// See [`IdMap::entry`]
+pub struct IdMapEntry<'a, V> {
+    value: &'a mut Option<V>,
+    len: &'a mut usize,
+}
+
+impl<'a, V> IdMapEntry<'a, V> {
+    pub fn or_insert_with(self, f: impl FnOnce() -> V) -> &'a mut V {
+        if self.value.is_none() {
+            *self.len += 1;
+        }
+        self.value.get_or_insert_with(f)
+    }
+
+    pub fn or_insert(self, value: V) -> &'a mut V {
+        self.or_insert_with(|| value)
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+}
+
+impl<K: Id, V> Index<&K> for IdMap<K, V> {
+    type Output = V;
+
+    fn index(&self, key: &K) -> &V {
+        self.get(key).expect("key not in IdMap")
+    }
+}
+
+impl<K: Id, V> IndexMut<&K> for IdMap<K, V> {
+    fn index_mut(&mut self, key: &K) -> &mut V {
+        self.get_mut(key).expect("key not in IdMap")
+    }
+}
+
+impl<K: Id, V> FromIterator<(K, V)> for IdMap<K, V> {
+    fn from_iter<T: IntoIterator<Item = (K, V)>>(iter: T) -> Self {
+        let mut map = Self::new();
+        for (k, v) in iter {
+            map.insert(k, v);
+        }
+        map
+    }
+}
+
+impl<K, V: HeapSize> HeapSize for IdMap<K, V> {
+    fn heap_size(&self) -> usize {
+        self.values.heap_size()
+    }
+}
+
+impl<'a, K: Id, V> IntoIterator for &'a IdMap<K, V> {
+    type Item = (K, &'a V);
+    type IntoIter = Box<dyn Iterator<Item = (K, &'a V)> + 'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Box::new(self.iter())
+    }
+}
+
+/// Gives each distinct name a small integer id, so maps can be keyed by integers instead of strings.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Interner<I> {
+    names: Vec<String>,
+    ids: FxHashMap<String, u32>,
+    _id: PhantomData<I>,
+}
+
+impl<I> Default for Interner<I> {
+    fn default() -> Self {
+        Self {
+            names: Vec::new(),
+            ids: Default::default(),
+            _id: PhantomData,
+        }
+    }
+}
+
+impl<I: Id> Interner<I> {
+    pub fn intern(&mut self, name: &str) -> I {
+        if let Some(&id) = self.ids.get(name) {
+            return I::from_index(id as usize);
+        }
+        let id = self.names.len();
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id as u32);
+        I::from_index(id)
+    }
+
+    pub fn get(&self, name: &str) -> Option<I> {
+        self.ids.get(name).map(|&id| I::from_index(id as usize))
+    }
+
+    pub fn name(&self, id: I) -> &str {
+        &self.names[id.index()]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (I, &str)> {
+        self.names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (I::from_index(i), &**name))
+    }
+}
+
+impl<I> HeapSize for Interner<I> {
+    fn heap_size(&self) -> usize {
+        self.names.heap_size() + self.ids.heap_size()
+    }
+}
+
+/// Names of the pins and instances of a graph.
+/// Pins are full paths (e.g `and4/A`), their instance is the part before the last `/`.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SymbolTable {
+    pins: Interner<PinId>,
+    instances: Interner<InstanceId>,
+    /// instance of each pin, None for the ports of the design
+    pin_instance: Vec<Option<InstanceId>>,
+}
+
+impl SymbolTable {
+    pub fn intern_pin(&mut self, pin: &str) -> PinId {
+        let id = self.pins.intern(pin);
+        if id.index() == self.pin_instance.len() {
+            let instance = pin
+                .rsplit_once('/')
+                .map(|(instance, _)| self.instances.intern(instance));
+            self.pin_instance.push(instance);
+        }
+        id
+    }
+
+    pub fn intern_instance(&mut self, instance: &str) -> InstanceId {
+        self.instances.intern(instance)
+    }
+
+    pub fn pin_id(&self, pin: &str) -> Option<PinId> {
+        self.pins.get(pin)
+    }
+
+    pub fn instance_id(&self, instance: &str) -> Option<InstanceId> {
+        self.instances.get(instance)
+    }
+
+    /// Full name of the pin, e.g. `and4/A`
+    pub fn pin_name(&self, pin: PinId) -> &str {
+        self.pins.name(pin)
+    }
+
+    /// Name of the pin inside its instance, e.g. `A` for `and4/A`
+    pub fn pin_short_name(&self, pin: PinId) -> &str {
+        crate::pin_name_ref(self.pins.name(pin))
+    }
+
+    pub fn instance_name(&self, instance: InstanceId) -> &str {
+        self.instances.name(instance)
+    }
+
+    /// Instance the pin belongs to, None for the ports of the design
+    pub fn pin_instance(&self, pin: PinId) -> Option<InstanceId> {
+        self.pin_instance[pin.index()]
+    }
+
+    pub fn pins(&self) -> &Interner<PinId> {
+        &self.pins
+    }
+
+    /// Pins whose full name matches a glob pattern (see [`crate::glob_match`]), or the pin of that name when there
+    /// is no wildcard in the pattern
+    pub fn pins_matching(&self, pattern: &str) -> Vec<PinId> {
+        if !pattern.contains(['*', '?']) {
+            return self.pin_id(pattern).into_iter().collect();
+        }
+        self.pins
+            .iter()
+            .filter(|(_, name)| crate::glob_match(pattern, name))
+            .map(|(pin, _)| pin)
+            .collect()
+    }
+
+    pub fn instances(&self) -> &Interner<InstanceId> {
+        &self.instances
+    }
+}
+
+impl HeapSize for SymbolTable {
+    fn heap_size(&self) -> usize {
+        self.pins.heap_size() + self.instances.heap_size() + self.pin_instance.heap_size()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize)]
 pub enum TriUnate {
     #[serde(rename = "positive_unate")]
     Positive,
@@ -28,7 +404,8 @@ pub enum TriUnate {
     Non,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, PartialOrd, Ord)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Transition {
     /// 0 -> 1
     #[serde(rename = "rise")]
@@ -58,10 +435,68 @@ impl Neg for Transition {
     }
 }
 
-#[derive(Debug, Deserialize, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Deserialize, Eq, PartialEq)]
 pub enum BiUnate {
     #[serde(rename = "positive")]
     Positive,
     #[serde(rename = "negative")]
     Negative,
 }
+
+/// Which value of a `(min:typ:max)` triple to use when reading delays.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Corner {
+    #[default]
+    Min,
+    Typ,
+    Max,
+}
+
+impl Corner {
+    pub const ALL: [Corner; 3] = [Corner::Min, Corner::Typ, Corner::Max];
+
+    /// Pick the value for this corner out of a triple.
+    /// Falls back to the other corners (typ first) when the value is missing, as in `(1.0::1.2)`.
+    pub fn select<T: Copy>(self, min: Option<T>, typ: Option<T>, max: Option<T>) -> Option<T> {
+        let selected = match self {
+            Corner::Min => min,
+            Corner::Typ => typ,
+            Corner::Max => max,
+        };
+        selected.or(typ).or(min).or(max)
+    }
+}
+
+impl From<Corner> for sdfparse::SDFCorner {
+    fn from(corner: Corner) -> Self {
+        match corner {
+            Corner::Min => sdfparse::SDFCorner::Min,
+            Corner::Typ => sdfparse::SDFCorner::Typ,
+            Corner::Max => sdfparse::SDFCorner::Max,
+        }
+    }
+}
+
+impl Display for Corner {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Corner::Min => write!(f, "min"),
+            Corner::Typ => write!(f, "typ"),
+            Corner::Max => write!(f, "max"),
+        }
+    }
+}
+
+impl FromStr for Corner {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "min" => Ok(Corner::Min),
+            "typ" => Ok(Corner::Typ),
+            "max" => Ok(Corner::Max),
+            _ => Err(format!("Unknown corner {} (expected min, typ or max)", s)),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+/*
+ * C API of stars, static timing analysis from SDF files.
+ *
+ * Graphs, analyses and paths are created in an arena and referred to by handles, 0 meaning that the call failed
+ * (see stars_last_error). They are all freed with the arena. Times are in ns.
+ *
+ *     StarsArena *arena = stars_arena_new();
+ *     StarsGraph graph = stars_read_sdf(arena, "design.sdf", STARS_CORNER_MAX);
+ *     StarsAnalysis analysis = stars_analyze(arena, graph, 10.0f);
+ *     StarsPath path = stars_worst_path(arena, analysis, NULL);
+ *     if (!path)
+ *         fprintf(stderr, "%s\n", stars_last_error(arena));
+ *     for (size_t i = 0; i < stars_path_len(arena, path); i++)
+ *         printf("%s %.3f\n", stars_path_pin(arena, path, i), stars_path_arrival(arena, path, i));
+ *     stars_arena_free(arena);
+ *
+ * An arena must not be used from several threads at once.
+ */
+
+#ifndef STARS_H
+#define STARS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct StarsArena StarsArena;
+
+typedef uint32_t StarsGraph;
+typedef uint32_t StarsAnalysis;
+typedef uint32_t StarsPath;
+
+enum {
+    STARS_CORNER_MIN = 0,
+    STARS_CORNER_TYP = 1,
+    STARS_CORNER_MAX = 2,
+};
+
+enum {
+    STARS_RISE = 0,
+    STARS_FALL = 1,
+};
+
+StarsArena *stars_arena_new(void);
+void stars_arena_free(StarsArena *arena);
+/* message of the last call that returned 0, valid until the next call on the arena */
+const char *stars_last_error(const StarsArena *arena);
+
+/* timing graph of the SDF source `content` of `len` bytes, read at one of the STARS_CORNER_* */
+StarsGraph stars_parse_sdf(StarsArena *arena, const char *content, size_t len, uint32_t corner);
+/* same for a SDF file, which can be gzip or zstd compressed */
+StarsGraph stars_read_sdf(StarsArena *arena, const char *path, uint32_t corner);
+
+/* arrival and required times of the graph, unconstrained when `period` is not positive */
+StarsAnalysis stars_analyze(StarsArena *arena, StarsGraph graph, float period);
+/* slack of the worst endpoint, NaN without endpoint */
+float stars_worst_slack(const StarsArena *arena, StarsAnalysis analysis);
+
+/* worst path to the pin `endpoint` (e.g "u1/D"), or to the worst endpoint when NULL */
+StarsPath stars_worst_path(StarsArena *arena, StarsAnalysis analysis, const char *endpoint);
+size_t stars_path_len(const StarsArena *arena, StarsPath path);
+float stars_path_slack(const StarsArena *arena, StarsPath path);
+/* pins of the path from the startpoint to the endpoint, the strings are owned by the arena */
+const char *stars_path_pin(const StarsArena *arena, StarsPath path, size_t i);
+/* STARS_RISE or STARS_FALL, -1 when out of range */
+int32_t stars_path_transition(const StarsArena *arena, StarsPath path, size_t i);
+float stars_path_arrival(const StarsArena *arena, StarsPath path, size_t i);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* STARS_H */
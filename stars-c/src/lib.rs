@@ -0,0 +1,382 @@
+//! C API of stars, declared in `include/stars.h`.
+//!
+//! Everything is created in a `StarsArena` and referred to by a handle, an index in the arena starting at 1 so
+//! that 0 reports an error (see `stars_last_error`). The objects live until the arena is freed, so the caller
+//! never frees them one by one and a handle can't dangle while its arena is alive.
+
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use stars::analysis::{AnalysisOptions, PathReport, SDFGraphAnalyzed};
+use stars::graph::SDFGraph;
+use stars::types::{Corner, PinTrans, Transition};
+
+pub type StarsGraph = u32;
+pub type StarsAnalysis = u32;
+pub type StarsPath = u32;
+
+/// Graphs, analyses and paths created through the API
+#[derive(Default)]
+pub struct StarsArena {
+    graphs: Vec<SDFGraph>,
+    /// analyses with the index of their graph
+    analyses: Vec<(usize, SDFGraphAnalyzed)>,
+    paths: Vec<Path>,
+    /// message of the last failed call, empty if none
+    error: CString,
+}
+
+/// Pins of a path, copied out of the [`PathReport`] with their names so they can be handed to C
+struct Path {
+    pins: Vec<(CString, Transition, f32)>,
+    slack: f32,
+}
+
+impl StarsArena {
+    fn fail(&mut self, message: impl Into<String>) -> u32 {
+        // interior NULs can't be represented, the message is cut there
+        let mut message = message.into().into_bytes();
+        message.truncate(message.iter().position(|&b| b == 0).unwrap_or(message.len()));
+        self.error = CString::new(message).unwrap();
+        0
+    }
+
+    fn graph(&self, graph: StarsGraph) -> Option<&SDFGraph> {
+        self.graphs.get((graph as usize).checked_sub(1)?)
+    }
+
+    fn analysis(&self, analysis: StarsAnalysis) -> Option<(&SDFGraph, &SDFGraphAnalyzed)> {
+        let (graph, analysis) = self.analyses.get((analysis as usize).checked_sub(1)?)?;
+        Some((&self.graphs[*graph], analysis))
+    }
+
+    fn path(&self, path: StarsPath) -> Option<&Path> {
+        self.paths.get((path as usize).checked_sub(1)?)
+    }
+
+    /// Run `f`, turning its error or panic into the error of the arena and a 0 handle
+    fn call(&mut self, f: impl FnOnce(&mut Self) -> Result<u32, String>) -> u32 {
+        match catch_unwind(AssertUnwindSafe(|| f(self))) {
+            Ok(Ok(handle)) => handle,
+            Ok(Err(message)) => self.fail(message),
+            Err(_) => self.fail("stars panicked"),
+        }
+    }
+
+    fn add_graph(&mut self, content: &str, corner: u32) -> Result<StarsGraph, String> {
+        let corner = *Corner::ALL
+            .get(corner as usize)
+            .ok_or_else(|| format!("Unknown corner {}", corner))?;
+        let sdf = sdfparse::SDF::parse_str(content)?;
+        let graph = SDFGraph::from_sdf(sdf, corner).map_err(|e| e.to_string())?;
+        self.graphs.push(graph);
+        Ok(self.graphs.len() as u32)
+    }
+}
+
+/// # Safety
+/// `ptr` must be NULL or a valid NUL-terminated string
+unsafe fn str_arg<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, String> {
+    if ptr.is_null() {
+        return Err(format!("{} is NULL", name));
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map_err(|_| format!("{} is not valid UTF-8", name))
+}
+
+/// New empty arena, to free with `stars_arena_free`
+#[no_mangle]
+pub extern "C" fn stars_arena_new() -> *mut StarsArena {
+    Box::into_raw(Box::default())
+}
+
+/// Free the arena and everything created in it
+///
+/// # Safety
+/// `arena` must be NULL or come from `stars_arena_new`, and not be used afterwards
+#[no_mangle]
+pub unsafe extern "C" fn stars_arena_free(arena: *mut StarsArena) {
+    if !arena.is_null() {
+        drop(Box::from_raw(arena));
+    }
+}
+
+/// Message of the last call that returned 0, valid until the next call on the arena
+///
+/// # Safety
+/// `arena` must come from `stars_arena_new`
+#[no_mangle]
+pub unsafe extern "C" fn stars_last_error(arena: *const StarsArena) -> *const c_char {
+    (*arena).error.as_ptr()
+}
+
+/// Build the timing graph of the SDF source `content` of `len` bytes at `corner` (0 min, 1 typ, 2 max)
+///
+/// # Safety
+/// `arena` must come from `stars_arena_new` and `content` point to `len` readable bytes
+#[no_mangle]
+pub unsafe extern "C" fn stars_parse_sdf(
+    arena: *mut StarsArena,
+    content: *const c_char,
+    len: usize,
+    corner: u32,
+) -> StarsGraph {
+    let arena = &mut *arena;
+    if content.is_null() {
+        return arena.fail("content is NULL");
+    }
+    let bytes = std::slice::from_raw_parts(content as *const u8, len);
+    arena.call(|arena| {
+        let content = std::str::from_utf8(bytes).map_err(|e| format!("SDF is not valid UTF-8: {}", e))?;
+        arena.add_graph(content, corner)
+    })
+}
+
+/// Same as `stars_parse_sdf` for a SDF file, which can be gzip or zstd compressed
+///
+/// # Safety
+/// `arena` must come from `stars_arena_new` and `path` be a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn stars_read_sdf(arena: *mut StarsArena, path: *const c_char, corner: u32) -> StarsGraph {
+    let arena = &mut *arena;
+    let path = str_arg(path, "path");
+    arena.call(|arena| {
+        let path = path?;
+        let content = stars::read_input(path).map_err(|e| format!("{}: {}", path, e))?;
+        arena.add_graph(&content, corner)
+    })
+}
+
+/// Propagate the arrival and required times through `graph` with a clock `period` in ns, unconstrained when the
+/// period is not positive
+///
+/// # Safety
+/// `arena` must come from `stars_arena_new`
+#[no_mangle]
+pub unsafe extern "C" fn stars_analyze(arena: *mut StarsArena, graph: StarsGraph, period: f32) -> StarsAnalysis {
+    let arena = &mut *arena;
+    arena.call(|arena| {
+        let timing_graph = (arena.graph(graph)).ok_or_else(|| format!("Invalid graph handle {}", graph))?;
+        let options = AnalysisOptions {
+            period: (period > 0.0).then_some(period),
+            ..Default::default()
+        };
+        let analysis = SDFGraphAnalyzed::analyze_with(timing_graph, &options);
+        arena.analyses.push((graph as usize - 1, analysis));
+        Ok(arena.analyses.len() as u32)
+    })
+}
+
+/// Slack of the worst endpoint in ns, NaN for an invalid handle or a design without endpoint
+///
+/// # Safety
+/// `arena` must come from `stars_arena_new`
+#[no_mangle]
+pub unsafe extern "C" fn stars_worst_slack(arena: *const StarsArena, analysis: StarsAnalysis) -> f32 {
+    let Some((graph, analysis)) = (*arena).analysis(analysis) else {
+        return f32::NAN;
+    };
+    let endpoints = analysis.endpoints_by_slack(graph);
+    endpoints.first().map_or(f32::NAN, |(_, slack)| *slack)
+}
+
+/// Worst path to the pin `endpoint`, or to the worst endpoint of the design when it is NULL
+///
+/// # Safety
+/// `arena` must come from `stars_arena_new` and `endpoint` be NULL or a NUL-terminated string
+#[no_mangle]
+pub unsafe extern "C" fn stars_worst_path(
+    arena: *mut StarsArena,
+    analysis: StarsAnalysis,
+    endpoint: *const c_char,
+) -> StarsPath {
+    let arena = &mut *arena;
+    let endpoint = match endpoint.is_null() {
+        true => None,
+        false => Some(str_arg(endpoint, "endpoint")),
+    };
+    arena.call(|arena| {
+        let (graph, analyzed) =
+            (arena.analysis(analysis)).ok_or_else(|| format!("Invalid analysis handle {}", analysis))?;
+        let report = match endpoint {
+            Some(pin) => analyzed.pin_path_report(graph, pin?).map_err(|e| e.to_string())?,
+            None => {
+                let endpoints = analyzed.endpoints_by_slack(graph);
+                let (worst, _) = endpoints.first().ok_or("No endpoint is reached")?;
+                analyzed
+                    .path_report(graph, worst)
+                    .ok_or("No path to the worst endpoint")?
+            }
+        };
+        let path = Path::new(graph, analyzed, &report);
+        arena.paths.push(path);
+        Ok(arena.paths.len() as u32)
+    })
+}
+
+impl Path {
+    fn new(graph: &SDFGraph, analysis: &SDFGraphAnalyzed, report: &PathReport) -> Self {
+        let name = |node: PinTrans| CString::new(graph.symbols.pin_name(node.0)).unwrap_or_default();
+        Self {
+            pins: (report.pins())
+                .map(|(node, arrival)| (name(node), node.1, arrival))
+                .collect(),
+            slack: analysis.slack(&report.endpoint).unwrap_or(f32::NAN),
+        }
+    }
+}
+
+/// Number of pins of the path, from its startpoint to its endpoint. 0 for an invalid handle.
+///
+/// # Safety
+/// `arena` must come from `stars_arena_new`
+#[no_mangle]
+pub unsafe extern "C" fn stars_path_len(arena: *const StarsArena, path: StarsPath) -> usize {
+    (*arena).path(path).map_or(0, |path| path.pins.len())
+}
+
+/// Slack of the endpoint of the path in ns, NaN for an invalid handle
+///
+/// # Safety
+/// `arena` must come from `stars_arena_new`
+#[no_mangle]
+pub unsafe extern "C" fn stars_path_slack(arena: *const StarsArena, path: StarsPath) -> f32 {
+    (*arena).path(path).map_or(f32::NAN, |path| path.slack)
+}
+
+/// Name of the `i`-th pin of the path, owned by the arena. NULL when out of range.
+///
+/// # Safety
+/// `arena` must come from `stars_arena_new`
+#[no_mangle]
+pub unsafe extern "C" fn stars_path_pin(arena: *const StarsArena, path: StarsPath, i: usize) -> *const c_char {
+    let pin = (*arena).path(path).and_then(|path| path.pins.get(i));
+    pin.map_or(std::ptr::null(), |(name, _, _)| name.as_ptr())
+}
+
+/// Transition of the `i`-th pin of the path: 0 rise, 1 fall, -1 when out of range
+///
+/// # Safety
+/// `arena` must come from `stars_arena_new`
+#[no_mangle]
+pub unsafe extern "C" fn stars_path_transition(arena: *const StarsArena, path: StarsPath, i: usize) -> i32 {
+    let pin = (*arena).path(path).and_then(|path| path.pins.get(i));
+    match pin {
+        Some((_, Transition::Rise, _)) => 0,
+        Some((_, Transition::Fall, _)) => 1,
+        None => -1,
+    }
+}
+
+/// Arrival time in ns of the `i`-th pin of the path, NaN when out of range
+///
+/// # Safety
+/// `arena` must come from `stars_arena_new`
+#[no_mangle]
+pub unsafe extern "C" fn stars_path_arrival(arena: *const StarsArena, path: StarsPath, i: usize) -> f32 {
+    let pin = (*arena).path(path).and_then(|path| path.pins.get(i));
+    pin.map_or(f32::NAN, |(_, _, arrival)| *arrival)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUFFER: &str = r#"(DELAYFILE
+ (SDFVERSION "3.0")
+ (DESIGN "top")
+ (DIVIDER .)
+ (TIMESCALE 1ns)
+ (CELL (CELLTYPE "top") (INSTANCE)
+  (DELAY (ABSOLUTE
+   (INTERCONNECT a u1.A (0.010::0.030) (0.010::0.030))
+   (INTERCONNECT u1.X x (0.020::0.040) (0.020::0.040)))))
+ (CELL (CELLTYPE "sky130_fd_sc_hd__buf_1") (INSTANCE u1)
+  (DELAY (ABSOLUTE (IOPATH A X (0.100::0.300) (0.200::0.400)))))
+)"#;
+
+    unsafe fn last_error(arena: *const StarsArena) -> String {
+        CStr::from_ptr(stars_last_error(arena)).to_str().unwrap().to_string()
+    }
+
+    unsafe fn pin(arena: *const StarsArena, path: StarsPath, i: usize) -> &'static str {
+        CStr::from_ptr(stars_path_pin(arena, path, i)).to_str().unwrap()
+    }
+
+    #[test]
+    fn test_worst_path() {
+        unsafe {
+            let arena = stars_arena_new();
+            let graph = stars_parse_sdf(arena, BUFFER.as_ptr() as *const c_char, BUFFER.len(), 2);
+            assert_eq!(graph, 1);
+            let analysis = stars_analyze(arena, graph, 1.0);
+            assert_eq!(analysis, 1);
+
+            // the fall of the buffer is the slowest, on the max corner
+            let path = stars_worst_path(arena, analysis, std::ptr::null());
+            assert_eq!(path, 1);
+            assert_eq!(stars_path_len(arena, path), 4);
+            let pins: Vec<_> = (0..4).map(|i| pin(arena, path, i)).collect();
+            assert_eq!(pins, ["a", "u1/A", "u1/X", "x"]);
+            assert_eq!(stars_path_transition(arena, path, 3), 1);
+            assert!((stars_path_arrival(arena, path, 3) - 0.47).abs() < 1e-5);
+            assert!((stars_path_slack(arena, path) - 0.53).abs() < 1e-5);
+            assert_eq!(stars_path_slack(arena, path), stars_worst_slack(arena, analysis));
+
+            // out of range pins
+            assert!(stars_path_pin(arena, path, 4).is_null());
+            assert_eq!(stars_path_transition(arena, path, 4), -1);
+            assert!(stars_path_arrival(arena, path, 4).is_nan());
+
+            // the path to a given endpoint
+            let endpoint = CString::new("x").unwrap();
+            assert_eq!(stars_worst_path(arena, analysis, endpoint.as_ptr()), 2);
+            assert_eq!(stars_path_len(arena, 2), 4);
+
+            stars_arena_free(arena);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        unsafe {
+            let arena = stars_arena_new();
+            assert_eq!(last_error(arena), "");
+
+            assert_eq!(stars_parse_sdf(arena, std::ptr::null(), 0, 0), 0);
+            assert_eq!(last_error(arena), "content is NULL");
+            assert_eq!(
+                stars_parse_sdf(arena, BUFFER.as_ptr() as *const c_char, BUFFER.len(), 3),
+                0
+            );
+            assert_eq!(last_error(arena), "Unknown corner 3");
+            assert_eq!(stars_parse_sdf(arena, "(DELAYFILE".as_ptr() as *const c_char, 10, 0), 0);
+            assert_ne!(last_error(arena), "");
+            assert_eq!(stars_read_sdf(arena, std::ptr::null(), 0), 0);
+            assert_eq!(last_error(arena), "path is NULL");
+
+            // no graph, analysis or path was created, so every handle is invalid
+            assert_eq!(stars_analyze(arena, 1, 1.0), 0);
+            assert_eq!(last_error(arena), "Invalid graph handle 1");
+            assert_eq!(stars_analyze(arena, 0, 1.0), 0);
+            assert_eq!(last_error(arena), "Invalid graph handle 0");
+            assert_eq!(stars_worst_path(arena, 1, std::ptr::null()), 0);
+            assert_eq!(last_error(arena), "Invalid analysis handle 1");
+            assert!(stars_worst_slack(arena, 1).is_nan());
+            assert_eq!(stars_path_len(arena, 1), 0);
+            assert!(stars_path_slack(arena, 1).is_nan());
+            assert!(stars_path_pin(arena, 0, 0).is_null());
+            assert_eq!(stars_path_transition(arena, 0, 0), -1);
+
+            // an unknown endpoint
+            let graph = stars_parse_sdf(arena, BUFFER.as_ptr() as *const c_char, BUFFER.len(), 0);
+            let analysis = stars_analyze(arena, graph, 1.0);
+            let endpoint = CString::new("nope").unwrap();
+            assert_eq!(stars_worst_path(arena, analysis, endpoint.as_ptr()), 0);
+            assert_ne!(last_error(arena), "");
+
+            stars_arena_free(arena);
+        }
+    }
+}
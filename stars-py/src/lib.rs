@@ -0,0 +1,447 @@
+//! Python bindings of stars, built into the `stars_py` module with maturin (`pip install ./stars-py`).
+//!
+//! ```python
+//! import stars_py
+//!
+//! graph = stars_py.Graph.read("design.sdf", corner="max")
+//! analysis = graph.analyze(period=10.0)
+//! for endpoint, transition, slack in analysis.endpoints_by_slack()[:10]:
+//!     path = analysis.path(endpoint, transition)
+//!     print(slack, path.startpoint, [pin for pin, _, _ in path.pins])
+//! ```
+//!
+//! Pins are given by their full name (`u1/A`) and transitions as `"rise"` or `"fall"`. When a transition is
+//! optional, the worst of the two is taken.
+
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use stars::analysis::{AnalysisOptions, PathReport, SDFGraphAnalyzed};
+use stars::error::StarsError;
+use stars::graph::SDFGraph;
+use stars::sdc::Sdc;
+use stars::types::{Corner, PinId, PinSet, PinTrans, Transition};
+
+fn py_err(e: StarsError) -> PyErr {
+    match e {
+        StarsError::Io(e) => PyIOError::new_err(e.to_string()),
+        e => PyValueError::new_err(e.to_string()),
+    }
+}
+
+fn parse_corner(corner: &str) -> PyResult<Corner> {
+    corner.parse().map_err(PyValueError::new_err)
+}
+
+fn parse_transition(transition: &str) -> PyResult<Transition> {
+    match transition {
+        "rise" => Ok(Transition::Rise),
+        "fall" => Ok(Transition::Fall),
+        _ => Err(PyValueError::new_err(format!(
+            "Unknown transition {} (expected rise or fall)",
+            transition
+        ))),
+    }
+}
+
+fn transition_name(transition: Transition) -> &'static str {
+    match transition {
+        Transition::Rise => "rise",
+        Transition::Fall => "fall",
+    }
+}
+
+/// Timing graph of a design, built from its SDF at one corner
+#[pyclass(module = "stars_py", frozen)]
+struct Graph {
+    graph: SDFGraph,
+}
+
+impl Graph {
+    fn name(&self, node: PinTrans) -> (String, &'static str) {
+        (self.graph.symbols.pin_name(node.0).to_string(), transition_name(node.1))
+    }
+
+    fn pin_id(&self, pin: &str) -> PyResult<PinId> {
+        (self.graph.symbols.pin_id(pin)).ok_or_else(|| py_err(StarsError::UnknownPin(pin.to_string())))
+    }
+
+    /// Nodes of a pin, only the given transition if any
+    fn nodes(&self, pin: &str, transition: Option<&str>) -> PyResult<Vec<PinTrans>> {
+        let id = self.pin_id(pin)?;
+        Ok(match transition {
+            Some(transition) => vec![(id, parse_transition(transition)?)],
+            None => vec![(id, Transition::Rise), (id, Transition::Fall)],
+        })
+    }
+}
+
+#[pymethods]
+impl Graph {
+    /// Build the graph of a SDF file, which can be gzip or zstd compressed
+    #[staticmethod]
+    #[pyo3(signature = (path, corner = "max"))]
+    fn read(path: &str, corner: &str) -> PyResult<Self> {
+        let content = stars::read_input(path).map_err(|e| PyIOError::new_err(format!("{}: {}", path, e)))?;
+        Self::parse(&content, corner)
+    }
+
+    /// Build the graph of the SDF source `content`
+    #[staticmethod]
+    #[pyo3(signature = (content, corner = "max"))]
+    fn parse(content: &str, corner: &str) -> PyResult<Self> {
+        let corner = parse_corner(corner)?;
+        let sdf = sdfparse::SDF::parse_str(content).map_err(PyValueError::new_err)?;
+        let graph = SDFGraph::from_sdf(sdf, corner).map_err(py_err)?;
+        Ok(Self { graph })
+    }
+
+    #[getter]
+    fn corner(&self) -> String {
+        self.graph.corner.to_string()
+    }
+
+    /// Names of all the pins of the design
+    fn pins(&self) -> Vec<String> {
+        (self.graph.symbols.pins().iter())
+            .map(|(_, name)| name.to_string())
+            .collect()
+    }
+
+    /// Instances of the design with their celltype
+    fn instances(&self) -> Vec<(String, String)> {
+        (self.graph.instance_celltype.iter())
+            .map(|(instance, celltype)| (self.graph.symbols.instance_name(instance).to_string(), celltype.clone()))
+            .collect()
+    }
+
+    /// Pins where the paths start (input ports and register outputs), with their transition
+    fn startpoints(&self) -> Vec<(String, &'static str)> {
+        self.graph.inputs.iter().map(|&node| self.name(node)).collect()
+    }
+
+    /// Pins where the paths end (output ports and register inputs), with their transition
+    fn endpoints(&self) -> Vec<(String, &'static str)> {
+        self.graph.outputs.iter().map(|&node| self.name(node)).collect()
+    }
+
+    /// Pins driven by `pin`: the sinks of its net for a driver, the outputs of its cell for an input pin
+    fn fanout(&self, pin: &str) -> PyResult<Vec<String>> {
+        let node = (self.pin_id(pin)?, Transition::Rise);
+        let edges = self.graph.graph.get(&node).into_iter().flatten();
+        let pins: PinSet = edges.map(|edge| edge.dst.0).collect();
+        Ok(pins
+            .into_iter()
+            .map(|pin| self.graph.symbols.pin_name(pin).to_string())
+            .collect())
+    }
+
+    /// Propagate the arrival and required times through the graph. The constraints come from the `sdc` file when
+    /// given, `period` and `propagated_clock` override them.
+    #[pyo3(signature = (period = None, propagated_clock = None, sdc = None))]
+    fn analyze(
+        slf: Bound<'_, Self>,
+        period: Option<f32>,
+        propagated_clock: Option<bool>,
+        sdc: Option<&str>,
+    ) -> PyResult<Analysis> {
+        let graph = &slf.get().graph;
+        let mut options = match sdc {
+            Some(path) => {
+                let content = stars::read_input(path).map_err(|e| PyIOError::new_err(format!("{}: {}", path, e)))?;
+                let sdc = Sdc::parse(&content).map_err(|e| py_err(e.into()))?;
+                sdc.analysis_options(graph)
+            }
+            None => AnalysisOptions::default(),
+        };
+        if period.is_some() {
+            options.period = period;
+        }
+        if let Some(propagated_clock) = propagated_clock {
+            options.propagated_clock = propagated_clock;
+        }
+        let analysis = SDFGraphAnalyzed::analyze_with(graph, &options);
+        Ok(Analysis {
+            graph: slf.unbind(),
+            analysis,
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Graph({} instances, {} pins, corner {})",
+            self.graph.instance_celltype.len(),
+            self.graph.symbols.pins().len(),
+            self.graph.corner
+        )
+    }
+}
+
+/// Arrival, required times and slacks of a graph, see `Graph.analyze`. Times are in ns.
+#[pyclass(module = "stars_py", frozen)]
+struct Analysis {
+    graph: Py<Graph>,
+    analysis: SDFGraphAnalyzed,
+}
+
+impl Analysis {
+    fn endpoints(&self, graph: &Graph) -> Vec<(PinTrans, f32)> {
+        self.analysis.endpoints_by_slack(&graph.graph)
+    }
+
+    fn path_of(&self, graph: &Graph, report: PathReport) -> Path {
+        let slack = self.analysis.slack(&report.endpoint);
+        Path::new(&graph.graph, report, slack)
+    }
+}
+
+#[pymethods]
+impl Analysis {
+    /// Latest arrival time at a pin, None if no path reaches it
+    #[pyo3(signature = (pin, transition = None))]
+    fn arrival(&self, pin: &str, transition: Option<&str>) -> PyResult<Option<f32>> {
+        let nodes = self.graph.get().nodes(pin, transition)?;
+        Ok(nodes
+            .iter()
+            .filter_map(|node| self.analysis.arrival(node))
+            .reduce(f32::max))
+    }
+
+    /// Slack of an endpoint, None if it is unconstrained or not reached
+    #[pyo3(signature = (endpoint, transition = None))]
+    fn slack(&self, endpoint: &str, transition: Option<&str>) -> PyResult<Option<f32>> {
+        let nodes = self.graph.get().nodes(endpoint, transition)?;
+        Ok(nodes
+            .iter()
+            .filter_map(|node| self.analysis.slack(node))
+            .reduce(f32::min))
+    }
+
+    /// Slack of the worst path going through a pin
+    #[pyo3(signature = (pin, transition = None))]
+    fn slack_through(&self, pin: &str, transition: Option<&str>) -> PyResult<Option<f32>> {
+        let graph = self.graph.get();
+        let nodes = graph.nodes(pin, transition)?;
+        Ok(nodes
+            .iter()
+            .filter_map(|node| self.analysis.slack_through(&graph.graph, node))
+            .reduce(f32::min))
+    }
+
+    /// Slack of the worst endpoint, None when nothing is constrained
+    #[getter]
+    fn worst_slack(&self) -> Option<f32> {
+        let endpoints = self.endpoints(self.graph.get());
+        endpoints.first().map(|(_, slack)| *slack)
+    }
+
+    /// Endpoints with their transition and slack, worst first
+    fn endpoints_by_slack(&self) -> Vec<(String, &'static str, f32)> {
+        let graph = self.graph.get();
+        (self.endpoints(graph).into_iter())
+            .map(|(node, slack)| {
+                let (pin, transition) = graph.name(node);
+                (pin, transition, slack)
+            })
+            .collect()
+    }
+
+    /// Worst path to an endpoint, or to any pin of the design
+    #[pyo3(signature = (pin, transition = None))]
+    fn path(&self, pin: &str, transition: Option<&str>) -> PyResult<Path> {
+        let graph = self.graph.get();
+        let report = match transition {
+            Some(_) => {
+                let node = graph.nodes(pin, transition)?[0];
+                self.analysis.node_path_report(&graph.graph, &node)
+            }
+            None => self.analysis.pin_path_report(&graph.graph, pin),
+        };
+        Ok(self.path_of(graph, report.map_err(py_err)?))
+    }
+
+    /// Worst path going through a pin, from its startpoint to its endpoint
+    fn path_through(&self, pin: &str) -> PyResult<Option<Path>> {
+        let graph = self.graph.get();
+        let report = self
+            .analysis
+            .extract_worst_path_through(&graph.graph, graph.pin_id(pin)?);
+        Ok(report.map(|report| self.path_of(graph, report)))
+    }
+
+    /// Worst path of each of the `n` worst endpoints
+    fn worst_paths(&self, n: usize) -> Vec<Path> {
+        let graph = self.graph.get();
+        (self.endpoints(graph).into_iter().take(n))
+            .filter_map(|(endpoint, _)| self.analysis.path_report(&graph.graph, &endpoint))
+            .map(|report| self.path_of(graph, report))
+            .collect()
+    }
+
+    /// Report of the worst path to an endpoint, as printed by `stars report`
+    #[pyo3(signature = (endpoint, transition = None))]
+    fn report(&self, endpoint: &str, transition: Option<&str>) -> PyResult<String> {
+        let graph = self.graph.get();
+        let nodes = graph.nodes(endpoint, transition)?;
+        let slacks = nodes
+            .iter()
+            .filter_map(|node| Some((*node, self.analysis.slack(node)?)));
+        let node = slacks
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(nodes[0], |(node, _)| node);
+        let mut report = String::new();
+        stars::report::report_path(&mut report, &graph.graph, &self.analysis, &node, None).unwrap();
+        Ok(report)
+    }
+}
+
+/// A timing path, from its startpoint to its endpoint
+#[pyclass(module = "stars_py", frozen, get_all)]
+struct Path {
+    startpoint: String,
+    endpoint: String,
+    /// transition at the endpoint
+    transition: &'static str,
+    /// arrival time at the endpoint
+    arrival: f32,
+    /// None for the unconstrained endpoints
+    slack: Option<f32>,
+    cell_delay: f32,
+    net_delay: f32,
+    /// pins of the path in order as (pin, transition, arrival)
+    pins: Vec<(String, &'static str, f32)>,
+    /// cells of the path in order as (instance, celltype, net delay to the cell, delay through the cell), None
+    /// for the ports
+    stages: Vec<(Option<String>, Option<String>, f32, f32)>,
+}
+
+impl Path {
+    fn new(graph: &SDFGraph, report: PathReport, slack: Option<f32>) -> Self {
+        let symbols = &graph.symbols;
+        let name = |node: PinTrans| symbols.pin_name(node.0).to_string();
+        Self {
+            startpoint: name(report.startpoint()),
+            endpoint: name(report.endpoint),
+            transition: transition_name(report.endpoint.1),
+            arrival: report.total_delay,
+            slack,
+            cell_delay: report.cell_delay(),
+            net_delay: report.net_delay(),
+            pins: (report.pins())
+                .map(|(node, arrival)| (name(node), transition_name(node.1), arrival))
+                .collect(),
+            stages: (report.stages.iter())
+                .map(|stage| {
+                    let instance = stage
+                        .instance
+                        .map(|instance| symbols.instance_name(instance).to_string());
+                    (instance, stage.celltype.clone(), stage.net_delay, stage.cell_delay)
+                })
+                .collect(),
+        }
+    }
+}
+
+#[pymethods]
+impl Path {
+    fn __len__(&self) -> usize {
+        self.pins.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Path({} -> {}, arrival {:.3}, slack {})",
+            self.startpoint,
+            self.endpoint,
+            self.arrival,
+            self.slack.map_or("-".to_string(), |slack| format!("{:.3}", slack))
+        )
+    }
+}
+
+#[pymodule]
+fn stars_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Graph>()?;
+    m.add_class::<Analysis>()?;
+    m.add_class::<Path>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::types::PyDict;
+
+    use super::*;
+
+    const BUFFER: &str = r#"(DELAYFILE
+ (SDFVERSION "3.0")
+ (DESIGN "top")
+ (DIVIDER .)
+ (TIMESCALE 1ns)
+ (CELL (CELLTYPE "top") (INSTANCE)
+  (DELAY (ABSOLUTE
+   (INTERCONNECT a u1.A (0.010::0.030) (0.010::0.030))
+   (INTERCONNECT u1.X x (0.020::0.040) (0.020::0.040)))))
+ (CELL (CELLTYPE "sky130_fd_sc_hd__buf_1") (INSTANCE u1)
+  (DELAY (ABSOLUTE (IOPATH A X (0.100::0.300) (0.200::0.400)))))
+)"#;
+
+    #[test]
+    fn test_module() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let globals = PyDict::new(py);
+            globals
+                .set_item("stars_py", pyo3::wrap_pymodule!(stars_py)(py))
+                .unwrap();
+            globals.set_item("BUFFER", BUFFER).unwrap();
+            let result = py.run(
+                cr#"
+graph = stars_py.Graph.parse(BUFFER)
+assert graph.corner == "max", graph.corner
+assert graph.instances() == [("", "top"), ("u1", "sky130_fd_sc_hd__buf_1")], graph.instances()
+assert sorted(graph.startpoints()) == [("a", "fall"), ("a", "rise")], graph.startpoints()
+assert sorted(graph.endpoints()) == [("x", "fall"), ("x", "rise")], graph.endpoints()
+assert graph.fanout("u1/A") == ["u1/X"]
+
+# the fall of the buffer is the slowest, on the max corner
+analysis = graph.analyze(period=1.0)
+assert abs(analysis.worst_slack - 0.53) < 1e-5, analysis.worst_slack
+assert [(pin, tr) for pin, tr, _ in analysis.endpoints_by_slack()] == [("x", "fall"), ("x", "rise")]
+assert abs(analysis.arrival("x", "rise") - 0.37) < 1e-5
+assert analysis.slack("x") == analysis.worst_slack
+assert analysis.slack_through("u1/A") == analysis.worst_slack
+
+path = analysis.path("x")
+assert (path.startpoint, path.endpoint, path.transition) == ("a", "x", "fall")
+assert len(path) == 4
+assert [pin for pin, _, _ in path.pins] == ["a", "u1/A", "u1/X", "x"]
+assert abs(path.cell_delay - 0.4) < 1e-5 and abs(path.net_delay - 0.07) < 1e-5
+assert path.stages[1][:2] == ("u1", "sky130_fd_sc_hd__buf_1"), path.stages
+assert analysis.path_through("u1/X").endpoint == "x"
+assert len(analysis.worst_paths(5)) == 2
+assert "u1/X" in analysis.report("x")
+
+# the errors are raised as exceptions
+for call, error in [
+    (lambda: stars_py.Graph.parse(BUFFER, corner="fast"), ValueError),
+    (lambda: stars_py.Graph.parse("(DELAYFILE"), ValueError),
+    (lambda: stars_py.Graph.read("/nonexistent.sdf"), IOError),
+    (lambda: analysis.slack("nope"), ValueError),
+    (lambda: analysis.arrival("x", "up"), ValueError),
+]:
+    try:
+        call()
+    except error:
+        pass
+    else:
+        raise AssertionError("no error raised")
+"#,
+                Some(&globals),
+                None,
+            );
+            if let Err(e) = result {
+                panic!("{}", e);
+            }
+        });
+    }
+}
@@ -0,0 +1,104 @@
+//! WebAssembly bindings of stars, for the viewer in `www/` that analyzes a SDF in the browser.
+//!
+//! Built with `wasm-pack build --target web --out-dir www/pkg` from this directory. The core is used without its
+//! `fs` feature: the SDF and SDC are handed over as strings, read by the page from the files the user picked.
+//!
+//! ```js
+//! import init, { Timing } from "./pkg/stars_wasm.js";
+//!
+//! await init();
+//! const timing = new Timing(sdf, "max", 10.0, undefined);
+//! const summary = JSON.parse(timing.summary(20));
+//! const path = JSON.parse(timing.path(summary.endpoints[0].pin, summary.endpoints[0].transition));
+//! ```
+//!
+//! The results are JSON strings in the format of `stars --json` (see `stars::json`), times are in ns.
+
+use stars::analysis::{AnalysisOptions, SDFGraphAnalyzed};
+use stars::graph::SDFGraph;
+use stars::json::{AnalysisJson, PathJson};
+use stars::sdc::Sdc;
+use stars::types::{Corner, PinTrans, Transition};
+use wasm_bindgen::prelude::*;
+
+fn parse_transition(transition: &str) -> Result<Transition, JsError> {
+    match transition {
+        "rise" => Ok(Transition::Rise),
+        "fall" => Ok(Transition::Fall),
+        _ => Err(JsError::new(&format!(
+            "Unknown transition {} (expected rise or fall)",
+            transition
+        ))),
+    }
+}
+
+/// Timing graph of a SDF and its analysis
+#[wasm_bindgen]
+pub struct Timing {
+    graph: SDFGraph,
+    analysis: SDFGraphAnalyzed,
+}
+
+impl Timing {
+    fn node(&self, pin: &str, transition: &str) -> Result<PinTrans, JsError> {
+        let id = (self.graph.symbols.pin_id(pin)).ok_or_else(|| JsError::new(&format!("Unknown pin {}", pin)))?;
+        Ok((id, parse_transition(transition)?))
+    }
+}
+
+#[wasm_bindgen]
+impl Timing {
+    /// Build the graph of the SDF source `sdf` at `corner` (min, typ or max) and analyze it. The constraints come
+    /// from the SDC source `sdc` when given, `period` overrides its clock period.
+    #[wasm_bindgen(constructor)]
+    pub fn new(sdf: &str, corner: &str, period: Option<f32>, sdc: Option<String>) -> Result<Timing, JsError> {
+        let corner: Corner = corner.parse().map_err(|e: String| JsError::new(&e))?;
+        let sdf = sdfparse::SDF::parse_str(sdf).map_err(|e| JsError::new(&e))?;
+        let graph = SDFGraph::from_sdf(sdf, corner)?;
+        let mut options = match sdc {
+            Some(sdc) => Sdc::parse(&sdc)?.analysis_options(&graph),
+            None => AnalysisOptions::default(),
+        };
+        if period.is_some() {
+            options.period = period;
+        }
+        let analysis = SDFGraphAnalyzed::analyze_with(&graph, &options);
+        Ok(Self { graph, analysis })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn instances(&self) -> usize {
+        self.graph.instance_celltype.len()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn pins(&self) -> usize {
+        self.graph.symbols.pins().len()
+    }
+
+    /// Endpoints by slack and the worst path of the `top` worst ones, see `AnalysisJson`
+    pub fn summary(&self, top: usize) -> String {
+        AnalysisJson::new(&self.graph, &self.analysis, top).to_json()
+    }
+
+    /// Worst path to a pin, see `PathJson`. Its slack is the one of the worst path through the pin when it isn't
+    /// an endpoint.
+    pub fn path(&self, pin: &str, transition: &str) -> Result<String, JsError> {
+        let node = self.node(pin, transition)?;
+        if self.analysis.arrival(&node).is_none() {
+            return Err(JsError::new(&format!("No path reaches {} ({})", pin, transition)));
+        }
+        let slack = (self.analysis.slack(&node))
+            .or_else(|| self.analysis.slack_through(&self.graph, &node))
+            .unwrap_or(f32::NAN);
+        Ok(PathJson::new(&self.graph, &self.analysis, &node, slack).to_json())
+    }
+
+    /// Report of the worst path to a pin, as printed by `stars report`
+    pub fn report(&self, pin: &str, transition: &str) -> Result<String, JsError> {
+        let node = self.node(pin, transition)?;
+        let mut report = String::new();
+        stars::report::report_path(&mut report, &self.graph, &self.analysis, &node, None)?;
+        Ok(report)
+    }
+}